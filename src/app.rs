@@ -1,8 +1,13 @@
-use crate::modes::{Mode, ReadMode, Selected};
+use crate::config::{DateFormat, EntryColumns, PaneLayout};
+use crate::jumps::{Jump, JumpFeed};
+use crate::modes::{
+    ContentField, FeedField, MarkAction, Mode, ReadMode, Selected, TagAction, VirtualFeed,
+};
 use crate::util;
 use anyhow::Result;
 use copypasta::{ClipboardContext, ClipboardProvider};
-use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tui::{backend::CrosstermBackend, Terminal};
 
@@ -28,6 +33,40 @@ macro_rules! delegate_to_locked_mut_inner {
     };
 }
 
+/// how many actions can be undone
+const UNDO_STACK_LIMIT: usize = 100;
+
+/// how many errors the error log keeps
+const ERROR_LOG_LIMIT: usize = 500;
+
+/// An error, as it is kept in the error log
+#[derive(Debug)]
+pub struct ErrorLogEntry {
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// the title (or link) of the feed the error happened with, if any
+    pub feed: Option<String>,
+    pub message: String,
+}
+
+/// how far `<` and `>` resize the feeds pane, as a percentage of the screen's width
+const FEEDS_PANE_PERCENT_STEP: u16 = 5;
+const MIN_FEEDS_PANE_PERCENT: u16 = 10;
+const MAX_FEEDS_PANE_PERCENT: u16 = 60;
+
+/// the largest count that can prefix a key, like the `5` in `5j`
+const MAX_PENDING_COUNT: usize = 10_000;
+
+/// The state needed to revert an action
+#[derive(Debug)]
+pub enum UndoAction {
+    /// the previous value of the flag for every entry the action changed
+    SetFlag(
+        crate::rss::EntryFlag,
+        Vec<(crate::rss::EntryId, Option<chrono::DateTime<chrono::Utc>>)>,
+    ),
+    DeleteFeed(crate::rss::DeletedFeed),
+}
+
 #[derive(Clone, Debug)]
 pub struct App {
     inner: Arc<Mutex<AppImpl>>,
@@ -35,92 +74,441 @@ pub struct App {
 
 impl App {
     delegate_to_locked_inner![
+        (search_is_empty, bool),
+        (search_is_active, bool),
+        (save_session, Result<()>),
         (error_flash_is_empty, bool),
-        (feed_ids, Result<Vec<crate::rss::FeedId>>),
+        (error_log_is_shown, bool),
+        (stats_are_shown, bool),
+        (queue_is_shown, bool),
+        (queue_is_stopping, bool),
+        (speech_progress, Option<crate::speech::Progress>),
+        (mark_is_pending, bool),
+        (refresh_is_cancelled, bool),
+        (visual_is_empty, bool),
+        (due_feed_ids, Result<Vec<crate::rss::FeedId>>),
+        (scheduled_feed_ids, Result<Vec<crate::rss::FeedId>>),
+        (unread_count, Result<usize>),
         (feed_subscription_input, String),
         (force_redraw, Result<()>),
         (http_client, ureq::Agent),
+        (database_path, std::path::PathBuf),
         (mode, Mode),
         (selected, Selected),
-        (selected_feed_id, crate::rss::FeedId),
+        (selected_feed_id, Option<crate::rss::FeedId>),
+        (current_feed_id, Option<crate::rss::FeedId>),
+        (current_entry_link, Option<String>),
+        (current_entry_media_link, Result<Option<String>>),
         (open_link_in_browser, Result<()>),
+        (entry_for_viewer, Result<(String, String)>),
+        (action_entry, Result<serde_json::Value>),
     ];
 
     delegate_to_locked_mut_inner![
         (clear_error_flash, ()),
+        (close_error_log, ()),
+        (toggle_error_log, ()),
+        (close_stats, ()),
+        (toggle_stats, Result<()>),
+        (close_queue, ()),
+        (toggle_queue, Result<()>),
+        (toggle_queued, Result<()>),
+        (toggle_queue_playback, Result<bool>),
+        (finish_playing_queue, ()),
+        (toggle_speech, Result<Option<Vec<String>>>),
+        (add_feed_from_clipboard, Result<String>),
+        (stop_speech, bool),
+        (finish_speech, ()),
+        (start_refresh, ()),
+        (finish_refresh, ()),
+        (cancel_refresh, bool),
+        (update_queue, Result<()>),
+        (toggle_feeds_pane, ()),
+        (grow_feeds_pane, ()),
+        (shrink_feeds_pane, ()),
+        (take_pending_count, Option<usize>),
+        (next_replayed_key, Option<KeyEvent>),
         (clear_flash, ()),
-        (on_down, Result<()>),
-        (on_enter, Result<()>),
+        (cancel_delete_feed, ()),
+        (cancel_note, ()),
+        (cancel_pipe, ()),
+        (cancel_share, ()),
+        (pop_share_input, ()),
+        (start_share, ()),
+        (take_share, Result<String>),
+        (start_pipe, ()),
+        (pop_pipe_input, ()),
+        (take_pipe, Result<crate::pipe::Pipe>),
+        (cancel_feed_edit, ()),
+        (save_feed_edit, Result<()>),
+        (start_feed_edit, ()),
+        (switch_feed_edit_field, ()),
+        (pop_feed_edit_input, ()),
+        (cancel_tag, ()),
+        (complete_tag, ()),
+        (confirm_tag, Result<()>),
+        (clear_search, Result<()>),
+        (confirm_search, Result<()>),
+        (previous_search, Result<()>),
+        (next_search, Result<()>),
+        (search_past_searches, Result<()>),
+        (open_entry, Result<()>),
+        (jump_back, Result<()>),
+        (jump_forward, Result<()>),
         (on_left, Result<()>),
         (on_right, Result<()>),
-        (on_up, Result<()>),
+        (next_search_match, Result<()>),
+        (next_unread_entry, Result<()>),
+        (next_unread_feed, Result<()>),
         (page_up, ()),
         (page_down, ()),
-        (pop_feed_subscription_input, ()),
+        (take_feed_preview, Option<crate::rss::FeedPreview>),
+        (pop_note_input, ()),
+        (pop_tag_input, ()),
+        (pop_command_input, ()),
+        (cancel_finder, ()),
+        (confirm_finder, Result<()>),
+        (next_finder_match, ()),
+        (pop_finder_input, ()),
+        (previous_finder_match, ()),
+        (complete_command, ()),
+        (previous_command, ()),
+        (next_command, ()),
+        (search_command_history, ()),
+        (cancel_command, ()),
+        (take_command, Result<crate::command::Command>),
+        (pop_search_input, Result<()>),
+        (previous_search_match, Result<()>),
         (put_current_link_in_clipboard, Result<()>),
+        (open_comments_in_browser, Result<()>),
         (reset_feed_subscription_input, ()),
         (select_feeds, ()),
+        (start_search, ()),
+        (start_command, ()),
+        (start_finder, Result<()>),
         (delete_feed, Result<()>),
+        (delete_entries, Result<()>),
+        (exit_visual, ()),
+        (save_note, Result<()>),
+        (start_note, Result<()>),
+        (toggle_star, Result<()>),
+        (toggle_visual, ()),
+        (mark_feed_as_read, Result<()>),
+        (undo, Result<()>),
+        (cycle_entry_sort, Result<()>),
+        (toggle_feed_pinned, Result<()>),
+        (cycle_feed_sort, Result<()>),
+        (toggle_content_field, Result<()>),
+        (
+            toggle_translation,
+            Result<Option<(crate::rss::EntryId, String)>>
+        ),
+        (toggle_fetch_full_content, Result<()>),
         (toggle_help, Result<()>),
         (toggle_read, Result<()>),
         (toggle_read_mode, Result<()>),
         (update_current_feed_and_entries, Result<()>),
+        (load_feeds, Result<()>),
+        (render_current_entry, Result<()>),
+        (take_pending_images, Vec<String>),
     ];
 
     pub fn new(
         options: crate::Options,
         event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
     ) -> Result<App> {
-        Ok(App {
-            inner: Arc::new(Mutex::new(AppImpl::new(options, event_s)?)),
-        })
+        let (loader_s, loader_r) = std::sync::mpsc::channel();
+        let app = App {
+            inner: Arc::new(Mutex::new(AppImpl::new(options, event_s, loader_s)?)),
+        };
+        crate::loader::spawn(app.clone(), loader_r);
+        Ok(app)
+    }
+
+    /// Keep the lock on the database, listening for other russes' requests with `handle`,
+    /// and moving to whichever profile's database is switched to.
+    pub fn listen(
+        &self,
+        mut instance_lock: crate::remote::InstanceLock,
+        handle: crate::remote::Handler,
+    ) -> Result<()> {
+        instance_lock.listen(handle)?;
+        self.inner.lock().unwrap().instance_lock = Some(instance_lock);
+        Ok(())
+    }
+
+    /// Let go of the database's lock and socket, for quitting.
+    /// The handler keeps the app alive, so they wouldn't be dropped otherwise.
+    pub fn release_instance_lock(&self) {
+        self.inner.lock().unwrap().instance_lock = None;
+    }
+
+    /// Show the feeds the loader thread loaded, unless they've been loaded again since,
+    /// keeping the current feed selected, and loading its entries again too.
+    pub fn finish_loading_feeds(
+        &self,
+        generation: u64,
+        loaded: crate::loader::LoadedFeeds,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if generation != inner.feeds_generation {
+            return Ok(());
+        }
+
+        inner.set_feeds(loaded);
+        inner.reselect_current_feed()?;
+        inner.update_current_feed()?;
+        inner.load_current_entries()?;
+        inner.force_redraw()
+    }
+
+    /// Open or preview the entry the loader thread read, unless another has been
+    /// selected, or the entries left, since it was asked to.
+    pub fn finish_loading_entry(
+        &self,
+        generation: u64,
+        loaded: crate::loader::LoadedEntry,
+        open: bool,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if generation != inner.entry_generation {
+            return Ok(());
+        }
+
+        inner.entry_loading = false;
+
+        let is_selected = matches!(
+            &inner.current_entry_meta,
+            Some(entry_meta) if entry_meta.id == loaded.entry_id
+        );
+        let is_shown = match inner.selected {
+            Selected::Entries => true,
+            // a preview isn't shown over the open entry
+            Selected::Entry(_) => open,
+            _ => false,
+        };
+        if !is_selected || !is_shown {
+            return Ok(());
+        }
+
+        inner.loaded_entry = Some(loaded);
+        let result = if open {
+            inner.selected = Selected::Entries;
+            inner.entry_scroll_position = 0;
+            inner.on_enter()
+        } else {
+            inner.render_current_entry()
+        };
+        inner.loaded_entry = None;
+        result?;
+
+        inner.force_redraw()
+    }
+
+    /// Show the entries the loader thread loaded,
+    /// unless they've been loaded again, or for another feed, since it was asked to.
+    pub fn finish_loading_entries(
+        &self,
+        generation: u64,
+        loaded: crate::loader::LoadedEntries,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if generation != inner.entries_generation {
+            return Ok(());
+        }
+
+        inner.entries_loading = false;
+        inner.set_entries(loaded);
+        inner.force_redraw()
     }
 
     pub fn draw(&self, terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
         let mut inner = self.inner.lock().unwrap();
 
+        Self::draw_frame(terminal, &mut inner)?;
+
+        if inner.hyperlinks {
+            let needs_redraw = crate::hyperlink::write(
+                terminal.backend_mut(),
+                &inner.written_links,
+                &inner.drawn_links,
+                &inner.drawn_entry_lines,
+            )?;
+
+            // an old link is still on screen, somewhere tui doesn't know to redraw
+            if needs_redraw {
+                terminal.clear()?;
+                Self::draw_frame(terminal, &mut inner)?;
+                crate::hyperlink::write(
+                    terminal.backend_mut(),
+                    &[],
+                    &inner.drawn_links,
+                    &inner.drawn_entry_lines,
+                )?;
+            }
+
+            inner.written_links = std::mem::take(&mut inner.drawn_links);
+        }
+
+        if inner.images.is_some() {
+            Self::draw_images(terminal, &mut inner)?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear the screen, for when another program has had it, so the next draw draws everything.
+    pub fn clear(&self, terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        terminal.clear()?;
+        inner.written_images.clear();
+        Ok(())
+    }
+
+    fn draw_images(
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        inner: &mut AppImpl,
+    ) -> Result<()> {
+        // resizing the screen clears it
+        let screen = terminal.size()?;
+        if screen != inner.written_images_screen {
+            inner.written_images.clear();
+            inner.written_images_screen = screen;
+        }
+
+        let needs_redraw = match &inner.images {
+            Some(images) => images.needs_redraw(&inner.written_images, &inner.drawn_images),
+            None => false,
+        };
+
+        if needs_redraw {
+            terminal.clear()?;
+            Self::draw_frame(terminal, inner)?;
+            inner.written_images.clear();
+
+            if inner.hyperlinks {
+                crate::hyperlink::write(
+                    terminal.backend_mut(),
+                    &[],
+                    &inner.drawn_links,
+                    &inner.drawn_entry_lines,
+                )?;
+                inner.written_links = std::mem::take(&mut inner.drawn_links);
+            }
+        }
+
+        let AppImpl {
+            images,
+            written_images,
+            drawn_images,
+            ..
+        } = inner;
+
+        if let Some(images) = images {
+            images.write(terminal.backend_mut(), written_images, drawn_images)?;
+        }
+        inner.written_images = std::mem::take(&mut inner.drawn_images);
+
+        Ok(())
+    }
+
+    fn draw_frame(
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        inner: &mut AppImpl,
+    ) -> Result<()> {
         terminal.draw(|f| {
-            let chunks = crate::ui::predraw(f);
+            let chunks = crate::ui::predraw(f, inner);
 
             assert!(
-                chunks.len() >= 2,
-                "There must be at least two chunks in order to draw two columns"
+                chunks.len() >= 3,
+                "There must be at least three chunks in order to draw two columns and the status bar"
             );
 
-            let new_width = chunks[1].width;
+            let new_width = crate::ui::entry_chunk(&chunks, inner.layout).width;
 
+            // rewrap the open or previewed entry to the new width,
+            // but don't open the selected one if none is open
             if inner.entry_column_width != new_width {
                 inner.entry_column_width = new_width;
-                inner.on_enter().unwrap_or_else(|e| {
-                    inner.error_flash = vec![e];
+
+                let result = match inner.selected {
+                    Selected::Entry(_) => inner.on_enter(),
+                    _ if inner.layout == PaneLayout::ThreePane => inner.render_current_entry(),
+                    _ => Ok(()),
+                };
+
+                result.unwrap_or_else(|e| {
+                    inner.push_error(None, e);
                 })
             }
 
-            inner.entry_column_width = chunks[1].width;
-
-            crate::ui::draw(f, chunks, &mut inner);
+            crate::ui::draw(f, chunks, inner);
         })?;
 
         Ok(())
     }
 
     pub fn on_key(&self, keycode: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(mark_action) = inner.pending_mark.take() {
+                inner.pending_count = None;
+                return match keycode {
+                    KeyCode::Char(name) if name.is_ascii_alphabetic() => {
+                        inner.on_mark(mark_action, name)
+                    }
+                    // `@@` replays the last macro again
+                    KeyCode::Char('@') if matches!(mark_action, MarkAction::ReplayMacro(_)) => {
+                        inner.on_mark(mark_action, '@')
+                    }
+                    // anything else cancels it
+                    _ => Ok(()),
+                };
+            }
+        }
+
+        // digits build up a count for the next key, like `5j`,
+        // except for a leading `0`
+        if let KeyCode::Char(c @ '0'..='9') = keycode {
+            let mut inner = self.inner.lock().unwrap();
+
+            if c != '0' || inner.pending_count.is_some() {
+                let digit = c.to_digit(10).unwrap() as usize;
+                let count = inner.pending_count.unwrap_or(0) * 10 + digit;
+                inner.pending_count = Some(count.min(MAX_PENDING_COUNT));
+                return Ok(());
+            }
+        }
+
+        let count = self.take_pending_count().unwrap_or(1);
+
         match (keycode, modifiers) {
             // movement
             (KeyCode::Left, _) | (KeyCode::Char('h'), _) => self.on_left(),
-            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => self.on_down(),
-            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => self.on_up(),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => self.on_down_by(count),
+            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => self.on_up_by(count),
             (KeyCode::Right, _) | (KeyCode::Char('l'), _) => self.on_right(),
             (KeyCode::PageUp, _) => {
-                self.page_up();
+                for _ in 0..count {
+                    self.page_up();
+                }
                 Ok(())
             }
             (KeyCode::PageDown, _) => {
-                self.page_down();
+                for _ in 0..count {
+                    self.page_down();
+                }
                 Ok(())
             }
             // modes, selections, editing, etc.
-            (KeyCode::Enter, _) => self.on_enter(),
+            (KeyCode::Enter, _) => self.open_entry(),
+            // before `o` and `i`, which they'd otherwise be taken for
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                (0..count).try_for_each(|_| self.jump_back())
+            }
+            (KeyCode::Char('i') | KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                (0..count).try_for_each(|_| self.jump_forward())
+            }
             (KeyCode::Char('?'), _) => self.toggle_help(),
             (KeyCode::Char('a'), _) => self.toggle_read_mode(),
             (KeyCode::Char('e'), _) | (KeyCode::Char('i'), _) => {
@@ -128,8 +516,94 @@ impl App {
                 inner.mode = Mode::Editing;
                 Ok(())
             }
+            (KeyCode::Char('/'), _) => {
+                self.start_search();
+                Ok(())
+            }
+            // `n` and `N` go through search matches while searching, and unread entries otherwise
+            (KeyCode::Char('n'), _) if self.search_is_active() => {
+                (0..count).try_for_each(|_| self.next_search_match())
+            }
+            (KeyCode::Char('N'), _) if self.search_is_active() => {
+                (0..count).try_for_each(|_| self.previous_search_match())
+            }
+            (KeyCode::Char('n'), _) => (0..count).try_for_each(|_| self.next_unread_entry()),
+            (KeyCode::Char('N'), _) | (KeyCode::Tab, _) => {
+                (0..count).try_for_each(|_| self.next_unread_feed())
+            }
+            (KeyCode::Char('f'), _) => self.toggle_fetch_full_content(),
+            (KeyCode::Char('s'), _) => self.cycle_entry_sort(),
+            (KeyCode::Char('J'), _) => self.move_feed_by(count as isize),
+            (KeyCode::Char('K'), _) => self.move_feed_by(-(count as isize)),
+            (KeyCode::Char('p'), KeyModifiers::NONE) => self.toggle_feed_pinned(),
+            (KeyCode::Char('L'), _) => self.cycle_feed_sort(),
+            (KeyCode::Char('m'), _) => {
+                self.start_mark(MarkAction::Set);
+                Ok(())
+            }
+            (KeyCode::Char('\''), _) => {
+                self.start_mark(MarkAction::Jump);
+                Ok(())
+            }
+            (KeyCode::Char('Z'), _) => {
+                self.toggle_macro_recording();
+                Ok(())
+            }
+            (KeyCode::Char('@'), _) => {
+                self.start_mark(MarkAction::ReplayMacro(count));
+                Ok(())
+            }
+            (KeyCode::Char('b'), _) => self.toggle_content_field(),
+            (KeyCode::Char('R'), _) => self.mark_feed_as_read(),
+            (KeyCode::Char('u'), _) => self.undo(),
+            (KeyCode::Char('v'), _) => {
+                self.toggle_visual();
+                Ok(())
+            }
+            (KeyCode::Char('*'), _) => self.toggle_star(),
+            (KeyCode::Char('A'), _) => self.start_note(),
+            (KeyCode::Char('S'), _) => {
+                self.start_share();
+                Ok(())
+            }
+            (KeyCode::Char('|'), _) => {
+                self.start_pipe();
+                Ok(())
+            }
+            (KeyCode::Char('F'), _) => {
+                self.start_feed_edit();
+                Ok(())
+            }
+            (KeyCode::Char('E'), _) => {
+                self.toggle_error_log();
+                Ok(())
+            }
+            (KeyCode::Char('D'), _) => self.toggle_stats(),
+            (KeyCode::Char('Q'), _) => self.toggle_queue(),
+            (KeyCode::Char('+'), _) => self.toggle_queued(),
+            (KeyCode::Char('<'), _) => {
+                self.shrink_feeds_pane();
+                Ok(())
+            }
+            (KeyCode::Char('>'), _) => {
+                self.grow_feeds_pane();
+                Ok(())
+            }
+            (KeyCode::Char('z'), _) => {
+                self.toggle_feeds_pane();
+                Ok(())
+            }
+            (KeyCode::Char(':'), _) => {
+                self.start_command();
+                Ok(())
+            }
+            (KeyCode::Char('p'), KeyModifiers::CONTROL) => self.start_finder(),
+            (KeyCode::Char('t'), _) => self.start_tag(TagAction::Toggle),
+            (KeyCode::Char('T'), _) => self.start_tag(TagAction::Browse),
+            (KeyCode::Char('d'), _) => self.delete_entries(),
             (KeyCode::Char('c'), _) => self.put_current_link_in_clipboard(),
             (KeyCode::Char('o'), _) => self.open_link_in_browser(),
+            (KeyCode::Char('C'), _) => self.open_comments_in_browser(),
             _ => Ok(()),
         }
     }
@@ -139,9 +613,27 @@ impl App {
         inner.flash = Some(flash)
     }
 
+    pub fn set_queue_now_playing(&self, now_playing: String) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.queue_now_playing = Some(now_playing)
+    }
+
+    pub fn set_speech_paragraph(&self, paragraph: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(speech) = &mut inner.speech {
+            speech.paragraph = paragraph;
+        }
+    }
+
     pub fn push_error_flash(&self, e: anyhow::Error) {
         let mut inner = self.inner.lock().unwrap();
-        inner.error_flash.push(e);
+        inner.push_error(None, e);
+    }
+
+    /// like `push_error_flash`, but records the feed the error happened with
+    pub fn push_feed_error_flash(&self, feed_id: crate::rss::FeedId, e: anyhow::Error) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.push_error(Some(feed_id), e);
     }
 
     pub fn set_mode(&self, mode: Mode) {
@@ -149,9 +641,168 @@ impl App {
         inner.mode = mode;
     }
 
-    pub fn push_feed_subscription_input(&self, input: char) {
+    /// edit the feed being added like a line in a shell
+    pub fn edit_feed_subscription_input(&self, keycode: KeyCode, modifiers: KeyModifiers) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.feed_subscription_input.on_key(keycode, modifiers) {
+            inner.confirm_delete_feed = false;
+            inner.feed_preview = None;
+        }
+    }
+
+    pub fn set_feed_preview(&self, feed_preview: crate::rss::FeedPreview) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.set_feed_preview(feed_preview)
+    }
+
+    pub fn push_search_input(&self, input: char) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let AppImpl {
+            search_history,
+            search_input,
+            ..
+        } = &mut *inner;
+
+        if search_history.search().is_some() {
+            search_history.push_search(input, search_input);
+        } else {
+            search_input.push(input);
+        }
+        inner.update_search()
+    }
+
+    /// Put what was pasted into whichever input is open, all at once,
+    /// rather than as keys, which would run commands, and submit it at its newlines.
+    pub fn paste(&self, text: &str) -> Result<()> {
+        let text = crate::input::sanitize(text);
+
+        match self.mode() {
+            // its letters would be taken for keys
+            Mode::Normal => (),
+            Mode::Editing => {
+                let mut inner = self.inner.lock().unwrap();
+                inner.feed_subscription_input.insert_str(&text);
+                if !text.is_empty() {
+                    inner.confirm_delete_feed = false;
+                    inner.feed_preview = None;
+                }
+            }
+            Mode::Command => text.chars().for_each(|c| self.push_command_input(c)),
+            Mode::Search => text.chars().try_for_each(|c| self.push_search_input(c))?,
+            Mode::Finder => text.chars().for_each(|c| self.push_finder_input(c)),
+            Mode::FeedEdit => text.chars().for_each(|c| self.push_feed_edit_input(c)),
+            Mode::Note => text.chars().for_each(|c| self.push_note_input(c)),
+            Mode::Pipe => text.chars().for_each(|c| self.push_pipe_input(c)),
+            Mode::Share => text.chars().for_each(|c| self.push_share_input(c)),
+            Mode::Tag => text.chars().for_each(|c| self.push_tag_input(c)),
+        }
+
+        Ok(())
+    }
+
+    pub fn push_note_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.note_input.push(input);
+    }
+
+    pub fn push_share_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.share_input.push(input);
+    }
+
+    pub fn push_pipe_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pipe_input.push(input);
+    }
+
+    pub fn push_feed_edit_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.feed_edit_input.push(input);
+    }
+
+    pub fn on_down_by(&self, count: usize) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_down_by(count)
+    }
+
+    pub fn on_up_by(&self, count: usize) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_up_by(count)
+    }
+
+    pub fn start_mark(&self, mark_action: MarkAction) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending_mark = Some(mark_action);
+    }
+
+    /// `Z` stops the recording, or starts one once it has the register's letter
+    fn toggle_macro_recording(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.macros.stop_recording() {
+            Some((register, len)) => {
+                inner.flash = Some(format!("Recorded {} keys into @{}", len, register))
+            }
+            None => inner.pending_mark = Some(MarkAction::RecordMacro),
+        }
+    }
+
+    /// Remember a key that was pressed, if a macro is being recorded.
+    pub fn record_key(&self, key: KeyEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.macros.record(key);
+    }
+
+    pub fn move_feed_by(&self, by: isize) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.move_feed_by(by)
+    }
+
+    pub fn push_finder_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(finder) = &mut inner.finder {
+            finder.push(input);
+        }
+    }
+
+    pub fn push_command_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        let AppImpl {
+            command_history,
+            command_input,
+            ..
+        } = &mut *inner;
+
+        if command_history.search().is_some() {
+            command_history.push_search(input, command_input);
+        } else {
+            command_input.push(input);
+        }
+    }
+
+    /// run the commands that don't need the IO thread or the terminal
+    pub fn run_command(&self, command: crate::command::Command) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.run_command(command)
+    }
+
+    pub fn push_tag_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tag_input.push(input);
+    }
+
+    pub fn start_tag(&self, tag_action: TagAction) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.start_tag(tag_action)
+    }
+
+    pub fn open_feed(&self, title: &str) -> Result<String> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.open_feed(title)
+    }
+
+    pub fn set_translation(&self, entry_id: crate::rss::EntryId, html: String) -> Result<()> {
         let mut inner = self.inner.lock().unwrap();
-        inner.feed_subscription_input.push(input);
+        inner.set_translation(entry_id, html)
     }
 
     pub fn set_feeds(&self, feeds: Vec<crate::rss::Feed>) {
@@ -165,46 +816,282 @@ impl App {
 pub struct AppImpl {
     // database stuff
     pub conn: rusqlite::Connection,
+    pub database_path: std::path::PathBuf,
+    /// `None` for the default profile
+    pub profile: Option<String>,
+    /// for SQLCipher, which every profile's database is unlocked with
+    database_passphrase: Option<String>,
+    /// the lock on the database, and the socket other russes send requests to
+    instance_lock: Option<crate::remote::InstanceLock>,
     // network stuff
     pub http_client: ureq::Agent,
     // feed stuff
     pub current_feed: Option<crate::rss::Feed>,
     pub feeds: util::StatefulList<crate::rss::Feed>,
+    /// (unread, total)
+    pub current_feed_entry_counts: (usize, usize),
+    /// counts loads of the entries list, so the loader thread's stale ones are dropped
+    entries_generation: u64,
+    /// the loader thread hasn't caught up with the current feed yet
+    pub entries_loading: bool,
+    /// counts loads of the feeds list, like `entries_generation`
+    feeds_generation: u64,
+    /// counts loads of the selected entry, to open or preview it, like `entries_generation`
+    entry_generation: u64,
+    /// the loader thread is still reading the entry being opened or previewed
+    pub entry_loading: bool,
+    /// what the loader thread read, for the next render of the entry it's for
+    loaded_entry: Option<crate::loader::LoadedEntry>,
+    /// how many of the entries list's entries have been loaded, before filtering
+    entries_fetched: usize,
+    /// the entries list has every entry there is to load
+    entries_exhausted: bool,
+    loader: std::sync::mpsc::Sender<crate::loader::Load>,
     // entry stuff
     pub current_entry_meta: Option<crate::rss::EntryMeta>,
     pub entries: util::StatefulList<crate::rss::EntryMeta>,
     pub entry_selection_position: usize,
     pub current_entry_text: String,
+    /// how the open entry's lines look, by line: its headings, quotes, and highlighted code
+    pub entry_line_styles: HashMap<usize, crate::code::LineStyles>,
+    /// see `UiConfig::hyperlinks`
+    pub hyperlinks: bool,
+    /// the open entry's links, if `hyperlinks` is on
+    pub entry_links: Vec<crate::hyperlink::EntryLink>,
+    /// where the entry's links were drawn this frame, to be written over tui's drawing
+    pub drawn_links: Vec<crate::hyperlink::DrawnLink>,
+    /// the entry's lines that were drawn this frame, to write over old links with
+    pub drawn_entry_lines: Vec<crate::hyperlink::DrawnLine>,
+    /// the links written over the last frame
+    written_links: Vec<crate::hyperlink::DrawnLink>,
+    /// whether entries show their content or their description
+    pub content_field: ContentField,
+    /// the last entry translated with `Y`, and its translation's HTML
+    translation: Option<(crate::rss::EntryId, String)>,
+    /// whether that entry shows its translation, rather than itself
+    translation_shown: bool,
+    /// loaded the first time an entry has code in it
+    highlighter: Option<crate::code::Highlighter>,
+    /// with `--enhanced-graphics`, in a terminal that can draw images
+    pub images: Option<crate::images::Images>,
+    /// where the open entry's images go
+    pub entry_images: Vec<crate::images::ImageRegion>,
+    /// where the entry's images were drawn this frame, to be written over tui's drawing
+    pub drawn_images: Vec<crate::images::DrawnImage>,
+    /// the images written over the last frame
+    written_images: Vec<crate::images::DrawnImage>,
+    /// how big the screen was when the images were written, as resizing it clears them
+    written_images_screen: tui::layout::Rect,
     pub entry_scroll_position: u16,
     pub entry_lines_len: usize,
     pub entry_lines_rendered_len: u16,
     pub entry_column_width: u16,
+    pub layout: PaneLayout,
+    /// how much of the screen's width the feeds pane takes
+    pub feeds_pane_percent: u16,
+    /// hidden while reading, but always shown when it is needed, see `feeds_pane_is_shown`
+    pub feeds_pane_hidden: bool,
     // modes
     pub should_quit: bool,
     pub selected: Selected,
     pub mode: Mode,
     pub read_mode: ReadMode,
+    pub feed_sort: crate::rss::FeedSort,
     pub show_help: bool,
     // misc
     pub error_flash: Vec<anyhow::Error>,
-    pub feed_subscription_input: String,
+    /// every recent error, newest last, even those cleared from the flash
+    pub error_log: std::collections::VecDeque<ErrorLogEntry>,
+    pub show_error_log: bool,
+    /// the statistics view, loaded when it is shown
+    pub stats: Option<crate::stats::Stats>,
+    /// the playback queue, loaded when it is shown
+    pub queue: Option<Vec<crate::rss::QueuedEntry>>,
+    pub queue_playing: bool,
+    /// what the queue is playing, once the player has started
+    pub queue_now_playing: Option<String>,
+    /// stop playing the queue once what is playing now is done
+    pub queue_stopping: bool,
+    /// how far `V` has got reading the open entry aloud, while it is
+    pub speech: Option<crate::speech::Progress>,
+    /// whether feeds are being refreshed, so the refresh can be cancelled
+    refreshing: bool,
+    /// skip the feeds the refresh hasn't got to yet
+    refresh_cancelled: bool,
+    /// see `Options::enhanced_graphics`
+    pub enhanced_graphics: bool,
+    pub feed_subscription_input: crate::input::LineInput,
+    /// the feed that was typed, shown before subscribing to it
+    pub feed_preview: Option<crate::rss::FeedPreview>,
     pub flash: Option<String>,
+    pub search_input: String,
+    pub note_input: String,
+    pub feed_edit_input: String,
+    pub pipe_input: String,
+    pub share_input: String,
+    /// the last command an entry was piped to, to start the pipe input with
+    last_pipe_command: String,
+    pub feed_edit_field: FeedField,
+    pub tag_input: String,
+    pub command_input: String,
+    pub finder: Option<crate::finder::Finder>,
+    /// where big moves have jumped from, for `ctrl-o` and `ctrl-i`
+    pub jumps: crate::jumps::JumpList,
+    /// after `m`, `'`, `Z`, or `@`, waiting for the mark's or register's letter
+    pub pending_mark: Option<MarkAction>,
+    pub macros: crate::macros::Macros,
+    /// the count typed before a key, like the `5` in `5j`
+    pub pending_count: Option<usize>,
+    /// previously run commands, across sessions
+    pub command_history: crate::history::History,
+    /// previous searches, across sessions
+    pub search_history: crate::history::History,
+    /// the widest entries are wrapped to, no matter how wide the column is
+    pub entry_max_width: Option<u16>,
+    pub tag_action: TagAction,
+    /// every tag in use, for completing the tag input
+    pub tags: Vec<String>,
+    /// when set, the entries list shows the entries with this tag instead of the current feed's
+    pub entries_tag: Option<String>,
+    /// only the entries from these days are listed, set with `:filter`
+    pub entries_date_range: crate::rss::DateRange,
+    /// the virtual feed selected above the feeds, if it is one rather than a feed,
+    /// in which case no feed is selected
+    pub virtual_feed: Option<VirtualFeed>,
+    /// the selection in the feeds pane, virtual feeds and all, for drawing it
+    pub feeds_pane_state: tui::widgets::ListState,
+    pub confirm_delete_feed: bool,
+    /// where visual selection of entries started, if it is active
+    pub visual_anchor: Option<usize>,
+    pub undo_stack: Vec<UndoAction>,
+    pub entries_filter: Option<String>,
+    pub entry_search: Option<String>,
+    pub entry_search_matches: Vec<u16>,
+    pub entry_search_match_position: usize,
     event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
     pub is_wsl: bool,
+    /// see `UiConfig::hide_read_feeds`
+    pub hide_read_feeds: bool,
+    /// how many feeds `hide_read_feeds` is hiding
+    pub hidden_feeds_len: usize,
+    /// see `UiConfig::advance_after_read`
+    pub advance_after_read: bool,
+    /// see `UiConfig::mark_read`
+    pub mark_read: crate::config::MarkRead,
+    /// see `UiConfig::date_format`
+    pub date_format: DateFormat,
+    /// see `UiConfig::entry_columns`
+    pub entry_columns: EntryColumns,
+    /// every feed's title, including hidden feeds, for entries lists with more than one feed
+    pub feed_titles: HashMap<crate::rss::FeedId, String>,
+    /// see `UiConfig::scrollbar`
+    pub scrollbar: bool,
+}
+
+/// the text of `html`, without its tags, for comparing
+fn html_text(html: &str) -> String {
+    scraper::Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The block above an entry's content, with what it is, who wrote it, when,
+/// and how long it takes to read. Times are in the local timezone.
+fn entry_header(
+    entry_meta: &crate::rss::EntryMeta,
+    feed_title: Option<&str>,
+    body: &str,
+    line_length: u16,
+) -> String {
+    let local_time = |time: &chrono::DateTime<chrono::Utc>| {
+        time.with_timezone(&chrono::Local)
+            .format("%a %-d %b %Y, %H:%M")
+            .to_string()
+    };
+
+    let mut lines = vec![];
+
+    if let Some(title) = &entry_meta.title {
+        lines.push(title.to_owned());
+    }
+
+    let byline = entry_meta
+        .author
+        .iter()
+        .map(|author| format!("by {}", author))
+        .chain(feed_title.map(|feed_title| format!("in {}", feed_title)))
+        .collect::<Vec<_>>();
+    if !byline.is_empty() {
+        lines.push(byline.join(" "));
+    }
+
+    let mut dates = vec![];
+    if let Some(pub_date) = &entry_meta.pub_date {
+        if entry_meta.is_future_dated() {
+            dates.push(format!(
+                "Published {}, after it was fetched {}",
+                local_time(pub_date),
+                local_time(&entry_meta.inserted_at)
+            ));
+        } else {
+            dates.push(format!("Published {}", local_time(pub_date)));
+        }
+    }
+    match entry_meta.modified_at {
+        // atom feeds always say when an entry was updated, even if it never was
+        Some(modified_at) if Some(modified_at) != entry_meta.pub_date => {
+            dates.push(format!("Updated {}", local_time(&modified_at)))
+        }
+        _ => (),
+    }
+    if dates.is_empty() {
+        dates.push(format!("Fetched {}", local_time(&entry_meta.inserted_at)));
+    }
+    lines.push(dates.join(" · "));
+
+    if !entry_meta.categories.is_empty() {
+        lines.push(format!("Categories: {}", entry_meta.categories.join(", ")));
+    }
+
+    if let Some(comments_link) = &entry_meta.comments_link {
+        lines.push(format!("Comments: {} (C to open)", comments_link));
+    }
+
+    let words_len = util::word_count(body);
+    lines.push(format!(
+        "~{} min read · {} words",
+        util::reading_minutes(words_len),
+        util::format_thousands(words_len)
+    ));
+
+    lines.push("─".repeat(line_length.into()));
+
+    format!("{}\n\n", lines.join("\n"))
 }
 
 impl AppImpl {
     pub fn new(
         options: crate::Options,
         event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
+        loader: std::sync::mpsc::Sender<crate::loader::Load>,
     ) -> Result<AppImpl> {
-        let mut conn = rusqlite::Connection::open(&options.database_path)?;
+        let database_path = options.database_path().to_owned();
+        let mut conn = crate::rss::open_db(&database_path, options.database_passphrase.as_deref())?;
 
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(options.network_timeout)
-            .build();
+        let http_client =
+            crate::http::client(options.network_timeout, options.user_agent.as_deref());
 
         crate::rss::initialize_db(&mut conn)?;
+        // header values set before they were kept in the keyring,
+        // which are moved when their feeds refresh anyway, if this can't
+        if let Err(e) = crate::rss::move_feed_headers_to_keyring(
+            &conn,
+            &crate::keyring::Keyring::open(&database_path),
+        ) {
+            log::warn!("unable to move feed headers to the keyring: {:?}", e);
+        }
         let feeds: util::StatefulList<crate::rss::Feed> = vec![].into();
         let entries: util::StatefulList<crate::rss::EntryMeta> = vec![].into();
         // default to having nothing selected,
@@ -217,87 +1104,537 @@ impl AppImpl {
 
         let mut app = AppImpl {
             conn,
+            database_path,
+            profile: options.profile,
+            database_passphrase: options.database_passphrase,
+            instance_lock: None,
             http_client,
             should_quit: false,
             error_flash: vec![],
+            error_log: std::collections::VecDeque::new(),
+            show_error_log: false,
+            stats: None,
+            queue: None,
+            queue_playing: false,
+            queue_now_playing: None,
+            queue_stopping: false,
+            speech: None,
+            refreshing: false,
+            refresh_cancelled: false,
+            enhanced_graphics: options.enhanced_graphics,
             feeds,
+            current_feed_entry_counts: (0, 0),
+            entries_generation: 0,
+            entries_loading: false,
+            feeds_generation: 0,
+            entry_generation: 0,
+            entry_loading: false,
+            loaded_entry: None,
+            entries_fetched: 0,
+            entries_exhausted: true,
+            loader,
             entries,
             selected,
             entry_scroll_position: 0,
             entry_lines_len: 0,
             entry_lines_rendered_len: 0,
             entry_column_width: 0,
+            layout: options.config.ui.layout,
+            feeds_pane_percent: options.config.ui.layout.default_feeds_pane_percent(),
+            feeds_pane_hidden: false,
             current_entry_meta: None,
             current_entry_text: String::new(),
+            entry_line_styles: HashMap::new(),
+            hyperlinks: options.config.ui.hyperlinks,
+            entry_links: vec![],
+            drawn_links: vec![],
+            drawn_entry_lines: vec![],
+            written_links: vec![],
+            content_field: ContentField::Content,
+            translation: None,
+            translation_shown: false,
+            highlighter: None,
+            images: options
+                .enhanced_graphics
+                .then(crate::images::Protocol::detect)
+                .flatten()
+                .map(crate::images::Images::new),
+            entry_images: vec![],
+            drawn_images: vec![],
+            written_images: vec![],
+            written_images_screen: tui::layout::Rect::default(),
             current_feed: initial_current_feed,
-            feed_subscription_input: String::new(),
+            feed_subscription_input: crate::input::LineInput::default(),
+            feed_preview: None,
             mode: Mode::Normal,
             read_mode: ReadMode::ShowUnread,
+            feed_sort: crate::rss::FeedSort::Manual,
             show_help: true,
             entry_selection_position: 0,
             flash: None,
+            search_input: String::new(),
+            note_input: String::new(),
+            feed_edit_input: String::new(),
+            pipe_input: String::new(),
+            share_input: String::new(),
+            last_pipe_command: String::new(),
+            feed_edit_field: FeedField::Title,
+            tag_input: String::new(),
+            command_input: String::new(),
+            finder: None,
+            jumps: crate::jumps::JumpList::default(),
+            pending_mark: None,
+            macros: crate::macros::Macros::default(),
+            pending_count: None,
+            command_history: crate::history::History::default(),
+            search_history: crate::history::History::default(),
+            entry_max_width: None,
+            tag_action: TagAction::Toggle,
+            tags: vec![],
+            entries_tag: None,
+            entries_date_range: crate::rss::DateRange::default(),
+            virtual_feed: None,
+            feeds_pane_state: tui::widgets::ListState::default(),
+            confirm_delete_feed: false,
+            visual_anchor: None,
+            undo_stack: vec![],
+            entries_filter: None,
+            entry_search: None,
+            entry_search_matches: vec![],
+            entry_search_match_position: 0,
             event_s,
             is_wsl,
+            hide_read_feeds: options.config.ui.hide_read_feeds,
+            advance_after_read: options.config.ui.advance_after_read,
+            mark_read: options.config.ui.mark_read,
+            date_format: options.config.ui.date_format.clone(),
+            entry_columns: options.config.ui.entry_columns.clone(),
+            feed_titles: HashMap::new(),
+            scrollbar: options.config.ui.scrollbar,
+            hidden_feeds_len: 0,
         };
 
-        app.update_feeds()?;
-        app.update_current_feed_and_entries()?;
+        app.load_database()?;
+
+        Ok(app)
+    }
+
+    fn load_database(&mut self) -> Result<()> {
+        self.command_history =
+            crate::history::History::new(crate::rss::get_history(&self.conn, "command")?);
+        self.search_history =
+            crate::history::History::new(crate::rss::get_history(&self.conn, "search")?);
+
+        self.update_feeds()?;
+        self.update_current_feed_and_entries()?;
 
         // we default to having Selected::None,
         // so if there are actually feeds, select them
-        if !app.feeds.items.is_empty() {
-            app.select_feeds()
+        if !self.feeds.items.is_empty() {
+            self.select_feeds()
         }
 
-        Ok(app)
+        self.restore_session()
     }
 
-    pub fn delete_feed(&mut self) -> Result<()> {
-        if matches!(self.selected, Selected::Feeds) && matches!(self.mode(), Mode::Editing) {
-            let feed_id = self.selected_feed_id();
-            crate::rss::delete_feed(&mut self.conn, feed_id)?;
-
-            // Remove the feed in app state
-            let feeds_len = self.feeds.items.len();
+    /// switch to another profile's database,
+    /// leaving this one as if russ had quit
+    fn switch_profile(&mut self, profile: String) -> Result<()> {
+        let profile = if profile == crate::DEFAULT_PROFILE {
+            None
+        } else {
+            Some(profile)
+        };
 
-            for i in 0..feeds_len {
-                if self.feeds.items[i].id == feed_id {
-                    self.feeds.items.remove(i);
+        let database_path = crate::profile_database_path(profile.as_deref())?;
 
-                    if i == feeds_len - 1 {
-                        self.feeds.previous();
-                    }
+        if database_path == self.database_path {
+            return Ok(());
+        }
 
-                    break;
-                }
+        // like starting russ with it, so requests go to the database they're for
+        let instance_lock = match &self.instance_lock {
+            Some(instance_lock) => {
+                Some(instance_lock.move_to(&database_path)?.ok_or_else(|| {
+                    anyhow::anyhow!("Russ is already running with {}", database_path.display())
+                })?)
             }
+            None => None,
+        };
 
-            // Remove the entries from the feed in app state
-            self.entries.items.retain(|entry| entry.feed_id != feed_id);
+        let mut conn = crate::rss::open_db(&database_path, self.database_passphrase.as_deref())?;
+        crate::rss::initialize_db(&mut conn)?;
 
-            // Update
-            self.update_current_feed_and_entries()?;
+        self.save_session()?;
+
+        if instance_lock.is_some() {
+            self.instance_lock = instance_lock;
         }
+        self.conn = conn;
+        self.database_path = database_path;
+        self.profile = profile;
+
+        // nothing from the old database makes sense in the new one
+        self.feeds = vec![].into();
+        self.entries = vec![].into();
+        self.current_feed = None;
+        self.current_entry_meta = None;
+        self.current_entry_text = String::new();
+        self.entry_scroll_position = 0;
+        self.entry_selection_position = 0;
+        self.selected = Selected::None;
+        self.read_mode = ReadMode::ShowUnread;
+        self.visual_anchor = None;
+        self.undo_stack.clear();
+        self.entries_tag = None;
+        self.entries_filter = None;
+        self.search_input.clear();
+        self.clear_entry_search();
+
+        self.load_database()?;
+
+        self.flash = Some(format!(
+            "Switched to profile {}",
+            self.profile.as_deref().unwrap_or(crate::DEFAULT_PROFILE)
+        ));
 
         Ok(())
     }
 
-    pub fn update_feeds(&mut self) -> Result<()> {
-        let feeds = crate::rss::get_feeds(&self.conn)?.into();
-        self.feeds = feeds;
-        Ok(())
+    pub fn database_path(&self) -> std::path::PathBuf {
+        self.database_path.clone()
     }
 
-    pub fn update_current_feed_and_entries(&mut self) -> Result<()> {
-        self.update_current_feed()?;
+    /// put the user back where they were when they last quit
+    fn restore_session(&mut self) -> Result<()> {
+        let session = match crate::rss::get_session(&self.conn)? {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+
+        self.read_mode = session.read_mode;
+        self.feed_sort = session.feed_sort;
+        self.refilter_feeds()?;
+        if let Some(feeds_pane_percent) = session.feeds_pane_percent {
+            self.resize_feeds_pane(feeds_pane_percent);
+        }
+        self.feeds_pane_hidden = session.feeds_pane_hidden;
+
+        let feed_idx = session
+            .feed_id
+            .and_then(|feed_id| self.feeds.items.iter().position(|feed| feed.id == feed_id));
+
+        // the feed may have been deleted since
+        if feed_idx.is_none() {
+            return self.update_current_feed_and_entries();
+        }
+
+        self.feeds.state.select(feed_idx);
+        self.update_current_feed_and_entries()?;
+
+        let entry_idx = match session.entry_id {
+            Some(entry_id) => self.find_listed_entry(entry_id)?,
+            None => None,
+        };
+
+        if let Some(entry_idx) = entry_idx {
+            self.entries.state.select(Some(entry_idx));
+            self.entry_selection_position = entry_idx;
+            self.selected = Selected::Entries;
+            self.update_current_entry_meta()?;
+
+            if session.entry_open {
+                self.on_enter()?;
+                self.entry_scroll_position = session.entry_scroll_position;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save_session(&self) -> Result<()> {
+        let entry_id = match &self.selected {
+            Selected::Entries => self.current_entry_meta.as_ref().map(|entry| entry.id),
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            _ => None,
+        };
+
+        crate::rss::save_session(
+            &self.conn,
+            &crate::rss::Session {
+                feed_id: self.current_feed.as_ref().map(|feed| feed.id),
+                entry_id,
+                entry_open: matches!(self.selected, Selected::Entry(_)),
+                read_mode: self.read_mode.clone(),
+                entry_scroll_position: self.entry_scroll_position,
+                feeds_pane_percent: Some(self.feeds_pane_percent),
+                feeds_pane_hidden: self.feeds_pane_hidden,
+                feed_sort: self.feed_sort,
+            },
+        )
+    }
+
+    /// deleting a feed takes two presses of `Del`, the first asks for confirmation
+    pub fn delete_feed(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Feeds) && matches!(self.mode(), Mode::Editing) {
+            if !self.confirm_delete_feed {
+                self.confirm_delete_feed = true;
+                return Ok(());
+            }
+
+            self.confirm_delete_feed = false;
+
+            self.delete_current_feed()?;
+        }
+
+        Ok(())
+    }
+
+    fn delete_current_feed(&mut self) -> Result<()> {
+        let feed_id = match self.selected_feed_id() {
+            Some(feed_id) => feed_id,
+            None => return Ok(()),
+        };
+        let deleted_feed = crate::rss::delete_feed(&mut self.conn, feed_id)?;
+        self.jumps.forget_feed(feed_id);
+        self.push_undo(UndoAction::DeleteFeed(deleted_feed));
+
+        // Remove the feed in app state
+        let feeds_len = self.feeds.items.len();
+
+        for i in 0..feeds_len {
+            if self.feeds.items[i].id == feed_id {
+                self.feeds.items.remove(i);
+
+                if i == feeds_len - 1 {
+                    self.feeds.previous();
+                }
+
+                break;
+            }
+        }
+
+        // Remove the entries from the feed in app state
+        self.entries.items.retain(|entry| entry.feed_id != feed_id);
+
+        // Update
+        self.update_current_feed_and_entries()?;
+
+        Ok(())
+    }
+
+    pub fn cancel_delete_feed(&mut self) {
+        self.confirm_delete_feed = false;
+    }
+
+    fn push_undo(&mut self, undo_action: UndoAction) {
+        if self.undo_stack.len() >= UNDO_STACK_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(undo_action);
+    }
+
+    pub fn undo(&mut self) -> Result<()> {
+        match self.undo_stack.pop() {
+            Some(UndoAction::SetFlag(entry_flag, entries_flag)) => {
+                crate::rss::set_entries_flag(&mut self.conn, entry_flag, &entries_flag)?;
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+            }
+            Some(UndoAction::DeleteFeed(deleted_feed)) => {
+                crate::rss::restore_feed(&mut self.conn, &deleted_feed)?;
+                self.update_feeds()?;
+
+                let restored_feed_idx = self
+                    .feeds
+                    .items
+                    .iter()
+                    .position(|feed| feed.id == deleted_feed.feed_id);
+                self.feeds.state.select(restored_feed_idx);
+
+                self.selected = Selected::Feeds;
+                self.update_current_feed_and_entries()?;
+            }
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    pub fn mark_feed_as_read(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Feeds | Selected::Entries) {
+            if let Some(current_feed) = &self.current_feed {
+                let entry_ids =
+                    crate::rss::mark_feed_as_read(&mut self.conn, current_feed.id, None)?;
+
+                self.push_undo(UndoAction::SetFlag(
+                    crate::rss::EntryFlag::Read,
+                    entry_ids
+                        .into_iter()
+                        .map(|entry_id| (entry_id, None))
+                        .collect(),
+                ));
+
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+                self.update_entry_selection_position();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mark the entries older than `older_than` in the feeds as read, or all of them,
+    /// for catching up after being away without losing the last few days.
+    fn mark_feeds_as_read(
+        &mut self,
+        feed_ids: Vec<crate::rss::FeedId>,
+        older_than: Option<chrono::Duration>,
+    ) -> Result<()> {
+        let before = older_than.map(|older_than| chrono::Utc::now() - older_than);
+        let mut entry_ids = vec![];
+
+        for feed_id in feed_ids {
+            entry_ids.extend(crate::rss::mark_feed_as_read(
+                &mut self.conn,
+                feed_id,
+                before,
+            )?);
+        }
+
+        if let Some(older_than) = older_than {
+            self.flash = Some(format!(
+                "Marked {} {} older than {} read",
+                entry_ids.len(),
+                if entry_ids.len() == 1 {
+                    "entry"
+                } else {
+                    "entries"
+                },
+                util::format_interval(older_than)
+            ));
+        }
+
+        self.push_undo(UndoAction::SetFlag(
+            crate::rss::EntryFlag::Read,
+            entry_ids
+                .into_iter()
+                .map(|entry_id| (entry_id, None))
+                .collect(),
+        ));
+
+        self.update_current_entries()?;
+        self.update_current_entry_meta()?;
+        self.update_entry_selection_position();
+
+        Ok(())
+    }
+
+    pub fn update_feeds(&mut self) -> Result<()> {
+        let loaded = self.feeds_query().run(&self.conn)?;
+        self.set_feeds(loaded);
+        Ok(())
+    }
+
+    fn feeds_query(&self) -> crate::loader::FeedsQuery {
+        crate::loader::FeedsQuery {
+            feed_sort: self.feed_sort,
+            hide_read_feeds: self.hide_read_feeds && matches!(self.read_mode, ReadMode::ShowUnread),
+        }
+    }
+
+    fn set_feeds(&mut self, loaded: crate::loader::LoadedFeeds) {
+        let mut feeds = loaded.feeds;
+
+        self.feed_titles = feeds
+            .iter()
+            .flat_map(|feed| feed.title.clone().map(|title| (feed.id, title)))
+            .collect();
+
+        if let Some(unread_feed_ids) = loaded.unread_feed_ids {
+            let current_feed_id = self.current_feed.as_ref().map(|feed| feed.id);
+
+            // the current feed stays, so reading its last entry doesn't pull it out from under you
+            let feeds_len = feeds.len();
+            feeds.retain(|feed| {
+                unread_feed_ids.contains(&feed.id) || Some(feed.id) == current_feed_id
+            });
+            self.hidden_feeds_len = feeds_len - feeds.len();
+        } else {
+            self.hidden_feeds_len = 0;
+        }
+
+        self.feeds = feeds.into();
+    }
+
+    /// reload the feeds list, for when which feeds are hidden may have changed,
+    /// keeping the current feed selected
+    pub fn refilter_feeds(&mut self) -> Result<()> {
+        self.update_feeds()?;
+        self.reselect_current_feed()
+    }
+
+    /// Like `refilter_feeds`, and then loading the current feed's entries again,
+    /// but on the loader thread, for after refreshing, which can change them all.
+    pub fn load_feeds(&mut self) -> Result<()> {
+        self.feeds_generation += 1;
+
+        let load = crate::loader::Load {
+            generation: self.feeds_generation,
+            database_path: self.database_path.clone(),
+            database_passphrase: self.database_passphrase.clone(),
+            request: crate::loader::LoadRequest::Feeds(self.feeds_query()),
+        };
+
+        if self.loader.send(load).is_err() {
+            self.refilter_feeds()?;
+            self.update_current_feed_and_entries()?;
+        }
+
+        Ok(())
+    }
+
+    /// select the current feed in the reloaded feeds list, if it's still there
+    fn reselect_current_feed(&mut self) -> Result<()> {
+        let current_feed_id = self.current_feed.as_ref().map(|feed| feed.id);
+
+        let feed_idx = self
+            .feeds
+            .items
+            .iter()
+            .position(|feed| Some(feed.id) == current_feed_id);
+
+        if feed_idx.is_some() {
+            self.feeds.state.select(feed_idx);
+        } else {
+            self.update_current_feed_and_entries()?;
+        }
+
+        // every feed may have been hidden, and now some are back
+        if matches!(self.selected, Selected::None) && !self.feeds.items.is_empty() {
+            self.select_feeds();
+            self.update_current_feed_and_entries()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update_current_feed_and_entries(&mut self) -> Result<()> {
+        self.update_current_feed()?;
         self.update_current_entries()?;
         Ok(())
     }
 
     fn update_current_feed(&mut self) -> Result<()> {
+        if self.feeds.state.selected().is_some() {
+            self.virtual_feed = None;
+        }
+
         self.current_feed = if self.feeds.items.is_empty() {
             self.selected = Selected::None;
             None
+        } else if self.virtual_feed.is_some() {
+            None
         } else {
             let selected_idx = match self.feeds.state.selected() {
                 Some(idx) => idx,
@@ -310,20 +1647,182 @@ impl AppImpl {
             Some(crate::rss::get_feed(&self.conn, feed_id)?)
         };
 
+        // the statistics follow the selected feed
+        if let Some(stats) = &mut self.stats {
+            stats.feed_activity = self
+                .current_feed
+                .as_ref()
+                .map(|feed| crate::stats::FeedActivity::load(&self.conn, feed))
+                .transpose()?;
+        }
+
+        Ok(())
+    }
+
+    /// re-read the current feed from the database after changing it,
+    /// keeping the feeds list in sync without losing the selection
+    fn reload_current_feed(&mut self) -> Result<()> {
+        self.update_current_feed()?;
+
+        if let (Some(selected_idx), Some(current_feed)) =
+            (self.feeds.state.selected(), &self.current_feed)
+        {
+            self.feeds.items[selected_idx] = current_feed.clone();
+        }
+
         Ok(())
     }
 
+    fn entries_query(&self) -> crate::loader::EntriesQuery {
+        crate::loader::EntriesQuery {
+            read_mode: self.read_mode.clone(),
+            tag: self.entries_tag.clone(),
+            virtual_feed: self.virtual_feed,
+            feed: self
+                .current_feed
+                .as_ref()
+                .map(|feed| (feed.id, feed.entry_sort)),
+            filter: crate::rss::EntriesFilter {
+                date_range: self.entries_date_range,
+                title: self.entries_filter.clone(),
+            },
+            // enough to keep the selection where it is
+            limit: (self.entry_selection_position / crate::loader::PAGE_SIZE + 1)
+                * crate::loader::PAGE_SIZE,
+        }
+    }
+
     fn update_current_entries(&mut self) -> Result<()> {
-        let entries = if let Some(feed) = &self.current_feed {
-            crate::rss::get_entries_metas(&self.conn, &self.read_mode, feed.id)?
-                .into_iter()
-                .collect::<Vec<_>>()
-                .into()
-        } else {
-            vec![].into()
+        // anything the loader thread is still loading is out of date now
+        self.entries_generation += 1;
+        self.entries_loading = false;
+
+        let loaded = self.entries_query().run(&self.conn)?;
+        self.set_entries(loaded);
+        Ok(())
+    }
+
+    /// Like `update_current_entries`, but on the loader thread,
+    /// for moving between feeds without waiting on their entries.
+    /// Until they're loaded, the last feed's entries are still listed.
+    fn load_current_entries(&mut self) -> Result<()> {
+        self.entries_generation += 1;
+        self.entries_loading = true;
+
+        let load = crate::loader::Load {
+            generation: self.entries_generation,
+            database_path: self.database_path.clone(),
+            database_passphrase: self.database_passphrase.clone(),
+            request: crate::loader::LoadRequest::Entries(self.entries_query()),
+        };
+
+        if self.loader.send(load).is_err() {
+            // the loader thread is gone, so there's nothing for it but to wait
+            self.update_current_entries()?;
+        }
+
+        Ok(())
+    }
+
+    /// where the selection is in the feeds pane, which lists the virtual feeds before the feeds
+    pub fn feeds_pane_position(&self) -> Option<usize> {
+        match self.virtual_feed {
+            Some(virtual_feed) => VirtualFeed::ALL
+                .iter()
+                .position(|other| *other == virtual_feed),
+            None => Some(VirtualFeed::ALL.len() + self.feeds.state.selected()?),
+        }
+    }
+
+    fn feeds_pane_len(&self) -> usize {
+        VirtualFeed::ALL.len() + self.feeds.items.len()
+    }
+
+    /// select the virtual feed or feed at `position` in the feeds pane, and load its entries
+    fn select_feeds_pane_position(&mut self, position: usize) -> Result<()> {
+        match VirtualFeed::ALL.get(position) {
+            Some(virtual_feed) => {
+                self.virtual_feed = Some(*virtual_feed);
+                self.feeds.unselect();
+            }
+            None => {
+                self.virtual_feed = None;
+                self.feeds
+                    .state
+                    .select(Some(position - VirtualFeed::ALL.len()));
+            }
+        }
+
+        self.select_feed_and_load_entries()
+    }
+
+    fn select_feed_and_load_entries(&mut self) -> Result<()> {
+        self.update_current_feed()?;
+        self.load_current_entries()
+    }
+
+    /// Add the next page of entries to the end of the list,
+    /// leaving out any that are already listed, as entries added since shift the pages along.
+    fn load_more_entries(&mut self) -> Result<()> {
+        if self.entries_exhausted {
+            return Ok(());
+        }
+
+        let page = crate::rss::Page {
+            offset: self.entries_fetched,
+            limit: crate::loader::PAGE_SIZE,
         };
+        let entries = self.entries_query().entries(&self.conn, page)?;
+        self.entries_fetched += entries.len();
+        self.entries_exhausted = entries.len() < page.limit;
+
+        let listed = self
+            .entries
+            .items
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<std::collections::HashSet<_>>();
+        let entries = entries
+            .into_iter()
+            .filter(|entry| !listed.contains(&entry.id));
+        self.entries.items.extend(entries);
+
+        Ok(())
+    }
+
+    /// where `entry_id` is in the entries list, loading more of it until it's found
+    fn find_listed_entry(&mut self, entry_id: crate::rss::EntryId) -> Result<Option<usize>> {
+        loop {
+            let position = self
+                .entries
+                .items
+                .iter()
+                .position(|entry| entry.id == entry_id);
+
+            if position.is_some() || self.entries_exhausted {
+                return Ok(position);
+            }
+
+            self.load_more_entries()?;
+        }
+    }
+
+    /// Load the current feed's entries here and now if the loader thread hasn't yet,
+    /// before going into them, as they may still be the last feed's.
+    fn wait_for_entries(&mut self) -> Result<()> {
+        if self.entries_loading {
+            self.update_current_entries()?;
+        }
+
+        Ok(())
+    }
 
-        self.entries = entries;
+    fn set_entries(&mut self, loaded: crate::loader::LoadedEntries) {
+        self.entries_fetched = loaded.entries.len();
+        self.entries_exhausted = loaded.exhausted;
+        self.entries = loaded.entries.into();
+
+        self.current_feed_entry_counts = loaded.counts;
 
         if self.entry_selection_position < self.entries.items.len() {
             self.entries
@@ -335,7 +1834,6 @@ impl AppImpl {
                 None => self.entries.reset(),
             }
         }
-        Ok(())
     }
 
     fn update_entry_selection_position(&mut self) {
@@ -367,13 +1865,39 @@ impl AppImpl {
     }
 
     fn update_current_entry_meta(&mut self) -> Result<()> {
+        if let Some(selected_idx) = self.entries.state.selected() {
+            if selected_idx + crate::loader::LOAD_MORE_MARGIN >= self.entries.items.len() {
+                self.load_more_entries()?;
+            }
+        }
+
         if let Some(entry_meta) = self.get_selected_entry_meta() {
             let entry_meta = entry_meta?;
+            let is_new_entry = !matches!(
+                &self.current_entry_meta,
+                Some(current_entry_meta) if current_entry_meta.id == entry_meta.id
+            );
+
             self.current_entry_meta = Some(entry_meta);
+
+            // only rerender the preview when the selection has moved to another entry
+            if self.layout == PaneLayout::ThreePane
+                && is_new_entry
+                && !matches!(self.selected, Selected::Entry(_))
+            {
+                self.entry_scroll_position = 0;
+                self.load_selected_entry(false)?;
+            }
         }
         Ok(())
     }
 
+    /// the furthest the open entry can scroll, with its last line at the bottom
+    fn max_entry_scroll_position(&self) -> u16 {
+        let entry_lines_len = u16::try_from(self.entry_lines_len).unwrap_or(u16::MAX);
+        entry_lines_len.saturating_sub(self.entry_lines_rendered_len)
+    }
+
     fn page_up(&mut self) {
         if matches!(self.selected, Selected::Entry(_)) {
             self.entry_scroll_position = if let Some(position) = self
@@ -387,135 +1911,2265 @@ impl AppImpl {
         };
     }
 
-    fn page_down(&mut self) {
-        if matches!(self.selected, Selected::Entry(_)) {
-            self.entry_scroll_position = if self.entry_scroll_position
-                + self.entry_lines_rendered_len
-                >= self.entry_lines_len as u16
-            {
-                self.entry_lines_len as u16
-            } else {
-                self.entry_scroll_position + self.entry_lines_rendered_len
-            };
+    fn page_down(&mut self) {
+        if matches!(self.selected, Selected::Entry(_)) {
+            self.entry_scroll_position = self
+                .entry_scroll_position
+                .saturating_add(self.entry_lines_rendered_len)
+                .min(self.max_entry_scroll_position());
+        }
+    }
+
+    /// open the selected entry, remembering where it was opened from, for `ctrl-o`
+    pub fn open_entry(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Entries) && self.current_entry_meta.is_some() {
+            self.push_jump();
+            return self.load_selected_entry(true);
+        }
+
+        self.on_enter()
+    }
+
+    /// Read the selected entry on the loader thread, then open it, or preview it if not `open`.
+    /// Until it's read, the last entry is still shown.
+    fn load_selected_entry(&mut self, open: bool) -> Result<()> {
+        let entry_id = match &self.current_entry_meta {
+            Some(entry_meta) if !self.entries.items.is_empty() => entry_meta.id,
+            _ => return Ok(()),
+        };
+
+        self.entry_generation += 1;
+        self.entry_loading = true;
+
+        let load = crate::loader::Load {
+            generation: self.entry_generation,
+            database_path: self.database_path.clone(),
+            database_passphrase: self.database_passphrase.clone(),
+            request: crate::loader::LoadRequest::Entry { entry_id, open },
+        };
+
+        if self.loader.send(load).is_err() {
+            // the loader thread is gone, so it's read here
+            self.entry_loading = false;
+            if open {
+                self.selected = Selected::Entries;
+                self.entry_scroll_position = 0;
+                return self.on_enter();
+            }
+            return self.render_current_entry();
+        }
+
+        Ok(())
+    }
+
+    pub fn on_enter(&mut self) -> Result<()> {
+        match self.selected {
+            Selected::Entries | Selected::Entry(_) => {
+                if !self.entries.items.is_empty() {
+                    if let Some(entry_meta) = self.current_entry_meta.clone() {
+                        self.render_current_entry()?;
+
+                        // opening a new entry starts a new search,
+                        // but rewrapping the open entry keeps the current one
+                        if matches!(self.selected, Selected::Entries) {
+                            self.clear_entry_search();
+                            self.visual_anchor = None;
+
+                            if self.mark_read == crate::config::MarkRead::OnOpen {
+                                if let Some(idx) = self.entries.state.selected() {
+                                    self.mark_listed_entries_read(idx..idx + 1)?;
+                                }
+                            }
+                        }
+                        self.find_entry_search_matches();
+
+                        let entry_meta = self.current_entry_meta.clone().unwrap_or(entry_meta);
+                        self.selected = Selected::Entry(Box::new(entry_meta));
+                    }
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// wrap the current entry's text to the entry column,
+    /// whether it is open or previewed in the three-pane layout
+    pub fn render_current_entry(&mut self) -> Result<()> {
+        let entry_meta = match &self.current_entry_meta {
+            Some(entry_meta) if !self.entries.items.is_empty() => entry_meta.clone(),
+            _ => return Ok(()),
+        };
+
+        // what the loader thread read, if it's what's being rendered, or read here if not
+        let loaded_entry = match self.loaded_entry.take() {
+            Some(loaded_entry) if loaded_entry.entry_id == entry_meta.id => Some(loaded_entry),
+            _ => match self.get_selected_entry() {
+                Some(entry) => Some(crate::loader::LoadedEntry {
+                    entry_id: entry_meta.id,
+                    content: entry?,
+                    note: crate::rss::get_entry_note(&self.conn, entry_meta.id)?,
+                }),
+                None => None,
+            },
+        };
+
+        if let Some(loaded_entry) = loaded_entry {
+            let html = match &self.translation {
+                Some((entry_id, translation))
+                    if self.translation_shown && *entry_id == entry_meta.id =>
+                {
+                    translation.clone()
+                }
+                _ => self.entry_html(&loaded_entry.content),
+            };
+
+            // minimum is 1
+            let line_length = if self.entry_column_width >= 5 {
+                self.entry_column_width - 4
+            } else {
+                1
+            };
+
+            let line_length = match self.entry_max_width {
+                Some(entry_max_width) => line_length.min(entry_max_width),
+                None => line_length,
+            };
+
+            let (html, code_blocks) = crate::code::extract(&html);
+            let (html, entry_images) = match &self.images {
+                Some(_) => crate::images::extract(&html, entry_meta.link.as_deref()),
+                None => (html, vec![]),
+            };
+            let body = html2text::from_read(html.as_bytes(), line_length.into());
+            let (body, code_regions) = crate::code::splice(&body, &code_blocks);
+            let (body, body_styles) = crate::markup::decorate(&body, &code_regions);
+
+            let feed_title = self
+                .current_feed
+                .as_ref()
+                .and_then(|feed| feed.title.as_deref());
+            let mut text = entry_header(&entry_meta, feed_title, &body, line_length);
+            let header_lines_len = text.matches('\n').count();
+            text.push_str(&body);
+
+            // show the note below the content,
+            // so it can be searched along with it
+            if let Some(note) = &loaded_entry.note {
+                text.push_str("\n--- Note ---\n");
+                text.push_str(note);
+                text.push('\n');
+            }
+
+            // html2text wraps the body, but not the header, the code, or the note,
+            // and lines have to be wrapped here rather than by the terminal
+            // for scrolling and search to know which line is which
+            let mut lines = vec![];
+            // where each line starts once it's wrapped, to find the code in the wrapped lines
+            let mut line_starts = vec![];
+            let mut image_regions = vec![];
+            for line in text.split('\n') {
+                line_starts.push(lines.len());
+
+                let (line, image_idxs) = crate::images::take_placeholders(line);
+                if image_idxs.is_empty() || !line.trim().is_empty() {
+                    lines.extend(
+                        util::wrap_to_width(&line, line_length.into())
+                            .split('\n')
+                            .map(str::to_owned),
+                    );
+                }
+
+                // an image takes up blank lines, to be drawn over,
+                // and until it's fetched, its alt text stands in for it
+                for entry_image in image_idxs.iter().filter_map(|i| entry_images.get(*i)) {
+                    let size = self.images.as_mut().and_then(|images| {
+                        images.size_in_cells(
+                            &entry_image.url,
+                            line_length,
+                            self.entry_lines_rendered_len,
+                        )
+                    });
+
+                    match size {
+                        Some((cols, rows)) => {
+                            image_regions.push(crate::images::ImageRegion {
+                                line: lines.len(),
+                                cols,
+                                rows,
+                                url: entry_image.url.clone(),
+                            });
+                            lines.resize(lines.len() + rows as usize, String::new());
+                        }
+                        None => {
+                            let alt = match entry_image.alt.as_str() {
+                                "" => "[image]".to_string(),
+                                alt => format!("[image: {}]", alt),
+                            };
+                            lines.extend(
+                                util::wrap_to_width(&alt, line_length.into())
+                                    .split('\n')
+                                    .map(str::to_owned),
+                            );
+                        }
+                    }
+                }
+            }
+            line_starts.push(lines.len());
+            self.entry_images = image_regions;
+
+            // a style for a whole line covers every line it wraps to
+            let mut line_styles = HashMap::new();
+            for (line_idx, style) in body_styles {
+                let line_idx = header_lines_len + line_idx;
+                let wrapped_lines = line_starts[line_idx]..line_starts[line_idx + 1];
+                for (wrapped_idx, line) in wrapped_lines.clone().zip(&lines[wrapped_lines]) {
+                    line_styles.insert(wrapped_idx, vec![(style, 0..line.len())]);
+                }
+            }
+
+            if !code_regions.is_empty() {
+                let highlighter = self
+                    .highlighter
+                    .get_or_insert_with(crate::code::Highlighter::new);
+
+                line_styles.extend(code_regions.iter().flat_map(|region| {
+                    let wrapped_lines = line_starts[header_lines_len + region.lines.start]
+                        ..line_starts[header_lines_len + region.lines.end];
+                    let region_lines = lines[wrapped_lines.clone()]
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>();
+
+                    highlighter
+                        .highlight(
+                            &code_blocks[region.block_idx],
+                            &region.prefix,
+                            &region_lines,
+                        )
+                        .map(|styles| wrapped_lines.zip(styles).collect::<Vec<_>>())
+                        .unwrap_or_default()
+                }));
+            }
+
+            self.entry_line_styles = line_styles;
+
+            if self.hyperlinks {
+                let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+                // the title is only the first line if there is a title
+                let entry_link = entry_meta.title.as_ref().and(entry_meta.link.as_deref());
+                self.entry_links = crate::hyperlink::find_links(&lines, entry_link);
+            }
+
+            let text = lines.join("\n");
+
+            self.entry_lines_len = text.matches('\n').count();
+            self.current_entry_text = text;
+
+            // rewrapping to a wider column can leave
+            // the old scroll position past the end
+            self.entry_scroll_position = self
+                .entry_scroll_position
+                .min(self.max_entry_scroll_position());
+        }
+
+        Ok(())
+    }
+
+    /// Whichever of the entry's content and description `content_field` says,
+    /// or both, labeled, when they say different things.
+    fn entry_html(&self, entry: &crate::rss::EntryContent) -> String {
+        // the extracted full content comes before the content tag
+        let content = entry.full_content.as_ref().or(entry.content.as_ref());
+
+        match (content, entry.description.as_ref()) {
+            (Some(content), Some(description))
+                if util::differ_significantly(&html_text(content), &html_text(description)) =>
+            {
+                let (first, second) = match self.content_field {
+                    ContentField::Content => (("Content", content), ("Description", description)),
+                    ContentField::Description => {
+                        (("Description", description), ("Content", content))
+                    }
+                };
+
+                Some(format!(
+                    "<p>--- {} ---</p><div>{}</div><p>--- {} ---</p><div>{}</div>",
+                    first.0, first.1, second.0, second.1
+                ))
+            }
+            (Some(content), Some(description)) => Some(match self.content_field {
+                ContentField::Content => content.to_owned(),
+                ContentField::Description => description.to_owned(),
+            }),
+            (content, description) => content.or(description).cloned(),
+        }
+        .unwrap_or_else(|| "No content or description tag provided.".to_string())
+    }
+
+    /// The open entry for `View`: its text as it is shown, and its HTML.
+    pub fn entry_for_viewer(&self) -> Result<(String, String)> {
+        if !matches!(self.selected, Selected::Entry(_)) {
+            return Err(anyhow::anyhow!("Open an entry to view it"));
+        }
+
+        let entry = self
+            .get_selected_entry()
+            .ok_or_else(|| anyhow::anyhow!("Open an entry to view it"))??;
+
+        Ok((self.current_entry_text.clone(), self.entry_html(&entry)))
+    }
+
+    fn start_search(&mut self) {
+        self.search_history.reset();
+
+        match self.selected {
+            Selected::Entries => {
+                self.search_input = self.entries_filter.clone().unwrap_or_default();
+                self.mode = Mode::Search;
+            }
+            Selected::Entry(_) => {
+                self.search_input = self.entry_search.clone().unwrap_or_default();
+                self.mode = Mode::Search;
+            }
+            _ => (),
+        }
+    }
+
+    fn update_search(&mut self) -> Result<()> {
+        match self.selected {
+            Selected::Entry(_) => {
+                self.update_entry_search();
+                Ok(())
+            }
+            _ => self.update_entries_filter(),
+        }
+    }
+
+    /// narrow the entries list to the titles matching what has been typed so far
+    fn update_entries_filter(&mut self) -> Result<()> {
+        self.entries_filter = if self.search_input.is_empty() {
+            None
+        } else {
+            Some(self.search_input.clone())
+        };
+
+        self.entry_selection_position = 0;
+        self.update_current_entries()?;
+        self.update_current_entry_meta()
+    }
+
+    /// find the lines of the current entry matching what has been typed so far,
+    /// and scroll to the first one
+    fn update_entry_search(&mut self) {
+        self.entry_search = if self.search_input.is_empty() {
+            None
+        } else {
+            Some(self.search_input.clone())
+        };
+
+        self.find_entry_search_matches();
+
+        if let Some(line) = self.entry_search_matches.first() {
+            self.entry_scroll_position = (*line).min(self.max_entry_scroll_position());
+        }
+    }
+
+    fn find_entry_search_matches(&mut self) {
+        self.entry_search_match_position = 0;
+        self.entry_search_matches = match &self.entry_search {
+            Some(entry_search) => {
+                let entry_search = entry_search.to_lowercase();
+                self.current_entry_text
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| line.to_lowercase().contains(&entry_search))
+                    .map(|(i, _)| i as u16)
+                    .collect()
+            }
+            None => vec![],
+        };
+    }
+
+    fn clear_entry_search(&mut self) {
+        self.entry_search = None;
+        self.entry_search_matches.clear();
+        self.entry_search_match_position = 0;
+    }
+
+    pub fn pop_search_input(&mut self) -> Result<()> {
+        if self.search_history.search().is_some() {
+            self.search_history.pop_search(&mut self.search_input);
+        } else {
+            self.search_input.pop();
+        }
+        self.update_search()
+    }
+
+    pub fn previous_search(&mut self) -> Result<()> {
+        self.search_history.previous(&mut self.search_input);
+        self.update_search()
+    }
+
+    pub fn next_search(&mut self) -> Result<()> {
+        self.search_history.next(&mut self.search_input);
+        self.update_search()
+    }
+
+    /// `ctrl-r`: search the previous searches
+    pub fn search_past_searches(&mut self) -> Result<()> {
+        self.search_history.search_older(&mut self.search_input);
+        self.update_search()
+    }
+
+    pub fn confirm_search(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        if self.search_history.push(&self.search_input) {
+            crate::rss::add_to_history(&self.conn, "search", &self.search_input)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_search(&mut self) -> Result<()> {
+        // esc while searching the history goes back to what was typed
+        if self.search_history.search().is_some() {
+            self.search_history.cancel_search(&mut self.search_input);
+            return self.update_search();
+        }
+
+        self.mode = Mode::Normal;
+        self.search_input.clear();
+        self.update_search()
+    }
+
+    pub fn search_is_empty(&self) -> bool {
+        self.entries_filter.is_none() && self.entry_search.is_none()
+    }
+
+    fn next_search_match(&mut self) -> Result<()> {
+        match self.selected {
+            Selected::Entries if self.entries_filter.is_some() => self.on_down()?,
+            Selected::Entry(_) if !self.entry_search_matches.is_empty() => {
+                self.entry_search_match_position =
+                    (self.entry_search_match_position + 1) % self.entry_search_matches.len();
+                self.entry_scroll_position = self.entry_search_matches
+                    [self.entry_search_match_position]
+                    .min(self.max_entry_scroll_position());
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// whether `n` and `N` go through search matches
+    fn search_is_active(&self) -> bool {
+        match self.selected {
+            Selected::Entries => self.entries_filter.is_some(),
+            Selected::Entry(_) => self.entry_search.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Select the next unread entry in the current feed, wrapping around to the first.
+    /// If an entry is open, open the next unread one instead.
+    fn next_unread_entry(&mut self) -> Result<()> {
+        self.push_jump();
+        self.select_next_unread_entry()
+    }
+
+    fn select_next_unread_entry(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::None) {
+            return Ok(());
+        }
+
+        self.wait_for_entries()?;
+
+        let start = match self.selected {
+            Selected::Feeds => 0,
+            _ => self.entries.state.selected().map_or(0, |idx| idx + 1),
+        };
+        let entries_len = self.entries.items.len();
+
+        let next_unread_idx = (0..entries_len)
+            .map(|offset| (start + offset) % entries_len)
+            .find(|idx| self.entries.items[*idx].read_at.is_none());
+
+        if let Some(idx) = next_unread_idx {
+            self.select_entry(idx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Go to the next feed with unread entries, wrapping around to the first,
+    /// and select its first unread entry.
+    fn next_unread_feed(&mut self) -> Result<()> {
+        if self.feeds.items.is_empty() {
+            return Ok(());
+        }
+
+        self.push_jump();
+
+        let unread_feed_ids = crate::rss::get_unread_feed_ids(&self.conn)?;
+        let start = self.feeds.state.selected().map_or(0, |idx| idx + 1);
+        let feeds_len = self.feeds.items.len();
+
+        let next_unread_idx = (0..feeds_len)
+            .map(|offset| (start + offset) % feeds_len)
+            .find(|idx| unread_feed_ids.contains(&self.feeds.items[*idx].id));
+
+        let feed_idx = match next_unread_idx {
+            Some(feed_idx) => feed_idx,
+            None => return Ok(()),
+        };
+
+        self.feeds.state.select(Some(feed_idx));
+        self.selected = Selected::Feeds;
+        self.visual_anchor = None;
+        self.entries_tag = None;
+        self.entries_filter = None;
+        self.search_input.clear();
+        self.clear_entry_search();
+        self.entry_scroll_position = 0;
+        self.entry_selection_position = 0;
+        self.current_entry_text = String::new();
+        self.update_current_feed_and_entries()?;
+
+        self.select_next_unread_entry()
+    }
+
+    /// select the entry at `idx`, opening it if an entry is open
+    fn select_entry(&mut self, idx: usize) -> Result<()> {
+        let entry_is_open = matches!(self.selected, Selected::Entry(_));
+
+        self.entries.state.select(Some(idx));
+        self.entry_selection_position = idx;
+
+        // the open entry stays open until the next one is read
+        if entry_is_open {
+            self.update_current_entry_meta()?;
+            return self.load_selected_entry(true);
+        }
+
+        self.selected = Selected::Entries;
+        self.update_current_entry_meta()
+    }
+
+    fn previous_search_match(&mut self) -> Result<()> {
+        match self.selected {
+            Selected::Entries if self.entries_filter.is_some() => self.on_up()?,
+            Selected::Entry(_) if !self.entry_search_matches.is_empty() => {
+                self.entry_search_match_position = self
+                    .entry_search_match_position
+                    .checked_sub(1)
+                    .unwrap_or(self.entry_search_matches.len() - 1);
+                self.entry_scroll_position = self.entry_search_matches
+                    [self.entry_search_match_position]
+                    .min(self.max_entry_scroll_position());
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_fetch_full_content(&mut self) -> Result<()> {
+        if let (Selected::Feeds, Some(feed_id)) = (&self.selected, self.selected_feed_id()) {
+            crate::rss::toggle_feed_fetch_full_content(&self.conn, feed_id)?;
+            self.reload_current_feed()?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the selected feed `by` places down the feeds list, or up, if it's negative,
+    /// staying among the pinned feeds if it's pinned, and among the rest if it isn't.
+    pub fn move_feed_by(&mut self, by: isize) -> Result<()> {
+        let mut feed_idx = match (&self.selected, self.feeds.state.selected()) {
+            (Selected::Feeds, Some(feed_idx)) => feed_idx,
+            _ => return Ok(()),
+        };
+        if self.feed_sort != crate::rss::FeedSort::Manual {
+            self.flash = Some(format!(
+                "Feeds are sorted by {}, press L for your order to move them",
+                self.feed_sort.describe()
+            ));
+            return Ok(());
+        }
+
+        let pinned = self.feeds.items[feed_idx].pinned;
+
+        for _ in 0..by.unsigned_abs() {
+            let next_idx = if by < 0 {
+                feed_idx.checked_sub(1)
+            } else {
+                Some(feed_idx + 1)
+            };
+
+            match next_idx.filter(|next_idx| {
+                matches!(self.feeds.items.get(*next_idx), Some(feed) if feed.pinned == pinned)
+            }) {
+                Some(next_idx) => {
+                    crate::rss::swap_feeds(
+                        &mut self.conn,
+                        self.feeds.items[feed_idx].id,
+                        self.feeds.items[next_idx].id,
+                    )?;
+                    self.feeds.items.swap(feed_idx, next_idx);
+                    feed_idx = next_idx;
+                }
+                None => break,
+            }
+        }
+
+        self.feeds.state.select(Some(feed_idx));
+        Ok(())
+    }
+
+    pub fn cycle_feed_sort(&mut self) -> Result<()> {
+        self.feed_sort = self.feed_sort.next();
+        self.load_feeds()?;
+        self.flash = Some(format!("Sorting feeds by {}", self.feed_sort.describe()));
+        Ok(())
+    }
+
+    pub fn toggle_feed_pinned(&mut self) -> Result<()> {
+        if let (Selected::Feeds, Some(feed_id)) = (&self.selected, self.selected_feed_id()) {
+            crate::rss::toggle_feed_pinned(&self.conn, feed_id)?;
+            self.reload_current_feed()?;
+            self.refilter_feeds()?;
+            self.flash = Some(
+                match self.current_feed.as_ref().map(|feed| feed.pinned) {
+                    Some(true) => "Pinned this feed to the top",
+                    _ => "Unpinned this feed",
+                }
+                .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// For feeds that put the useful text in the description rather than the content.
+    /// When the two say different things, both are shown, and this swaps which comes first.
+    pub fn toggle_content_field(&mut self) -> Result<()> {
+        self.content_field = match self.content_field {
+            ContentField::Content => ContentField::Description,
+            ContentField::Description => ContentField::Content,
+        };
+
+        self.flash = Some(
+            match self.content_field {
+                ContentField::Content => "Showing entries' content",
+                ContentField::Description => "Showing entries' descriptions",
+            }
+            .to_string(),
+        );
+
+        self.render_current_entry()
+    }
+
+    /// Show the open entry's translation, or the entry again if it's already shown.
+    /// Returns the entry's id and HTML, to be translated, if it hasn't been yet.
+    pub fn toggle_translation(&mut self) -> Result<Option<(crate::rss::EntryId, String)>> {
+        let entry_id = match &self.selected {
+            Selected::Entry(entry_meta) => entry_meta.id,
+            _ => return Err(anyhow::anyhow!("Open an entry to translate it")),
+        };
+
+        match &self.translation {
+            Some((translated_id, _)) if *translated_id == entry_id => {
+                self.translation_shown = !self.translation_shown;
+                self.flash = Some(
+                    if self.translation_shown {
+                        "Showing the translation"
+                    } else {
+                        "Showing the original"
+                    }
+                    .to_string(),
+                );
+                self.render_current_entry()?;
+                Ok(None)
+            }
+            _ => {
+                let entry = crate::rss::get_entry_content(&self.conn, entry_id)?;
+                Ok(Some((entry_id, self.entry_html(&entry))))
+            }
+        }
+    }
+
+    /// show the translation of the entry, if it's still the one open
+    pub fn set_translation(&mut self, entry_id: crate::rss::EntryId, html: String) -> Result<()> {
+        self.translation = Some((entry_id, html));
+        self.translation_shown = true;
+
+        match &self.selected {
+            Selected::Entry(entry_meta) if entry_meta.id == entry_id => {
+                self.flash = Some("Showing the translation".to_string());
+                self.render_current_entry()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn cycle_entry_sort(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Feeds | Selected::Entries) {
+            if let Some(current_feed) = &self.current_feed {
+                crate::rss::set_feed_entry_sort(
+                    &self.conn,
+                    current_feed.id,
+                    current_feed.entry_sort.next(),
+                )?;
+                self.reload_current_feed()?;
+
+                self.entry_selection_position = 0;
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_help(&mut self) -> Result<()> {
+        self.show_help = !self.show_help;
+        Ok(())
+    }
+
+    fn push_error(&mut self, feed_id: Option<crate::rss::FeedId>, e: anyhow::Error) {
+        let feed = feed_id.and_then(|feed_id| {
+            self.feeds
+                .items
+                .iter()
+                .find(|feed| feed.id == feed_id)
+                .and_then(|feed| feed.title.clone().or_else(|| feed.feed_link.clone()))
+        });
+
+        match &feed {
+            Some(feed) => log::error!("{}: {:?}", feed, e),
+            None => log::error!("{:?}", e),
+        }
+
+        if self.error_log.len() >= ERROR_LOG_LIMIT {
+            self.error_log.pop_front();
+        }
+
+        self.error_log.push_back(ErrorLogEntry {
+            at: chrono::Utc::now(),
+            feed,
+            message: format!("{:?}", e),
+        });
+
+        self.error_flash.push(e);
+    }
+
+    fn toggle_error_log(&mut self) {
+        self.show_error_log = !self.show_error_log;
+    }
+
+    pub fn close_error_log(&mut self) {
+        self.show_error_log = false;
+    }
+
+    pub fn error_log_is_shown(&self) -> bool {
+        self.show_error_log
+    }
+
+    fn grow_feeds_pane(&mut self) {
+        self.resize_feeds_pane(self.feeds_pane_percent + FEEDS_PANE_PERCENT_STEP);
+    }
+
+    fn shrink_feeds_pane(&mut self) {
+        self.resize_feeds_pane(
+            self.feeds_pane_percent
+                .saturating_sub(FEEDS_PANE_PERCENT_STEP),
+        );
+    }
+
+    fn resize_feeds_pane(&mut self, percent: u16) {
+        self.feeds_pane_hidden = false;
+        self.feeds_pane_percent = percent.clamp(MIN_FEEDS_PANE_PERCENT, MAX_FEEDS_PANE_PERCENT);
+    }
+
+    fn toggle_feeds_pane(&mut self) {
+        self.feeds_pane_hidden = !self.feeds_pane_hidden;
+    }
+
+    /// A hidden feeds pane still shows up when it's needed:
+    /// when the feeds are selected, or when typing into one of its inputs.
+    pub fn feeds_pane_is_shown(&self) -> bool {
+        !self.feeds_pane_hidden
+            || matches!(self.selected, Selected::Feeds | Selected::None)
+            || !matches!(self.mode, Mode::Normal)
+    }
+
+    fn toggle_stats(&mut self) -> Result<()> {
+        self.stats = match self.stats {
+            Some(_) => None,
+            None => Some(crate::stats::Stats::load(
+                &self.conn,
+                self.current_feed.as_ref(),
+            )?),
+        };
+
+        Ok(())
+    }
+
+    pub fn close_stats(&mut self) {
+        self.stats = None;
+    }
+
+    pub fn stats_are_shown(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    fn toggle_queue(&mut self) -> Result<()> {
+        self.queue = match self.queue {
+            Some(_) => None,
+            None => Some(crate::rss::get_queue(&self.conn)?),
+        };
+
+        Ok(())
+    }
+
+    pub fn close_queue(&mut self) {
+        self.queue = None;
+    }
+
+    pub fn queue_is_shown(&self) -> bool {
+        self.queue.is_some()
+    }
+
+    /// reload the queue, if it is shown
+    pub fn update_queue(&mut self) -> Result<()> {
+        if self.queue.is_some() {
+            self.queue = Some(crate::rss::get_queue(&self.conn)?);
+        }
+
+        Ok(())
+    }
+
+    /// the open entry, or the selected one
+    fn current_entry_id(&self) -> Option<crate::rss::EntryId> {
+        match &self.selected {
+            Selected::Entries => self
+                .entries
+                .items
+                .get(self.entry_selection_position)
+                .map(|entry| entry.id),
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            Selected::Feeds | Selected::None => None,
+        }
+    }
+
+    /// what there is to play in the open entry, or the selected one
+    fn current_entry_media_link(&self) -> Result<Option<String>> {
+        match self.current_entry_id() {
+            Some(entry_id) => crate::rss::get_media_link(&self.conn, entry_id),
+            None => Ok(None),
+        }
+    }
+
+    fn toggle_queued(&mut self) -> Result<()> {
+        let entry_id = match self.current_entry_id() {
+            Some(entry_id) => entry_id,
+            None => return Ok(()),
+        };
+
+        if crate::rss::get_media_link(&self.conn, entry_id)?.is_none() {
+            self.flash = Some("This entry has nothing to play".to_string());
+            return Ok(());
+        }
+
+        self.flash = Some(if crate::rss::toggle_queued(&self.conn, entry_id)? {
+            "Added to the queue".to_string()
+        } else {
+            "Removed from the queue".to_string()
+        });
+
+        self.update_queue()
+    }
+
+    /// Start playing the queue, returning whether it should be played,
+    /// or stop it, or keep it going, if it is already playing.
+    pub fn toggle_queue_playback(&mut self) -> Result<bool> {
+        if self.queue_playing {
+            self.queue_stopping = !self.queue_stopping;
+            self.flash = Some(if self.queue_stopping {
+                "The queue will stop after this one".to_string()
+            } else {
+                "The queue will keep playing".to_string()
+            });
+            return Ok(false);
+        }
+
+        if crate::rss::get_queue(&self.conn)?.is_empty() {
+            self.flash = Some("The queue is empty, add entries to it with +".to_string());
+            return Ok(false);
+        }
+
+        self.queue_playing = true;
+        self.queue_stopping = false;
+
+        Ok(true)
+    }
+
+    pub fn queue_is_stopping(&self) -> bool {
+        self.queue_stopping
+    }
+
+    /// Start reading the open entry aloud, returning its paragraphs to read,
+    /// or pause the reading, or carry on with it, if it has already started.
+    pub fn toggle_speech(&mut self) -> Result<Option<Vec<String>>> {
+        if let Some(speech) = &mut self.speech {
+            speech.paused = !speech.paused;
+            self.flash = Some(if speech.paused {
+                "Paused reading aloud".to_string()
+            } else {
+                "Reading aloud".to_string()
+            });
+            return Ok(None);
+        }
+
+        if !matches!(self.selected, Selected::Entry(_)) {
+            return Err(anyhow::anyhow!("Open an entry to read it aloud"));
+        }
+
+        let paragraphs = crate::speech::paragraphs(&self.current_entry_text);
+        if paragraphs.is_empty() {
+            return Err(anyhow::anyhow!("The entry has nothing to read aloud"));
+        }
+
+        self.speech = Some(crate::speech::Progress {
+            paragraph: 0,
+            paragraphs: paragraphs.len(),
+            paused: false,
+            stopping: false,
+        });
+
+        Ok(Some(paragraphs))
+    }
+
+    /// Returns whether there was any reading aloud to stop.
+    pub fn stop_speech(&mut self) -> bool {
+        match &mut self.speech {
+            Some(speech) => {
+                speech.stopping = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn speech_progress(&self) -> Option<crate::speech::Progress> {
+        self.speech
+    }
+
+    pub fn finish_speech(&mut self) {
+        self.speech = None;
+    }
+
+    pub fn start_refresh(&mut self) {
+        self.refreshing = true;
+        self.refresh_cancelled = false;
+    }
+
+    pub fn finish_refresh(&mut self) {
+        self.refreshing = false;
+        self.refresh_cancelled = false;
+    }
+
+    /// Returns whether there was a refresh to cancel.
+    /// The feeds already being fetched are let finish, and kept.
+    pub fn cancel_refresh(&mut self) -> bool {
+        if !self.refreshing {
+            return false;
+        }
+
+        self.refresh_cancelled = true;
+        self.flash = Some("Cancelling refresh...".to_string());
+        true
+    }
+
+    pub fn refresh_is_cancelled(&self) -> bool {
+        self.refresh_cancelled
+    }
+
+    pub fn finish_playing_queue(&mut self) {
+        self.queue_playing = false;
+        self.queue_now_playing = None;
+        self.queue_stopping = false;
+    }
+
+    pub fn clear_error_flash(&mut self) {
+        self.error_flash = vec![];
+    }
+
+    pub fn reset_feed_subscription_input(&mut self) {
+        self.feed_subscription_input.clear();
+        self.feed_preview = None;
+    }
+
+    /// Show the preview, unless what was typed has changed since it was fetched.
+    pub fn set_feed_preview(&mut self, feed_preview: crate::rss::FeedPreview) {
+        if matches!(self.mode, Mode::Editing)
+            && feed_preview.input == self.feed_subscription_input.text()
+        {
+            self.feed_preview = Some(feed_preview);
+        }
+    }
+
+    pub fn take_feed_preview(&mut self) -> Option<crate::rss::FeedPreview> {
+        self.feed_preview.take()
+    }
+
+    pub fn feed_subscription_input(&self) -> String {
+        self.feed_subscription_input.text().to_owned()
+    }
+
+    pub fn error_flash_is_empty(&self) -> bool {
+        self.error_flash.is_empty()
+    }
+
+    pub fn clear_flash(&mut self) {
+        self.flash = None
+    }
+
+    pub fn select_feeds(&mut self) {
+        self.selected = Selected::Feeds;
+    }
+
+    pub fn selected(&self) -> Selected {
+        self.selected.clone()
+    }
+
+    /// the feed selected in the feeds pane, or `None` if it's a virtual feed
+    pub fn selected_feed_id(&self) -> Option<crate::rss::FeedId> {
+        let selected_idx = self.feeds.state.selected()?;
+        Some(self.feeds.items[selected_idx].id)
+    }
+
+    pub fn current_feed_id(&self) -> Option<crate::rss::FeedId> {
+        self.current_feed.as_ref().map(|feed| feed.id)
+    }
+
+    pub fn unread_count(&self) -> Result<usize> {
+        crate::rss::get_unread_count(&self.conn)
+    }
+
+    /// Select the feed called `title`, or else the first with `title` in its title,
+    /// ignoring case, returning the feed's own title.
+    pub fn open_feed(&mut self, title: &str) -> Result<String> {
+        let wanted = title.to_lowercase();
+        let titles = self
+            .feeds
+            .items
+            .iter()
+            .map(|feed| feed.title.as_deref().unwrap_or_default().to_lowercase())
+            .collect::<Vec<_>>();
+
+        let feed_idx = titles
+            .iter()
+            .position(|title| *title == wanted)
+            .or_else(|| titles.iter().position(|title| title.contains(&wanted)))
+            .ok_or_else(|| anyhow::anyhow!("No feed called {:?} is listed", title))?;
+
+        self.push_jump();
+        while !matches!(self.selected, Selected::Feeds | Selected::None) {
+            self.on_left()?;
+        }
+        self.selected = Selected::Feeds;
+        self.feeds.state.select(Some(feed_idx));
+        self.select_feed_and_load_entries()?;
+
+        Ok(self.feeds.items[feed_idx]
+            .title
+            .clone()
+            .unwrap_or_else(|| title.to_owned()))
+    }
+
+    /// the feeds refreshing all of them refreshes, leaving out paused feeds
+    /// and those whose refresh interval hasn't passed
+    pub fn due_feed_ids(&self) -> Result<Vec<crate::rss::FeedId>> {
+        let ids = crate::rss::get_due_feed_ids(&self.conn)?;
+        Ok(ids)
+    }
+
+    pub fn scheduled_feed_ids(&self) -> Result<Vec<crate::rss::FeedId>> {
+        let ids = crate::rss::get_scheduled_feed_ids(&self.conn)?;
+        Ok(ids)
+    }
+
+    pub fn toggle_read(&mut self) -> Result<()> {
+        let selected = self.selected.clone();
+        match selected {
+            Selected::Entry(entry) => {
+                entry.toggle_read(&self.conn)?;
+                self.push_undo(UndoAction::SetFlag(
+                    crate::rss::EntryFlag::Read,
+                    vec![(entry.id, entry.read_at)],
+                ));
+                self.clear_entry_search();
+                self.selected = Selected::Entries;
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+                self.entry_scroll_position = 0;
+            }
+            Selected::Entries if self.visual_anchor.is_some() => {
+                self.toggle_selected_entries_flag(crate::rss::EntryFlag::Read)?;
+            }
+            Selected::Entries => {
+                if let Some(entry_meta) = self.current_entry_meta.clone() {
+                    let advance = self.advance_after_read && entry_meta.read_at.is_none();
+                    let selection_ids = self.selection_ids_after_toggle(entry_meta.id, advance);
+
+                    entry_meta.toggle_read(&self.conn)?;
+                    self.push_undo(UndoAction::SetFlag(
+                        crate::rss::EntryFlag::Read,
+                        vec![(entry_meta.id, entry_meta.read_at)],
+                    ));
+                    self.update_current_entries()?;
+                    self.select_first_listed_entry(&selection_ids)?;
+                }
+            }
+            Selected::Feeds => (),
+            Selected::None => (),
+        }
+
+        Ok(())
+    }
+
+    /// Mark the listed entries in `idxs` read, as `UiConfig::mark_read` does,
+    /// without reloading the list, so they stay where they are until it's next loaded.
+    fn mark_listed_entries_read(&mut self, idxs: std::ops::Range<usize>) -> Result<()> {
+        let now = chrono::Utc::now();
+        let entry_ids = self.entries.items[idxs.clone()]
+            .iter()
+            .filter(|entry| entry.read_at.is_none())
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+
+        if entry_ids.is_empty() {
+            return Ok(());
+        }
+
+        crate::rss::set_entries_flag(
+            &mut self.conn,
+            crate::rss::EntryFlag::Read,
+            &entry_ids
+                .iter()
+                .map(|entry_id| (*entry_id, Some(now)))
+                .collect::<Vec<_>>(),
+        )?;
+        self.push_undo(UndoAction::SetFlag(
+            crate::rss::EntryFlag::Read,
+            entry_ids.iter().map(|entry_id| (*entry_id, None)).collect(),
+        ));
+
+        for entry in &mut self.entries.items[idxs] {
+            entry.read_at.get_or_insert(now);
+        }
+        if let Some(entry_meta) = &mut self.current_entry_meta {
+            if entry_ids.contains(&entry_meta.id) {
+                entry_meta.read_at = Some(now);
+            }
+        }
+        self.current_feed_entry_counts.0 = self
+            .current_feed_entry_counts
+            .0
+            .saturating_sub(entry_ids.len());
+
+        Ok(())
+    }
+
+    /// with `MarkRead::OnScrollPast`, mark the entries the selection moved down past
+    /// from `previous_idx` read. Wrapping around to the top passes nothing.
+    fn mark_scrolled_past_read(&mut self, previous_idx: Option<usize>) -> Result<()> {
+        if self.mark_read != crate::config::MarkRead::OnScrollPast {
+            return Ok(());
+        }
+
+        match (previous_idx, self.entries.state.selected()) {
+            (Some(previous_idx), Some(idx)) if idx > previous_idx => {
+                self.mark_listed_entries_read(previous_idx..idx)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The entries to select after toggling `entry_id`, most wanted first.
+    /// If `advance`, that's the next unread entry, wrapping around.
+    /// Otherwise it's the entry itself, unless it leaves the list,
+    /// in which case it's whichever entry took its place.
+    fn selection_ids_after_toggle(
+        &self,
+        entry_id: crate::rss::EntryId,
+        advance: bool,
+    ) -> Vec<crate::rss::EntryId> {
+        let idx = self
+            .entries
+            .items
+            .iter()
+            .position(|entry| entry.id == entry_id)
+            .unwrap_or(0);
+        let (before, after) = self.entries.items.split_at(idx);
+        let after = after.get(1..).unwrap_or_default();
+
+        let mut ids = vec![];
+
+        if advance {
+            ids.extend(
+                after
+                    .iter()
+                    .chain(before)
+                    .filter(|entry| entry.read_at.is_none())
+                    .map(|entry| entry.id),
+            );
+        }
+
+        ids.push(entry_id);
+        ids.extend(
+            after
+                .iter()
+                .chain(before.iter().rev())
+                .map(|entry| entry.id),
+        );
+
+        ids
+    }
+
+    /// Select the first of `entry_ids` that is still listed, by id rather than position,
+    /// so the cursor stays put when entries leave the list.
+    fn select_first_listed_entry(&mut self, entry_ids: &[crate::rss::EntryId]) -> Result<()> {
+        let idx = entry_ids.iter().find_map(|entry_id| {
+            self.entries
+                .items
+                .iter()
+                .position(|entry| entry.id == *entry_id)
+        });
+
+        match idx {
+            Some(idx) => {
+                self.entries.state.select(Some(idx));
+                self.entry_selection_position = idx;
+            }
+            None => {
+                self.entries.reset();
+                self.entry_selection_position = 0;
+            }
+        }
+
+        self.update_current_entry_meta()
+    }
+
+    fn toggle_visual(&mut self) {
+        if matches!(self.selected, Selected::Entries) && !self.entries.items.is_empty() {
+            self.visual_anchor = match self.visual_anchor {
+                Some(_) => None,
+                None => Some(self.entry_selection_position),
+            };
+        }
+    }
+
+    pub fn exit_visual(&mut self) {
+        self.visual_anchor = None;
+    }
+
+    pub fn visual_is_empty(&self) -> bool {
+        self.visual_anchor.is_none()
+    }
+
+    /// the indexes of the entries between the visual anchor and the cursor
+    pub fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        self.visual_anchor.map(|anchor| {
+            anchor.min(self.entry_selection_position)..=anchor.max(self.entry_selection_position)
+        })
+    }
+
+    /// the visually selected entries if there are any,
+    /// otherwise the entry under the cursor or being read
+    fn selected_entries(&self) -> Vec<crate::rss::EntryMeta> {
+        match (&self.selected, self.visual_range()) {
+            (Selected::Entries, Some(visual_range)) => self
+                .entries
+                .items
+                .get(visual_range)
+                .map(|entries| entries.to_vec())
+                .unwrap_or_default(),
+            (Selected::Entries, None) => self.current_entry_meta.iter().cloned().collect(),
+            (Selected::Entry(entry_meta), _) => vec![entry_meta.as_ref().clone()],
+            _ => vec![],
+        }
+    }
+
+    /// sets the flag on all of the selected entries,
+    /// unless they all have it already, in which case it is unset
+    fn toggle_selected_entries_flag(&mut self, entry_flag: crate::rss::EntryFlag) -> Result<()> {
+        let entries = self.selected_entries();
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let flagged_at = |entry: &crate::rss::EntryMeta| match entry_flag {
+            crate::rss::EntryFlag::Read => entry.read_at,
+            crate::rss::EntryFlag::Starred => entry.starred_at,
+            // deleted entries are never listed
+            crate::rss::EntryFlag::Deleted => None,
+        };
+
+        let new_flagged_at = if entries.iter().all(|entry| flagged_at(entry).is_some()) {
+            None
+        } else {
+            Some(chrono::Utc::now())
+        };
+
+        let previous = entries
+            .iter()
+            .map(|entry| (entry.id, flagged_at(entry)))
+            .collect::<Vec<_>>();
+
+        let changes = entries
+            .iter()
+            .map(|entry| (entry.id, new_flagged_at))
+            .collect::<Vec<_>>();
+
+        crate::rss::set_entries_flag(&mut self.conn, entry_flag, &changes)?;
+        self.push_undo(UndoAction::SetFlag(entry_flag, previous));
+
+        self.visual_anchor = None;
+        self.update_current_entries()?;
+        self.update_current_entry_meta()?;
+        self.update_entry_selection_position();
+
+        if let (Selected::Entry(_), Some(entry_meta)) = (&self.selected, &self.current_entry_meta) {
+            self.selected = Selected::Entry(Box::new(entry_meta.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn start_note(&mut self) -> Result<()> {
+        let entry_id = match &self.selected {
+            Selected::Entries => self.current_entry_meta.as_ref().map(|entry| entry.id),
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            _ => None,
+        };
+
+        if let Some(entry_id) = entry_id {
+            self.note_input = crate::rss::get_entry_note(&self.conn, entry_id)?.unwrap_or_default();
+            self.mode = Mode::Note;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_note(&mut self) -> Result<()> {
+        let entry_id = match &self.selected {
+            Selected::Entries => self.current_entry_meta.as_ref().map(|entry| entry.id),
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            _ => None,
+        };
+
+        if let Some(entry_id) = entry_id {
+            crate::rss::set_entry_note(&self.conn, entry_id, &self.note_input)?;
+        }
+
+        self.cancel_note();
+
+        // re-render the open entry to show the new note
+        if matches!(self.selected, Selected::Entry(_)) {
+            self.on_enter()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_note(&mut self) {
+        self.note_input.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn pop_note_input(&mut self) {
+        self.note_input.pop();
+    }
+
+    fn start_share(&mut self) {
+        let entry_meta = match &self.selected {
+            Selected::Entries => self.current_entry_meta.as_ref(),
+            Selected::Entry(entry_meta) => Some(entry_meta.as_ref()),
+            _ => None,
+        };
+
+        if let Some(entry_meta) = entry_meta {
+            self.share_input = crate::mastodon::default_status(
+                entry_meta.title.as_deref(),
+                entry_meta.link.as_deref(),
+            );
+            self.mode = Mode::Share;
+        }
+    }
+
+    /// the status to post, leaving share mode
+    pub fn take_share(&mut self) -> Result<String> {
+        let status = self.share_input.trim().to_owned();
+        self.cancel_share();
+
+        if status.is_empty() {
+            Err(anyhow::anyhow!("Nothing to share"))
+        } else {
+            Ok(status)
+        }
+    }
+
+    pub fn cancel_share(&mut self) {
+        self.share_input.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn pop_share_input(&mut self) {
+        self.share_input.pop();
+    }
+
+    fn start_pipe(&mut self) {
+        if matches!(self.selected, Selected::Entry(_)) {
+            self.pipe_input = self.last_pipe_command.clone();
+            self.mode = Mode::Pipe;
+        } else {
+            self.flash = Some("Open an entry to pipe it to a command".to_string());
+        }
+    }
+
+    /// the open entry and the command to pipe it to, leaving pipe mode
+    pub fn take_pipe(&mut self) -> Result<crate::pipe::Pipe> {
+        let command = self.pipe_input.trim().to_owned();
+        self.cancel_pipe();
+
+        if command.is_empty() {
+            return Err(anyhow::anyhow!("No command given"));
+        }
+
+        let entry_meta = match &self.selected {
+            Selected::Entry(entry_meta) => entry_meta,
+            _ => return Err(anyhow::anyhow!("Open an entry to pipe it to a command")),
+        };
+
+        self.last_pipe_command = command.clone();
+
+        Ok(crate::pipe::Pipe {
+            command,
+            text: self.current_entry_text.clone(),
+            title: entry_meta.title.clone(),
+            link: entry_meta.link.clone(),
+        })
+    }
+
+    /// The open entry, or the selected one, for the script's action functions.
+    pub fn action_entry(&self) -> Result<serde_json::Value> {
+        let entry_meta = match &self.selected {
+            Selected::Entries => self.entries.items.get(self.entry_selection_position),
+            Selected::Entry(entry_meta) => Some(entry_meta.as_ref()),
+            Selected::Feeds | Selected::None => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("Select an entry to run an action on it"))?;
+
+        Ok(serde_json::json!({
+            "id": entry_meta.id,
+            "feed_id": entry_meta.feed_id,
+            "title": entry_meta.title,
+            "author": entry_meta.author,
+            "link": self.get_current_link(),
+            "pub_date": entry_meta.pub_date.map(|pub_date| pub_date.to_rfc3339()),
+            "read": entry_meta.read_at.is_some(),
+            "starred": entry_meta.starred_at.is_some(),
+            "tags": entry_meta.tags,
+        }))
+    }
+
+    pub fn cancel_pipe(&mut self) {
+        self.pipe_input.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn pop_pipe_input(&mut self) {
+        self.pipe_input.pop();
+    }
+
+    fn start_feed_edit(&mut self) {
+        if matches!(self.selected, Selected::Feeds) && self.current_feed.is_some() {
+            self.feed_edit_field = FeedField::Title;
+            self.fill_feed_edit_input();
+            self.mode = Mode::FeedEdit;
+        }
+    }
+
+    /// start the input off with the current value of the field being edited
+    fn fill_feed_edit_input(&mut self) {
+        self.feed_edit_input = self
+            .current_feed
+            .as_ref()
+            .and_then(|feed| match self.feed_edit_field {
+                FeedField::Title => feed.custom_title.clone().or_else(|| feed.title.clone()),
+                FeedField::Url => feed.feed_link.clone(),
+                FeedField::Certificate => feed.certificate_fingerprint.clone(),
+            })
+            .unwrap_or_default();
+    }
+
+    /// switch between editing the title, the url, and the pinned certificate,
+    /// dropping any unsaved changes
+    pub fn switch_feed_edit_field(&mut self) {
+        self.feed_edit_field = match self.feed_edit_field {
+            FeedField::Title => FeedField::Url,
+            FeedField::Url => FeedField::Certificate,
+            FeedField::Certificate => FeedField::Title,
+        };
+        self.fill_feed_edit_input();
+    }
+
+    pub fn save_feed_edit(&mut self) -> Result<()> {
+        if let Some(feed_id) = self.current_feed_id() {
+            match self.feed_edit_field {
+                FeedField::Title => {
+                    crate::rss::set_feed_custom_title(&self.conn, feed_id, &self.feed_edit_input)?
+                }
+                FeedField::Url => {
+                    crate::rss::set_feed_url(&self.conn, feed_id, &self.feed_edit_input)?
+                }
+                FeedField::Certificate => {
+                    let fingerprint = crate::rss::set_feed_certificate_fingerprint(
+                        &self.conn,
+                        feed_id,
+                        &self.feed_edit_input,
+                    )?;
+                    let feed_url = crate::rss::get_feed_url(&self.conn, feed_id)?;
+                    crate::http::pin_certificate(
+                        &self.http_client,
+                        &feed_url,
+                        crate::http::PinnedBy::Feed(feed_id),
+                        fingerprint,
+                    )?
+                }
+            }
+            self.reload_current_feed()?;
+        }
+
+        self.cancel_feed_edit();
+
+        Ok(())
+    }
+
+    pub fn cancel_feed_edit(&mut self) {
+        self.feed_edit_input.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn pop_feed_edit_input(&mut self) {
+        self.feed_edit_input.pop();
+    }
+
+    fn start_finder(&mut self) -> Result<()> {
+        use crate::finder::{Finder, FinderItem, FinderTarget};
+
+        let feeds = self.feeds.items.iter().map(|feed| FinderItem {
+            target: FinderTarget::Feed(feed.id),
+            text: feed
+                .title
+                .clone()
+                .or_else(|| feed.feed_link.clone())
+                .unwrap_or_default(),
+        });
+
+        let entries = crate::rss::get_entries_titles(&self.conn)?.into_iter().map(
+            |(entry_id, feed_id, title)| FinderItem {
+                target: FinderTarget::Entry(feed_id, entry_id),
+                text: title,
+            },
+        );
+
+        self.finder = Some(Finder::new(feeds.chain(entries).collect()));
+        self.mode = Mode::Finder;
+
+        Ok(())
+    }
+
+    fn next_finder_match(&mut self) {
+        if let Some(finder) = &mut self.finder {
+            finder.next();
+        }
+    }
+
+    fn previous_finder_match(&mut self) {
+        if let Some(finder) = &mut self.finder {
+            finder.previous();
+        }
+    }
+
+    fn pop_finder_input(&mut self) {
+        if let Some(finder) = &mut self.finder {
+            finder.pop();
+        }
+    }
+
+    fn cancel_finder(&mut self) {
+        self.finder = None;
+        self.mode = Mode::Normal;
+    }
+
+    /// jump to the selected feed, or open the selected entry
+    fn confirm_finder(&mut self) -> Result<()> {
+        use crate::finder::FinderTarget;
+
+        let target = self
+            .finder
+            .take()
+            .and_then(|finder| finder.selected().map(|item| item.target));
+        self.mode = Mode::Normal;
+
+        let (feed_id, entry_id) = match target {
+            Some(FinderTarget::Feed(feed_id)) => (feed_id, None),
+            Some(FinderTarget::Entry(feed_id, entry_id)) => (feed_id, Some(entry_id)),
+            None => return Ok(()),
+        };
+
+        self.push_jump();
+        self.go_to(Jump {
+            feed: JumpFeed::Feed(feed_id),
+            entry_id,
+            entry_open: entry_id.is_some(),
+        })
+    }
+
+    pub fn mark_is_pending(&self) -> bool {
+        self.pending_mark.is_some()
+    }
+
+    /// The next key of the macro being replayed, unless the loader thread is still reading
+    /// what the keys before it opened, which the keys after them are for.
+    pub fn next_replayed_key(&mut self) -> Option<KeyEvent> {
+        if self.entry_loading || self.entries_loading {
+            return None;
+        }
+
+        self.macros.next_key()
+    }
+
+    /// Put the mark `name` on the selected entry, or feed,
+    /// or go to the one it's on, remembering where it went from, for `ctrl-o`.
+    fn on_mark(&mut self, mark_action: MarkAction, name: char) -> Result<()> {
+        match mark_action {
+            MarkAction::Set => {
+                let (feed_id, entry_id) = match (&self.selected, &self.current_entry_meta) {
+                    (Selected::Entries | Selected::Entry(_), Some(entry_meta)) => {
+                        (entry_meta.feed_id, Some(entry_meta.id))
+                    }
+                    (Selected::Feeds, _) => match &self.current_feed {
+                        Some(feed) => (feed.id, None),
+                        None => return Ok(()),
+                    },
+                    _ => return Ok(()),
+                };
+
+                crate::rss::set_mark(&self.conn, name, feed_id, entry_id)?;
+                self.flash = Some(format!(
+                    "Marked this {} {}",
+                    if entry_id.is_some() { "entry" } else { "feed" },
+                    name
+                ));
+            }
+            MarkAction::Jump => match crate::rss::get_mark(&self.conn, name)? {
+                Some((feed_id, entry_id)) => {
+                    self.push_jump();
+                    self.go_to(Jump {
+                        feed: JumpFeed::Feed(feed_id),
+                        entry_id,
+                        entry_open: false,
+                    })?;
+                }
+                None => self.flash = Some(format!("Nothing is marked {}", name)),
+            },
+            MarkAction::RecordMacro => self.macros.start_recording(name),
+            MarkAction::ReplayMacro(count) => self.macros.replay(name, count)?,
+        }
+
+        Ok(())
+    }
+
+    /// where the selection is, to come back to with `ctrl-o`
+    fn current_jump(&self) -> Option<Jump> {
+        let feed = match (self.virtual_feed, &self.current_feed) {
+            (Some(virtual_feed), _) => JumpFeed::Virtual(virtual_feed),
+            (None, Some(feed)) => JumpFeed::Feed(feed.id),
+            (None, None) => return None,
+        };
+
+        let entry_id = match &self.selected {
+            Selected::Entries => self.current_entry_meta.as_ref().map(|entry| entry.id),
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            Selected::Feeds | Selected::None => None,
+        };
+
+        Some(Jump {
+            feed,
+            entry_id,
+            entry_open: matches!(self.selected, Selected::Entry(_)),
+        })
+    }
+
+    /// remember where the selection is, before a big move away from it
+    fn push_jump(&mut self) {
+        if let Some(jump) = self.current_jump() {
+            self.jumps.push(jump);
+        }
+    }
+
+    pub fn jump_back(&mut self) -> Result<()> {
+        let current = match self.current_jump() {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+
+        match self.jumps.back(current) {
+            Some(jump) => self.go_to(jump),
+            None => Ok(()),
+        }
+    }
+
+    pub fn jump_forward(&mut self) -> Result<()> {
+        match self.jumps.forward() {
+            Some(jump) => self.go_to(jump),
+            None => Ok(()),
+        }
+    }
+
+    /// Select the feed, and the entry, opening it if it was open,
+    /// starting from a clean slate in the feed.
+    fn go_to(&mut self, jump: Jump) -> Result<()> {
+        match jump.feed {
+            JumpFeed::Feed(feed_id) => {
+                let feed_idx = self.feeds.items.iter().position(|feed| feed.id == feed_id);
+                if feed_idx.is_none() {
+                    self.flash = Some("That feed isn't listed anymore".to_string());
+                    return Ok(());
+                }
+                self.virtual_feed = None;
+                self.feeds.state.select(feed_idx);
+            }
+            JumpFeed::Virtual(virtual_feed) => {
+                self.virtual_feed = Some(virtual_feed);
+                self.feeds.unselect();
+            }
+        }
+
+        self.selected = Selected::Feeds;
+        self.visual_anchor = None;
+        self.entries_tag = None;
+        self.entries_filter = None;
+        self.search_input.clear();
+        self.clear_entry_search();
+        self.entry_scroll_position = 0;
+        self.entry_selection_position = 0;
+        self.current_entry_text = String::new();
+        self.update_current_feed_and_entries()?;
+
+        if let Some(entry_id) = jump.entry_id {
+            // the entry may be hidden by the read mode
+            if self.find_listed_entry(entry_id)?.is_none() {
+                self.read_mode = match self.read_mode {
+                    ReadMode::ShowUnread => ReadMode::ShowRead,
+                    ReadMode::ShowRead => ReadMode::ShowUnread,
+                    ReadMode::All => ReadMode::All,
+                };
+                self.update_current_entries()?;
+            }
+
+            if let Some(entry_idx) = self.find_listed_entry(entry_id)? {
+                self.entries.state.select(Some(entry_idx));
+                self.entry_selection_position = entry_idx;
+                self.selected = Selected::Entries;
+                self.update_current_entry_meta()?;
+
+                if jump.entry_open {
+                    self.on_enter()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start_command(&mut self) {
+        self.command_input.clear();
+        self.command_history.reset();
+        self.mode = Mode::Command;
+    }
+
+    /// the command names that the command being typed could complete to
+    pub fn matching_commands(&self) -> Vec<&'static str> {
+        if self.command_input.contains(char::is_whitespace) {
+            return vec![];
+        }
+
+        crate::command::COMMAND_NAMES
+            .iter()
+            .filter(|name| name.starts_with(self.command_input.as_str()))
+            .copied()
+            .collect()
+    }
+
+    fn complete_command(&mut self) {
+        self.command_history.finish_search();
+        let matching_commands = self.matching_commands();
+
+        if let Some(common_prefix) = util::common_prefix(&matching_commands) {
+            self.command_input = common_prefix.to_owned();
+
+            // a single match is complete, so get ready for its argument
+            if matching_commands.len() == 1 {
+                self.command_input.push(' ');
+            }
+        }
+    }
+
+    fn previous_command(&mut self) {
+        self.command_history.previous(&mut self.command_input);
+    }
+
+    fn next_command(&mut self) {
+        self.command_history.next(&mut self.command_input);
+    }
+
+    /// `ctrl-r`: search the previously run commands
+    fn search_command_history(&mut self) {
+        self.command_history.search_older(&mut self.command_input);
+    }
+
+    fn pop_command_input(&mut self) {
+        if self.command_history.search().is_some() {
+            self.command_history.pop_search(&mut self.command_input);
+        } else {
+            self.command_input.pop();
+        }
+    }
+
+    fn cancel_command(&mut self) {
+        // esc while searching the history goes back to what was typed
+        if self.command_history.search().is_some() {
+            self.command_history.cancel_search(&mut self.command_input);
+            return;
         }
+
+        self.command_input.clear();
+        self.mode = Mode::Normal;
     }
 
-    pub fn on_enter(&mut self) -> Result<()> {
-        match self.selected {
-            Selected::Entries | Selected::Entry(_) => {
+    /// leave command mode, returning the command that was typed
+    fn take_command(&mut self) -> Result<crate::command::Command> {
+        let command_input = std::mem::take(&mut self.command_input);
+        self.mode = Mode::Normal;
+
+        // not even for this session, as it'd be on screen with `up`
+        if !crate::command::has_secret(&command_input) && self.command_history.push(&command_input)
+        {
+            crate::rss::add_to_history(&self.conn, "command", &command_input)?;
+        }
+
+        command_input.parse()
+    }
+
+    fn run_command(&mut self, command: crate::command::Command) -> Result<()> {
+        use crate::command::{Command, Setting};
+
+        match command {
+            Command::DeleteFeed => self.delete_current_feed(),
+            Command::MarkAllRead(older_than) => {
+                let feed_ids = self.feeds.items.iter().map(|feed| feed.id).collect();
+                self.mark_feeds_as_read(feed_ids, older_than)
+            }
+            Command::MarkFeedRead(older_than) => match self.selected_feed_id() {
+                Some(feed_id) => self.mark_feeds_as_read(vec![feed_id], older_than),
+                None => Err(anyhow::anyhow!("There's no feed selected to mark read")),
+            },
+            Command::ExportStarred(dir) => {
+                let entries = crate::rss::get_starred_entries_metas(
+                    &self.conn,
+                    &ReadMode::All,
+                    crate::rss::EntrySort::PubDateDesc,
+                )?;
+                self.export_markdown(&entries, &dir)
+            }
+            Command::ExportTagged { tag, dir } => {
+                let entries = crate::rss::get_tagged_entries_metas(
+                    &self.conn,
+                    &ReadMode::All,
+                    crate::rss::EntrySort::PubDateDesc,
+                    &tag,
+                )?;
+                self.export_markdown(&entries, &dir)
+            }
+            Command::Search(term) => {
+                if matches!(self.selected, Selected::Feeds) {
+                    self.on_right()?;
+                }
+
+                if !matches!(self.selected, Selected::Entries | Selected::Entry(_)) {
+                    return Err(anyhow::anyhow!("There are no entries to search"));
+                }
+
+                self.start_search();
+                self.search_input = term;
+                self.update_search()?;
+                self.confirm_search()
+            }
+            Command::Filter(date_range) => {
+                self.entries_date_range = date_range;
+                self.entry_selection_position = 0;
+                self.update_current_entries()?;
+
                 if !self.entries.items.is_empty() {
-                    if let Some(entry_meta) = &self.current_entry_meta {
-                        if let Some(entry) = self.get_selected_entry() {
-                            let entry = entry?;
-                            let empty_string =
-                                String::from("No content or description tag provided.");
-
-                            // try content tag first,
-                            // if there is not content tag,
-                            // go to description tag,
-                            // if no description tag,
-                            // use empty string.
-                            // TODO figure out what to actually do if there are neither
-                            let entry_html = entry
-                                .content
-                                .as_ref()
-                                .or(entry.description.as_ref())
-                                .or(Some(&empty_string));
-
-                            // minimum is 1
-                            let line_length = if self.entry_column_width >= 5 {
-                                self.entry_column_width - 4
-                            } else {
-                                1
-                            };
+                    self.entries.reset();
+                } else {
+                    self.entries.unselect();
+                }
 
-                            if let Some(html) = entry_html {
-                                let text =
-                                    html2text::from_read(html.as_bytes(), line_length.into());
-                                self.entry_lines_len = text.matches('\n').count();
-                                self.current_entry_text = text;
-                            } else {
-                                self.current_entry_text = String::new();
-                            }
-                        }
+                self.flash = Some(if date_range.is_empty() {
+                    "Listing entries from every day".to_string()
+                } else {
+                    format!("Listing entries {}", date_range.describe())
+                });
+                Ok(())
+            }
+            Command::Block(domain) => {
+                let domain = crate::rss::block_domain(&self.conn, &domain)?;
+
+                // the entries already there are hidden too, until it's unblocked
+                let entry_ids = crate::rss::get_entry_ids_on_domain(&self.conn, &domain)?;
+
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+                self.update_entry_selection_position();
+
+                self.flash = Some(format!(
+                    "Blocked {}, hiding {} {} from it",
+                    domain,
+                    entry_ids.len(),
+                    if entry_ids.len() == 1 {
+                        "entry"
+                    } else {
+                        "entries"
+                    }
+                ));
+                Ok(())
+            }
+            Command::Unblock(domain) => {
+                self.flash = Some(if crate::rss::unblock_domain(&self.conn, &domain)? {
+                    self.update_current_entries()?;
+                    self.update_current_entry_meta()?;
+                    self.update_entry_selection_position();
+                    format!("Unblocked {}, showing its entries again", domain)
+                } else {
+                    format!("{} isn't blocked", domain)
+                });
+                Ok(())
+            }
+            Command::Blocked => {
+                let domains = crate::rss::get_blocked_domains(&self.conn)?;
+                self.flash = Some(if domains.is_empty() {
+                    "No domains are blocked".to_string()
+                } else {
+                    format!("Blocked: {}", domains.join(", "))
+                });
+                Ok(())
+            }
+            Command::MuteLanguage(language) => {
+                let language = crate::rss::mute_language(&self.conn, &language)?;
+
+                // like blocking, the entries already there are hidden too, until it's unmuted
+                let entry_ids = crate::rss::get_entry_ids_in_language(&self.conn, &language)?;
+
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+                self.update_entry_selection_position();
 
-                        self.selected = Selected::Entry(entry_meta.clone());
+                self.flash = Some(format!(
+                    "Muted {}, hiding {} {} in it",
+                    language,
+                    entry_ids.len(),
+                    if entry_ids.len() == 1 {
+                        "entry"
+                    } else {
+                        "entries"
+                    }
+                ));
+                Ok(())
+            }
+            Command::UnmuteLanguage(language) => {
+                self.flash = Some(if crate::rss::unmute_language(&self.conn, &language)? {
+                    self.update_current_entries()?;
+                    self.update_current_entry_meta()?;
+                    self.update_entry_selection_position();
+                    format!("Unmuted {}, showing its entries again", language)
+                } else {
+                    format!("{} isn't muted", language)
+                });
+                Ok(())
+            }
+            Command::MutedLanguages => {
+                let languages = crate::rss::get_muted_languages(&self.conn)?;
+                self.flash = Some(if languages.is_empty() {
+                    "No languages are muted".to_string()
+                } else {
+                    format!("Muted: {}", languages.join(", "))
+                });
+                Ok(())
+            }
+            Command::Header { name, value } => {
+                let feed_id = match self.selected_feed_id() {
+                    Some(feed_id) => feed_id,
+                    None => return Ok(()),
+                };
+                crate::rss::set_feed_header(
+                    &self.conn,
+                    &crate::keyring::Keyring::open(&self.database_path),
+                    feed_id,
+                    &name,
+                    value.as_deref(),
+                )?;
+                self.flash = Some(match value {
+                    Some(_) => format!("Sending {} with this feed", name),
+                    None => format!("No longer sending {} with this feed", name),
+                });
+                Ok(())
+            }
+            Command::Headers => {
+                let feed_id = match self.selected_feed_id() {
+                    Some(feed_id) => feed_id,
+                    None => return Ok(()),
+                };
+                let names = crate::rss::get_feed_header_names(&self.conn, feed_id)?;
+                // only the names, as values like `Authorization`'s are secret
+                self.flash = Some(if names.is_empty() {
+                    "This feed is sent no extra headers".to_string()
+                } else {
+                    format!("This feed is sent {}", names.join(", "))
+                });
+                Ok(())
+            }
+            Command::Interval(refresh_interval) => {
+                let feed_id = match self.selected_feed_id() {
+                    Some(feed_id) => feed_id,
+                    None => return Ok(()),
+                };
+                crate::rss::set_feed_refresh_interval(&self.conn, feed_id, refresh_interval)?;
+                self.reload_current_feed()?;
+                self.flash = Some(match refresh_interval {
+                    Some(refresh_interval) => format!(
+                        "Refreshing this feed every {}",
+                        util::format_interval(refresh_interval)
+                    ),
+                    None => "Refreshing this feed with the rest".to_string(),
+                });
+                Ok(())
+            }
+            Command::Pause => {
+                let feed_id = match self.selected_feed_id() {
+                    Some(feed_id) => feed_id,
+                    None => return Ok(()),
+                };
+                crate::rss::toggle_feed_paused(&self.conn, feed_id)?;
+                self.reload_current_feed()?;
+                self.flash = Some(
+                    match self.current_feed.as_ref().map(|feed| feed.paused) {
+                        Some(true) => "Paused this feed",
+                        _ => "Unpaused this feed",
                     }
+                    .to_string(),
+                );
+                Ok(())
+            }
+            Command::Profile(profile) => self.switch_profile(profile),
+            Command::Set(Setting::HideReadFeeds(hide_read_feeds)) => {
+                self.hide_read_feeds = hide_read_feeds;
+                self.refilter_feeds()
+            }
+            Command::Set(Setting::AdvanceAfterRead(advance_after_read)) => {
+                self.advance_after_read = advance_after_read;
+                Ok(())
+            }
+            Command::Set(Setting::MarkRead(mark_read)) => {
+                self.mark_read = mark_read;
+                Ok(())
+            }
+            Command::Set(Setting::Width(width)) => {
+                self.entry_max_width = if width == 0 { None } else { Some(width) };
+
+                if matches!(self.selected, Selected::Entry(_)) {
+                    self.on_enter()?;
                 }
 
                 Ok(())
             }
-            _ => Ok(()),
+            Command::Add(_) | Command::Quit | Command::Refresh | Command::RefreshAll => {
+                unreachable!("{:?} is run by the main thread", command)
+            }
         }
     }
 
-    pub fn toggle_help(&mut self) -> Result<()> {
-        self.show_help = !self.show_help;
+    fn export_markdown(&mut self, entries: &[crate::rss::EntryMeta], dir: &str) -> Result<()> {
+        if entries.is_empty() {
+            return Err(anyhow::anyhow!("There are no entries to export"));
+        }
+
+        let dir = util::expand_tilde(dir);
+        let exported_len = crate::export::export_markdown(&self.conn, entries, &dir)?;
+        self.flash = Some(format!(
+            "Exported {} entries to {}",
+            exported_len,
+            dir.display()
+        ));
+
         Ok(())
     }
 
-    pub fn clear_error_flash(&mut self) {
-        self.error_flash = vec![];
-    }
+    fn start_tag(&mut self, tag_action: TagAction) -> Result<()> {
+        if tag_action == TagAction::Toggle && self.selected_entries().is_empty() {
+            return Ok(());
+        }
 
-    pub fn reset_feed_subscription_input(&mut self) {
-        self.feed_subscription_input.clear();
-    }
+        self.tags = crate::rss::get_tags(&self.conn)?;
 
-    pub fn pop_feed_subscription_input(&mut self) {
-        self.feed_subscription_input.pop();
-    }
+        if tag_action == TagAction::Browse && self.tags.is_empty() {
+            self.flash = Some("No entries have been tagged yet".to_string());
+            return Ok(());
+        }
 
-    pub fn feed_subscription_input(&self) -> String {
-        self.feed_subscription_input.clone()
-    }
+        self.tag_input.clear();
+        self.tag_action = tag_action;
+        self.mode = Mode::Tag;
 
-    pub fn error_flash_is_empty(&self) -> bool {
-        self.error_flash.is_empty()
+        Ok(())
     }
 
-    pub fn clear_flash(&mut self) {
-        self.flash = None
-    }
+    /// the tags that the tag being typed could complete to
+    pub fn matching_tags(&self) -> Vec<&str> {
+        let partial_tag = self.partial_tag();
 
-    pub fn select_feeds(&mut self) {
-        self.selected = Selected::Feeds;
+        self.tags
+            .iter()
+            .filter(|tag| tag.starts_with(partial_tag))
+            .map(|tag| tag.as_str())
+            .collect()
     }
 
-    pub fn selected(&self) -> Selected {
-        self.selected.clone()
-    }
+    /// entries can be given several comma-separated tags at once,
+    /// so only the last one is completed
+    fn partial_tag(&self) -> &str {
+        let partial_tag = match self.tag_action {
+            TagAction::Toggle => self.tag_input.rsplit(',').next().unwrap_or_default(),
+            TagAction::Browse => &self.tag_input,
+        };
 
-    pub fn selected_feed_id(&self) -> crate::rss::FeedId {
-        let selected_idx = self.feeds.state.selected().unwrap();
-        self.feeds.items[selected_idx].id
+        partial_tag.trim_start()
     }
 
-    pub fn feed_ids(&self) -> Result<Vec<crate::rss::FeedId>> {
-        let ids = crate::rss::get_feed_ids(&self.conn)?;
-        Ok(ids)
+    /// complete the tag being typed as far as the existing tags agree
+    fn complete_tag(&mut self) {
+        let matching_tags = self.matching_tags();
+
+        let common_prefix = match util::common_prefix(&matching_tags) {
+            Some(common_prefix) => common_prefix.to_owned(),
+            None => return,
+        };
+
+        let partial_tag_len = self.partial_tag().len();
+        self.tag_input
+            .truncate(self.tag_input.len() - partial_tag_len);
+        self.tag_input.push_str(&common_prefix);
     }
 
-    pub fn toggle_read(&mut self) -> Result<()> {
-        let selected = self.selected.clone();
-        match selected {
-            Selected::Entry(entry) => {
-                entry.toggle_read(&self.conn)?;
-                self.selected = Selected::Entries;
+    fn confirm_tag(&mut self) -> Result<()> {
+        let tag_input = std::mem::take(&mut self.tag_input);
+        self.mode = Mode::Normal;
+
+        match self.tag_action {
+            TagAction::Toggle => {
+                let entries = self.selected_entries();
+                let entry_ids = entries.iter().map(|entry| entry.id).collect::<Vec<_>>();
+
+                for tag in tag_input
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                {
+                    // like flags, a tag is removed only if every selected entry has it
+                    let tagged = !entries
+                        .iter()
+                        .all(|entry| entry.tags.iter().any(|t| t == tag));
+                    crate::rss::set_entries_tag(&mut self.conn, &entry_ids, tag, tagged)?;
+                }
+
+                self.visual_anchor = None;
                 self.update_current_entries()?;
                 self.update_current_entry_meta()?;
-                self.entry_scroll_position = 0;
+                self.update_entry_selection_position();
+
+                if let (Selected::Entry(_), Some(entry_meta)) =
+                    (&self.selected, &self.current_entry_meta)
+                {
+                    self.selected = Selected::Entry(Box::new(entry_meta.clone()));
+                }
             }
-            Selected::Entries => {
-                if let Some(entry_meta) = &self.current_entry_meta {
-                    entry_meta.toggle_read(&self.conn)?;
+            TagAction::Browse => {
+                let tag = tag_input.trim();
+
+                if tag.is_empty() {
+                    return Ok(());
+                }
+
+                self.entries_tag = Some(tag.to_owned());
+                self.entry_selection_position = 0;
+                self.visual_anchor = None;
+                self.entry_scroll_position = 0;
+                self.clear_entry_search();
+                self.current_entry_text = String::new();
+                self.update_current_entries()?;
+
+                if self.entries.items.is_empty() {
+                    self.entries_tag = None;
+                    self.selected = Selected::Feeds;
                     self.update_current_entries()?;
+                    self.flash = Some(format!("No entries are tagged {}", tag));
+                } else {
+                    self.selected = Selected::Entries;
+                    self.entries.reset();
                     self.update_current_entry_meta()?;
-                    self.update_entry_selection_position();
                 }
             }
-            Selected::Feeds => (),
-            Selected::None => (),
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_tag(&mut self) {
+        self.tag_input.clear();
+        self.mode = Mode::Normal;
+    }
+
+    pub fn pop_tag_input(&mut self) {
+        self.tag_input.pop();
+    }
+
+    pub fn toggle_star(&mut self) -> Result<()> {
+        self.toggle_selected_entries_flag(crate::rss::EntryFlag::Starred)
+    }
+
+    pub fn delete_entries(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Entries) {
+            self.toggle_selected_entries_flag(crate::rss::EntryFlag::Deleted)?;
         }
 
         Ok(())
@@ -527,17 +4181,28 @@ impl AppImpl {
     }
 
     pub fn toggle_read_mode(&mut self) -> Result<()> {
+        // unread, then read, then all of them
         match (&self.read_mode, &self.selected) {
-            (ReadMode::ShowRead, Selected::Feeds) | (ReadMode::ShowRead, Selected::Entries) => {
+            (ReadMode::All, Selected::Feeds) | (ReadMode::All, Selected::Entries) => {
                 self.entry_selection_position = 0;
                 self.read_mode = ReadMode::ShowUnread
             }
+            (ReadMode::ShowRead, Selected::Feeds) | (ReadMode::ShowRead, Selected::Entries) => {
+                self.entry_selection_position = 0;
+                self.read_mode = ReadMode::All
+            }
             (ReadMode::ShowUnread, Selected::Feeds) | (ReadMode::ShowUnread, Selected::Entries) => {
                 self.entry_selection_position = 0;
                 self.read_mode = ReadMode::ShowRead
             }
+            // every feed is hidden for having nothing unread
+            (ReadMode::ShowUnread, Selected::None) if self.hidden_feeds_len > 0 => {
+                self.entry_selection_position = 0;
+                self.read_mode = ReadMode::ShowRead
+            }
             _ => (),
         }
+        self.refilter_feeds()?;
         self.update_current_entries()?;
 
         if !self.entries.items.is_empty() {
@@ -551,8 +4216,18 @@ impl AppImpl {
         Ok(())
     }
 
-    fn get_current_link(&self) -> Option<&str> {
+    /// the link of the open entry, or the selected one
+    fn current_entry_link(&self) -> Option<String> {
         match &self.selected {
+            Selected::Entries | Selected::Entry(_) => self.get_current_link(),
+            Selected::Feeds | Selected::None => None,
+        }
+    }
+
+    /// the selected feed's link, or the entry's, without any tracking parameters
+    /// it was stored with before they were stripped
+    fn get_current_link(&self) -> Option<String> {
+        let link = match &self.selected {
             Selected::Feeds => self
                 .current_feed
                 .as_ref()
@@ -564,9 +4239,52 @@ impl AppImpl {
                 .and_then(|entry| entry.link.as_deref()),
             Selected::Entry(e) => e.link.as_deref(),
             Selected::None => None,
+        };
+
+        link.map(crate::links::strip_tracking_params)
+    }
+
+    /// the comments link of the open entry, or the selected one
+    fn get_current_comments_link(&self) -> Option<&str> {
+        match &self.selected {
+            Selected::Entries => self
+                .entries
+                .items
+                .get(self.entry_selection_position)
+                .and_then(|entry| entry.comments_link.as_deref()),
+            Selected::Entry(e) => e.comments_link.as_deref(),
+            Selected::Feeds | Selected::None => None,
         }
     }
 
+    /// Start adding the feed in the clipboard, returning what to preview.
+    fn add_feed_from_clipboard(&mut self) -> Result<String> {
+        let contents = if self.is_wsl {
+            #[cfg(target_os = "linux")]
+            {
+                util::get_wsl_clipboard_contents()?
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                unreachable!("This should never happen. This code should only be reachable if the target OS is WSL.")
+            }
+        } else {
+            let mut ctx = ClipboardContext::new().map_err(|e| anyhow::anyhow!(e))?;
+            ctx.get_contents().map_err(|e| anyhow::anyhow!(e))?
+        };
+
+        let input = crate::input::feed_input_from_clipboard(&contents)?;
+
+        self.feed_subscription_input.clear();
+        self.feed_subscription_input.insert_str(&input);
+        self.feed_preview = None;
+        self.confirm_delete_feed = false;
+        self.mode = Mode::Editing;
+
+        Ok(input)
+    }
+
     fn put_current_link_in_clipboard(&mut self) -> Result<()> {
         let current_link = self.get_current_link();
 
@@ -574,7 +4292,7 @@ impl AppImpl {
             #[cfg(target_os = "linux")]
             {
                 if let Some(current_link) = current_link {
-                    util::set_wsl_clipboard_contents(current_link)
+                    util::set_wsl_clipboard_contents(&current_link)
                 } else {
                     Ok(())
                 }
@@ -586,7 +4304,7 @@ impl AppImpl {
             }
         } else if let Some(current_link) = current_link {
             let mut ctx = ClipboardContext::new().map_err(|e| anyhow::anyhow!(e))?;
-            ctx.set_contents(current_link.to_owned())
+            ctx.set_contents(current_link)
                 .map_err(|e| anyhow::anyhow!(e))
         } else {
             Ok(())
@@ -595,8 +4313,19 @@ impl AppImpl {
 
     fn open_link_in_browser(&self) -> Result<()> {
         if let Some(current_link) = self.get_current_link() {
-            webbrowser::open(current_link).map_err(|e| anyhow::anyhow!(e))
+            webbrowser::open(&current_link).map_err(|e| anyhow::anyhow!(e))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn open_comments_in_browser(&mut self) -> Result<()> {
+        if let Some(comments_link) = self.get_current_comments_link() {
+            webbrowser::open(comments_link).map_err(|e| anyhow::anyhow!(e))
         } else {
+            if !matches!(self.selected, Selected::Feeds) {
+                self.flash = Some("This entry has no comments link".to_string());
+            }
             Ok(())
         }
     }
@@ -606,14 +4335,24 @@ impl AppImpl {
             Selected::Feeds => (),
             Selected::Entries => {
                 self.entry_selection_position = 0;
-                self.selected = Selected::Feeds
+                self.visual_anchor = None;
+                self.selected = Selected::Feeds;
+                if self.entries_filter.is_some() {
+                    self.search_input.clear();
+                    self.update_entries_filter()?;
+                }
+                if self.entries_tag.take().is_some() {
+                    self.update_current_entries()?;
+                }
             }
             Selected::Entry(_) => {
                 self.entry_scroll_position = 0;
-                self.selected = {
+                self.clear_entry_search();
+                // the three-pane layout keeps previewing the entry
+                if self.layout == PaneLayout::TwoPane {
                     self.current_entry_text = String::new();
-                    Selected::Entries
                 }
+                self.selected = Selected::Entries;
             }
             Selected::None => (),
         }
@@ -624,8 +4363,9 @@ impl AppImpl {
     pub fn on_up(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
-                self.feeds.previous();
-                self.update_current_feed_and_entries()?;
+                let feeds_pane_len = self.feeds_pane_len();
+                let position = self.feeds_pane_position().unwrap_or(0);
+                self.select_feeds_pane_position((position + feeds_pane_len - 1) % feeds_pane_len)?;
             }
             Selected::Entries => {
                 if !self.entries.items.is_empty() {
@@ -645,9 +4385,91 @@ impl AppImpl {
         Ok(())
     }
 
+    /// the images the open entry is waiting on, for the IO thread to fetch
+    pub fn take_pending_images(&mut self) -> Vec<String> {
+        match &mut self.images {
+            Some(images) => images.take_pending(),
+            None => vec![],
+        }
+    }
+
+    pub fn take_pending_count(&mut self) -> Option<usize> {
+        self.pending_count.take()
+    }
+
+    /// like `on_up`, but `count` times,
+    /// stopping at the top rather than wrapping around
+    fn on_up_by(&mut self, count: usize) -> Result<()> {
+        if count <= 1 {
+            return self.on_up();
+        }
+
+        match self.selected {
+            Selected::Feeds => {
+                let position = self.feeds_pane_position().unwrap_or(0);
+                self.select_feeds_pane_position(position.saturating_sub(count))?;
+            }
+            Selected::Entries => {
+                if !self.entries.items.is_empty() {
+                    self.entries.previous_by(count);
+                    self.entry_selection_position = self.entries.state.selected().unwrap();
+                    self.update_current_entry_meta()?;
+                }
+            }
+            Selected::Entry(_) => {
+                let count = u16::try_from(count).unwrap_or(u16::MAX);
+                self.entry_scroll_position = self.entry_scroll_position.saturating_sub(count);
+            }
+            Selected::None => (),
+        }
+
+        Ok(())
+    }
+
+    /// like `on_down`, but `count` times,
+    /// stopping at the bottom rather than wrapping around
+    fn on_down_by(&mut self, count: usize) -> Result<()> {
+        if count <= 1 {
+            return self.on_down();
+        }
+
+        match self.selected {
+            Selected::Feeds => {
+                let position = self.feeds_pane_position().unwrap_or(0);
+                let last = self.feeds_pane_len() - 1;
+                self.select_feeds_pane_position(position.saturating_add(count).min(last))?;
+            }
+            Selected::Entries => {
+                if !self.entries.items.is_empty() {
+                    let previous_idx = self.entries.state.selected();
+                    let target = previous_idx.unwrap_or(0) + count;
+                    while target >= self.entries.items.len() && !self.entries_exhausted {
+                        self.load_more_entries()?;
+                    }
+                    self.entries.next_by(count);
+                    self.entry_selection_position = self.entries.state.selected().unwrap();
+                    self.mark_scrolled_past_read(previous_idx)?;
+                    self.update_current_entry_meta()?;
+                }
+            }
+            Selected::Entry(_) => {
+                let count = u16::try_from(count).unwrap_or(u16::MAX);
+                self.entry_scroll_position = self
+                    .entry_scroll_position
+                    .saturating_add(count)
+                    .min(self.max_entry_scroll_position());
+            }
+            Selected::None => (),
+        }
+
+        Ok(())
+    }
+
     pub fn on_right(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
+                self.wait_for_entries()?;
+
                 if !self.entries.items.is_empty() {
                     self.selected = Selected::Entries;
                     self.entries.reset();
@@ -655,7 +4477,7 @@ impl AppImpl {
                 }
                 Ok(())
             }
-            Selected::Entries => self.on_enter(),
+            Selected::Entries => self.open_entry(),
             Selected::Entry(_) => Ok(()),
             Selected::None => Ok(()),
         }
@@ -664,20 +4486,25 @@ impl AppImpl {
     pub fn on_down(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
-                self.feeds.next();
-                self.update_current_feed_and_entries()?;
+                let feeds_pane_len = self.feeds_pane_len();
+                let position = self
+                    .feeds_pane_position()
+                    .map_or(0, |position| position + 1);
+                self.select_feeds_pane_position(position % feeds_pane_len)?;
             }
             Selected::Entries => {
                 if !self.entries.items.is_empty() {
+                    let previous_idx = self.entries.state.selected();
                     self.entries.next();
                     self.entry_selection_position = self.entries.state.selected().unwrap();
+                    self.mark_scrolled_past_read(previous_idx)?;
                     self.update_current_entry_meta()?;
                 }
             }
             Selected::Entry(_) => {
-                if let Some(n) = self.entry_scroll_position.checked_add(1) {
-                    self.entry_scroll_position = n
-                };
+                if self.entry_scroll_position < self.max_entry_scroll_position() {
+                    self.entry_scroll_position += 1;
+                }
             }
             Selected::None => (),
         }
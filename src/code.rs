@@ -0,0 +1,296 @@
+//! Code blocks in entries.
+//!
+//! html2text wraps and reflows `<pre>` blocks like any other text,
+//! so they are taken out of the HTML before it is rendered, put back verbatim
+//! afterwards, and highlighted with syntect, in whatever language their
+//! `class` or `lang` attributes say they are in.
+
+use scraper::{ElementRef, Html, Node, Selector};
+use std::ops::Range;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use tui::style::{Color, Modifier, Style};
+
+/// What a code block is replaced with in the HTML, followed by its index.
+/// It has no spaces, so html2text only breaks it up in the narrowest of columns.
+const PLACEHOLDER: &str = "RUSSCODEBLOCK";
+
+/// one of syntect's default themes, chosen for dark terminals
+const THEME: &str = "base16-ocean.dark";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeBlock {
+    /// the words in the block's `class` and `lang` attributes,
+    /// any of which may name its language
+    pub language_hints: Vec<String>,
+    pub code: String,
+}
+
+/// where a code block ended up in the rendered text
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeRegion {
+    pub lines: Range<usize>,
+    /// what html2text put before the block's lines, like `> ` in a blockquote
+    pub prefix: String,
+    pub block_idx: usize,
+}
+
+/// the styles of a line, as byte ranges of it
+pub type LineStyles = Vec<(Style, Range<usize>)>;
+
+/// `html` with every `<pre>` swapped for a placeholder, and the code blocks that were there
+pub fn extract(html: &str) -> (String, Vec<CodeBlock>) {
+    if !html.contains("<pre") {
+        return (html.to_owned(), vec![]);
+    }
+
+    let mut document = Html::parse_fragment(html);
+    let pre = Selector::parse("pre").expect("static selector is valid");
+
+    // a `<pre>` in another one is already part of its code
+    let pre_ids = document
+        .select(&pre)
+        .filter(|element| {
+            !element
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|ancestor| ancestor.value().name() == "pre")
+        })
+        .map(|element| element.id())
+        .collect::<Vec<_>>();
+
+    let mut blocks = vec![];
+
+    for (i, id) in pre_ids.into_iter().enumerate() {
+        let element = document
+            .tree
+            .get(id)
+            .and_then(ElementRef::wrap)
+            .expect("selected elements are elements");
+
+        blocks.push(CodeBlock {
+            language_hints: language_hints(&element),
+            code: element
+                .text()
+                .collect::<String>()
+                .trim_matches('\n')
+                .to_owned(),
+        });
+
+        let mut node = document.tree.get_mut(id).expect("selected elements exist");
+        while let Some(mut child) = node.first_child() {
+            child.detach();
+        }
+        node.append(Node::Text(scraper::node::Text {
+            text: format!("{}{}", PLACEHOLDER, i).into(),
+        }));
+    }
+
+    (document.root_element().html(), blocks)
+}
+
+fn language_hints(pre: &ElementRef) -> Vec<String> {
+    let code = Selector::parse("code").expect("static selector is valid");
+
+    std::iter::once(*pre)
+        .chain(pre.select(&code))
+        .flat_map(|element| {
+            let element = element.value();
+            [
+                element.attr("class"),
+                element.attr("lang"),
+                element.attr("data-lang"),
+            ]
+        })
+        .flatten()
+        .flat_map(|attr| attr.split_whitespace())
+        .map(|word| {
+            // `language-rust` and `lang-rust` are the common ones,
+            // GitHub uses `highlight-source-rust`
+            ["language-", "lang-", "highlight-source-"]
+                .iter()
+                .find_map(|prefix| word.strip_prefix(prefix))
+                .unwrap_or(word)
+                .to_lowercase()
+        })
+        .collect()
+}
+
+/// Put `blocks` back into `text`, which html2text rendered from HTML that `extract` returned,
+/// returning the text and where each block is in it.
+pub fn splice(text: &str, blocks: &[CodeBlock]) -> (String, Vec<CodeRegion>) {
+    if blocks.is_empty() {
+        return (text.to_owned(), vec![]);
+    }
+
+    let mut lines = vec![];
+    let mut regions = vec![];
+
+    for line in text.lines() {
+        let block = line.find(PLACEHOLDER).and_then(|idx| {
+            let block_idx: usize = line[idx + PLACEHOLDER.len()..].trim_end().parse().ok()?;
+            Some((&line[..idx], block_idx, blocks.get(block_idx)?))
+        });
+
+        match block {
+            Some((prefix, block_idx, block)) => {
+                let start = lines.len();
+                lines.extend(
+                    block
+                        .code
+                        .lines()
+                        .map(|code_line| format!("{}{}", prefix, code_line)),
+                );
+                regions.push(CodeRegion {
+                    lines: start..lines.len(),
+                    prefix: prefix.to_owned(),
+                    block_idx,
+                });
+            }
+            None => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+
+    (text, regions)
+}
+
+#[derive(Debug)]
+pub struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme: Theme,
+}
+
+impl Highlighter {
+    /// loading syntect's syntaxes takes a moment, so this is only done once there is code
+    pub fn new() -> Highlighter {
+        let mut themes = ThemeSet::load_defaults().themes;
+
+        Highlighter {
+            syntax_set: SyntaxSet::load_defaults_nonewlines(),
+            theme: themes
+                .remove(THEME)
+                .expect("syntect has its default themes"),
+        }
+    }
+
+    /// The styles of each of `lines`, which are consecutive lines of `block`,
+    /// or `None` if the block's language is unknown.
+    /// The `prefix` of a line isn't highlighted.
+    pub fn highlight(
+        &self,
+        block: &CodeBlock,
+        prefix: &str,
+        lines: &[&str],
+    ) -> Option<Vec<LineStyles>> {
+        let syntax = block
+            .language_hints
+            .iter()
+            .find_map(|hint| self.syntax_set.find_syntax_by_token(hint))?;
+
+        let mut highlight_lines = HighlightLines::new(syntax, &self.theme);
+
+        lines
+            .iter()
+            .map(|line| {
+                let (offset, code) = match line.strip_prefix(prefix) {
+                    Some(code) => (prefix.len(), code),
+                    None => (0, *line),
+                };
+
+                let mut start = offset;
+                let styles = highlight_lines
+                    .highlight_line(code, &self.syntax_set)
+                    .ok()?
+                    .into_iter()
+                    .map(|(style, piece)| {
+                        let range = start..start + piece.len();
+                        start = range.end;
+                        (tui_style(style), range)
+                    })
+                    .collect();
+
+                Some(styles)
+            })
+            .collect()
+    }
+}
+
+/// just the foreground, the terminal's background stays
+fn tui_style(style: syntect::highlighting::Style) -> Style {
+    let mut tui_style = Style::default().fg(Color::Rgb(
+        style.foreground.r,
+        style.foreground.g,
+        style.foreground.b,
+    ));
+
+    if style.font_style.contains(FontStyle::BOLD) {
+        tui_style = tui_style.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        tui_style = tui_style.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        tui_style = tui_style.add_modifier(Modifier::UNDERLINED);
+    }
+
+    tui_style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<p>Some code:</p>
+<pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+<p>and more</p>
+<pre>plain</pre>"#;
+
+    #[test]
+    fn it_keeps_code_blocks_from_being_reflowed() {
+        let (html, blocks) = extract(HTML);
+
+        assert_eq!(
+            blocks,
+            vec![
+                CodeBlock {
+                    language_hints: vec!["rust".to_string()],
+                    code: "fn main() {\n    println!(\"hi\");\n}".to_string(),
+                },
+                CodeBlock {
+                    language_hints: vec![],
+                    code: "plain".to_string(),
+                },
+            ]
+        );
+
+        let (text, regions) = splice(&html2text::from_read(html.as_bytes(), 40), &blocks);
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            lines[regions[0].lines.clone()],
+            ["fn main() {", "    println!(\"hi\");", "}"]
+        );
+        assert_eq!(lines[regions[1].lines.clone()], ["plain"]);
+    }
+
+    #[test]
+    fn it_highlights_known_languages() {
+        let highlighter = Highlighter::new();
+        let (_, blocks) = extract(HTML);
+        let lines = blocks[0].code.lines().collect::<Vec<_>>();
+
+        let styles = highlighter.highlight(&blocks[0], "", &lines).unwrap();
+        assert_eq!(styles.len(), 3);
+        assert_eq!(styles[0].last().unwrap().1.end, lines[0].len());
+        assert!(styles[0].len() > 1);
+
+        assert!(highlighter.highlight(&blocks[1], "", &["plain"]).is_none());
+    }
+}
@@ -0,0 +1,378 @@
+//! The `:` command line, for actions that don't merit their own key.
+
+use crate::rss::DateRange;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// command names, for completion
+pub const COMMAND_NAMES: &[&str] = &[
+    "add",
+    "block",
+    "blocked",
+    "delete-feed",
+    "export-starred",
+    "export-tagged",
+    "filter",
+    "header",
+    "headers",
+    "interval",
+    "mark-all-read",
+    "mark-feed-read",
+    "mute-language",
+    "muted-languages",
+    "pause",
+    "profile",
+    "quit",
+    "refresh",
+    "refresh-all",
+    "search",
+    "set",
+    "unblock",
+    "unmute-language",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// subscribe to the feed at a url
+    Add(String),
+    /// hide the entries linking to a domain, until it is unblocked
+    Block(String),
+    /// list the blocked domains
+    Blocked,
+    /// delete the selected feed, without asking for confirmation
+    DeleteFeed,
+    /// write the starred entries to Markdown files in a directory
+    ExportStarred(String),
+    /// write the entries with a tag to Markdown files in a directory
+    ExportTagged {
+        tag: String,
+        dir: String,
+    },
+    /// only list the entries from these days, or all of them again if it's empty
+    Filter(DateRange),
+    /// send a header with the selected feed's requests, or stop sending it if there's no value
+    Header {
+        name: String,
+        value: Option<String>,
+    },
+    /// list the names of the headers sent with the selected feed's requests
+    Headers,
+    /// refresh the selected feed on its own schedule, or `None` for only with the rest
+    Interval(Option<chrono::Duration>),
+    /// mark every entry in every feed as read, or only those older than this
+    MarkAllRead(Option<chrono::Duration>),
+    /// mark every entry in the selected feed as read, or only those older than this
+    MarkFeedRead(Option<chrono::Duration>),
+    /// hide the entries in a language, until it is unmuted
+    MuteLanguage(String),
+    /// list the muted languages
+    MutedLanguages,
+    /// stop or start refreshing the selected feed
+    Pause,
+    /// switch to another profile's database
+    Profile(String),
+    Quit,
+    /// refresh the selected feed
+    Refresh,
+    RefreshAll,
+    /// filter the entries, or search the open entry, like `/`
+    Search(String),
+    Set(Setting),
+    /// stop leaving out the entries linking to a domain
+    Unblock(String),
+    /// stop leaving out the entries in a language
+    UnmuteLanguage(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    /// the maximum width entries are wrapped to, 0 to wrap to the column
+    Width(u16),
+    /// only list the feeds with unread entries while showing unread entries
+    HideReadFeeds(bool),
+    /// select the next unread entry after marking one read from the entries list
+    AdvanceAfterRead(bool),
+    /// when entries are marked read, besides with `r`
+    MarkRead(crate::config::MarkRead),
+}
+
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, argument) = match s.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (s, ""),
+        };
+
+        let require_argument = |usage: &str| {
+            if argument.is_empty() {
+                Err(anyhow!("Usage: :{} {}", name, usage))
+            } else {
+                Ok(argument.to_owned())
+            }
+        };
+
+        match name {
+            "add" => Ok(Command::Add(require_argument("<url> [<fingerprint>]")?)),
+            "block" => Ok(Command::Block(require_argument("<domain>")?)),
+            "blocked" => Ok(Command::Blocked),
+            "delete-feed" => Ok(Command::DeleteFeed),
+            "export-starred" => Ok(Command::ExportStarred(require_argument("<dir>")?)),
+            "export-tagged" => {
+                let usage = "<tag> <dir>";
+                let (tag, dir) = require_argument(usage)?
+                    .split_once(char::is_whitespace)
+                    .map(|(tag, dir)| (tag.to_owned(), dir.trim().to_owned()))
+                    .ok_or_else(|| anyhow!("Usage: :{} {}", name, usage))?;
+                Ok(Command::ExportTagged { tag, dir })
+            }
+            "filter" => match argument {
+                "" | "off" => Ok(Command::Filter(DateRange::default())),
+                argument => Ok(Command::Filter(parse_date_range(
+                    argument,
+                    chrono::Local::now().date_naive(),
+                )?)),
+            },
+            "header" => {
+                let header = require_argument("<name>: <value>|<name>")?;
+                Ok(match header.split_once(':') {
+                    Some((name, value)) => Command::Header {
+                        name: name.trim().to_owned(),
+                        value: Some(value.trim().to_owned()),
+                    },
+                    None => Command::Header {
+                        name: header,
+                        value: None,
+                    },
+                })
+            }
+            "headers" => Ok(Command::Headers),
+            "interval" => match require_argument("<interval, like 2h or 1d>|off")?.as_str() {
+                "off" => Ok(Command::Interval(None)),
+                interval => Ok(Command::Interval(Some(crate::util::parse_interval(
+                    interval,
+                )?))),
+            },
+            "mark-all-read" => Ok(Command::MarkAllRead(parse_age(argument)?)),
+            "mark-feed-read" => Ok(Command::MarkFeedRead(parse_age(argument)?)),
+            "mute-language" => Ok(Command::MuteLanguage(require_argument("<language>")?)),
+            "muted-languages" => Ok(Command::MutedLanguages),
+            "pause" => Ok(Command::Pause),
+            "profile" => Ok(Command::Profile(require_argument("<name>")?)),
+            "q" | "quit" => Ok(Command::Quit),
+            "refresh" => Ok(Command::Refresh),
+            "refresh-all" => Ok(Command::RefreshAll),
+            "search" => Ok(Command::Search(require_argument("<term>")?)),
+            "set" => Ok(Command::Set(require_argument("<option>=<value>")?.parse()?)),
+            "unblock" => Ok(Command::Unblock(require_argument("<domain>")?)),
+            "unmute-language" => Ok(Command::UnmuteLanguage(require_argument("<language>")?)),
+            "" => Err(anyhow!("No command given")),
+            _ => Err(anyhow!("Unknown command: {}", name)),
+        }
+    }
+}
+
+impl FromStr for Setting {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (option, value) = s
+            .split_once('=')
+            .map(|(option, value)| (option.trim(), value.trim()))
+            .ok_or_else(|| anyhow!("Usage: :set <option>=<value>"))?;
+
+        match option {
+            "width" => Ok(Setting::Width(value.parse().map_err(|_| {
+                anyhow!("width must be a number of columns, not {}", value)
+            })?)),
+            "hide-read-feeds" => Ok(Setting::HideReadFeeds(parse_switch(option, value)?)),
+            "advance-after-read" => Ok(Setting::AdvanceAfterRead(parse_switch(option, value)?)),
+            "mark-read" => Ok(Setting::MarkRead(value.parse()?)),
+            _ => Err(anyhow!("Unknown option: {}", option)),
+        }
+    }
+}
+
+/// Whether a typed command could have a secret in it, like `:header Authorization: Bearer ...`,
+/// so it's kept out of the history, typo or not.
+pub fn has_secret(input: &str) -> bool {
+    let name = input.split_whitespace().next().unwrap_or_default();
+    name == "header"
+}
+
+/// `since <day>`, `until <day>`, or both, where a day is like `2024-01-31`,
+/// or a while before `today`, like `2w`
+fn parse_date_range(s: &str, today: chrono::NaiveDate) -> Result<DateRange> {
+    let usage = || anyhow!("Usage: :filter [since <day>] [until <day>]|off");
+    let parse_day = |day: &str| {
+        chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").or_else(|_| {
+            crate::util::parse_interval(day)
+                .map(|interval| today - interval)
+                .map_err(|_| {
+                    anyhow!(
+                        "{} isn't a day, like 2024-01-31, or a while ago, like 2w",
+                        day
+                    )
+                })
+        })
+    };
+
+    let mut date_range = DateRange::default();
+    let mut words = s.split_whitespace();
+    while let Some(bound) = words.next() {
+        let day = parse_day(words.next().ok_or_else(usage)?)?;
+        match bound {
+            "since" => date_range.since = Some(day),
+            "until" => date_range.until = Some(day),
+            _ => return Err(usage()),
+        }
+    }
+
+    Ok(date_range)
+}
+
+/// how old entries have to be, like `7d`, or every entry if there's no age
+fn parse_age(s: &str) -> Result<Option<chrono::Duration>> {
+    match s {
+        "" => Ok(None),
+        age => Ok(Some(crate::util::parse_interval(age)?)),
+    }
+}
+
+/// options that are either on or off
+fn parse_switch(option: &str, value: &str) -> Result<bool> {
+    match value {
+        "true" | "on" => Ok(true),
+        "false" | "off" => Ok(false),
+        _ => Err(anyhow!("{} must be true or false, not {}", option, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_commands() {
+        assert_eq!(
+            "add https://example.com/feed.xml"
+                .parse::<Command>()
+                .unwrap(),
+            Command::Add("https://example.com/feed.xml".to_string())
+        );
+        assert_eq!(
+            "  search  two words ".parse::<Command>().unwrap(),
+            Command::Search("two words".to_string())
+        );
+        assert_eq!(
+            "set width=100".parse::<Command>().unwrap(),
+            Command::Set(Setting::Width(100))
+        );
+        assert_eq!(
+            "export-tagged rust ~/notes/rust"
+                .parse::<Command>()
+                .unwrap(),
+            Command::ExportTagged {
+                tag: "rust".to_string(),
+                dir: "~/notes/rust".to_string()
+            }
+        );
+        assert_eq!(
+            "set hide-read-feeds=on".parse::<Command>().unwrap(),
+            Command::Set(Setting::HideReadFeeds(true))
+        );
+        assert_eq!(
+            "set advance-after-read=false".parse::<Command>().unwrap(),
+            Command::Set(Setting::AdvanceAfterRead(false))
+        );
+        assert_eq!(
+            "set mark-read=on-scroll-past".parse::<Command>().unwrap(),
+            Command::Set(Setting::MarkRead(crate::config::MarkRead::OnScrollPast))
+        );
+        assert!("set mark-read=sometimes".parse::<Command>().is_err());
+        assert_eq!(
+            "interval 3d".parse::<Command>().unwrap(),
+            Command::Interval(Some(chrono::Duration::days(3)))
+        );
+        assert_eq!(
+            "interval off".parse::<Command>().unwrap(),
+            Command::Interval(None)
+        );
+        assert_eq!(
+            "header Authorization: Bearer abc:123"
+                .parse::<Command>()
+                .unwrap(),
+            Command::Header {
+                name: "Authorization".to_string(),
+                value: Some("Bearer abc:123".to_string())
+            }
+        );
+        assert_eq!(
+            "header Accept-Language".parse::<Command>().unwrap(),
+            Command::Header {
+                name: "Accept-Language".to_string(),
+                value: None
+            }
+        );
+        assert_eq!("q".parse::<Command>().unwrap(), Command::Quit);
+        assert_eq!(
+            "mark-all-read".parse::<Command>().unwrap(),
+            Command::MarkAllRead(None)
+        );
+        assert_eq!(
+            "mark-feed-read 1w".parse::<Command>().unwrap(),
+            Command::MarkFeedRead(Some(chrono::Duration::weeks(1)))
+        );
+        assert_eq!(
+            "filter off".parse::<Command>().unwrap(),
+            Command::Filter(DateRange::default())
+        );
+    }
+
+    #[test]
+    fn it_parses_date_ranges() {
+        let day = |s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let today = day("2024-03-15");
+
+        assert_eq!(
+            parse_date_range("since 2024-01-01 until 2024-02-01", today).unwrap(),
+            DateRange {
+                since: Some(day("2024-01-01")),
+                until: Some(day("2024-02-01"))
+            }
+        );
+        assert_eq!(
+            parse_date_range("since 2w", today).unwrap(),
+            DateRange {
+                since: Some(day("2024-03-01")),
+                until: None
+            }
+        );
+        assert!(parse_date_range("since", today).is_err());
+        assert!(parse_date_range("since yesterday", today).is_err());
+        assert!(parse_date_range("before 2024-01-01", today).is_err());
+    }
+
+    #[test]
+    fn it_rejects_bad_commands() {
+        assert!("add".parse::<Command>().is_err());
+        assert!("block".parse::<Command>().is_err());
+        assert!("export-tagged rust".parse::<Command>().is_err());
+        assert!("interval soon".parse::<Command>().is_err());
+        assert!("mark-all-read everything".parse::<Command>().is_err());
+        assert!("set width=wide".parse::<Command>().is_err());
+        assert!("set height=1".parse::<Command>().is_err());
+        assert!("set hide-read-feeds=yes please".parse::<Command>().is_err());
+        assert!("frobnicate".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn it_knows_which_commands_have_secrets() {
+        assert!(has_secret(" header Authorization: Bearer abc"));
+        assert!(has_secret("header"));
+        assert!(!has_secret("headers"));
+        assert!(!has_secret("add https://example.com/feed.xml"));
+    }
+}
@@ -0,0 +1,705 @@
+//! The config file, for the settings that are too involved for command line flags.
+
+use crate::keyring::Keyring;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub ui: UiConfig,
+    pub hooks: Hooks,
+    pub viewer: ViewerConfig,
+    pub player: PlayerConfig,
+    pub speech: SpeechConfig,
+    pub wallabag: Option<WallabagConfig>,
+    pub pocket: Option<PocketConfig>,
+    pub mastodon: Option<MastodonConfig>,
+    pub maildir: Option<MaildirConfig>,
+    pub newsletters: Option<NewslettersConfig>,
+    pub translation: Option<TranslationConfig>,
+    pub backups: BackupsConfig,
+    pub rules: Vec<Rule>,
+    pub script: Option<ScriptConfig>,
+    pub actions: Vec<Action>,
+    pub sites: Vec<Site>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    pub layout: PaneLayout,
+    /// only list the feeds with unread entries while showing unread entries
+    pub hide_read_feeds: bool,
+    /// select the next unread entry after marking one read from the entries list
+    pub advance_after_read: bool,
+    pub mark_read: MarkRead,
+    pub date_format: DateFormat,
+    pub entry_columns: EntryColumns,
+    /// show a scrollbar on the right of the open entry
+    pub scrollbar: bool,
+    /// make links in entries clickable with OSC 8, which not every terminal supports
+    pub hyperlinks: bool,
+}
+
+/// how the feeds, entries, and the open entry are laid out
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneLayout {
+    /// feeds | entries, with the open entry taking the entries' place
+    #[default]
+    TwoPane,
+    /// feeds | entries | the selected entry, previewed as the selection moves
+    ThreePane,
+}
+
+impl PaneLayout {
+    /// how wide the feeds pane starts out, as a percentage of the screen
+    pub fn default_feeds_pane_percent(&self) -> u16 {
+        match self {
+            PaneLayout::TwoPane => 30,
+            PaneLayout::ThreePane => 20,
+        }
+    }
+}
+
+/// when entries are marked read, besides with `r`
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkRead {
+    /// only with `r`
+    #[default]
+    Manual,
+    /// when they're opened
+    OnOpen,
+    /// when the selection moves down past them in the entries list
+    OnScrollPast,
+}
+
+impl std::str::FromStr for MarkRead {
+    type Err = anyhow::Error;
+
+    /// as `:set` takes it, like `on-open`
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "manual" => Ok(MarkRead::Manual),
+            "on-open" => Ok(MarkRead::OnOpen),
+            "on-scroll-past" => Ok(MarkRead::OnScrollPast),
+            _ => Err(anyhow::anyhow!(
+                "mark-read must be manual, on-open, or on-scroll-past, not {}",
+                s
+            )),
+        }
+    }
+}
+
+/// How entries' dates are shown in the entries list:
+/// `"relative"`, like `3h ago`, a `strftime` format string in local time, or `"none"`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub enum DateFormat {
+    #[default]
+    Relative,
+    Absolute(String),
+    None,
+}
+
+impl TryFrom<String> for DateFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: String) -> Result<Self> {
+        match format.as_str() {
+            "relative" => Ok(DateFormat::Relative),
+            "none" => Ok(DateFormat::None),
+            _ => {
+                // chrono panics on bad formats when formatting, so catch them here
+                if chrono::format::StrftimeItems::new(&format)
+                    .any(|item| matches!(item, chrono::format::Item::Error))
+                {
+                    Err(anyhow::anyhow!("Invalid date format: {}", format))
+                } else {
+                    Ok(DateFormat::Absolute(format))
+                }
+            }
+        }
+    }
+}
+
+/// the columns of the entries list, in order
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct EntryColumns(pub Vec<EntryColumn>);
+
+impl Default for EntryColumns {
+    fn default() -> Self {
+        EntryColumns(vec![
+            EntryColumn {
+                kind: EntryColumnKind::Title,
+                width: None,
+            },
+            EntryColumn {
+                kind: EntryColumnKind::Date,
+                width: None,
+            },
+        ])
+    }
+}
+
+/// A column, written like `"author"`, or `"author:20"` to make it 20 wide.
+/// Without a width, the title takes up whatever the other columns leave,
+/// and the other columns are as wide as what is in them.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub struct EntryColumn {
+    pub kind: EntryColumnKind,
+    pub width: Option<u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryColumnKind {
+    /// a dot for unread entries
+    Read,
+    Title,
+    Date,
+    /// the entry's feed, for when the entries are from more than one
+    Feed,
+    Author,
+    /// how long the entry's content is
+    Length,
+    /// the language the entry was guessed to be in
+    Language,
+}
+
+impl TryFrom<String> for EntryColumn {
+    type Error = anyhow::Error;
+
+    fn try_from(column: String) -> Result<Self> {
+        let (kind, width) = match column.split_once(':') {
+            Some((kind, width)) => (
+                kind,
+                Some(width.parse().map_err(|_| {
+                    anyhow::anyhow!("Column width must be a number, not {}", width)
+                })?),
+            ),
+            None => (column.as_str(), None),
+        };
+
+        let kind = match kind {
+            "read" => EntryColumnKind::Read,
+            "title" => EntryColumnKind::Title,
+            "date" => EntryColumnKind::Date,
+            "feed" => EntryColumnKind::Feed,
+            "author" => EntryColumnKind::Author,
+            "length" => EntryColumnKind::Length,
+            "language" => EntryColumnKind::Language,
+            _ => return Err(anyhow::anyhow!("Unknown entry column: {}", kind)),
+        };
+
+        Ok(EntryColumn { kind, width })
+    }
+}
+
+/// shell commands run when things happen, see the README for what they are given
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hooks {
+    /// run once for every entry a refresh adds
+    pub on_new_entry: Option<String>,
+    /// run once after refreshing one or all feeds
+    pub post_refresh: Option<String>,
+    /// posted to with the entries a refresh adds to each feed
+    pub webhooks: Vec<Webhook>,
+}
+
+/// a url to post new entries to, like a Slack or Matrix bridge's, or an ntfy topic
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: String,
+    /// the feeds whose entries are posted, by title or feed url, or every feed's if empty
+    #[serde(default)]
+    pub feeds: Vec<String>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// the feed and its new entries, with a `text` summary, which Slack shows
+    #[default]
+    Json,
+    /// only the summary, as plain text, for services like ntfy that show what they're sent
+    Text,
+}
+
+impl Webhook {
+    pub fn wants(&self, feed: &crate::rss::Feed) -> bool {
+        is_one_of(&self.feeds, feed)
+    }
+}
+
+/// whether `feed` is one of `feeds`, by title or feed url, or `feeds` is empty, meaning every feed
+fn is_one_of(feeds: &[String], feed: &crate::rss::Feed) -> bool {
+    feeds.is_empty()
+        || feeds.iter().any(|wanted| {
+            feed.title
+                .as_deref()
+                .map(|title| title.eq_ignore_ascii_case(wanted))
+                .unwrap_or(false)
+                || feed.feed_link.as_deref() == Some(wanted.as_str())
+        })
+}
+
+/// Highlights the new entries it matches, however they're added, and notifies about them.
+/// Every rule that matches an entry notifies, but only the first one to highlight it does.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// the feeds it's for, by title or feed url, or every feed if empty
+    #[serde(default)]
+    pub feeds: Vec<String>,
+    /// words, separated by `|`, one of which the entry's title or categories have to have in them,
+    /// ignoring case, or every entry if there are none
+    #[serde(default)]
+    pub matches: String,
+    pub highlight: Option<crate::rss::Highlight>,
+    /// run for each entry the rule matches, like the `on_new_entry` hook
+    pub notify: Option<String>,
+}
+
+impl Rule {
+    pub fn wants(&self, feed: &crate::rss::Feed, entry: &crate::rss::Entry) -> bool {
+        let words = self
+            .matches
+            .split('|')
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        let has_word = |text: &str| {
+            let text = text.to_lowercase();
+            words.iter().any(|word| text.contains(word.as_str()))
+        };
+
+        is_one_of(&self.feeds, feed)
+            && (words.is_empty()
+                || entry.title.as_deref().map(has_word).unwrap_or(false)
+                || entry.categories.iter().any(|category| has_word(category)))
+    }
+}
+
+/// a Rhai script, with a `transform` function for new entries, and functions for `[[actions]]`
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptConfig {
+    pub path: String,
+}
+
+/// A key that calls one of the script's functions with the selected entry,
+/// in place of whatever the key did before.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Action {
+    pub key: char,
+    pub function: String,
+}
+
+/// A site whose links are subscribed to as its feeds, like a Nitter instance's,
+/// with `{name}`s in `url` for the parts of the link `feed` has in it too.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Site {
+    pub url: String,
+    pub feed: String,
+}
+
+/// what `O` opens the entry in
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewerConfig {
+    /// given the path of a file with the entry in it, defaults to `$PAGER`, then `less`
+    pub command: Option<String>,
+    /// give the viewer the entry's HTML, rather than its text as russ shows it
+    pub html: bool,
+    /// with `html`, a command the HTML is piped through, whose output the viewer gets instead
+    pub filter: Option<String>,
+}
+
+/// what `W` plays entries' links with
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerConfig {
+    /// given the link, defaults to `mpv`
+    pub command: Option<String>,
+}
+
+/// what `V` reads entries aloud with
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeechConfig {
+    /// given each paragraph on stdin, defaults to `espeak-ng`
+    pub command: Option<String>,
+}
+
+/// A Wallabag instance, and the API client and user to save entries with.
+/// The client secret and password can be left out, and kept in the keyring instead.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WallabagConfig {
+    pub url: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+impl WallabagConfig {
+    /// with the secrets that were left out of the config file filled in from the keyring
+    pub fn with_secrets(&self, keyring: &Keyring) -> Result<WallabagConfig> {
+        Ok(WallabagConfig {
+            client_secret: secret(keyring, &self.client_secret, "wallabag-client-secret")?,
+            password: secret(keyring, &self.password, "wallabag-password")?,
+            ..self.clone()
+        })
+    }
+}
+
+/// the access token comes from `russ pocket-login`, and is kept in the database
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PocketConfig {
+    pub consumer_key: String,
+}
+
+/// An instance, and an access token with the `write:statuses` scope.
+/// The access token can be left out, and kept in the keyring instead.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MastodonConfig {
+    pub url: String,
+    #[serde(default)]
+    pub access_token: String,
+}
+
+impl MastodonConfig {
+    /// with the access token filled in from the keyring, if it was left out of the config file
+    pub fn with_secrets(&self, keyring: &Keyring) -> Result<MastodonConfig> {
+        Ok(MastodonConfig {
+            access_token: secret(keyring, &self.access_token, "mastodon-access-token")?,
+            ..self.clone()
+        })
+    }
+}
+
+/// What `Y` translates entries with, and to. The API key can be left out,
+/// and kept in the keyring instead.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TranslationConfig {
+    #[serde(default)]
+    pub service: TranslationService,
+    /// for `command`, given the entry's HTML on stdin, printing it translated
+    pub command: Option<String>,
+    /// LibreTranslate's, or DeepL's, when it isn't the usual one
+    pub url: Option<String>,
+    #[serde(default)]
+    pub api_key: String,
+    /// the language to translate to, like `en`
+    #[serde(default = "default_translation_target")]
+    pub target: String,
+}
+
+fn default_translation_target() -> String {
+    "en".to_string()
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationService {
+    #[default]
+    Command,
+    Libretranslate,
+    Deepl,
+}
+
+impl TranslationConfig {
+    /// with the API key filled in from the keyring, if it was left out of the config file.
+    /// Only DeepL has to have one.
+    pub fn with_secrets(&self, keyring: &Keyring) -> Result<TranslationConfig> {
+        let api_key = match self.service {
+            TranslationService::Command => String::new(),
+            TranslationService::Libretranslate if self.api_key.is_empty() => {
+                keyring.get("translation-api-key")?.unwrap_or_default()
+            }
+            _ => secret(keyring, &self.api_key, "translation-api-key")?,
+        };
+
+        Ok(TranslationConfig {
+            api_key,
+            ..self.clone()
+        })
+    }
+}
+
+/// the secret from the config file, or from the keyring, where `russ store-secret` puts it
+fn secret(keyring: &Keyring, configured: &str, name: &str) -> Result<String> {
+    if !configured.is_empty() {
+        return Ok(configured.to_owned());
+    }
+
+    keyring.get(name)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Add {} to the config file, or store it in the keyring with `russ store-secret {}`",
+            name,
+            name
+        )
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MaildirConfig {
+    /// where `russ export-maildir` exports to, with a folder in it for each feed
+    pub path: String,
+    /// export new entries after every refresh, too
+    #[serde(default)]
+    pub export_after_refresh: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NewslettersConfig {
+    /// where newsletters are delivered to, with a feed for each sender
+    pub maildir: String,
+    /// a command that fetches new mail into `maildir`, like from IMAP, run before it's read
+    pub sync_command: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupsConfig {
+    /// how many of the daily backups to keep, with 0 for none at all
+    pub keep: usize,
+}
+
+impl Default for BackupsConfig {
+    fn default() -> Self {
+        BackupsConfig { keep: 3 }
+    }
+}
+
+impl Config {
+    /// Load the config from `path`, or from `$XDG_CONFIG_HOME/russ/config.toml` if there is no `path`.
+    /// It's fine for the default config file not to exist, but not one that was asked for.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match default_config_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let config = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read config file {}", path.display()))?;
+
+        toml::from_str(&config)
+            .with_context(|| format!("Unable to parse config file {}", path.display()))
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "russ")
+        .map(|project_dirs| project_dirs.config_dir().join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_hooks() {
+        let config: Config = toml::from_str(
+            r#"
+            [hooks]
+            on_new_entry = "notify-send \"$RUSS_ENTRY_TITLE\""
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.hooks.on_new_entry.as_deref(),
+            Some("notify-send \"$RUSS_ENTRY_TITLE\"")
+        );
+        assert_eq!(config.hooks.post_refresh, None);
+
+        assert_eq!(toml::from_str::<Config>("").unwrap(), Config::default());
+        assert!(toml::from_str::<Config>("[hooks]\non_new_entyr = \"x\"").is_err());
+    }
+
+    #[test]
+    fn it_parses_webhooks() {
+        let config: Config = toml::from_str(
+            r#"
+            [[hooks.webhooks]]
+            url = "https://hooks.slack.com/services/x"
+
+            [[hooks.webhooks]]
+            url = "https://ntfy.sh/russ"
+            feeds = ["example", "https://other.com/feed.xml"]
+            format = "text"
+            "#,
+        )
+        .unwrap();
+
+        let webhooks = &config.hooks.webhooks;
+        assert_eq!(webhooks[0].format, WebhookFormat::Json);
+        assert_eq!(webhooks[1].format, WebhookFormat::Text);
+
+        let mut feed =
+            crate::rss::Feed::new(Some("Example".to_string()), None, crate::rss::FeedKind::Rss);
+        assert!(webhooks[0].wants(&feed));
+        assert!(webhooks[1].wants(&feed));
+        feed.title = Some("Other".to_string());
+        assert!(!webhooks[1].wants(&feed));
+        feed.feed_link = Some("https://other.com/feed.xml".to_string());
+        assert!(webhooks[1].wants(&feed));
+    }
+
+    #[test]
+    fn it_parses_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            feeds = ["LWN.net"]
+            matches = "security | CVE"
+            highlight = "red"
+            notify = "notify-send \"$RUSS_ENTRY_TITLE\""
+            "#,
+        )
+        .unwrap();
+
+        let rule = &config.rules[0];
+        assert_eq!(rule.highlight, Some(crate::rss::Highlight::Red));
+
+        let feed =
+            crate::rss::Feed::new(Some("lwn.net".to_string()), None, crate::rss::FeedKind::Rss);
+        let mut item = rss::Item::default();
+        item.set_title("[$] A kernel security fix".to_string());
+        let mut entry = crate::rss::Entry::from(&item);
+        assert!(rule.wants(&feed, &entry));
+        entry.title = Some("Kernel release".to_string());
+        assert!(!rule.wants(&feed, &entry));
+        entry.categories = vec!["cve".to_string()];
+        assert!(rule.wants(&feed, &entry));
+
+        let other_feed =
+            crate::rss::Feed::new(Some("Other".to_string()), None, crate::rss::FeedKind::Rss);
+        assert!(!rule.wants(&other_feed, &entry));
+
+        assert!(toml::from_str::<Config>("[[rules]]\nhighlight = \"plaid\"").is_err());
+    }
+
+    #[test]
+    fn it_parses_the_script_and_actions() {
+        let config: Config = toml::from_str(
+            r#"
+            [script]
+            path = "~/.config/russ/russ.rhai"
+
+            [[actions]]
+            key = "S"
+            function = "archive"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.script,
+            Some(ScriptConfig {
+                path: "~/.config/russ/russ.rhai".to_string()
+            })
+        );
+        assert_eq!(
+            config.actions,
+            vec![Action {
+                key: 'S',
+                function: "archive".to_string(),
+            }]
+        );
+        assert!(toml::from_str::<Config>("[[actions]]\nkey = \"ab\"\nfunction = \"x\"").is_err());
+    }
+
+    #[test]
+    fn it_parses_the_layout() {
+        let config: Config = toml::from_str("[ui]\nlayout = \"three_pane\"").unwrap();
+        assert_eq!(config.ui.layout, PaneLayout::ThreePane);
+        assert_eq!(Config::default().ui.layout, PaneLayout::TwoPane);
+        assert!(toml::from_str::<Config>("[ui]\nlayout = \"four_pane\"").is_err());
+    }
+
+    #[test]
+    fn it_parses_date_formats() {
+        let parse = |date_format: &str| {
+            toml::from_str::<Config>(&format!("[ui]\ndate_format = \"{}\"", date_format))
+                .map(|config| config.ui.date_format)
+        };
+
+        assert_eq!(parse("relative").unwrap(), DateFormat::Relative);
+        assert_eq!(parse("none").unwrap(), DateFormat::None);
+        assert_eq!(
+            parse("%Y-%m-%d").unwrap(),
+            DateFormat::Absolute("%Y-%m-%d".to_string())
+        );
+        assert!(parse("%Q").is_err());
+        assert_eq!(Config::default().ui.date_format, DateFormat::Relative);
+    }
+
+    #[test]
+    fn it_parses_entry_columns() {
+        let config: Config =
+            toml::from_str("[ui]\nentry_columns = [\"read\", \"title\", \"feed:12\"]").unwrap();
+
+        assert_eq!(
+            config.ui.entry_columns,
+            EntryColumns(vec![
+                EntryColumn {
+                    kind: EntryColumnKind::Read,
+                    width: None
+                },
+                EntryColumn {
+                    kind: EntryColumnKind::Title,
+                    width: None
+                },
+                EntryColumn {
+                    kind: EntryColumnKind::Feed,
+                    width: Some(12)
+                },
+            ])
+        );
+        assert!(toml::from_str::<Config>("[ui]\nentry_columns = [\"feed:wide\"]").is_err());
+        assert!(toml::from_str::<Config>("[ui]\nentry_columns = [\"score\"]").is_err());
+    }
+
+    #[test]
+    fn it_requires_every_wallabag_setting() {
+        let config: Config = toml::from_str(
+            r#"
+            [wallabag]
+            url = "https://app.wallabag.it"
+            client_id = "id"
+            client_secret = "secret"
+            username = "me"
+            password = "hunter2"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.wallabag.unwrap().username, "me");
+        assert!(toml::from_str::<Config>("[wallabag]\nurl = \"https://app.wallabag.it\"").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Reading the dates feeds put on their entries, which are meant to be RFC 822 or RFC 3339,
+//! but are often neither: named timezones RFC 822 doesn't have, the wrong day of the week,
+//! full month names, `1st`s and `2nd`s, or seconds since the epoch.
+//! Whatever they were written as, they're stored in UTC, and shown in local time.
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+/// timezones feeds name, and their offsets from UTC in minutes
+const TIMEZONES: &[(&str, i32)] = &[
+    ("UTC", 0),
+    ("GMT", 0),
+    ("UT", 0),
+    ("Z", 0),
+    ("WET", 0),
+    ("WEST", 60),
+    ("BST", 60),
+    ("CET", 60),
+    ("MET", 60),
+    ("CEST", 120),
+    ("MEST", 120),
+    ("EET", 120),
+    ("EEST", 180),
+    ("MSK", 180),
+    ("IST", 330),
+    ("SGT", 480),
+    ("HKT", 480),
+    ("AWST", 480),
+    ("JST", 540),
+    ("KST", 540),
+    ("ACST", 570),
+    ("AEST", 600),
+    ("ACDT", 630),
+    ("AEDT", 660),
+    ("NZST", 720),
+    ("NZDT", 780),
+    ("NST", -210),
+    ("ADT", -180),
+    ("AST", -240),
+    ("EDT", -240),
+    ("EST", -300),
+    ("CDT", -300),
+    ("CST", -360),
+    ("MDT", -360),
+    ("MST", -420),
+    ("PDT", -420),
+    ("PST", -480),
+    ("AKDT", -480),
+    ("AKST", -540),
+    ("HST", -600),
+];
+
+const MONTHS: &[(&str, &str)] = &[
+    ("january", "Jan"),
+    ("february", "Feb"),
+    ("march", "Mar"),
+    ("april", "Apr"),
+    ("june", "Jun"),
+    ("july", "Jul"),
+    ("august", "Aug"),
+    ("september", "Sep"),
+    ("sept", "Sep"),
+    ("october", "Oct"),
+    ("november", "Nov"),
+    ("december", "Dec"),
+];
+
+const WEEKDAYS: &[&str] = &[
+    "mon",
+    "monday",
+    "tue",
+    "tues",
+    "tuesday",
+    "wed",
+    "wednesday",
+    "thu",
+    "thur",
+    "thurs",
+    "thursday",
+    "fri",
+    "friday",
+    "sat",
+    "saturday",
+    "sun",
+    "sunday",
+];
+
+/// ISO 8601 dates and times with an offset, which `diligent_date_parser` would
+/// otherwise read the date and time of, and drop the offset from
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%dT%H:%M:%S%.f %z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%Y-%m-%dT%H:%M%z",
+    "%Y-%m-%dT%H:%M %z",
+    "%Y-%m-%d %H:%M%z",
+    "%Y-%m-%d %H:%M %z",
+];
+
+/// `date`, in UTC, trying the formats feeds are meant to use first,
+/// then tidying up the ways they get them wrong. Dates without a timezone are taken to be in UTC.
+pub fn parse(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.split_whitespace().collect::<Vec<_>>().join(" ");
+    if date.is_empty() {
+        return None;
+    }
+
+    let date = with_numeric_offset(&date);
+    let tidied = tidy(&date);
+
+    parse_formats(&date)
+        .or_else(|| (tidied != date).then(|| parse_formats(&tidied)).flatten())
+        .map(|date| date.with_timezone(&Utc))
+        .or_else(|| timestamp(&date))
+}
+
+fn parse_formats(date: &str) -> Option<DateTime<FixedOffset>> {
+    OFFSET_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(date, format).ok())
+        .or_else(|| diligent_date_parser::parse_date(date))
+}
+
+/// `date` with its named timezone, like `CEST`, `(PST)`, or `GMT+2`, as an offset, like `+0200`
+fn with_numeric_offset(date: &str) -> String {
+    let (rest, zone) = match date.rsplit_once(' ') {
+        Some((rest, zone)) => (rest, zone),
+        None => return date.to_owned(),
+    };
+    let zone = zone.trim_matches(|c| c == '(' || c == ')');
+
+    // like `GMT+2` or `UTC-05:30`
+    let (name, adjustment) = match zone.find(['+', '-']) {
+        Some(i) if i > 0 => (&zone[..i], parse_offset(&zone[i..])),
+        _ => (zone, Some(0)),
+    };
+
+    let offset = TIMEZONES
+        .iter()
+        .find(|(timezone, _)| timezone.eq_ignore_ascii_case(name))
+        .and_then(|(_, offset)| Some(offset + adjustment?));
+
+    match offset {
+        Some(offset) => format!(
+            "{} {}{:02}{:02}",
+            rest,
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 60,
+            offset.abs() % 60
+        ),
+        None => date.to_owned(),
+    }
+}
+
+/// minutes, from an offset like `+2`, `-0530`, or `+05:30`
+fn parse_offset(offset: &str) -> Option<i32> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits = offset[1..].replace(':', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        3 | 4 => {
+            let (hours, minutes) = digits.split_at(digits.len() - 2);
+            (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?)
+        }
+        _ => return None,
+    };
+
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// `date` without its day of the week, which is often wrong, with its month abbreviated,
+/// without `st`, `nd`, `rd`, and `th` after its day, and with `-` rather than `/` in ISO dates
+fn tidy(date: &str) -> String {
+    let mut words = date
+        .split(' ')
+        .map(|word| {
+            let bare = word.trim_end_matches(',');
+            let lowercase = bare.to_lowercase();
+
+            if let Some((_, abbreviation)) = MONTHS.iter().find(|(month, _)| *month == lowercase) {
+                return word.replacen(bare, abbreviation, 1);
+            }
+
+            // 1st, 2nd, 3rd, 4th
+            let digits = bare.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            if !digits.is_empty()
+                && digits.len() <= 2
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && ["st", "nd", "rd", "th"].contains(&&lowercase[digits.len()..])
+            {
+                return word.replacen(bare, digits, 1);
+            }
+
+            // like 2024/01/02
+            if bare.len() == 10 && bare.chars().filter(|c| *c == '/').count() == 2 {
+                if let Some(4) = bare.find('/') {
+                    return word.replace('/', "-");
+                }
+            }
+
+            word.to_owned()
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(first) = words.first() {
+        if WEEKDAYS.contains(&first.trim_end_matches([',', '.']).to_lowercase().as_str()) {
+            words.remove(0);
+        }
+    }
+
+    words.join(" ")
+}
+
+/// seconds since the epoch, or milliseconds
+fn timestamp(date: &str) -> Option<DateTime<Utc>> {
+    if !date.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let number = date.parse::<i64>().ok()?;
+    match date.len() {
+        9 | 10 => Utc.timestamp_opt(number, 0).single(),
+        12 | 13 => Utc.timestamp_millis_opt(number).single(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date: &str) -> Option<DateTime<Utc>> {
+        Some(
+            DateTime::parse_from_rfc3339(date)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn it_parses_the_formats_feeds_are_meant_to_use() {
+        assert_eq!(
+            parse("Mon, 01 Jan 2024 10:00:00 +0200"),
+            utc("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse("Mon, 01 Jan 2024 10:00:00 EST"),
+            utc("2024-01-01T15:00:00Z")
+        );
+        assert_eq!(
+            parse(" 2024-01-01T10:00:00.5-05:00 "),
+            utc("2024-01-01T15:00:00.5Z")
+        );
+        assert_eq!(parse("2024-01-01"), utc("2024-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn it_parses_the_ways_feeds_get_dates_wrong() {
+        // the 1st was a Monday
+        assert_eq!(
+            parse("Tue, 01 Jan 2024 10:00:00 GMT"),
+            utc("2024-01-01T10:00:00Z")
+        );
+        assert_eq!(
+            parse("Monday, 01 January 2024 10:00:00 CEST"),
+            utc("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse("2024-01-01T10:00:00 CEST"),
+            utc("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse("2024-01-01 10:00:00 +0200"),
+            utc("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse("Mon, 01 Jan 2024 10:00:00 GMT+2"),
+            utc("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            parse("Mon, 01 Jan 2024 10:00:00 (PST)"),
+            utc("2024-01-01T18:00:00Z")
+        );
+        assert_eq!(parse("January 2nd, 2024"), utc("2024-01-02T00:00:00Z"));
+        assert_eq!(parse("2024/01/02 10:00:00"), utc("2024-01-02T10:00:00Z"));
+        assert_eq!(parse("1704067200"), utc("2024-01-01T00:00:00Z"));
+        assert_eq!(parse("1704067200000"), utc("2024-01-01T00:00:00Z"));
+        assert_eq!(parse("yesterday"), None);
+        assert_eq!(parse("  "), None);
+    }
+}
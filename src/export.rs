@@ -0,0 +1,190 @@
+//! Exporting entries out of russ, for keeping them somewhere else.
+
+use crate::rss::EntryMeta;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// the width entry content is wrapped to in exported files
+const EXPORT_LINE_LENGTH: usize = 80;
+
+/// Write each entry to its own Markdown file in `dir`, named for its date and title,
+/// with its feed, link, tags, content, and note.
+/// Files from an earlier export of the same entries are overwritten.
+///
+/// Returns how many entries were exported.
+pub fn export_markdown(
+    conn: &rusqlite::Connection,
+    entries: &[EntryMeta],
+    dir: &Path,
+) -> Result<usize> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Unable to create {}", dir.display()))?;
+
+    let feed_titles = crate::rss::get_feeds(conn)?
+        .into_iter()
+        .map(|feed| (feed.id, feed.title.or(feed.feed_link)))
+        .collect::<HashMap<_, _>>();
+
+    let mut file_names = HashSet::new();
+
+    for entry in entries {
+        let content = crate::rss::get_entry_content(conn, entry.id)?;
+        let html = content
+            .full_content
+            .or(content.content)
+            .or(content.description);
+        let note = crate::rss::get_entry_note(conn, entry.id)?;
+        let feed_title = feed_titles.get(&entry.feed_id).cloned().flatten();
+
+        let markdown = entry_markdown(
+            entry,
+            feed_title.as_deref(),
+            html.as_deref(),
+            note.as_deref(),
+        );
+
+        let mut file_name = entry_file_name(entry);
+        // two entries with the same date and title should not overwrite each other
+        if !file_names.insert(file_name.clone()) {
+            file_name = format!("{}-{}.md", file_name.trim_end_matches(".md"), entry.id);
+            file_names.insert(file_name.clone());
+        }
+
+        let path = dir.join(&file_name);
+        std::fs::write(&path, markdown)
+            .with_context(|| format!("Unable to write {}", path.display()))?;
+    }
+
+    Ok(entries.len())
+}
+
+fn entry_markdown(
+    entry: &EntryMeta,
+    feed_title: Option<&str>,
+    html: Option<&str>,
+    note: Option<&str>,
+) -> String {
+    let mut markdown = String::new();
+
+    markdown.push_str("# ");
+    markdown.push_str(entry.title.as_deref().unwrap_or("Untitled"));
+    markdown.push_str("\n\n");
+
+    if let Some(feed_title) = feed_title {
+        markdown.push_str(&format!("- Feed: {}\n", feed_title));
+    }
+
+    if let Some(author) = &entry.author {
+        markdown.push_str(&format!("- Author: {}\n", author));
+    }
+
+    markdown.push_str(&format!(
+        "- Date: {}\n",
+        entry
+            .pub_date
+            .unwrap_or(entry.inserted_at)
+            .format("%Y-%m-%d")
+    ));
+
+    if let Some(link) = &entry.link {
+        markdown.push_str(&format!("- Link: <{}>\n", link));
+    }
+
+    if !entry.tags.is_empty() {
+        markdown.push_str(&format!("- Tags: {}\n", entry.tags.join(", ")));
+    }
+
+    if let Some(html) = html {
+        markdown.push('\n');
+        markdown.push_str(&html2text::from_read(html.as_bytes(), EXPORT_LINE_LENGTH));
+    }
+
+    if let Some(note) = note {
+        markdown.push_str("\n## Note\n\n");
+        markdown.push_str(note);
+        markdown.push('\n');
+    }
+
+    markdown
+}
+
+/// like `2024-01-02-some-title.md`
+fn entry_file_name(entry: &EntryMeta) -> String {
+    let date = entry
+        .pub_date
+        .unwrap_or(entry.inserted_at)
+        .format("%Y-%m-%d");
+
+    let mut slug = String::new();
+    for c in entry.title.as_deref().unwrap_or("untitled").chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    // stay well under common file name length limits
+    let slug = match slug.char_indices().nth(60) {
+        Some((i, _)) => slug[..i].trim_end_matches('-'),
+        None => slug,
+    };
+
+    if slug.is_empty() {
+        format!("{}-{}.md", date, entry.id)
+    } else {
+        format!("{}-{}.md", date, slug)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_meta(title: &str) -> EntryMeta {
+        EntryMeta {
+            id: 7,
+            feed_id: 1,
+            title: Some(title.to_string()),
+            author: None,
+            pub_date: Some("2024-01-02T03:04:05Z".parse().unwrap()),
+            link: Some("https://example.com/a".to_string()),
+            read_at: None,
+            inserted_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            starred_at: None,
+            tags: vec!["rust".to_string()],
+            modified_at: None,
+            categories: vec![],
+            content_len: 0,
+            comments_link: None,
+            highlight: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn it_names_files_for_the_date_and_title() {
+        assert_eq!(
+            entry_file_name(&entry_meta("What's new in Rust 1.60?")),
+            "2024-01-02-what-s-new-in-rust-1-60.md"
+        );
+        assert_eq!(entry_file_name(&entry_meta("???")), "2024-01-02-7.md");
+    }
+
+    #[test]
+    fn it_writes_markdown() {
+        let markdown = entry_markdown(
+            &entry_meta("A title"),
+            Some("A feed"),
+            Some("<p>Some content</p>"),
+            Some("a note"),
+        );
+
+        assert!(markdown.starts_with("# A title\n\n- Feed: A feed\n- Date: 2024-01-02\n"));
+        assert!(markdown.contains("- Link: <https://example.com/a>\n- Tags: rust\n"));
+        assert!(markdown.contains("\nSome content\n"));
+        assert!(markdown.ends_with("## Note\n\na note\n"));
+    }
+}
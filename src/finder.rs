@@ -0,0 +1,178 @@
+//! The fuzzy finder, for jumping straight to any feed or entry by its title.
+
+use crate::rss::{EntryId, FeedId};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FinderTarget {
+    Feed(FeedId),
+    Entry(FeedId, EntryId),
+}
+
+#[derive(Clone, Debug)]
+pub struct FinderItem {
+    pub target: FinderTarget,
+    /// what is shown, and matched against
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Finder {
+    items: Vec<FinderItem>,
+    pub input: String,
+    /// indexes into `items`, best match first
+    matches: Vec<usize>,
+    pub selection: usize,
+}
+
+impl Finder {
+    pub fn new(items: Vec<FinderItem>) -> Self {
+        let mut finder = Finder {
+            items,
+            input: String::new(),
+            matches: vec![],
+            selection: 0,
+        };
+        finder.update_matches();
+        finder
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.update_matches();
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.update_matches();
+    }
+
+    pub fn next(&mut self) {
+        if self.selection + 1 < self.matches.len() {
+            self.selection += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selection = self.selection.saturating_sub(1);
+    }
+
+    pub fn selected(&self) -> Option<&FinderItem> {
+        self.matches
+            .get(self.selection)
+            .map(|&item_idx| &self.items[item_idx])
+    }
+
+    pub fn matches(&self) -> impl Iterator<Item = &FinderItem> {
+        self.matches.iter().map(|&item_idx| &self.items[item_idx])
+    }
+
+    pub fn matches_len(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored_matches = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(item_idx, item)| {
+                score(&self.input, &item.text).map(|score| (score, item_idx))
+            })
+            .collect::<Vec<_>>();
+
+        // the sort is stable, so equally good matches stay in their original order
+        scored_matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        self.matches = scored_matches
+            .into_iter()
+            .map(|(_, item_idx)| item_idx)
+            .collect();
+        self.selection = 0;
+    }
+}
+
+/// How well `pattern` matches `candidate`, or `None` if it doesn't.
+///
+/// Every character of `pattern` has to appear in `candidate`, in order,
+/// ignoring case. Characters matched right after the previous match,
+/// or at the start of a word, score higher.
+pub fn score(pattern: &str, candidate: &str) -> Option<i64> {
+    let mut pattern = pattern.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_char: Option<char> = None;
+
+    for c in candidate.chars().flat_map(char::to_lowercase) {
+        let pattern_char = match pattern.peek() {
+            Some(pattern_char) => *pattern_char,
+            None => break,
+        };
+
+        if c == pattern_char {
+            pattern.next();
+            score += 1;
+
+            if previous_matched {
+                score += 5;
+            }
+
+            let at_word_start = match previous_char {
+                Some(previous) => !previous.is_alphanumeric(),
+                None => true,
+            };
+
+            if at_word_start {
+                score += 3;
+            }
+
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+
+        previous_char = Some(c);
+    }
+
+    if pattern.peek().is_none() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_scores_subsequences() {
+        assert!(score("rst", "Rust blog").is_some());
+        assert!(score("tsr", "Rust blog").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+
+        // consecutive characters and word starts beat scattered ones
+        assert!(score("blog", "Rust blog").unwrap() > score("blog", "bad long ago").unwrap());
+    }
+
+    #[test]
+    fn it_sorts_the_best_matches_first() {
+        let item = |id, text: &str| FinderItem {
+            target: FinderTarget::Feed(id),
+            text: text.to_string(),
+        };
+
+        let mut finder = Finder::new(vec![
+            item(1, "bad long ago"),
+            item(2, "Rust blog"),
+            item(3, "nothing"),
+        ]);
+        assert_eq!(finder.matches_len(), 3);
+
+        for c in "blog".chars() {
+            finder.push(c);
+        }
+
+        assert_eq!(finder.matches_len(), 2);
+        assert_eq!(finder.selected().unwrap().target, FinderTarget::Feed(2));
+    }
+}
@@ -0,0 +1,218 @@
+//! What's been typed into the command and search inputs before, kept in the database
+//! so it's there across sessions. `up` and `down` go through it, and `ctrl-r` searches it
+//! for what's typed next, like a shell's reverse search.
+
+/// how many inputs of each kind are kept
+pub const HISTORY_LIMIT: usize = 500;
+
+#[derive(Debug, Default)]
+pub struct History {
+    /// oldest first, without duplicates
+    inputs: Vec<String>,
+    /// which input is being shown, if any
+    position: Option<usize>,
+    /// what was typed before going through the history, to come back to
+    draft: String,
+    /// what's being searched for with `ctrl-r`, if anything
+    search: Option<String>,
+}
+
+impl History {
+    pub fn new(inputs: Vec<String>) -> Self {
+        History {
+            inputs,
+            ..Default::default()
+        }
+    }
+
+    /// start again from the newest input, for a new command or search
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+        self.search = None;
+    }
+
+    /// Add `input`, moving it to the end if it was already there.
+    /// Whether it should be saved, which it shouldn't if it's empty.
+    pub fn push(&mut self, input: &str) -> bool {
+        self.reset();
+
+        if input.trim().is_empty() {
+            return false;
+        }
+
+        self.inputs.retain(|existing| existing != input);
+        self.inputs.push(input.to_owned());
+        if self.inputs.len() > HISTORY_LIMIT {
+            self.inputs.remove(0);
+        }
+
+        true
+    }
+
+    /// show the input before the one shown, remembering what was typed
+    pub fn previous(&mut self, input: &mut String) {
+        self.search = None;
+
+        let position = match self.position {
+            Some(position) => position.saturating_sub(1),
+            None => match self.inputs.len().checked_sub(1) {
+                Some(position) => {
+                    self.draft = input.clone();
+                    position
+                }
+                None => return,
+            },
+        };
+
+        self.position = Some(position);
+        *input = self.inputs[position].clone();
+    }
+
+    /// show the input after the one shown, or what was typed, after the newest
+    pub fn next(&mut self, input: &mut String) {
+        self.search = None;
+
+        if let Some(position) = self.position {
+            if position + 1 < self.inputs.len() {
+                self.position = Some(position + 1);
+                *input = self.inputs[position + 1].clone();
+            } else {
+                self.position = None;
+                *input = std::mem::take(&mut self.draft);
+            }
+        }
+    }
+
+    /// what `ctrl-r` is searching for, if it is
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    /// `ctrl-r`: start searching, or show the next older match
+    pub fn search_older(&mut self, input: &mut String) {
+        match &self.search {
+            Some(_) => {
+                let before = self.position.unwrap_or(self.inputs.len());
+                self.find_match(before, input);
+            }
+            None => {
+                if self.position.is_none() {
+                    self.draft = input.clone();
+                }
+                self.search = Some(String::new());
+                self.find_match(self.inputs.len(), input);
+            }
+        }
+    }
+
+    /// add `c` to what's being searched for, and show the newest match from the one shown
+    pub fn push_search(&mut self, c: char, input: &mut String) {
+        if let Some(search) = &mut self.search {
+            search.push(c);
+            let from = self
+                .position
+                .map_or(self.inputs.len(), |position| position + 1);
+            self.find_match(from, input);
+        }
+    }
+
+    pub fn pop_search(&mut self, input: &mut String) {
+        if let Some(search) = &mut self.search {
+            search.pop();
+            self.find_match(self.inputs.len(), input);
+        }
+    }
+
+    /// stop searching, keeping the match
+    pub fn finish_search(&mut self) {
+        self.search = None;
+    }
+
+    /// stop searching, going back to what was typed
+    pub fn cancel_search(&mut self, input: &mut String) {
+        if self.search.take().is_some() {
+            self.position = None;
+            *input = std::mem::take(&mut self.draft);
+        }
+    }
+
+    /// show the newest input before `before` with the search in it, if there is one
+    fn find_match(&mut self, before: usize, input: &mut String) {
+        let search = self.search.as_deref().unwrap_or_default().to_lowercase();
+
+        let position = self.inputs[..before.min(self.inputs.len())]
+            .iter()
+            .rposition(|existing| existing.to_lowercase().contains(&search));
+
+        if let Some(position) = position {
+            self.position = Some(position);
+            *input = self.inputs[position].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_goes_through_the_history() {
+        let mut history = History::new(vec!["set width=80".to_string(), "tag rust".to_string()]);
+        assert!(history.push("set width=80"));
+        assert!(!history.push("  "));
+
+        let mut input = "ta".to_string();
+        history.previous(&mut input);
+        assert_eq!(input, "set width=80");
+        history.previous(&mut input);
+        assert_eq!(input, "tag rust");
+        history.previous(&mut input);
+        assert_eq!(input, "tag rust");
+        history.next(&mut input);
+        assert_eq!(input, "set width=80");
+        history.next(&mut input);
+        assert_eq!(input, "ta");
+    }
+
+    #[test]
+    fn it_searches_the_history() {
+        let mut history = History::new(vec![
+            "tag rust".to_string(),
+            "set width=80".to_string(),
+            "tag news".to_string(),
+        ]);
+
+        let mut input = "typed".to_string();
+        history.search_older(&mut input);
+        assert_eq!(history.search(), Some(""));
+        assert_eq!(input, "tag news");
+
+        for c in "Wid".chars() {
+            history.push_search(c, &mut input);
+        }
+        assert_eq!(input, "set width=80");
+
+        history.pop_search(&mut input);
+        history.pop_search(&mut input);
+        history.pop_search(&mut input);
+        history.push_search('t', &mut input);
+        assert_eq!(input, "tag news");
+        history.search_older(&mut input);
+        assert_eq!(input, "set width=80");
+        history.search_older(&mut input);
+        assert_eq!(input, "tag rust");
+        // nothing older matches
+        history.search_older(&mut input);
+        assert_eq!(input, "tag rust");
+
+        history.cancel_search(&mut input);
+        assert_eq!(history.search(), None);
+        assert_eq!(input, "typed");
+
+        history.search_older(&mut input);
+        history.push_search('r', &mut input);
+        history.finish_search();
+        assert_eq!(input, "tag rust");
+    }
+}
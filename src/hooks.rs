@@ -0,0 +1,248 @@
+//! Running the hooks from the config file, and posting to its webhooks,
+//! so notifications, archiving, and the like can be built outside of russ.
+//! The config's rules are applied here too, as they're run on the same new entries.
+
+use crate::config::{Hooks, Rule, Webhook, WebhookFormat};
+use crate::pipe::{run_with_stdin, shell_command};
+use crate::rss::{Entry, Feed, FeedId};
+use anyhow::{Context, Result};
+
+/// The `on_new_entry` hook, once for each new entry, with the entry as JSON on stdin
+/// and its title and link in `RUSS_ENTRY_TITLE` and `RUSS_ENTRY_LINK`,
+/// like piping an entry.
+pub fn on_new_entries(hooks: &Hooks, feed: &Feed, entries: &[Entry]) -> Result<()> {
+    let hook = match &hooks.on_new_entry {
+        Some(hook) => hook,
+        None => return Ok(()),
+    };
+
+    for entry in entries {
+        run_for_entry(hook, feed, entry).context("The on_new_entry hook failed")?;
+    }
+
+    Ok(())
+}
+
+/// What a feed's new entries go through however they're added, refreshing the feed
+/// or subscribing to it. It failing doesn't fail adding them, so it's reported to `on_error`.
+pub struct NewEntryRules<'a> {
+    /// its `transform`, before the entries are added
+    pub script: Option<&'a crate::scripting::Script>,
+    /// after they're added
+    pub rules: &'a [Rule],
+    pub on_error: &'a dyn Fn(anyhow::Error),
+}
+
+impl Default for NewEntryRules<'_> {
+    fn default() -> Self {
+        NewEntryRules {
+            script: None,
+            rules: &[],
+            on_error: &|e| log::warn!("{:?}", e),
+        }
+    }
+}
+
+impl NewEntryRules<'_> {
+    /// Leave out the entries the script's `transform` drops, and rewrite the rest,
+    /// keeping the tags it gives them in `tags`, for `tag`.
+    pub fn transform(
+        &self,
+        feed: &Feed,
+        entries: &mut Vec<Entry>,
+        tags: &mut Vec<(String, String)>,
+    ) {
+        if let Some(script) = self.script {
+            if let Err(e) = crate::scripting::transform_entries(script, feed, entries, tags) {
+                (self.on_error)(e);
+            }
+        }
+    }
+
+    /// Tag the feed's entries, by link, with the tags `transform` gave them, now they've been added.
+    pub fn tag(&self, conn: &mut rusqlite::Connection, feed_id: FeedId, tags: &[(String, String)]) {
+        for (link, tag) in tags {
+            let tagged =
+                crate::rss::get_entry_id_by_link(conn, feed_id, link).and_then(|entry_id| {
+                    match entry_id {
+                        Some(entry_id) => crate::rss::set_entries_tag(conn, &[entry_id], tag, true),
+                        None => Ok(()),
+                    }
+                });
+            if let Err(e) = tagged {
+                (self.on_error)(e.context("Unable to tag an entry as the script said to"));
+                return;
+            }
+        }
+    }
+
+    /// Apply the rules to the feed's entries, now they've been added.
+    pub fn apply(&self, conn: &rusqlite::Connection, feed_id: FeedId, entries: &[Entry]) {
+        if self.rules.is_empty() || entries.is_empty() {
+            return;
+        }
+
+        if let Err(e) = crate::rss::get_feed(conn, feed_id)
+            .and_then(|feed| apply_rules(conn, self.rules, &feed, entries))
+        {
+            (self.on_error)(e);
+        }
+    }
+}
+
+/// Highlight each entry with the first rule that matches it and has a highlight,
+/// and run every matching rule's `notify`, trying every rule even if one fails,
+/// and returning the first failure.
+fn apply_rules(
+    conn: &rusqlite::Connection,
+    rules: &[Rule],
+    feed: &Feed,
+    entries: &[Entry],
+) -> Result<()> {
+    let mut result = Ok(());
+
+    for entry in entries {
+        let matching_rules = rules
+            .iter()
+            .filter(|rule| rule.wants(feed, entry))
+            .collect::<Vec<_>>();
+
+        if let (Some(highlight), Some(link)) = (
+            matching_rules.iter().find_map(|rule| rule.highlight),
+            &entry.link,
+        ) {
+            crate::rss::set_entry_highlight(conn, feed.id, link, highlight)?;
+        }
+
+        for notify in matching_rules.iter().flat_map(|rule| &rule.notify) {
+            let notified =
+                run_for_entry(notify, feed, entry).context("A rule's notify command failed");
+            result = result.and(notified);
+        }
+    }
+
+    result
+}
+
+/// run `hook` with the entry as JSON on stdin, and its title and link in the environment
+fn run_for_entry(hook: &str, feed: &Feed, entry: &Entry) -> Result<()> {
+    let json = entry_json(feed, entry);
+
+    let mut command = shell_command(hook);
+    command
+        .env("RUSS_FEED_TITLE", feed.title.as_deref().unwrap_or_default())
+        .env(
+            "RUSS_ENTRY_TITLE",
+            entry.title.as_deref().unwrap_or_default(),
+        )
+        .env("RUSS_ENTRY_LINK", entry.link.as_deref().unwrap_or_default());
+
+    run_with_stdin(command, json.to_string().as_bytes())?;
+    Ok(())
+}
+
+/// an entry as the hooks get it, as JSON
+pub fn entry_json(feed: &Feed, entry: &Entry) -> serde_json::Value {
+    serde_json::json!({
+        "feed_id": feed.id,
+        "feed_title": feed.title,
+        "feed_link": feed.feed_link,
+        "title": entry.title,
+        "author": entry.author,
+        "link": entry.link,
+        "pub_date": entry.pub_date.map(|pub_date| pub_date.to_rfc3339()),
+    })
+}
+
+/// Post the entries a refresh added to a feed to each webhook that wants the feed's entries,
+/// trying every webhook even if one fails, and returning the first failure.
+pub fn post_webhooks(
+    http_client: &ureq::Agent,
+    hooks: &Hooks,
+    feed: &Feed,
+    entries: &[Entry],
+) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let mut result = Ok(());
+    for webhook in hooks.webhooks.iter().filter(|webhook| webhook.wants(feed)) {
+        let posted = post_webhook(http_client, webhook, feed, entries);
+        result = result.and(posted);
+    }
+
+    result
+}
+
+fn post_webhook(
+    http_client: &ureq::Agent,
+    webhook: &Webhook,
+    feed: &Feed,
+    entries: &[Entry],
+) -> Result<()> {
+    let feed_title = feed.title.as_deref().unwrap_or("No title");
+    let mut text = match entries.len() {
+        1 => format!("1 new entry in {}", feed_title),
+        len => format!("{} new entries in {}", len, feed_title),
+    };
+    for entry in entries {
+        text.push_str(&format!(
+            "\n{} {}",
+            entry.title.as_deref().unwrap_or("No title"),
+            entry.link.as_deref().unwrap_or_default()
+        ));
+    }
+
+    let request = http_client.post(&webhook.url);
+    match webhook.format {
+        WebhookFormat::Json => request
+            .set("Content-Type", "application/json; charset=UTF-8")
+            .send_string(
+                &serde_json::json!({
+                    "text": text,
+                    "feed_id": feed.id,
+                    "feed_title": feed.title,
+                    "feed_link": feed.feed_link,
+                    "entries": entries
+                        .iter()
+                        .map(|entry| entry_json(feed, entry))
+                        .collect::<Vec<_>>(),
+                })
+                .to_string(),
+            ),
+        WebhookFormat::Text => request
+            .set("Content-Type", "text/plain; charset=UTF-8")
+            .send_string(&text),
+    }
+    .with_context(|| format!("The webhook to {} failed", webhook.url))?;
+
+    Ok(())
+}
+
+/// The `post_refresh` hook, with how the refresh went as JSON on stdin,
+/// and the number of new entries in `RUSS_NEW_ENTRIES`
+pub fn post_refresh(
+    hooks: &Hooks,
+    feeds_refreshed: usize,
+    feeds_failed: usize,
+    new_entries: usize,
+) -> Result<()> {
+    let hook = match &hooks.post_refresh {
+        Some(hook) => hook,
+        None => return Ok(()),
+    };
+
+    let json = serde_json::json!({
+        "feeds_refreshed": feeds_refreshed,
+        "feeds_failed": feeds_failed,
+        "new_entries": new_entries,
+    });
+
+    let mut command = shell_command(hook);
+    command.env("RUSS_NEW_ENTRIES", new_entries.to_string());
+
+    run_with_stdin(command, json.to_string().as_bytes()).context("The post_refresh hook failed")?;
+
+    Ok(())
+}
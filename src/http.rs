@@ -0,0 +1,358 @@
+//! The HTTP client everything that goes to the network shares.
+//!
+//! Responses are compressed with gzip or brotli when the server can, and connections
+//! are kept open and reused, so refreshing feeds that share a host doesn't connect
+//! over and over. ureq speaks HTTP/1.1, so it's keep-alive rather than HTTP/2.
+//!
+//! Feeds on servers with self-signed certificates, like on an intranet, can be pinned
+//! to their certificate's fingerprint. That's per host rather than per feed, as connections
+//! are, so every feed on a pinned host has to present one of the certificates its feeds pinned,
+//! for as long as any of them has one pinned.
+
+use anyhow::{anyhow, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// so whoever runs a feed's server can tell who's asking, and where to find out more
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "russ/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/ckampfe/russ)"
+);
+
+/// the SHA-256 of each pinned host's certificates, by host, then by who pinned them
+static PINNED_CERTIFICATES: RwLock<BTreeMap<String, BTreeMap<PinnedBy, Fingerprint>>> =
+    RwLock::new(BTreeMap::new());
+
+/// who pinned a host's certificate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PinnedBy {
+    Feed(crate::rss::FeedId),
+    /// a feed being previewed or subscribed to, which doesn't have an id yet
+    Subscription(u64),
+}
+
+/// Refreshing all feeds fetches from this many threads at once,
+/// which can all be on the same host, like a forge or a video site.
+fn idle_connections_per_host() -> usize {
+    num_cpus::get() * 2
+}
+
+/// `timeout` is how long to wait for a connection, and then for each read.
+/// Some servers turn away clients they don't know, which a different `user_agent` can get past.
+pub fn client(timeout: Duration, user_agent: Option<&str>) -> ureq::Agent {
+    builder(timeout, user_agent).build()
+}
+
+/// like `client`, but returning redirects rather than following them, to see where they go
+pub fn client_without_redirects(timeout: Duration, user_agent: Option<&str>) -> ureq::Agent {
+    builder(timeout, user_agent).redirects(0).build()
+}
+
+fn builder(timeout: Duration, user_agent: Option<&str>) -> ureq::AgentBuilder {
+    ureq::AgentBuilder::new()
+        .user_agent(user_agent.unwrap_or(DEFAULT_USER_AGENT))
+        .timeout_connect(timeout)
+        .timeout_read(timeout)
+        .max_idle_connections(100)
+        .max_idle_connections_per_host(idle_connections_per_host())
+        .tls_config(tls_config())
+}
+
+fn tls_config() -> Arc<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let web_pki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .expect("the bundled roots are valid");
+
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])
+        .expect("ring supports TLS 1.2 and 1.3")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinningVerifier { web_pki }))
+        .with_no_client_auth();
+
+    Arc::new(config)
+}
+
+/// A certificate's SHA-256, written like `openssl x509 -fingerprint -sha256` does,
+/// with or without the colons, and in either case.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fingerprint([u8; 32]);
+
+impl std::str::FromStr for Fingerprint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let hex = trimmed
+            .rsplit_once('=')
+            .map(|(_, hex)| hex)
+            .unwrap_or(trimmed)
+            .replace(':', "");
+
+        let invalid = || anyhow!("{:?} is not a SHA-256 certificate fingerprint", s);
+
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 32];
+        for (byte, i) in bytes.iter_mut().zip((0..hex.len()).step_by(2)) {
+            *byte = u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid())?;
+        }
+
+        Ok(Fingerprint(bytes))
+    }
+}
+
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = self
+            .0
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>();
+        write!(f, "{}", hex.join(":"))
+    }
+}
+
+impl Fingerprint {
+    fn of(certificate: &[u8]) -> Fingerprint {
+        let digest = ring::digest::digest(&ring::digest::SHA256, certificate);
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest.as_ref());
+        Fingerprint(bytes)
+    }
+}
+
+/// Only accept `fingerprint`'s certificate, or another one pinned for it,
+/// from the host of `url` from now on, in place of any `pinned_by` pinned before, for any host.
+/// If there is no `fingerprint`, `pinned_by`'s pin is only removed, and the host goes back
+/// to having its certificate checked the usual way once no one else has one pinned for it.
+pub fn pin_certificate(
+    http_client: &ureq::Agent,
+    url: &str,
+    pinned_by: PinnedBy,
+    fingerprint: Option<Fingerprint>,
+) -> Result<()> {
+    let host = match fingerprint {
+        Some(_) => Some(http_client.get(url).request_url()?.host().to_owned()),
+        None => None,
+    };
+    let mut pinned_certificates = PINNED_CERTIFICATES
+        .write()
+        .map_err(|_| anyhow!("Unable to pin the certificate of {}", url))?;
+
+    unpin(&mut pinned_certificates, pinned_by);
+    if let (Some(host), Some(fingerprint)) = (host, fingerprint) {
+        pinned_certificates
+            .entry(host)
+            .or_default()
+            .insert(pinned_by, fingerprint);
+    }
+
+    Ok(())
+}
+
+/// Remove `pinned_by`'s pin, like when its feed is deleted.
+pub fn unpin_certificate(pinned_by: PinnedBy) {
+    if let Ok(mut pinned_certificates) = PINNED_CERTIFICATES.write() {
+        unpin(&mut pinned_certificates, pinned_by);
+    }
+}
+
+fn unpin(
+    pinned_certificates: &mut BTreeMap<String, BTreeMap<PinnedBy, Fingerprint>>,
+    pinned_by: PinnedBy,
+) {
+    pinned_certificates.retain(|_, pins| {
+        pins.remove(&pinned_by);
+        !pins.is_empty()
+    });
+}
+
+/// the certificates pinned for `host`, if any are
+fn pinned_certificates(host: &str) -> Vec<Fingerprint> {
+    PINNED_CERTIFICATES
+        .read()
+        .ok()
+        .and_then(|pinned_certificates| {
+            pinned_certificates
+                .get(host)
+                .map(|pins| pins.values().copied().collect())
+        })
+        .unwrap_or_default()
+}
+
+/// A pin for fetching a feed before it's subscribed to, removed when it's dropped,
+/// so a preview or a subscription that fails doesn't leave its host pinned.
+pub struct SubscriptionPin(PinnedBy);
+
+impl SubscriptionPin {
+    pub fn new(
+        http_client: &ureq::Agent,
+        url: &str,
+        fingerprint: Option<Fingerprint>,
+    ) -> Result<SubscriptionPin> {
+        static SUBSCRIPTIONS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let pinned_by = PinnedBy::Subscription(
+            SUBSCRIPTIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        );
+
+        pin_certificate(http_client, url, pinned_by, fingerprint)?;
+        Ok(SubscriptionPin(pinned_by))
+    }
+}
+
+impl Drop for SubscriptionPin {
+    fn drop(&mut self) {
+        unpin_certificate(self.0);
+    }
+}
+
+/// checks certificates the usual way, unless their host's is pinned
+#[derive(Debug)]
+struct PinningVerifier {
+    web_pki: Arc<WebPkiServerVerifier>,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str();
+        let pinned = pinned_certificates(host.as_ref());
+
+        if pinned.contains(&Fingerprint::of(end_entity)) {
+            Ok(ServerCertVerified::assertion())
+        } else if !pinned.is_empty() {
+            Err(rustls::Error::General(format!(
+                "the certificate of {} is not a pinned one",
+                host
+            )))
+        } else {
+            self.web_pki.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            )
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.web_pki.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.web_pki.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.web_pki.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_fingerprints() {
+        let with_colons = vec!["AB"; 32].join(":");
+
+        assert_eq!(
+            "ab".repeat(32).parse::<Fingerprint>().unwrap(),
+            Fingerprint([0xab; 32])
+        );
+        assert_eq!(
+            format!("sha256 Fingerprint={}", with_colons)
+                .parse::<Fingerprint>()
+                .unwrap()
+                .to_string(),
+            with_colons
+        );
+        assert!("ab:cd".parse::<Fingerprint>().is_err());
+        assert!("zz".repeat(32).parse::<Fingerprint>().is_err());
+        assert_eq!(
+            Fingerprint::of(b"").to_string().replace(':', ""),
+            "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+        );
+    }
+
+    #[test]
+    fn certificates_stay_pinned_while_a_feed_pins_them() {
+        let http_client = client(Duration::from_secs(1), None);
+        let (a, b) = (Fingerprint([0xa; 32]), Fingerprint([0xb; 32]));
+
+        pin_certificate(
+            &http_client,
+            "https://pinned.test/a",
+            PinnedBy::Feed(-1),
+            Some(a),
+        )
+        .unwrap();
+        pin_certificate(
+            &http_client,
+            "https://pinned.test/b",
+            PinnedBy::Feed(-2),
+            Some(b),
+        )
+        .unwrap();
+        // by who pinned them
+        assert_eq!(pinned_certificates("pinned.test"), vec![b, a]);
+
+        // clearing one feed's leaves the other's
+        pin_certificate(
+            &http_client,
+            "https://pinned.test/a",
+            PinnedBy::Feed(-1),
+            None,
+        )
+        .unwrap();
+        assert_eq!(pinned_certificates("pinned.test"), vec![b]);
+
+        // moving to another host takes the pin along
+        pin_certificate(
+            &http_client,
+            "https://moved.test/b",
+            PinnedBy::Feed(-2),
+            Some(b),
+        )
+        .unwrap();
+        assert!(pinned_certificates("pinned.test").is_empty());
+        unpin_certificate(PinnedBy::Feed(-2));
+        assert!(pinned_certificates("moved.test").is_empty());
+
+        let subscription_pin =
+            SubscriptionPin::new(&http_client, "https://subscribing.test", Some(a)).unwrap();
+        assert_eq!(pinned_certificates("subscribing.test"), vec![a]);
+        drop(subscription_pin);
+        assert!(pinned_certificates("subscribing.test").is_empty());
+    }
+}
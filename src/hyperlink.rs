@@ -0,0 +1,247 @@
+//! Clickable links in entries, with OSC 8, for terminals that support it.
+//!
+//! tui has no idea about OSC 8, and counts its escape codes as text,
+//! so links are written over the entry after tui has drawn it,
+//! and written over again without the link once they scroll away,
+//! so the terminal doesn't keep the old link on cells tui thinks haven't changed.
+
+use crate::util;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::Print;
+use std::io::Write;
+use std::ops::Range;
+
+/// a link in the open entry's text
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryLink {
+    pub line: usize,
+    /// bytes of the line
+    pub range: Range<usize>,
+    pub url: String,
+}
+
+/// a link as it was drawn on screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawnLink {
+    pub x: u16,
+    pub y: u16,
+    pub text: String,
+    pub url: String,
+}
+
+/// a line of the entry as it was drawn on screen, to draw over old links with
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawnLine {
+    pub x: u16,
+    pub y: u16,
+    pub text: String,
+}
+
+/// The links in an entry's `lines`: its title, on the first line, links to `entry_link`,
+/// and html2text's footnotes, like `some text[1]` and `[1]: https://...`,
+/// link to where they point.
+pub fn find_links(lines: &[&str], entry_link: Option<&str>) -> Vec<EntryLink> {
+    let mut links = vec![];
+
+    if let (Some(title), Some(entry_link)) = (lines.first(), entry_link) {
+        if !title.is_empty() {
+            links.push(EntryLink {
+                line: 0,
+                range: 0..title.len(),
+                url: entry_link.to_owned(),
+            });
+        }
+    }
+
+    let footnotes = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (number, url) = footnote(line)?;
+            Some((i, number, url))
+        })
+        .collect::<Vec<_>>();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((_, number, url)) = footnotes.iter().find(|(line_idx, _, _)| *line_idx == i) {
+            let url_start = line.len() - url.len();
+            links.push(EntryLink {
+                line: i,
+                range: url_start..line.len(),
+                url: url.to_string(),
+            });
+            // the footnote's own number links too
+            links.push(EntryLink {
+                line: i,
+                range: 0..number.len() + 2,
+                url: url.to_string(),
+            });
+            continue;
+        }
+
+        for (start, _) in line.match_indices('[') {
+            let reference = line[start + 1..]
+                .split_once(']')
+                .map(|(number, _)| number)
+                .filter(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()));
+
+            if let Some(number) = reference {
+                if let Some((_, _, url)) = footnotes.iter().find(|(_, n, _)| *n == number) {
+                    links.push(EntryLink {
+                        line: i,
+                        range: start..start + number.len() + 2,
+                        url: url.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    links.retain(|link| is_safe(&link.url));
+    links
+}
+
+/// the number and url of a footnote line, like `[1]: https://example.com`
+fn footnote(line: &str) -> Option<(&str, &str)> {
+    let (number, url) = line.strip_prefix('[')?.split_once("]: ")?;
+
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let url = url.trim_end();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((number, url))
+}
+
+/// a url with control characters in it could end the escape code early
+fn is_safe(url: &str) -> bool {
+    !url.chars().any(char::is_control)
+}
+
+/// Write the `drawn` links over what tui drew, and rewrite the `previous` ones
+/// that aren't there anymore from `lines`, without their links.
+/// Returns whether an old link couldn't be rewritten, because the entry has gone,
+/// in which case the whole screen has to be redrawn to be rid of it.
+pub fn write<W: Write>(
+    w: &mut W,
+    previous: &[DrawnLink],
+    drawn: &[DrawnLink],
+    lines: &[DrawnLine],
+) -> std::io::Result<bool> {
+    let mut needs_redraw = false;
+
+    for link in previous.iter().filter(|link| !drawn.contains(link)) {
+        let line = lines
+            .iter()
+            .find(|line| line.y == link.y && line.x <= link.x);
+
+        match line {
+            Some(line) => {
+                let text = text_at_columns(
+                    &line.text,
+                    (link.x - line.x).into(),
+                    util::display_width(&link.text),
+                );
+                queue!(w, MoveTo(link.x, link.y), Print(text))?;
+            }
+            None => needs_redraw = true,
+        }
+    }
+
+    for link in drawn {
+        queue!(
+            w,
+            MoveTo(link.x, link.y),
+            Print(format!(
+                "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+                link.url, link.text
+            ))
+        )?;
+    }
+
+    w.flush()?;
+
+    Ok(needs_redraw)
+}
+
+/// the `width` cells of `line` starting at column `start`, padded with spaces
+fn text_at_columns(line: &str, start: usize, width: usize) -> String {
+    let mut text = String::new();
+    let mut column = 0;
+
+    for c in line.chars() {
+        let c_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        if column >= start && column + c_width <= start + width {
+            text.push(c);
+        }
+        column += c_width;
+    }
+
+    let text_width = util::display_width(&text);
+    text.push_str(&" ".repeat(width.saturating_sub(text_width)));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_footnote_links() {
+        let lines = [
+            "A title",
+            "",
+            "Some text[1] and more[2], [not a link]",
+            "",
+            "[1]: https://example.com/a",
+            "[2]: https://example.com/b",
+        ];
+        let links = find_links(&lines, Some("https://example.com/entry"));
+
+        let link_texts = links
+            .iter()
+            .map(|link| (&lines[link.line][link.range.clone()], link.url.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            link_texts,
+            vec![
+                ("A title", "https://example.com/entry"),
+                ("[1]", "https://example.com/a"),
+                ("[2]", "https://example.com/b"),
+                ("https://example.com/a", "https://example.com/a"),
+                ("[1]", "https://example.com/a"),
+                ("https://example.com/b", "https://example.com/b"),
+                ("[2]", "https://example.com/b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rewrites_links_that_scrolled_away() {
+        let link = DrawnLink {
+            x: 3,
+            y: 1,
+            text: "[1]".to_string(),
+            url: "https://example.com".to_string(),
+        };
+        let lines = [DrawnLine {
+            x: 1,
+            y: 1,
+            text: "ab日本".to_string(),
+        }];
+
+        let mut out = vec![];
+        assert!(!write(&mut out, std::slice::from_ref(&link), &[], &lines).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("日 "));
+        assert!(!out.contains("\x1b]8"));
+
+        assert!(write(&mut vec![], &[link], &[], &[]).unwrap());
+    }
+}
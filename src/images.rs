@@ -0,0 +1,617 @@
+//! Images in entries, drawn inline with the kitty graphics protocol, sixel,
+//! or iTerm2's inline images, in terminals that speak one of them.
+//!
+//! Like links, tui has no idea about images, so the entry leaves blank lines
+//! where they go, and they are written over those once tui has drawn it.
+//! Images are downloaded on the IO thread, to a cache in `$XDG_CACHE_HOME/russ/images`.
+
+use anyhow::Result;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::Print;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use scraper::{Html, Node, Selector};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// What an image is replaced with in the HTML, followed by its index, like `code::PLACEHOLDER`
+const PLACEHOLDER: &str = "RUSSIMAGE";
+
+/// There's no asking the terminal how big its cells are without reading its reply
+/// from under crossterm, so this guesses, for sizing images in cells,
+/// and for sixel, which is drawn in pixels.
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+/// the most lines an image takes up, so it doesn't push the rest of the entry off screen
+const MAX_ROWS: u16 = 24;
+
+/// about as wide as an entry gets, for deciding how many pixels of an image are worth keeping
+const MAX_COLS: u32 = 200;
+
+/// images bigger than this aren't downloaded
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// kitty wants its base64 in chunks of at most this many bytes
+const KITTY_CHUNK_LEN: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+    Iterm,
+}
+
+impl Protocol {
+    /// Which protocol the terminal speaks, going by what it sets in the environment,
+    /// or `None` if it doesn't seem to speak any.
+    pub fn detect() -> Option<Protocol> {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<Protocol> {
+        let term = var("TERM").unwrap_or_default();
+        let term_program = var("TERM_PROGRAM").unwrap_or_default();
+
+        // tmux and screen don't pass images through,
+        // and keep the variables of whatever terminal they were started in
+        if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+            return None;
+        }
+
+        if term == "xterm-kitty" || term == "xterm-ghostty" || var("KITTY_WINDOW_ID").is_some() {
+            Some(Protocol::Kitty)
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || var("LC_TERMINAL").as_deref() == Some("iTerm2")
+        {
+            Some(Protocol::Iterm)
+        } else if term.starts_with("foot") || term.starts_with("mlterm") || term.contains("sixel") {
+            Some(Protocol::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+/// an `<img>` in an entry
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryImage {
+    /// resolved against the entry's link
+    pub url: String,
+    pub alt: String,
+}
+
+/// where an image goes in the open entry
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageRegion {
+    /// the first of its lines
+    pub line: usize,
+    pub cols: u16,
+    pub rows: u16,
+    pub url: String,
+}
+
+/// an image as it was drawn on screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawnImage {
+    pub x: u16,
+    pub y: u16,
+    pub cols: u16,
+    pub rows: u16,
+    pub url: String,
+}
+
+/// `html` with every `<img>` that has a `src` swapped for a placeholder, and those images,
+/// with their urls resolved against `base_url`
+pub fn extract(html: &str, base_url: Option<&str>) -> (String, Vec<EntryImage>) {
+    if !html.contains("<img") {
+        return (html.to_owned(), vec![]);
+    }
+
+    let mut document = Html::parse_fragment(html);
+    let img = Selector::parse("img").expect("static selector is valid");
+
+    let found = document
+        .select(&img)
+        .filter_map(|element| {
+            let url = resolve_url(base_url, element.value().attr("src")?)?;
+            let alt = element.value().attr("alt").unwrap_or_default().trim();
+            Some((element.id(), url, alt.to_owned()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut images = vec![];
+
+    for (i, (id, url, alt)) in found.into_iter().enumerate() {
+        images.push(EntryImage { url, alt });
+
+        let mut node = document.tree.get_mut(id).expect("selected elements exist");
+        node.insert_after(Node::Text(scraper::node::Text {
+            text: format!(" {}{} ", PLACEHOLDER, i).into(),
+        }));
+        node.detach();
+    }
+
+    (document.root_element().html(), images)
+}
+
+/// `url` made absolute, if it can be, and if it's one that can be fetched
+fn resolve_url(base_url: Option<&str>, url: &str) -> Option<String> {
+    let url = url.trim();
+
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Some(url.to_owned());
+    }
+
+    let base_url = base_url?;
+    let (scheme, rest) = base_url.split_once("://")?;
+    let origin_len = scheme.len() + 3 + rest.find('/').unwrap_or(rest.len());
+
+    if let Some(url) = url.strip_prefix("//") {
+        Some(format!("{}://{}", scheme, url))
+    } else if url.starts_with('/') {
+        Some(format!("{}{}", &base_url[..origin_len], url))
+    } else if url.is_empty() || url.contains(':') {
+        // `data:` and the like
+        None
+    } else {
+        let dir_len = base_url[origin_len..]
+            .rfind('/')
+            .map(|i| origin_len + i + 1)
+            .unwrap_or(origin_len);
+        let separator = if dir_len == origin_len { "/" } else { "" };
+        Some(format!("{}{}{}", &base_url[..dir_len], separator, url))
+    }
+}
+
+/// `line` without its image placeholders, and the indexes of the images they stood for
+pub fn take_placeholders(line: &str) -> (String, Vec<usize>) {
+    if !line.contains(PLACEHOLDER) {
+        return (line.to_owned(), vec![]);
+    }
+
+    let mut rest = String::new();
+    let mut image_idxs = vec![];
+
+    for (i, piece) in line.split(PLACEHOLDER).enumerate() {
+        if i == 0 {
+            rest.push_str(piece);
+            continue;
+        }
+
+        let digits_len = piece.chars().take_while(char::is_ascii_digit).count();
+        match piece[..digits_len].parse() {
+            Ok(image_idx) => {
+                image_idxs.push(image_idx);
+                rest.push_str(&piece[digits_len..]);
+            }
+            Err(_) => {
+                rest.push_str(PLACEHOLDER);
+                rest.push_str(piece);
+            }
+        }
+    }
+
+    (rest, image_idxs)
+}
+
+/// The images that have been fetched, and the escape codes that draw them.
+#[derive(Debug)]
+pub struct Images {
+    pub protocol: Protocol,
+    /// `None` if the image couldn't be decoded
+    loaded: HashMap<String, Option<RgbaImage>>,
+    /// asked for already, whether they were fetched or not, so they aren't asked for again
+    requested: HashSet<String>,
+    /// waiting to be fetched on the IO thread
+    pending: Vec<String>,
+    /// the escape codes for each image at each size it has been drawn at
+    encoded: HashMap<(String, u16, u16), String>,
+}
+
+impl Images {
+    pub fn new(protocol: Protocol) -> Images {
+        Images {
+            protocol,
+            loaded: HashMap::new(),
+            requested: HashSet::new(),
+            pending: vec![],
+            encoded: HashMap::new(),
+        }
+    }
+
+    /// How many columns and rows the image at `url` takes up, at most `max_cols` wide
+    /// and `max_rows` tall, if it has been fetched. If it hasn't, it's queued to be.
+    pub fn size_in_cells(&mut self, url: &str, max_cols: u16, max_rows: u16) -> Option<(u16, u16)> {
+        if !self.loaded.contains_key(url) {
+            match cache_path(url) {
+                Some(path) if path.exists() => {
+                    let image = std::fs::read(&path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|bytes| Ok(image::load_from_memory(&bytes)?))
+                        .map_err(|e| log::warn!("unable to load image {}: {}", url, e))
+                        .ok()
+                        .map(|image| {
+                            // there's no use keeping more pixels than the entry can show
+                            let (max_width, max_height) =
+                                (2 * CELL_WIDTH * MAX_COLS, 2 * CELL_HEIGHT * MAX_ROWS as u32);
+                            if image.width() > max_width || image.height() > max_height {
+                                image.thumbnail(max_width, max_height).to_rgba8()
+                            } else {
+                                image.to_rgba8()
+                            }
+                        });
+                    self.loaded.insert(url.to_owned(), image);
+                }
+                _ => {
+                    if self.requested.insert(url.to_owned()) {
+                        self.pending.push(url.to_owned());
+                    }
+                    return None;
+                }
+            }
+        }
+
+        let image = self.loaded.get(url)?.as_ref()?;
+        Some(fit_in_cells(
+            image.dimensions(),
+            max_cols,
+            max_rows.clamp(1, MAX_ROWS),
+        ))
+    }
+
+    /// the images that need fetching
+    pub fn take_pending(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Sixel and iTerm2 images are drawn into cells tui doesn't know have changed,
+    /// so when one moves or goes away, the whole screen has to be redrawn to be rid of it.
+    pub fn needs_redraw(&self, previous: &[DrawnImage], drawn: &[DrawnImage]) -> bool {
+        self.protocol != Protocol::Kitty && previous.iter().any(|image| !drawn.contains(image))
+    }
+
+    /// Write the `drawn` images over what tui drew, when they're not already on screen.
+    /// The `previous` images are the ones written last time, and are still on screen.
+    pub fn write<W: Write>(
+        &mut self,
+        w: &mut W,
+        previous: &[DrawnImage],
+        drawn: &[DrawnImage],
+    ) -> std::io::Result<()> {
+        if previous == drawn {
+            return Ok(());
+        }
+
+        let to_write = match self.protocol {
+            // kitty's images sit over the text rather than in it, so they're all deleted
+            // and put back, freeing the old ones' data too
+            Protocol::Kitty => {
+                queue!(w, Print("\x1b_Ga=d,d=A,q=2\x1b\\"))?;
+                drawn.iter().collect::<Vec<_>>()
+            }
+            Protocol::Sixel | Protocol::Iterm => drawn
+                .iter()
+                .filter(|image| !previous.contains(image))
+                .collect(),
+        };
+
+        for drawn_image in to_write {
+            if let Some(escape_codes) = self.escape_codes(drawn_image) {
+                queue!(w, MoveTo(drawn_image.x, drawn_image.y), Print(escape_codes))?;
+            }
+        }
+
+        w.flush()
+    }
+
+    fn escape_codes(&mut self, drawn_image: &DrawnImage) -> Option<&str> {
+        let key = (drawn_image.url.clone(), drawn_image.cols, drawn_image.rows);
+
+        if !self.encoded.contains_key(&key) {
+            let image = self.loaded.get(&drawn_image.url)?.as_ref()?;
+            let (cols, rows) = (drawn_image.cols.into(), drawn_image.rows.into());
+
+            let escape_codes = match self.protocol {
+                Protocol::Sixel => sixel(&image::imageops::resize(
+                    image,
+                    cols * CELL_WIDTH,
+                    rows * CELL_HEIGHT,
+                    FilterType::Triangle,
+                )),
+                Protocol::Kitty => kitty(&png(image)?, cols, rows),
+                Protocol::Iterm => iterm(&png(image)?, cols, rows),
+            };
+
+            self.encoded.insert(key.clone(), escape_codes);
+        }
+
+        self.encoded.get(&key).map(String::as_str)
+    }
+}
+
+/// `(cols, rows)` for an image of `(width, height)` pixels, keeping its shape
+fn fit_in_cells((width, height): (u32, u32), max_cols: u16, max_rows: u16) -> (u16, u16) {
+    let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+    let aspect = (height / CELL_HEIGHT as f64) / (width / CELL_WIDTH as f64);
+
+    let mut cols = (width / CELL_WIDTH as f64)
+        .ceil()
+        .clamp(1.0, max_cols.max(1) as f64);
+    let mut rows = (cols * aspect).ceil();
+
+    if rows > max_rows as f64 {
+        rows = max_rows as f64;
+        cols = (rows / aspect).floor().max(1.0);
+    }
+
+    (cols as u16, rows.max(1.0) as u16)
+}
+
+/// Download the image at `url` to the cache, unless it's there already.
+pub fn fetch(http_client: &ureq::Agent, url: &str) -> Result<()> {
+    let path = cache_path(url)
+        .ok_or_else(|| anyhow::anyhow!("Unable to find a cache directory for images"))?;
+
+    if path.exists() {
+        return Ok(());
+    }
+
+    let mut bytes = vec![];
+    http_client
+        .get(url)
+        .call()?
+        .into_reader()
+        .take(MAX_IMAGE_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err(anyhow::anyhow!("{} is too big to show", url));
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // written elsewhere first, so a half-written image is never read
+    let partial_path = path.with_extension("part");
+    std::fs::write(&partial_path, bytes)?;
+    std::fs::rename(partial_path, path)?;
+
+    Ok(())
+}
+
+fn cache_path(url: &str) -> Option<PathBuf> {
+    let project_dirs = directories::ProjectDirs::from("", "", "russ")?;
+
+    // FNV-1a, which, unlike std's hasher, is the same from one build to the next
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    Some(
+        project_dirs
+            .cache_dir()
+            .join("images")
+            .join(format!("{:016x}", hash)),
+    )
+}
+
+fn png(image: &RgbaImage) -> Option<Vec<u8>> {
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(image.clone())
+        .write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .map_err(|e| log::warn!("unable to encode image: {}", e))
+        .ok()?;
+    Some(png)
+}
+
+/// the kitty graphics protocol, stretching the image over `cols` by `rows` cells
+fn kitty(png: &[u8], cols: u32, rows: u32) -> String {
+    let data = base64::encode(png);
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK_LEN).collect::<Vec<_>>();
+    let mut escape_codes = String::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        // `q=2` keeps kitty from replying, which would come in as key presses,
+        // and `C=1` from moving the cursor
+        let control = if i == 0 {
+            format!("a=T,f=100,c={},r={},C=1,q=2,", cols, rows)
+        } else {
+            String::new()
+        };
+        let more = (i + 1 < chunks.len()) as u8;
+
+        escape_codes.push_str(&format!(
+            "\x1b_G{}m={};{}\x1b\\",
+            control,
+            more,
+            std::str::from_utf8(chunk).expect("base64 is ascii")
+        ));
+    }
+
+    escape_codes
+}
+
+/// iTerm2's inline images, stretching the image over `cols` by `rows` cells
+fn iterm(png: &[u8], cols: u32, rows: u32) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        png.len(),
+        cols,
+        rows,
+        base64::encode(png)
+    )
+}
+
+/// Sixel, in the 216 colors of a 6×6×6 color cube, with transparent pixels left alone.
+fn sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+
+    // `0;1;` leaves the pixels that aren't set as they were
+    let mut sixel = format!("\x1bP0;1;q\"1;1;{};{}", width, height);
+
+    for color in 0..216u32 {
+        let level = |c: u32| c * 100 / 5;
+        sixel.push_str(&format!(
+            "#{};2;{};{};{}",
+            color,
+            level(color / 36),
+            level(color / 6 % 6),
+            level(color % 6)
+        ));
+    }
+
+    let color_of = |x: u32, y: u32| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let level = |c: u8| (c as u32 * 5 + 127) / 255;
+        (a >= 128).then(|| level(r) * 36 + level(g) * 6 + level(b))
+    };
+
+    for band_y in (0..height).step_by(6) {
+        let band_height = (height - band_y).min(6);
+
+        // each column's six pixels, as a bit for each pixel of the color
+        let mut bands: HashMap<u32, Vec<u8>> = HashMap::new();
+        for x in 0..width {
+            for dy in 0..band_height {
+                if let Some(color) = color_of(x, band_y + dy) {
+                    bands
+                        .entry(color)
+                        .or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << dy;
+                }
+            }
+        }
+
+        let mut colors = bands.keys().copied().collect::<Vec<_>>();
+        colors.sort_unstable();
+
+        for (i, color) in colors.iter().enumerate() {
+            if i > 0 {
+                // back to the start of the band, for the next color
+                sixel.push('$');
+            }
+            sixel.push_str(&format!("#{}", color));
+            push_sixels(&mut sixel, &bands[color]);
+        }
+
+        sixel.push('-');
+    }
+
+    sixel.push_str("\x1b\\");
+    sixel
+}
+
+/// the columns of a band, run-length encoded
+fn push_sixels(sixel: &mut String, columns: &[u8]) {
+    let mut i = 0;
+
+    while i < columns.len() {
+        let run_len = columns[i..]
+            .iter()
+            .take_while(|column| **column == columns[i])
+            .count();
+        let c = char::from(0x3f + columns[i]);
+
+        if run_len > 3 {
+            sixel.push_str(&format!("!{}{}", run_len, c));
+        } else {
+            sixel.push_str(&c.to_string().repeat(run_len));
+        }
+
+        i += run_len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_the_protocol() {
+        let detect = |vars: &[(&str, &str)]| {
+            Protocol::from_env(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), Some(Protocol::Kitty));
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "iTerm.app")]),
+            Some(Protocol::Iterm)
+        );
+        assert_eq!(detect(&[("TERM", "foot")]), Some(Protocol::Sixel));
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), None);
+        assert_eq!(
+            detect(&[("TERM", "tmux-256color"), ("KITTY_WINDOW_ID", "1")]),
+            None
+        );
+    }
+
+    #[test]
+    fn it_swaps_images_for_placeholders() {
+        let (html, images) = extract(
+            r#"<p>Look: <img src="/a.png" alt="A"></p><img src="b.png"><img src="data:image/png;base64,AA">"#,
+            Some("https://example.com/posts/1"),
+        );
+
+        assert_eq!(
+            images,
+            vec![
+                EntryImage {
+                    url: "https://example.com/a.png".to_string(),
+                    alt: "A".to_string(),
+                },
+                EntryImage {
+                    url: "https://example.com/posts/b.png".to_string(),
+                    alt: String::new(),
+                },
+            ]
+        );
+
+        let text = html2text::from_read(html.as_bytes(), 40);
+        let placeholder_lines = text
+            .lines()
+            .map(take_placeholders)
+            .filter(|(_, image_idxs)| !image_idxs.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(placeholder_lines.len(), 2);
+        assert_eq!(placeholder_lines[0].0.trim(), "Look:");
+        assert_eq!(placeholder_lines[0].1, vec![0]);
+        assert_eq!(placeholder_lines[1].0.trim(), "");
+        assert_eq!(placeholder_lines[1].1, vec![1]);
+    }
+
+    #[test]
+    fn it_fits_images_in_cells() {
+        // as wide as it is tall, so half as many rows as columns
+        assert_eq!(fit_in_cells((100, 100), 80, 24), (10, 5));
+        assert_eq!(fit_in_cells((1000, 100), 40, 24), (40, 2));
+        assert_eq!(fit_in_cells((100, 1000), 80, 24), (4, 24));
+    }
+
+    #[test]
+    fn it_encodes_sixel() {
+        let image = RgbaImage::from_fn(5, 7, |x, _| {
+            if x == 0 {
+                image::Rgba([0, 0, 0, 0])
+            } else {
+                image::Rgba([255, 0, 0, 255])
+            }
+        });
+
+        let sixel = sixel(&image);
+        assert!(sixel.starts_with("\x1bP0;1;q\"1;1;5;7"));
+        // red is color 180, the first column is left alone,
+        // six rows of pixels, then one
+        assert!(sixel.ends_with("#180?!4~-#180?!4@-\x1b\\"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Importing subscriptions from other feed readers.
+
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+/// a feed from a newsboat `urls` file
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewsboatFeed {
+    pub url: String,
+    /// set with a `~` tag, like `"~Better title"`
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// The feeds in a newsboat `urls` file, and the lines that aren't feeds russ can subscribe to:
+/// query feeds, and `exec:` and `filter:` urls, which run commands.
+///
+/// Each line is a url followed by optional tags, any of which can be double-quoted.
+pub fn parse_newsboat_urls(s: &str) -> (Vec<NewsboatFeed>, Vec<String>) {
+    let mut feeds = vec![];
+    let mut skipped = vec![];
+
+    for line in s.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = split_newsboat_line(line).into_iter();
+
+        let url = match words.next() {
+            Some(url) => url,
+            None => continue,
+        };
+
+        if url.starts_with("query:") || url.starts_with("exec:") || url.starts_with("filter:") {
+            skipped.push(url);
+            continue;
+        }
+
+        let mut title = None;
+        let mut tags = vec![];
+
+        for word in words {
+            if let Some(custom_title) = word.strip_prefix('~') {
+                title = Some(custom_title.to_owned());
+            } else if word != "!" {
+                // `!` hides a feed in newsboat, which russ has no use for
+                tags.push(word);
+            }
+        }
+
+        feeds.push(NewsboatFeed { url, title, tags });
+    }
+
+    (feeds, skipped)
+}
+
+/// split on whitespace, except inside double quotes, where `\` escapes the next character
+fn split_newsboat_line(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_word = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+/// subscribe to every feed in a newsboat `urls` file that isn't already subscribed to,
+/// reporting progress on stdout and failures on stderr
+pub fn import_newsboat(
+    database_path: &Path,
+    database_passphrase: Option<&str>,
+    network_timeout: std::time::Duration,
+    user_agent: Option<&str>,
+    new_entry_rules: &crate::hooks::NewEntryRules,
+    urls_path: &Path,
+) -> Result<()> {
+    let urls = std::fs::read_to_string(urls_path)
+        .with_context(|| format!("Unable to read {}", urls_path.display()))?;
+
+    let (feeds, skipped) = parse_newsboat_urls(&urls);
+
+    for url in &skipped {
+        eprintln!("Skipping {}, russ can only import feed urls", url);
+    }
+
+    if feeds.iter().any(|feed| !feed.tags.is_empty()) {
+        eprintln!("Newsboat tags are not imported, russ only has tags on entries");
+    }
+
+    let mut conn = crate::rss::open_db(database_path, database_passphrase)?;
+    crate::rss::initialize_db(&mut conn)?;
+
+    let http_client = crate::http::client(network_timeout, user_agent);
+
+    let subscribed_urls = crate::rss::get_feeds(&conn)?
+        .into_iter()
+        .flat_map(|feed| feed.feed_link)
+        .map(|feed_link| crate::rss::normalize_feed_url(&feed_link))
+        .collect::<std::collections::HashSet<_>>();
+
+    let mut imported_len = 0usize;
+    let mut failed_len = 0usize;
+
+    for feed in &feeds {
+        if subscribed_urls.contains(&crate::rss::normalize_feed_url(&feed.url)) {
+            println!("Already subscribed to {}", feed.url);
+            continue;
+        }
+
+        let result =
+            crate::rss::subscribe_to_feed(&http_client, &mut conn, new_entry_rules, &feed.url)
+                .and_then(|feed_id| match &feed.title {
+                    Some(title) => crate::rss::set_feed_custom_title(&conn, feed_id, title),
+                    None => Ok(()),
+                });
+
+        match result {
+            Ok(()) => {
+                imported_len += 1;
+                println!("Subscribed to {}", feed.url);
+            }
+            Err(e) => {
+                failed_len += 1;
+                log::error!("unable to import {}: {:?}", feed.url, e);
+                eprintln!("Unable to subscribe to {}: {:#}", feed.url, e);
+            }
+        }
+    }
+
+    println!(
+        "Imported {} of {} feeds from {}",
+        imported_len,
+        feeds.len(),
+        urls_path.display()
+    );
+
+    if failed_len > 0 {
+        Err(anyhow!("{} feeds could not be imported", failed_len))
+    } else {
+        Ok(())
+    }
+}
+
+/// The feeds in a list of urls, like `russ add --from-file` reads, a url to a line,
+/// each followed by the fingerprint of its host's certificate if it's pinned,
+/// skipping blank lines and `#` comments.
+pub fn parse_url_list(s: &str) -> Vec<String> {
+    s.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// Subscribe to each of `inputs`, a url and maybe a fingerprint, with `subscribe`,
+/// skipping those in `subscribed_urls`, normalized with `normalize_feed_url`,
+/// and those listed twice, reporting each on stdout or stderr,
+/// then how many there were if there was more than one.
+pub fn subscribe_to_urls(
+    mut subscribed_urls: std::collections::HashSet<String>,
+    inputs: &[String],
+    mut subscribe: impl FnMut(&str) -> Result<String>,
+) -> Result<()> {
+    let mut subscribed_len = 0usize;
+    let mut skipped_len = 0usize;
+    let mut failed_len = 0usize;
+
+    for input in inputs {
+        let url = crate::rss::subscription_input_url(input);
+
+        if subscribed_urls.contains(&crate::rss::normalize_feed_url(url)) {
+            skipped_len += 1;
+            println!("Already subscribed to {}", url);
+            continue;
+        }
+
+        match subscribe(input) {
+            Ok(message) => {
+                subscribed_len += 1;
+                subscribed_urls.insert(crate::rss::normalize_feed_url(url));
+                println!("{}", message);
+            }
+            Err(e) => {
+                failed_len += 1;
+                log::error!("unable to subscribe to {}: {:?}", url, e);
+                eprintln!("Unable to subscribe to {}: {:#}", url, e);
+            }
+        }
+    }
+
+    if inputs.len() > 1 {
+        println!(
+            "{} feeds: {} new, {} already subscribed to, {} failed",
+            inputs.len(),
+            subscribed_len,
+            skipped_len,
+            failed_len
+        );
+    }
+
+    if failed_len > 0 {
+        Err(anyhow!("{} feeds could not be subscribed to", failed_len))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_newsboat_urls() {
+        let (feeds, skipped) = parse_newsboat_urls(
+            r#"
+            # a comment
+            https://example.com/feed.xml
+            https://example.org/atom.xml tech "long tag" "~A \"good\" title" !
+            "query:Unread Articles:unread = \"yes\""
+            exec:~/bin/feed.sh
+            "#,
+        );
+
+        assert_eq!(
+            feeds,
+            vec![
+                NewsboatFeed {
+                    url: "https://example.com/feed.xml".to_string(),
+                    title: None,
+                    tags: vec![],
+                },
+                NewsboatFeed {
+                    url: "https://example.org/atom.xml".to_string(),
+                    title: Some("A \"good\" title".to_string()),
+                    tags: vec!["tech".to_string(), "long tag".to_string()],
+                },
+            ]
+        );
+
+        assert_eq!(
+            skipped,
+            vec![
+                "query:Unread Articles:unread = \"yes\"".to_string(),
+                "exec:~/bin/feed.sh".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_url_lists() {
+        assert_eq!(
+            parse_url_list(
+                "https://example.com/feed.xml\n\n  # a comment\nhttps://intranet/feed.xml   AB:CD \n"
+            ),
+            vec![
+                "https://example.com/feed.xml".to_string(),
+                "https://intranet/feed.xml AB:CD".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! A line of input that can be edited like a shell's, with readline's keys,
+//! for typing long feed urls without having to backspace back to a typo.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// longer than this, and what's in the clipboard isn't a url
+const MAX_URL_LEN: usize = 2048;
+
+#[derive(Clone, Debug, Default)]
+pub struct LineInput {
+    text: String,
+    /// where in `text` the cursor is, in bytes, always on a `char` boundary
+    cursor: usize,
+    /// the last text `ctrl-w`, `ctrl-u`, `ctrl-k`, `alt-backspace`, or `alt-d` deleted, for `ctrl-y`
+    killed: String,
+}
+
+impl LineInput {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// how far the cursor is from the start of the line, in columns
+    pub fn cursor_column(&self) -> usize {
+        crate::util::display_width(&self.text[..self.cursor])
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Insert `s` at the cursor, as one line, like something pasted.
+    pub fn insert_str(&mut self, s: &str) {
+        let s = sanitize(s);
+        self.text.insert_str(self.cursor, &s);
+        self.cursor += s.len();
+    }
+
+    /// Do what `code` with `modifiers` does in readline, returning whether the text changed.
+    /// Keys that do nothing here do nothing.
+    pub fn on_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let len_before = self.text.len();
+
+        match (code, modifiers) {
+            (KeyCode::Char('a'), KeyModifiers::CONTROL) | (KeyCode::Home, _) => self.cursor = 0,
+            (KeyCode::Char('e'), KeyModifiers::CONTROL) | (KeyCode::End, _) => {
+                self.cursor = self.text.len()
+            }
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::Left, KeyModifiers::NONE) => {
+                self.cursor = self.previous_char()
+            }
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::Right, KeyModifiers::NONE) => {
+                self.cursor = self.next_char()
+            }
+            (KeyCode::Char('b'), KeyModifiers::ALT) | (KeyCode::Left, _) => {
+                self.cursor = self.previous_word_start(is_word_char)
+            }
+            (KeyCode::Char('f'), KeyModifiers::ALT) | (KeyCode::Right, _) => {
+                self.cursor = self.next_word_end()
+            }
+            (KeyCode::Backspace, KeyModifiers::ALT) => {
+                let start = self.previous_word_start(is_word_char);
+                self.kill(start..self.cursor);
+            }
+            (KeyCode::Char('h'), KeyModifiers::CONTROL) | (KeyCode::Backspace, _) => {
+                let start = self.previous_char();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                let end = self.next_char();
+                self.text.replace_range(self.cursor..end, "");
+            }
+            // like a shell, to the whitespace before the cursor, so a url goes all at once
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
+                let start = self.previous_word_start(|c| !c.is_whitespace());
+                self.kill(start..self.cursor);
+            }
+            (KeyCode::Char('d'), KeyModifiers::ALT) => {
+                let end = self.next_word_end();
+                self.kill(self.cursor..end);
+            }
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => self.kill(0..self.cursor),
+            (KeyCode::Char('k'), KeyModifiers::CONTROL) => self.kill(self.cursor..self.text.len()),
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+                let killed = self.killed.clone();
+                self.insert_str(&killed);
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            _ => (),
+        }
+
+        // every edit adds or removes something
+        self.text.len() != len_before
+    }
+
+    /// delete `range`, keeping it for `ctrl-y`
+    fn kill(&mut self, range: std::ops::Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.killed = self.text[range.clone()].to_owned();
+        self.cursor = range.start;
+        self.text.replace_range(range, "");
+    }
+
+    fn previous_char(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// the start of the word before the cursor, skipping what isn't part of one first
+    fn previous_word_start(&self, is_word_char: impl Fn(char) -> bool) -> usize {
+        let before = &self.text[..self.cursor];
+        let word_end = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| is_word_char(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+
+        before[..word_end]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    /// the end of the word after the cursor, skipping what isn't part of one first
+    fn next_word_end(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let word_start = after
+            .char_indices()
+            .find(|(_, c)| is_word_char(*c))
+            .map_or(after.len(), |(i, _)| i);
+
+        self.cursor
+            + after[word_start..]
+                .char_indices()
+                .find(|(_, c)| !is_word_char(*c))
+                .map_or(after.len(), |(i, _)| word_start + i)
+    }
+}
+
+/// `text` without newlines or other control characters, which a pasted url
+/// copied with a line break, or from a terminal, can have. Tabs are spaces.
+pub fn sanitize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c == '\t' { ' ' } else { c })
+        .filter(|c| !c.is_control())
+        .collect()
+}
+
+/// The feed to subscribe to from what's in the clipboard: its first line,
+/// if it's a url, or looks like one, like `example.com/feed.xml`,
+/// with a certificate fingerprint after it or not.
+pub fn feed_input_from_clipboard(contents: &str) -> Result<String> {
+    let input = contents
+        .lines()
+        .map(|line| sanitize(line).trim().to_owned())
+        .find(|line| !line.is_empty())
+        .ok_or_else(|| anyhow!("The clipboard is empty"))?;
+
+    let url = input.split_whitespace().next().unwrap_or_default();
+
+    // anything could be in the clipboard, so it's only ever fetched, never run
+    if url.starts_with("exec:") {
+        return Err(anyhow!(
+            "Not running a command from the clipboard; type exec: feeds in with i"
+        ));
+    }
+
+    let is_url = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            ["http", "https", "feed"].contains(&scheme.to_lowercase().as_str()) && !rest.is_empty()
+        }
+        None => {
+            let host = url.split('/').next().unwrap_or_default();
+            host.contains('.') && !host.starts_with('.') && !host.ends_with('.')
+        }
+    };
+
+    if !is_url || input.len() > MAX_URL_LEN {
+        return Err(anyhow!(
+            "The clipboard has no feed url in it, just {:?}",
+            input.chars().take(40).collect::<String>()
+        ));
+    }
+
+    // `feed://` is just asking for a feed reader
+    Ok(match input.strip_prefix("feed://") {
+        Some(rest) => format!("https://{}", rest),
+        None => input,
+    })
+}
+
+/// words are letters and digits, so `alt-backspace` goes back a part of a url at a time
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(input: &mut LineInput, keys: &[(KeyCode, KeyModifiers)]) {
+        for (code, modifiers) in keys {
+            input.on_key(*code, *modifiers);
+        }
+    }
+
+    fn ctrl(c: char) -> (KeyCode, KeyModifiers) {
+        (KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn alt(c: char) -> (KeyCode, KeyModifiers) {
+        (KeyCode::Char(c), KeyModifiers::ALT)
+    }
+
+    #[test]
+    fn it_moves_the_cursor() {
+        let mut input = LineInput::default();
+        input.insert_str("https://exämple.com/feed.xml");
+        assert_eq!(input.cursor_column(), 28);
+
+        type_keys(&mut input, &[alt('b'), alt('b')]);
+        assert_eq!(&input.text()[input.cursor..], "feed.xml");
+        type_keys(&mut input, &[alt('b'), alt('b')]);
+        assert_eq!(&input.text()[input.cursor..], "exämple.com/feed.xml");
+        assert_eq!(input.cursor_column(), 8);
+
+        type_keys(&mut input, &[alt('f')]);
+        assert_eq!(&input.text()[input.cursor..], ".com/feed.xml");
+        for _ in 0..5 {
+            input.on_key(KeyCode::Left, KeyModifiers::NONE);
+        }
+        assert_eq!(&input.text()[input.cursor..], "ämple.com/feed.xml");
+        assert_eq!(input.cursor_column(), 10);
+        type_keys(&mut input, &[ctrl('a'), ctrl('f')]);
+        assert_eq!(input.cursor_column(), 1);
+        type_keys(&mut input, &[ctrl('e'), ctrl('b')]);
+        assert_eq!(&input.text()[input.cursor..], "l");
+    }
+
+    #[test]
+    fn it_edits_the_text() {
+        let mut input = LineInput::default();
+        for c in "https://exmple.com/feed.xml AB:CD".chars() {
+            assert!(input.on_key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+
+        assert!(input.on_key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.text(), "https://exmple.com/feed.xml ");
+        type_keys(&mut input, &[(KeyCode::Backspace, KeyModifiers::ALT)]);
+        assert_eq!(input.text(), "https://exmple.com/feed.");
+
+        type_keys(&mut input, &[ctrl('y'), ctrl('a'), alt('f'), alt('f')]);
+        type_keys(
+            &mut input,
+            &[
+                (KeyCode::Left, KeyModifiers::NONE),
+                (KeyCode::Left, KeyModifiers::NONE),
+                (KeyCode::Left, KeyModifiers::NONE),
+                (KeyCode::Left, KeyModifiers::NONE),
+                (KeyCode::Char('a'), KeyModifiers::NONE),
+            ],
+        );
+        assert_eq!(input.text(), "https://example.com/feed.xml ");
+
+        assert!(!input.on_key(KeyCode::Left, KeyModifiers::NONE));
+        type_keys(&mut input, &[ctrl('d'), ctrl('k')]);
+        assert_eq!(input.text(), "https://ex");
+        type_keys(&mut input, &[ctrl('b'), ctrl('u')]);
+        assert_eq!(input.text(), "x");
+        type_keys(&mut input, &[alt('d')]);
+        assert_eq!(input.text(), "");
+
+        input.insert_str("https://example.com/\nfeed.xml\x1b[0m\r\n");
+        assert_eq!(input.text(), "https://example.com/feed.xml[0m");
+        assert_eq!(sanitize("a\tb\u{7}"), "a b");
+        input.clear();
+        assert_eq!((input.text(), input.cursor_column()), ("", 0));
+    }
+
+    #[test]
+    fn it_finds_feeds_in_the_clipboard() {
+        assert_eq!(
+            feed_input_from_clipboard("\n  https://example.com/feed.xml\r\nmore\n").unwrap(),
+            "https://example.com/feed.xml"
+        );
+        assert_eq!(
+            feed_input_from_clipboard("example.com/feed.xml AB:CD").unwrap(),
+            "example.com/feed.xml AB:CD"
+        );
+        assert_eq!(
+            feed_input_from_clipboard("feed://example.com/rss").unwrap(),
+            "https://example.com/rss"
+        );
+        assert!(feed_input_from_clipboard(" \n").is_err());
+        assert!(feed_input_from_clipboard("exec:rm -rf ~").is_err());
+        assert!(feed_input_from_clipboard("just some words.").is_err());
+        assert!(feed_input_from_clipboard("ftp://example.com").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Where russ has been, like vim's jump list, so `ctrl-o` can go back to the places
+//! big moves went from, like opening an entry, the finder, or the next unread feed,
+//! and `ctrl-i` can come forward again.
+
+use crate::modes::VirtualFeed;
+use crate::rss::{EntryId, FeedId};
+
+/// how many places the jump list keeps
+const JUMPS_LIMIT: usize = 100;
+
+/// what's selected in the feeds pane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JumpFeed {
+    Feed(FeedId),
+    Virtual(VirtualFeed),
+}
+
+/// a place in russ: a feed, and the entry that was selected or open in it, if any
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jump {
+    pub feed: JumpFeed,
+    pub entry_id: Option<EntryId>,
+    pub entry_open: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    /// where in the jumps going back and forth has got to,
+    /// or past the end of them when it hasn't
+    position: usize,
+}
+
+impl JumpList {
+    /// Remember `from`, before jumping from it. Any places gone back past are forgotten.
+    pub fn push(&mut self, from: Jump) {
+        self.jumps.truncate(self.position);
+
+        if self.jumps.last() != Some(&from) {
+            self.jumps.push(from);
+        }
+
+        if self.jumps.len() > JUMPS_LIMIT {
+            self.jumps.remove(0);
+        }
+
+        self.position = self.jumps.len();
+    }
+
+    /// The place before `current`, remembering `current` to come forward to again.
+    pub fn back(&mut self, current: Jump) -> Option<Jump> {
+        if self.position >= self.jumps.len() {
+            if self.jumps.last() != Some(&current) {
+                self.jumps.push(current);
+            }
+            self.position = self.jumps.len() - 1;
+        }
+
+        self.position = self.position.checked_sub(1)?;
+        Some(self.jumps[self.position])
+    }
+
+    /// the place gone back from, if any
+    pub fn forward(&mut self) -> Option<Jump> {
+        if self.position + 1 >= self.jumps.len() {
+            return None;
+        }
+
+        self.position += 1;
+        Some(self.jumps[self.position])
+    }
+
+    /// forget the places in a feed, for when it's deleted
+    pub fn forget_feed(&mut self, feed_id: FeedId) {
+        let position = self.position.min(self.jumps.len());
+        let removed_before = self.jumps[..position]
+            .iter()
+            .filter(|jump| jump.feed == JumpFeed::Feed(feed_id))
+            .count();
+
+        self.jumps
+            .retain(|jump| jump.feed != JumpFeed::Feed(feed_id));
+        self.position -= removed_before;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jump(feed_id: FeedId, entry_id: Option<EntryId>) -> Jump {
+        Jump {
+            feed: JumpFeed::Feed(feed_id),
+            entry_id,
+            entry_open: entry_id.is_some(),
+        }
+    }
+
+    #[test]
+    fn it_goes_back_and_forth() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(jump(1, None)), None);
+        assert_eq!(jumps.forward(), None);
+
+        let mut jumps = JumpList::default();
+        jumps.push(jump(1, None));
+        jumps.push(jump(1, None));
+        jumps.push(jump(1, Some(2)));
+
+        assert_eq!(jumps.back(jump(3, None)), Some(jump(1, Some(2))));
+        assert_eq!(jumps.back(jump(1, Some(2))), Some(jump(1, None)));
+        assert_eq!(jumps.back(jump(1, None)), None);
+        assert_eq!(jumps.forward(), Some(jump(1, Some(2))));
+        assert_eq!(jumps.forward(), Some(jump(3, None)));
+        assert_eq!(jumps.forward(), None);
+
+        // jumping somewhere new forgets the places gone back past
+        jumps.back(jump(3, None));
+        jumps.back(jump(1, Some(2)));
+        jumps.push(jump(1, None));
+        assert_eq!(jumps.forward(), None);
+        assert_eq!(jumps.back(jump(4, None)), Some(jump(1, None)));
+
+        jumps.forget_feed(1);
+        assert_eq!(jumps.forward(), None);
+        assert_eq!(jumps.back(jump(4, None)), None);
+    }
+}
@@ -0,0 +1,413 @@
+//! Secrets, like access tokens and passwords, kept out of the database and the config file.
+//!
+//! They go in the system's keyring: the Secret Service, through `secret-tool`, on Linux and
+//! the BSDs, and the login keychain, through `security`, on macOS. Where there isn't one,
+//! like on Windows, or when `$RUSS_KEYRING_PASSPHRASE` is set, they go in a file next to
+//! the database instead, encrypted with a key derived from that passphrase.
+//!
+//! Secrets are kept per database, so each profile has its own.
+
+use anyhow::{anyhow, Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// the names of the secrets `russ store-secret` stores
+pub const SECRET_NAMES: &[&str] = &[
+    "database-passphrase",
+    "mastodon-access-token",
+    "translation-api-key",
+    "wallabag-client-secret",
+    "wallabag-password",
+];
+
+const PASSPHRASE_VAR: &str = "RUSS_KEYRING_PASSPHRASE";
+
+/// what the system keyrings know russ's secrets by
+const SERVICE: &str = "russ";
+
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+pub struct Keyring {
+    /// the database the secrets belong to
+    database: String,
+    backend: Backend,
+}
+
+enum Backend {
+    SecretTool,
+    Security,
+    File { path: PathBuf, passphrase: String },
+    None,
+}
+
+impl Keyring {
+    pub fn open(database_path: &Path) -> Keyring {
+        let backend = match std::env::var(PASSPHRASE_VAR) {
+            Ok(passphrase) if !passphrase.is_empty() => Backend::File {
+                path: database_path.with_extension("secrets"),
+                passphrase,
+            },
+            _ if cfg!(target_os = "macos") => Backend::Security,
+            _ if cfg!(unix) && is_on_path("secret-tool") => Backend::SecretTool,
+            _ => Backend::None,
+        };
+
+        Keyring {
+            database: database_path.display().to_string(),
+            backend,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        match &self.backend {
+            Backend::SecretTool => run(
+                Command::new("secret-tool")
+                    .arg("lookup")
+                    .args(self.secret_tool_attributes(name)),
+                None,
+            ),
+            Backend::Security => run(
+                Command::new("security")
+                    .args(["find-generic-password", "-s", SERVICE, "-a"])
+                    .arg(self.account(name))
+                    .arg("-w"),
+                None,
+            )
+            .map(|secret| secret.map(|secret| secret.trim_end_matches('\n').to_owned())),
+            Backend::File { path, passphrase } => {
+                SecretsFile::read(path)?.decrypt(passphrase, name)
+            }
+            Backend::None => Err(no_keyring()),
+        }
+    }
+
+    pub fn set(&self, name: &str, secret: &str) -> Result<()> {
+        match &self.backend {
+            Backend::SecretTool => run(
+                Command::new("secret-tool")
+                    .args(["store", "--label"])
+                    .arg(format!("russ {}", name))
+                    .args(self.secret_tool_attributes(name)),
+                Some(secret),
+            )
+            .map(|_| ()),
+            Backend::Security => {
+                let (mut command, stdin) = self.security_add_command(name, secret);
+                run(&mut command, Some(&stdin)).map(|_| ())
+            }
+            Backend::File { path, passphrase } => {
+                let mut secrets_file = SecretsFile::read(path)?;
+                secrets_file.encrypt(passphrase, name, secret)?;
+                secrets_file.write(path)
+            }
+            Backend::None => Err(no_keyring()),
+        }
+    }
+
+    /// Forget the secret called `name`, if there is one.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        match &self.backend {
+            Backend::SecretTool => run(
+                Command::new("secret-tool")
+                    .arg("clear")
+                    .args(self.secret_tool_attributes(name)),
+                None,
+            )
+            .map(|_| ()),
+            Backend::Security => run(
+                Command::new("security")
+                    .args(["delete-generic-password", "-s", SERVICE, "-a"])
+                    .arg(self.account(name)),
+                None,
+            )
+            .map(|_| ()),
+            Backend::File { path, .. } => {
+                let mut secrets_file = SecretsFile::read(path)?;
+                if secrets_file.secrets.remove(name).is_some() {
+                    secrets_file.write(path)?;
+                }
+                Ok(())
+            }
+            // nothing could have been kept in it
+            Backend::None => Ok(()),
+        }
+    }
+
+    /// The secret called `name`, moving it out of the database's `credentials` table,
+    /// where russ used to keep tokens, if it's still there.
+    pub fn get_credential(
+        &self,
+        conn: &rusqlite::Connection,
+        name: &str,
+    ) -> Result<Option<String>> {
+        if let Some(secret) = self.get(name)? {
+            return Ok(Some(secret));
+        }
+
+        match crate::rss::get_credential(conn, name)? {
+            Some(token) => {
+                self.set(name, &token)?;
+                crate::rss::delete_credential(conn, name)?;
+                Ok(Some(token))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// `security` asks for the secret, and its confirmation, when `-w` comes last
+    /// without one, so it goes on stdin, never in the arguments anyone could see in `ps`
+    fn security_add_command(&self, name: &str, secret: &str) -> (Command, String) {
+        let mut command = Command::new("security");
+        command
+            .args(["add-generic-password", "-U", "-s", SERVICE, "-a"])
+            .arg(self.account(name))
+            .arg("-w");
+        (command, format!("{}\n{}\n", secret, secret))
+    }
+
+    /// a keyring in an encrypted file, for tests that keep secrets
+    #[cfg(test)]
+    pub fn in_file(path: PathBuf, passphrase: &str) -> Keyring {
+        Keyring {
+            database: "test.db".to_string(),
+            backend: Backend::File {
+                path,
+                passphrase: passphrase.to_string(),
+            },
+        }
+    }
+
+    fn account(&self, name: &str) -> String {
+        format!("{}:{}", self.database, name)
+    }
+
+    fn secret_tool_attributes<'a>(&'a self, name: &'a str) -> [&'a str; 6] {
+        [
+            "application",
+            SERVICE,
+            "database",
+            &self.database,
+            "name",
+            name,
+        ]
+    }
+}
+
+fn no_keyring() -> anyhow::Error {
+    anyhow!(
+        "There is no keyring to keep secrets in, set ${} to keep them in an encrypted file instead",
+        PASSPHRASE_VAR
+    )
+}
+
+fn is_on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Run a keyring's command, returning what it prints,
+/// or `None` if it fails without saying why, which is how they say there's no such secret.
+fn run(command: &mut Command, stdin: Option<&str>) -> Result<Option<String>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Unable to run the keyring")?;
+
+    let mut child_stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(stdin) = stdin {
+        child_stdin.write_all(stdin.as_bytes())?;
+    }
+    drop(child_stdin);
+
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if output.status.success() {
+        Ok(Some(String::from_utf8(output.stdout)?))
+    } else if stderr.trim().is_empty() || stderr.contains("could not be found") {
+        Ok(None)
+    } else {
+        Err(anyhow!("The keyring failed: {}", stderr.trim()))
+    }
+}
+
+/// the encrypted file, for when there's no keyring
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct SecretsFile {
+    /// base64, for deriving the key from the passphrase
+    salt: String,
+    /// by name, base64 of the nonce, then the encrypted secret
+    secrets: BTreeMap<String, String>,
+}
+
+impl SecretsFile {
+    fn read(path: &Path) -> Result<SecretsFile> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Unable to read secrets from {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0; 16];
+                SystemRandom::new()
+                    .fill(&mut salt)
+                    .map_err(|_| anyhow!("Unable to generate a salt"))?;
+
+                Ok(SecretsFile {
+                    salt: base64::encode(salt),
+                    secrets: BTreeMap::new(),
+                })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options
+            .open(path)?
+            .write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn key(&self, passphrase: &str) -> Result<LessSafeKey> {
+        let mut key = [0; 32];
+        ring::pbkdf2::derive(
+            ring::pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations aren't 0"),
+            &base64::decode(&self.salt)?,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &key)
+            .map_err(|_| anyhow!("Unable to derive a key from the passphrase"))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    fn encrypt(&mut self, passphrase: &str, name: &str, secret: &str) -> Result<()> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Unable to generate a nonce"))?;
+
+        let mut sealed = secret.as_bytes().to_vec();
+        // the name is authenticated, so secrets can't be swapped around in the file
+        self.key(passphrase)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(name.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow!("Unable to encrypt {}", name))?;
+
+        self.secrets.insert(
+            name.to_owned(),
+            base64::encode([&nonce[..], &sealed].concat()),
+        );
+
+        Ok(())
+    }
+
+    fn decrypt(&self, passphrase: &str, name: &str) -> Result<Option<String>> {
+        let sealed = match self.secrets.get(name) {
+            Some(sealed) => base64::decode(sealed)?,
+            None => return Ok(None),
+        };
+
+        let wrong = || anyhow!("Unable to decrypt {}, is ${} right?", name, PASSPHRASE_VAR);
+
+        if sealed.len() < NONCE_LEN {
+            return Err(wrong());
+        }
+        let (nonce, sealed) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| wrong())?;
+
+        let mut sealed = sealed.to_vec();
+        let secret = self
+            .key(passphrase)?
+            .open_in_place(nonce, Aad::from(name.as_bytes()), &mut sealed)
+            .map_err(|_| wrong())?;
+
+        Ok(Some(String::from_utf8(secret.to_vec())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_secrets_file_needs_the_passphrase() {
+        let path = std::env::temp_dir().join(format!("russ-test-{}.secrets", std::process::id()));
+        let keyring = |passphrase: &str| Keyring::in_file(path.clone(), passphrase);
+
+        assert_eq!(keyring("right").get("pocket").unwrap(), None);
+        keyring("right").set("pocket", "token").unwrap();
+        keyring("right")
+            .set("wallabag-password", "hunter2")
+            .unwrap();
+        assert_eq!(
+            keyring("right").get("pocket").unwrap().as_deref(),
+            Some("token")
+        );
+        assert!(keyring("wrong").get("pocket").is_err());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+        keyring("right").delete("wallabag-password").unwrap();
+        assert_eq!(keyring("right").get("wallabag-password").unwrap(), None);
+        keyring("right").delete("wallabag-password").unwrap();
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::rss::initialize_db(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO credentials (service, token) VALUES ('old', 'plaintext')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            keyring("right")
+                .get_credential(&conn, "old")
+                .unwrap()
+                .as_deref(),
+            Some("plaintext")
+        );
+        assert_eq!(crate::rss::get_credential(&conn, "old").unwrap(), None);
+        assert_eq!(
+            keyring("right").get("old").unwrap().as_deref(),
+            Some("plaintext")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn security_gets_the_secret_on_stdin() {
+        let keyring = Keyring {
+            database: "test.db".to_string(),
+            backend: Backend::Security,
+        };
+
+        let (command, stdin) = keyring.security_add_command("wallabag-password", "hunter2");
+        let args = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert!(!args.iter().any(|arg| arg.contains("hunter2")));
+        assert_eq!(args.last().map(String::as_str), Some("-w"));
+        assert!(args.contains(&"test.db:wallabag-password".to_string()));
+        assert_eq!(stdin, "hunter2\nhunter2\n");
+    }
+}
@@ -0,0 +1,308 @@
+//! Telling which language an entry is in, when it's added, so multilingual feeds' entries
+//! in languages you can't read can be muted.
+//!
+//! Languages with scripts of their own are told by their script, and those written with
+//! the Latin alphabet by which of their most common words turn up the most.
+//! Either way, it's a guess, and text too short to guess from has no language.
+
+/// (code, name), for the languages written with the Latin alphabet, and their most common words
+const LATIN_LANGUAGES: &[(&str, &str, &[&str])] = &[
+    (
+        "en",
+        "english",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "it", "for", "was", "with", "on", "are",
+            "this", "you", "be", "have", "not", "but", "they",
+        ],
+    ),
+    (
+        "fr",
+        "french",
+        &[
+            "le", "la", "les", "des", "et", "est", "une", "un", "du", "que", "dans", "pour", "pas",
+            "qui", "sur", "au", "avec", "ce", "sont", "il",
+        ],
+    ),
+    (
+        "de",
+        "german",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "sich",
+            "von", "auf", "für", "auch", "es", "dem", "im", "wir",
+        ],
+    ),
+    (
+        "es",
+        "spanish",
+        &[
+            "el", "la", "los", "las", "que", "de", "y", "en", "es", "por", "una", "un", "para",
+            "con", "no", "del", "se", "al", "lo", "como",
+        ],
+    ),
+    (
+        "it",
+        "italian",
+        &[
+            "il", "che", "di", "la", "e", "è", "per", "un", "una", "non", "sono", "del", "della",
+            "con", "gli", "le", "si", "da", "nel", "anche",
+        ],
+    ),
+    (
+        "pt",
+        "portuguese",
+        &[
+            "o", "a", "os", "as", "que", "de", "e", "é", "não", "um", "uma", "para", "com", "do",
+            "da", "em", "no", "na", "se", "mais",
+        ],
+    ),
+    (
+        "nl",
+        "dutch",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "niet", "op", "te", "zijn", "met",
+            "voor", "die", "ook", "er", "maar", "om", "aan", "bij",
+        ],
+    ),
+    (
+        "sv",
+        "swedish",
+        &[
+            "och", "att", "det", "som", "en", "är", "på", "för", "med", "inte", "har", "av",
+            "till", "den", "jag", "om", "ett", "men", "var", "kan",
+        ],
+    ),
+    (
+        "pl",
+        "polish",
+        &[
+            "i", "w", "z", "na", "się", "nie", "jest", "że", "do", "to", "jak", "co", "ale", "od",
+            "po", "za", "o", "dla", "tak", "są",
+        ],
+    ),
+    (
+        "tr",
+        "turkish",
+        &[
+            "ve", "bir", "bu", "da", "de", "için", "ile", "çok", "ne", "daha", "olan", "gibi",
+            "ama", "en", "o", "var", "değil", "kadar", "mı", "olarak",
+        ],
+    ),
+];
+
+/// (code, name), for the languages told by their script
+const SCRIPT_LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "arabic"),
+    ("el", "greek"),
+    ("fa", "persian"),
+    ("he", "hebrew"),
+    ("hi", "hindi"),
+    ("ja", "japanese"),
+    ("ko", "korean"),
+    ("ru", "russian"),
+    ("th", "thai"),
+    ("uk", "ukrainian"),
+    ("zh", "chinese"),
+];
+
+/// fewer letters than this aren't enough to guess from
+const MIN_LETTERS: usize = 20;
+
+/// only so much of an entry is needed to tell what it's in
+const MAX_CHARS: usize = 2000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+    Latin,
+    Arabic,
+    Cyrillic,
+    Devanagari,
+    Greek,
+    Han,
+    Hangul,
+    Hebrew,
+    Kana,
+    Thai,
+}
+
+fn script(c: char) -> Option<Script> {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' => Some(Script::Latin),
+        '\u{600}'..='\u{6ff}' => Some(Script::Arabic),
+        '\u{400}'..='\u{4ff}' => Some(Script::Cyrillic),
+        '\u{900}'..='\u{97f}' => Some(Script::Devanagari),
+        '\u{370}'..='\u{3ff}' => Some(Script::Greek),
+        '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}' => Some(Script::Han),
+        '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => Some(Script::Hangul),
+        '\u{590}'..='\u{5ff}' => Some(Script::Hebrew),
+        '\u{3040}'..='\u{30ff}' => Some(Script::Kana),
+        '\u{e00}'..='\u{e7f}' => Some(Script::Thai),
+        _ => None,
+    }
+}
+
+/// The language `text` is in, as its ISO 639-1 code, like `de`, if it can be told.
+/// HTML tags in it are skipped.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let text = strip_tags(text);
+
+    let letters = text.chars().filter_map(script).collect::<Vec<_>>();
+    if letters.len() < MIN_LETTERS {
+        return None;
+    }
+
+    let count = |wanted: Script| letters.iter().filter(|s| **s == wanted).count();
+    let (script, script_count) = [
+        Script::Latin,
+        Script::Arabic,
+        Script::Cyrillic,
+        Script::Devanagari,
+        Script::Greek,
+        Script::Han,
+        Script::Hangul,
+        Script::Hebrew,
+        Script::Kana,
+        Script::Thai,
+    ]
+    .into_iter()
+    .map(|script| (script, count(script)))
+    .max_by_key(|(_, count)| *count)?;
+
+    // Japanese has more kanji than kana, often enough
+    if script == Script::Han && count(Script::Kana) * 10 >= script_count {
+        return Some("ja");
+    }
+
+    match script {
+        Script::Latin => detect_latin(&text),
+        Script::Arabic if text.contains(['پ', 'چ', 'ژ', 'گ']) => Some("fa"),
+        Script::Arabic => Some("ar"),
+        Script::Cyrillic if text.contains(['і', 'ї', 'є', 'ґ']) => Some("uk"),
+        Script::Cyrillic => Some("ru"),
+        Script::Devanagari => Some("hi"),
+        Script::Greek => Some("el"),
+        Script::Han => Some("zh"),
+        Script::Hangul => Some("ko"),
+        Script::Hebrew => Some("he"),
+        Script::Kana => Some("ja"),
+        Script::Thai => Some("th"),
+    }
+}
+
+/// the language whose common words `text` has the most of, if one clearly does
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    let mut scores = LATIN_LANGUAGES
+        .iter()
+        .map(|(code, _, common_words)| {
+            let score = words
+                .iter()
+                .filter(|word| common_words.contains(&word.as_str()))
+                .count();
+            (*code, score)
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    match scores.as_slice() {
+        [(code, best), (_, second), ..] if *best >= 2 && best > second => Some(code),
+        _ => None,
+    }
+}
+
+/// `text` without its HTML tags, and only as much of it as is needed
+fn strip_tags(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_tag = false;
+
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                stripped.push(' ');
+            }
+            _ if !in_tag => stripped.push(c),
+            _ => (),
+        }
+
+        if stripped.len() >= MAX_CHARS {
+            break;
+        }
+    }
+
+    stripped
+}
+
+/// A language's code, from its code or its English name, like `de` or `German`.
+pub fn normalize(language: &str) -> Option<String> {
+    let language = language.trim().to_lowercase();
+
+    LATIN_LANGUAGES
+        .iter()
+        .map(|(code, name, _)| (*code, *name))
+        .chain(SCRIPT_LANGUAGES.iter().copied())
+        .find(|(code, name)| *code == language || *name == language)
+        .map(|(code, _)| code.to_owned())
+        .or_else(|| {
+            // languages russ can't tell are still codes, like `fi`
+            (matches!(language.len(), 2 | 3) && language.chars().all(|c| c.is_ascii_lowercase()))
+                .then_some(language)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_languages() {
+        assert_eq!(
+            detect("<p>The quick brown fox jumps over the lazy dog, and that is <b>all</b> it does.</p>"),
+            Some("en")
+        );
+        assert_eq!(
+            detect(
+                "Der schnelle braune Fuchs springt über den faulen Hund, und das ist nicht alles."
+            ),
+            Some("de")
+        );
+        assert_eq!(
+            detect("Le renard brun rapide saute par-dessus le chien paresseux, et il est content."),
+            Some("fr")
+        );
+        assert_eq!(
+            detect("El rápido zorro marrón salta sobre el perro perezoso, y los gatos lo miran."),
+            Some("es")
+        );
+        assert_eq!(
+            detect("Быстрая коричневая лиса прыгает через ленивую собаку."),
+            Some("ru")
+        );
+        assert_eq!(
+            detect("日本語のテキストは、ひらがなとカタカナと漢字で書かれています。"),
+            Some("ja")
+        );
+        assert_eq!(
+            detect("这是一个用中文写的句子，用来测试语言检测功能是否正常。"),
+            Some("zh")
+        );
+        assert_eq!(detect("Too short"), None);
+        assert_eq!(
+            detect("Lorem ipsum dolor sit amet, consectetur adipiscing"),
+            None
+        );
+    }
+
+    #[test]
+    fn it_normalizes_languages() {
+        assert_eq!(normalize(" German "), Some("de".to_string()));
+        assert_eq!(normalize("JA"), Some("ja".to_string()));
+        assert_eq!(normalize("fi"), Some("fi".to_string()));
+        assert_eq!(normalize("not a language"), None);
+    }
+}
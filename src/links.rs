@@ -0,0 +1,157 @@
+//! Tidying up entries' links: taking out the parameters that are only there to track
+//! who clicked them, like `utm_source` and `fbclid`, and telling which domain a link is on,
+//! for hiding the entries from blocked ones.
+
+/// parameters that start with these are only for tracking
+const TRACKING_PARAM_PREFIXES: &[&str] = &["utm_", "_hs", "mtm_", "pk_"];
+
+/// and these are too
+const TRACKING_PARAMS: &[&str] = &[
+    "dclid",
+    "fbclid",
+    "gbraid",
+    "gclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "mkt_tok",
+    "msclkid",
+    "oly_anon_id",
+    "oly_enc_id",
+    "twclid",
+    "vero_id",
+    "wbraid",
+    "yclid",
+];
+
+fn is_tracking_param(param: &str) -> bool {
+    let name = param
+        .split_once('=')
+        .map(|(name, _)| name)
+        .unwrap_or(param)
+        .to_ascii_lowercase();
+
+    TRACKING_PARAMS.contains(&name.as_str())
+        || TRACKING_PARAM_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// `link` without its tracking parameters, and without its `?` if that was all it had
+pub fn strip_tracking_params(link: &str) -> String {
+    let (link, fragment) = match link.split_once('#') {
+        Some((link, fragment)) => (link, Some(fragment)),
+        None => (link, None),
+    };
+
+    let mut stripped = match link.split_once('?') {
+        Some((path, query)) => {
+            let query = query
+                .split('&')
+                .filter(|param| !param.is_empty() && !is_tracking_param(param))
+                .collect::<Vec<_>>();
+
+            if query.is_empty() {
+                path.to_owned()
+            } else {
+                format!("{}?{}", path, query.join("&"))
+            }
+        }
+        None => link.to_owned(),
+    };
+
+    if let Some(fragment) = fragment {
+        stripped.push('#');
+        stripped.push_str(fragment);
+    }
+
+    stripped
+}
+
+/// the host `link` is on, lowercased, without any port or credentials
+pub fn host(link: &str) -> Option<String> {
+    let (_, rest) = link.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+
+    Some(host.trim_end_matches('.').to_ascii_lowercase()).filter(|host| !host.is_empty())
+}
+
+/// A domain to block, like `example.com`, from whatever was typed,
+/// which might have been a link to somewhere on it.
+pub fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim();
+
+    match host(domain) {
+        Some(host) => Some(host),
+        None => Some(
+            domain
+                .split('/')
+                .next()?
+                .trim_end_matches('.')
+                .to_ascii_lowercase(),
+        ),
+    }
+    .map(|domain| domain.trim_start_matches("www.").to_owned())
+    .filter(|domain| !domain.is_empty() && !domain.contains(char::is_whitespace))
+}
+
+/// whether `link` is on one of `domains`, or on a subdomain of one
+pub fn is_blocked(link: &str, domains: &[String]) -> bool {
+    let host = match host(link) {
+        Some(host) => host,
+        None => return false,
+    };
+
+    domains.iter().any(|domain| {
+        host == *domain
+            || host
+                .strip_suffix(domain.as_str())
+                .map(|subdomain| subdomain.ends_with('.'))
+                .unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_strips_tracking_params() {
+        assert_eq!(
+            strip_tracking_params(
+                "https://example.com/post?utm_source=rss&utm_medium=feed&id=3&fbclid=abc#comments"
+            ),
+            "https://example.com/post?id=3#comments"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/post?UTM_Campaign=x"),
+            "https://example.com/post"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/post?utmost=1"),
+            "https://example.com/post?utmost=1"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/"),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn it_blocks_domains_and_their_subdomains() {
+        let domains = vec![normalize_domain("https://www.Example.com/feed").unwrap()];
+        assert_eq!(domains, vec!["example.com"]);
+
+        assert!(is_blocked("https://example.com/a", &domains));
+        assert!(is_blocked("http://user@news.example.com:8080/a", &domains));
+        assert!(!is_blocked("https://notexample.com/a", &domains));
+        assert!(!is_blocked("https://example.com.au/a", &domains));
+        assert!(!is_blocked("not a link", &domains));
+        assert_eq!(normalize_domain("  "), None);
+    }
+}
@@ -0,0 +1,505 @@
+//! `russ check`, for finding out why a feed doesn't work, or works badly:
+//! fetching it, then saying what's wrong with it, from where it redirects to
+//! and how big it is, to entries russ can't tell apart or date.
+
+use anyhow::{anyhow, Result};
+use std::fmt::Display;
+use std::io::Read;
+
+/// how many redirects are followed before giving up, like browsers do
+const MAX_REDIRECTS: usize = 10;
+
+/// feeds bigger than this are slow to refresh, and usually have every entry there's ever been
+const HUGE_LEN: usize = 5 * 1024 * 1024;
+
+/// the most of a feed that's read
+const MAX_LEN: u64 = 100 * 1024 * 1024;
+
+/// how many examples of a problem with entries are given
+const EXAMPLES_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// russ can't subscribe to the feed
+    Error,
+    /// russ can, but it won't work as well as it could
+    Warning,
+    /// worth knowing, but nothing's wrong
+    Note,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(message: impl Into<String>) -> Problem {
+        Problem {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Problem {
+        Problem {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    fn note(message: impl Into<String>) -> Problem {
+        Problem {
+            severity: Severity::Note,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+/// what checking a feed found
+#[derive(Debug)]
+pub struct Report {
+    /// Atom, RSS, or what the document looked like if it wasn't a feed
+    pub kind: Option<&'static str>,
+    pub entries_len: usize,
+    pub len: usize,
+    pub problems: Vec<Problem>,
+}
+
+/// Fetch the feed at `url`, following its redirects with `http_client`,
+/// which mustn't follow them itself, and check it.
+pub fn check(http_client: &ureq::Agent, url: &str) -> Result<Report> {
+    let mut problems = vec![];
+    let mut url = url.trim().to_owned();
+    let mut redirects_len = 0;
+
+    let resp = loop {
+        let resp = match http_client.get(&url).call() {
+            Ok(resp) => resp,
+            Err(ureq::Error::Status(status, resp)) => {
+                return Err(anyhow!(
+                    "{} returned {} {}",
+                    url,
+                    status,
+                    resp.status_text()
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if !(300..400).contains(&resp.status()) {
+            break resp;
+        }
+
+        let location = resp
+            .header("Location")
+            .ok_or_else(|| anyhow!("{} redirects without saying where to", url))?;
+        let location = resolve(&url, location);
+
+        redirects_len += 1;
+        if redirects_len > MAX_REDIRECTS {
+            return Err(anyhow!(
+                "{} redirects more than {} times",
+                url,
+                MAX_REDIRECTS
+            ));
+        }
+
+        problems.push(match resp.status() {
+            301 | 308 => Problem::warning(format!(
+                "{} has moved to {} for good, subscribe to that instead",
+                url, location
+            )),
+            status => Problem::note(format!("{} redirects ({}) to {}", url, status, location)),
+        });
+        url = location;
+    };
+
+    if redirects_len > 1 {
+        problems.push(Problem::warning(format!(
+            "it takes {} redirects to get to the feed, each of them another request every refresh",
+            redirects_len
+        )));
+    }
+
+    let content_type = resp.header("Content-Type").map(|s| s.to_owned());
+    let mut body = vec![];
+    resp.into_reader().take(MAX_LEN).read_to_end(&mut body)?;
+
+    let mut report = check_document(&body, content_type.as_deref());
+    problems.append(&mut report.problems);
+    report.problems = problems;
+
+    Ok(report)
+}
+
+/// `location`, from a redirect from `url`, which may be relative to it
+fn resolve(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_owned();
+    }
+
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    let origin_len = rest.find('/').unwrap_or(rest.len());
+    let origin = format!("{}://{}", scheme, &rest[..origin_len]);
+
+    if let Some(location) = location.strip_prefix("//") {
+        format!("{}://{}", scheme, location)
+    } else if location.starts_with('/') {
+        format!("{}{}", origin, location)
+    } else {
+        let path = &rest[origin_len..];
+        let dir = &path[..path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+        format!(
+            "{}{}{}",
+            origin,
+            if dir.is_empty() { "/" } else { dir },
+            location
+        )
+    }
+}
+
+/// Check a feed's document, and the content type it was served as, if it was.
+pub fn check_document(body: &[u8], content_type: Option<&str>) -> Report {
+    let mut report = Report {
+        kind: None,
+        entries_len: 0,
+        len: body.len(),
+        problems: vec![],
+    };
+    let problems = &mut report.problems;
+
+    if body.len() > HUGE_LEN {
+        problems.push(Problem::warning(format!(
+            "it's {} MB, which makes it slow to refresh",
+            body.len() / 1024 / 1024
+        )));
+    }
+
+    if let Some(content_type) = content_type {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        if !mime.contains("xml") && !mime.starts_with("text/plain") {
+            problems.push(Problem::note(format!(
+                "it's served as {}, rather than as XML",
+                mime
+            )));
+        }
+    }
+
+    let start = String::from_utf8_lossy(&body[..body.len().min(512)])
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .to_lowercase();
+    if start.starts_with('{') {
+        report.kind = Some("JSON");
+        problems.push(Problem::error(
+            "it looks like a JSON Feed, which russ doesn't support, look for an RSS or Atom feed",
+        ));
+        return report;
+    }
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        report.kind = Some("HTML");
+        problems.push(Problem::error(
+            "it's a web page, not a feed, look in it for a <link rel=\"alternate\"> to its feed",
+        ));
+        return report;
+    }
+
+    let root = match root_element(body) {
+        Ok(root) => root,
+        Err(e) => {
+            problems.push(Problem::error(format!("it isn't valid XML: {}", e)));
+            return report;
+        }
+    };
+
+    match root.as_str() {
+        "feed" => {
+            report.kind = Some("Atom");
+            match atom_syndication::Feed::read_from(body) {
+                Ok(feed) => {
+                    report.entries_len = feed.entries().len();
+                    check_atom_entries(&feed, problems);
+                }
+                Err(e) => problems.push(Problem::error(format!("it isn't valid Atom: {}", e))),
+            }
+        }
+        "rss" | "RDF" => {
+            report.kind = Some("RSS");
+            match rss::Channel::read_from(body) {
+                Ok(channel) => {
+                    report.entries_len = channel.items().len();
+                    check_rss_items(&channel, problems);
+                }
+                Err(e) => problems.push(Problem::error(format!("it isn't valid RSS: {}", e))),
+            }
+        }
+        root => problems.push(Problem::error(format!(
+            "it's XML, but not RSS or Atom, as its root element is <{}>",
+            root
+        ))),
+    }
+
+    if report.kind.is_some() && report.entries_len == 0 {
+        report
+            .problems
+            .push(Problem::warning("it has no entries".to_string()));
+    }
+
+    report
+}
+
+/// read the whole document, so any XML errors are found, returning its root element's name
+fn root_element(body: &[u8]) -> Result<String> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_reader(body);
+    let mut buf = vec![];
+    let mut root = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(start) | Event::Empty(start)) if root.is_none() => {
+                root = Some(String::from_utf8_lossy(start.local_name().as_ref()).into_owned());
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => (),
+            Err(e) => return Err(anyhow!("{}, at byte {}", e, reader.error_position())),
+        }
+        buf.clear();
+    }
+
+    root.ok_or_else(|| anyhow!("it's empty"))
+}
+
+type EntryDate<'a> = Result<chrono::DateTime<chrono::Utc>, &'a str>;
+
+/// What's wrong with the entries, which russ tells apart by their links, and dates.
+/// Each is the entry's title, link, and date, if it gives one, or the one it gives that can't be read.
+fn check_entries<'a>(
+    entries: impl Iterator<Item = (Option<&'a str>, Option<&'a str>, Option<EntryDate<'a>>)>,
+    problems: &mut Vec<Problem>,
+) {
+    let mut without_links = vec![];
+    let mut links = std::collections::HashSet::new();
+    let mut duplicate_links = vec![];
+    let mut without_dates = vec![];
+    let mut bad_dates = vec![];
+    let mut future_dates = vec![];
+    let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+
+    for (title, link, date) in entries {
+        let title = title.unwrap_or("an entry with no title").to_owned();
+
+        match link {
+            None => without_links.push(title.clone()),
+            Some(link) if !links.insert(link) => duplicate_links.push(link.to_owned()),
+            Some(_) => (),
+        }
+
+        match date {
+            None => without_dates.push(title),
+            Some(Err(date)) => bad_dates.push(format!("{:?}", date)),
+            Some(Ok(date)) if date > tomorrow => future_dates.push(date.to_rfc3339()),
+            Some(Ok(_)) => (),
+        }
+    }
+
+    // what's wrong with one entry, and with more than one
+    let mut push = |severity, examples: Vec<String>, (one, many): (&str, &str)| {
+        if !examples.is_empty() {
+            problems.push(Problem {
+                severity,
+                message: format!(
+                    "{} {}, like {}",
+                    examples.len(),
+                    if examples.len() == 1 { one } else { many },
+                    examples
+                        .iter()
+                        .take(EXAMPLES_LEN)
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+    };
+
+    push(
+        Severity::Warning,
+        without_links,
+        (
+            "entry has no link, which russ tells entries apart by, so it's only added once",
+            "entries have no link, which russ tells entries apart by, so they're only added once",
+        ),
+    );
+    push(
+        Severity::Warning,
+        duplicate_links,
+        (
+            "entry has the same link as another, so only one of them is added",
+            "entries have the same link as another, so only one of them is added",
+        ),
+    );
+    push(
+        Severity::Note,
+        without_dates,
+        (
+            "entry has no date, so it's dated when it's added",
+            "entries have no date, so they're dated when they're added",
+        ),
+    );
+    push(
+        Severity::Warning,
+        bad_dates,
+        (
+            "entry has a date russ can't read, so it's dated when it's added",
+            "entries have dates russ can't read, so they're dated when they're added",
+        ),
+    );
+    push(
+        Severity::Warning,
+        future_dates,
+        (
+            "entry is dated in the future, so it stays at the top",
+            "entries are dated in the future, so they stay at the top",
+        ),
+    );
+}
+
+fn check_atom_entries(feed: &atom_syndication::Feed, problems: &mut Vec<Problem>) {
+    let without_ids = feed
+        .entries()
+        .iter()
+        .filter(|entry| entry.id().trim().is_empty())
+        .count();
+    if without_ids > 0 {
+        problems.push(Problem::note(match without_ids {
+            1 => "1 entry has no <id>, which Atom requires".to_string(),
+            _ => format!("{} entries have no <id>, which Atom requires", without_ids),
+        }));
+    }
+
+    // russ keeps the `alternate` link, or the first, as the entry's link
+    let entries = feed.entries().iter().map(|entry| {
+        let link = entry
+            .links()
+            .iter()
+            .find(|link| link.rel() == "alternate")
+            .or_else(|| entry.links().first())
+            .map(|link| link.href());
+        let date = entry
+            .published()
+            .map(|date| Ok(date.with_timezone(&chrono::Utc)));
+
+        (Some(entry.title().as_str()), link, date)
+    });
+    check_entries(entries, problems);
+}
+
+fn check_rss_items(channel: &rss::Channel, problems: &mut Vec<Problem>) {
+    let without_guids = channel
+        .items()
+        .iter()
+        .filter(|item| item.guid().is_none())
+        .count();
+    if without_guids > 0 {
+        problems.push(Problem::note(match without_guids {
+            1 => "1 item has no <guid>".to_string(),
+            _ => format!("{} items have no <guid>", without_guids),
+        }));
+    }
+
+    let entries = channel.items().iter().map(|item| {
+        let date = item
+            .pub_date()
+            .map(|date| crate::dates::parse(date).ok_or(date));
+        (item.title(), item.link(), date)
+    });
+    check_entries(entries, problems);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(report: &Report) -> Vec<String> {
+        report
+            .problems
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_problems_with_feeds() {
+        let report = check_document(
+            br#"<?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Test</title>
+                <item><title>a</title><link>https://example.com/a</link><pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate></item>
+                <item><title>b</title><link>https://example.com/a</link><pubDate>yesterday-ish</pubDate></item>
+                <item><title>c</title></item>
+              </channel>
+            </rss>"#,
+            Some("text/html; charset=utf-8"),
+        );
+
+        assert_eq!(report.kind, Some("RSS"));
+        assert_eq!(report.entries_len, 3);
+        assert_eq!(
+            messages(&report),
+            vec![
+                "note: it's served as text/html, rather than as XML",
+                "note: 3 items have no <guid>",
+                "warning: 1 entry has no link, which russ tells entries apart by, so it's only added once, like c",
+                "warning: 1 entry has the same link as another, so only one of them is added, like https://example.com/a",
+                "note: 1 entry has no date, so it's dated when it's added, like c",
+                "warning: 1 entry has a date russ can't read, so it's dated when it's added, like \"yesterday-ish\"",
+            ]
+        );
+
+        let report = check_document(b"<rss><channel><title>a</channel></rss>", None);
+        assert_eq!(report.problems[0].severity, Severity::Error);
+        assert!(report.problems[0].message.starts_with("it isn't valid XML"));
+
+        let report = check_document(b"<!DOCTYPE html><html></html>", None);
+        assert_eq!(report.kind, Some("HTML"));
+
+        let report = check_document(
+            br#"<feed xmlns="http://www.w3.org/2005/Atom"><title>a</title></feed>"#,
+            Some("application/atom+xml"),
+        );
+        assert_eq!(messages(&report), vec!["warning: it has no entries"]);
+    }
+
+    #[test]
+    fn redirects_are_resolved() {
+        let url = "https://example.com/blog/feed.xml";
+        assert_eq!(
+            resolve(url, "https://example.org/a"),
+            "https://example.org/a"
+        );
+        assert_eq!(resolve(url, "//example.org/a"), "https://example.org/a");
+        assert_eq!(resolve(url, "/a"), "https://example.com/a");
+        assert_eq!(
+            resolve(url, "atom.xml"),
+            "https://example.com/blog/atom.xml"
+        );
+        assert_eq!(resolve("https://example.com", "a"), "https://example.com/a");
+    }
+}
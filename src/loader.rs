@@ -0,0 +1,226 @@
+//! Loading the feeds list, the entries list, and the entry being opened
+//! on a thread of its own, with its own connection, so that moving through the feeds
+//! doesn't wait on a feed with thousands of entries, or opening an entry on a long one,
+//! before drawing the next frame.
+//!
+//! Each load is numbered, and only the newest one of each kind is shown,
+//! so holding down a key to skip past feeds skips loading them too.
+
+use crate::app::App;
+use crate::modes::{ReadMode, VirtualFeed};
+use crate::rss::{
+    EntriesFilter, EntryContent, EntryId, EntryMeta, EntrySort, Feed, FeedId, FeedSort, Page,
+};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+/// how many entries are loaded at a time, for feeds going back years
+pub const PAGE_SIZE: usize = 500;
+
+/// more entries are loaded once the selection is this close to the last one loaded
+pub const LOAD_MORE_MARGIN: usize = 50;
+
+/// which entries to list
+#[derive(Clone, Debug)]
+pub struct EntriesQuery {
+    pub read_mode: ReadMode,
+    /// listing a tag's entries, rather than a feed's
+    pub tag: Option<String>,
+    /// listing a virtual feed's entries, from every feed, rather than a feed's
+    pub virtual_feed: Option<VirtualFeed>,
+    pub feed: Option<(FeedId, EntrySort)>,
+    /// only the entries from some days, or with something in their titles,
+    /// filtered before they're paged, so every page is full of them
+    pub filter: EntriesFilter,
+    /// how many to load to begin with, the rest being loaded as they're scrolled to
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+pub struct LoadedEntries {
+    pub entries: Vec<EntryMeta>,
+    /// (unread, total)
+    pub counts: (usize, usize),
+    /// there are no more entries to load after these
+    pub exhausted: bool,
+}
+
+impl EntriesQuery {
+    /// the first `self.limit` entries, and the counts
+    pub fn run(&self, conn: &rusqlite::Connection) -> Result<LoadedEntries> {
+        let entries = self.entries(
+            conn,
+            Page {
+                offset: 0,
+                limit: self.limit,
+            },
+        )?;
+
+        let counts = match (&self.tag, self.virtual_feed, self.feed) {
+            (Some(tag), _, _) => crate::rss::get_tag_entry_counts(conn, tag)?,
+            (None, Some(virtual_feed), _) => match virtual_feed.since(chrono::Local::now()) {
+                Some(since) => crate::rss::get_entry_counts_since(conn, since)?,
+                None => (0, crate::rss::get_read_entry_count(conn)?),
+            },
+            (None, None, Some((feed_id, _))) => crate::rss::get_feed_entry_counts(conn, feed_id)?,
+            (None, None, None) => (0, 0),
+        };
+
+        Ok(LoadedEntries {
+            exhausted: entries.len() < self.limit,
+            entries,
+            counts,
+        })
+    }
+
+    pub fn entries(&self, conn: &rusqlite::Connection, page: Page) -> Result<Vec<EntryMeta>> {
+        match (&self.tag, self.virtual_feed, self.feed) {
+            (Some(tag), _, _) => crate::rss::get_tagged_entries_metas_page(
+                conn,
+                &self.read_mode,
+                EntrySort::PubDateDesc,
+                tag,
+                &self.filter,
+                page,
+            ),
+            (None, Some(virtual_feed), _) => match virtual_feed.since(chrono::Local::now()) {
+                Some(since) => crate::rss::get_entries_metas_since_page(
+                    conn,
+                    &self.read_mode,
+                    since,
+                    &self.filter,
+                    page,
+                ),
+                None => crate::rss::get_read_entries_metas_page(conn, &self.filter, page),
+            },
+            (None, None, Some((feed_id, entry_sort))) => crate::rss::get_entries_metas_page(
+                conn,
+                &self.read_mode,
+                entry_sort,
+                feed_id,
+                &self.filter,
+                page,
+            ),
+            (None, None, None) => Ok(vec![]),
+        }
+    }
+}
+
+/// which feeds to list
+#[derive(Clone, Debug)]
+pub struct FeedsQuery {
+    pub feed_sort: FeedSort,
+    /// leaving out the feeds with nothing unread
+    pub hide_read_feeds: bool,
+}
+
+#[derive(Debug)]
+pub struct LoadedFeeds {
+    pub feeds: Vec<Feed>,
+    /// the feeds with something unread, if the others are left out
+    pub unread_feed_ids: Option<HashSet<FeedId>>,
+}
+
+impl FeedsQuery {
+    pub fn run(&self, conn: &rusqlite::Connection) -> Result<LoadedFeeds> {
+        Ok(LoadedFeeds {
+            feeds: crate::rss::get_sorted_feeds(conn, self.feed_sort)?,
+            unread_feed_ids: if self.hide_read_feeds {
+                Some(crate::rss::get_unread_feed_ids(conn)?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// what opening an entry reads from the database
+#[derive(Debug)]
+pub struct LoadedEntry {
+    pub entry_id: EntryId,
+    pub content: EntryContent,
+    pub note: Option<String>,
+}
+
+impl LoadedEntry {
+    pub fn load(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<LoadedEntry> {
+        Ok(LoadedEntry {
+            entry_id,
+            content: crate::rss::get_entry_content(conn, entry_id)?,
+            note: crate::rss::get_entry_note(conn, entry_id)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadRequest {
+    Feeds(FeedsQuery),
+    Entries(EntriesQuery),
+    /// an entry, to open, or to preview if not
+    Entry {
+        entry_id: EntryId,
+        open: bool,
+    },
+}
+
+#[derive(Debug)]
+pub struct Load {
+    /// counts loads of each kind, so the stale ones are dropped
+    pub generation: u64,
+    /// the current profile's, which the loader follows when it changes
+    pub database_path: PathBuf,
+    pub database_passphrase: Option<String>,
+    pub request: LoadRequest,
+}
+
+pub fn spawn(app: App, rx: Receiver<Load>) {
+    std::thread::spawn(move || {
+        let mut conn: Option<(PathBuf, rusqlite::Connection)> = None;
+
+        while let Ok(load) = rx.recv() {
+            // only the newest load of each kind is going to be shown
+            let mut loads: Vec<Load> = vec![];
+            for load in std::iter::once(load).chain(rx.try_iter()) {
+                let kind = std::mem::discriminant(&load.request);
+                loads.retain(|older| std::mem::discriminant(&older.request) != kind);
+                loads.push(load);
+            }
+
+            for load in loads {
+                let generation = load.generation;
+                let result = connection(&mut conn, &load).and_then(|conn| match load.request {
+                    LoadRequest::Feeds(query) => query
+                        .run(conn)
+                        .and_then(|loaded| app.finish_loading_feeds(generation, loaded)),
+                    LoadRequest::Entries(query) => query
+                        .run(conn)
+                        .and_then(|loaded| app.finish_loading_entries(generation, loaded)),
+                    LoadRequest::Entry { entry_id, open } => LoadedEntry::load(conn, entry_id)
+                        .and_then(|loaded| app.finish_loading_entry(generation, loaded, open)),
+                });
+
+                if let Err(e) = result {
+                    app.push_error_flash(e);
+                }
+            }
+        }
+    });
+}
+
+/// the loader's connection, opening it again if the database changed
+fn connection<'a>(
+    conn: &'a mut Option<(PathBuf, rusqlite::Connection)>,
+    request: &Load,
+) -> Result<&'a rusqlite::Connection> {
+    if !matches!(conn, Some((path, _)) if *path == request.database_path) {
+        let opened = crate::rss::open_db(
+            &request.database_path,
+            request.database_passphrase.as_deref(),
+        )?;
+        *conn = Some((request.database_path.clone(), opened));
+    }
+
+    Ok(&conn.as_ref().expect("just opened").1)
+}
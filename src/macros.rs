@@ -0,0 +1,197 @@
+//! Keyboard macros, like vim's: `Z` and a letter records the keys pressed after it into
+//! the letter's register, until `Z` is pressed again, and `@` and the letter presses them again.
+//! They're only kept while russ is running.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::KeyEvent;
+use std::collections::{HashMap, VecDeque};
+
+/// macros replaying macros this deep are a macro that replays itself, forever,
+/// however many keys a legitimate one, like `5000@a`, replays
+const MAX_DEPTH: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    /// the register being recorded into, and the keys pressed since
+    recording: Option<(char, Vec<KeyEvent>)>,
+    /// the keys left to replay, next first, with how deep in macros replaying macros they are
+    queue: VecDeque<(KeyEvent, usize)>,
+    /// how deep the key replayed last was, or 0 if it was actually pressed
+    depth: usize,
+    /// the register replayed last, for `@@`
+    last_replayed: Option<char>,
+}
+
+impl Macros {
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, vec![]));
+    }
+
+    /// Stop recording, keeping the keys in their register,
+    /// but not the last one, which stopped it. The register and how many keys it has.
+    pub fn stop_recording(&mut self) -> Option<(char, usize)> {
+        let (register, mut keys) = self.recording.take()?;
+        keys.pop();
+        let len = keys.len();
+        self.registers.insert(register, keys);
+        Some((register, len))
+    }
+
+    /// Remember `key`, which was actually pressed, if recording.
+    pub fn record(&mut self, key: KeyEvent) {
+        self.depth = 0;
+
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+
+    /// Replay `register`'s keys `count` times, before whatever is left of the macro replaying it, if any.
+    /// `@` is the register replayed last.
+    /// Gives up on a macro replayed by macros so deep it must be replaying itself.
+    pub fn replay(&mut self, register: char, count: usize) -> Result<()> {
+        let register = match register {
+            '@' => self
+                .last_replayed
+                .ok_or_else(|| anyhow!("No macro has been replayed yet"))?,
+            register => register,
+        };
+
+        let keys = self
+            .registers
+            .get(&register)
+            .filter(|keys| !keys.is_empty())
+            .ok_or_else(|| anyhow!("Nothing is recorded in {}", register))?;
+
+        let depth = self.depth + 1;
+        if depth > MAX_DEPTH {
+            self.queue.clear();
+            self.depth = 0;
+            return Err(anyhow!(
+                "Stopped replaying {} within {} macros, as it seems to replay itself",
+                register,
+                MAX_DEPTH
+            ));
+        }
+
+        for _ in 0..count {
+            for key in keys.iter().rev() {
+                self.queue.push_front((*key, depth));
+            }
+        }
+
+        self.last_replayed = Some(register);
+        Ok(())
+    }
+
+    /// The next key to replay, if any.
+    pub fn next_key(&mut self) -> Option<KeyEvent> {
+        let (key, depth) = self.queue.pop_front()?;
+        self.depth = depth;
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn replay_all(macros: &mut Macros) -> Vec<KeyEvent> {
+        std::iter::from_fn(|| macros.next_key()).collect()
+    }
+
+    #[test]
+    fn it_records_and_replays_macros() {
+        let mut macros = Macros::default();
+        assert!(macros.replay('a', 1).is_err());
+
+        macros.start_recording('a');
+        assert_eq!(macros.recording(), Some('a'));
+        for c in ['l', 'r', 'j', 'Z'] {
+            macros.record(key(c));
+        }
+        assert_eq!(macros.stop_recording(), Some(('a', 3)));
+        assert_eq!(macros.recording(), None);
+
+        // not recording
+        macros.record(key('x'));
+
+        macros.replay('a', 2).unwrap();
+        assert_eq!(
+            replay_all(&mut macros),
+            ['l', 'r', 'j', 'l', 'r', 'j'].map(key)
+        );
+
+        macros.replay('@', 1).unwrap();
+        assert_eq!(replay_all(&mut macros), ['l', 'r', 'j'].map(key));
+    }
+
+    #[test]
+    fn it_replays_macros_within_macros_first() {
+        let mut macros = Macros::default();
+        macros.start_recording('b');
+        macros.record(key('s'));
+        macros.record(key('Z'));
+        macros.stop_recording();
+
+        macros.start_recording('a');
+        for c in ['j', '@', 'b', 'k', 'Z'] {
+            macros.record(key(c));
+        }
+        macros.stop_recording();
+
+        macros.replay('a', 1).unwrap();
+        assert_eq!(macros.next_key(), Some(key('j')));
+        assert_eq!(macros.next_key(), Some(key('@')));
+        assert_eq!(macros.next_key(), Some(key('b')));
+        macros.replay('b', 1).unwrap();
+        assert_eq!(replay_all(&mut macros), ['s', 'k'].map(key));
+    }
+
+    #[test]
+    fn it_stops_macros_that_replay_themselves() {
+        let mut macros = Macros::default();
+        macros.start_recording('a');
+        for c in ['j', '@', 'a', 'Z'] {
+            macros.record(key(c));
+        }
+        macros.stop_recording();
+
+        macros.replay('a', 1).unwrap();
+        let result = loop {
+            match macros.next_key() {
+                Some(replayed) if replayed == key('a') => {
+                    if let Err(e) = macros.replay('a', 1) {
+                        break Err(e);
+                    }
+                }
+                Some(_) => (),
+                None => break Ok(()),
+            }
+        };
+        assert!(result.is_err());
+        assert_eq!(macros.next_key(), None);
+    }
+
+    #[test]
+    fn it_replays_macros_many_times() {
+        let mut macros = Macros::default();
+        macros.start_recording('a');
+        macros.record(key('j'));
+        macros.record(key('Z'));
+        macros.stop_recording();
+
+        macros.replay('a', 20_000).unwrap();
+        assert_eq!(replay_all(&mut macros).len(), 20_000);
+    }
+}
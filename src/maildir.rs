@@ -0,0 +1,271 @@
+//! Exporting entries to a maildir, one folder per feed,
+//! so they can be read and filtered with a mail client.
+
+use crate::modes::ReadMode;
+use crate::rss::{EntryMeta, Feed};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// the width entry content is wrapped to in messages
+const MESSAGE_LINE_LENGTH: usize = 78;
+
+/// Export every entry that isn't already in the maildir at `root`,
+/// returning how many were exported.
+///
+/// An entry's message is named for its id, so an entry is only exported once,
+/// even after a mail client moves or flags it.
+pub fn export(conn: &rusqlite::Connection, root: &Path) -> Result<usize> {
+    let mut exported_len = 0;
+
+    for feed in crate::rss::get_feeds(conn)? {
+        let entries = crate::rss::get_entries_metas(
+            conn,
+            &ReadMode::All,
+            crate::rss::EntrySort::PubDateAsc,
+            feed.id,
+        )?;
+
+        if entries.is_empty() {
+            continue;
+        }
+
+        let folder = root.join(folder_name(&feed));
+        for subfolder in ["cur", "new", "tmp"] {
+            std::fs::create_dir_all(folder.join(subfolder))
+                .with_context(|| format!("Unable to create maildir {}", folder.display()))?;
+        }
+
+        let exported_names = exported_names(&folder)?;
+
+        for entry in entries {
+            let name = message_name(&entry);
+
+            if exported_names.contains(&name) {
+                continue;
+            }
+
+            let content = crate::rss::get_entry_content(conn, entry.id)?;
+            let html = content
+                .full_content
+                .or(content.content)
+                .or(content.description);
+
+            let message = entry_message(&entry, &feed, html.as_deref());
+            write_message(&folder, &entry, &name, &message)?;
+            exported_len += 1;
+        }
+    }
+
+    Ok(exported_len)
+}
+
+/// the feed's title, without anything that would make it more than one directory
+fn folder_name(feed: &Feed) -> String {
+    let title = feed
+        .title
+        .as_deref()
+        .or(feed.feed_link.as_deref())
+        .unwrap_or("untitled");
+
+    let name = title
+        .chars()
+        .map(|c| {
+            if std::path::is_separator(c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+
+    // a leading `.` would hide it, or make it a Maildir++ subfolder
+    let name = name.trim().trim_start_matches('.');
+
+    if name.is_empty() {
+        format!("feed-{}", feed.id)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn message_name(entry: &EntryMeta) -> String {
+    format!("russ-{}", entry.id)
+}
+
+/// the names of the messages in `folder`, without the flags mail clients add to them
+fn exported_names(folder: &Path) -> Result<std::collections::HashSet<String>> {
+    let mut names = std::collections::HashSet::new();
+
+    for subfolder in ["cur", "new"] {
+        for dir_entry in std::fs::read_dir(folder.join(subfolder))? {
+            let file_name = dir_entry?.file_name();
+            let file_name = file_name.to_string_lossy();
+            let name = match file_name.split_once(':') {
+                Some((name, _info)) => name,
+                None => &file_name,
+            };
+            names.insert(name.to_owned());
+        }
+    }
+
+    Ok(names)
+}
+
+/// Messages are written to `tmp` and then moved into place, as maildir requires.
+/// Unread entries go in `new`, and read ones go in `cur`, flagged as seen.
+fn write_message(folder: &Path, entry: &EntryMeta, name: &str, message: &str) -> Result<()> {
+    let tmp_path = folder.join("tmp").join(name);
+    std::fs::write(&tmp_path, message)
+        .with_context(|| format!("Unable to write {}", tmp_path.display()))?;
+
+    let mut flags = String::new();
+    if entry.starred_at.is_some() {
+        flags.push('F');
+    }
+    if entry.read_at.is_some() {
+        flags.push('S');
+    }
+
+    let path: PathBuf = if flags.is_empty() {
+        folder.join("new").join(name)
+    } else {
+        folder.join("cur").join(format!("{}:2,{}", name, flags))
+    };
+
+    std::fs::rename(&tmp_path, &path)?;
+
+    Ok(())
+}
+
+fn entry_message(entry: &EntryMeta, feed: &Feed, html: Option<&str>) -> String {
+    let feed_title = feed.title.as_deref().unwrap_or("russ");
+    let from_name = match &entry.author {
+        Some(author) => format!("{} ({})", author, feed_title),
+        None => feed_title.to_owned(),
+    };
+
+    let mut message = String::new();
+
+    message.push_str(&format!(
+        "From: {} <russ@localhost>\n",
+        encode_header(&from_name)
+    ));
+    message.push_str(&format!(
+        "Subject: {}\n",
+        encode_header(entry.title.as_deref().unwrap_or("Untitled"))
+    ));
+    message.push_str(&format!(
+        "Date: {}\n",
+        entry.pub_date.unwrap_or(entry.inserted_at).to_rfc2822()
+    ));
+    message.push_str(&format!("Message-ID: <russ-{}@localhost>\n", entry.id));
+    if let Some(link) = &entry.link {
+        message.push_str(&format!("X-Russ-Link: {}\n", link));
+    }
+    message.push_str("MIME-Version: 1.0\n");
+    message.push_str("Content-Type: text/plain; charset=utf-8\n");
+    message.push_str("Content-Transfer-Encoding: 8bit\n");
+    message.push('\n');
+
+    if let Some(link) = &entry.link {
+        message.push_str(link);
+        message.push_str("\n\n");
+    }
+
+    if let Some(html) = html {
+        message.push_str(&html2text::from_read(html.as_bytes(), MESSAGE_LINE_LENGTH));
+    }
+
+    message
+}
+
+/// Header values have to be ASCII, so anything else is written as
+/// RFC 2047 `Q`-encoded words, each short enough for a header line
+/// and never splitting a character between two words.
+fn encode_header(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        return value.to_owned();
+    }
+
+    let max_encoded_len = 75 - "=?utf-8?Q??=".len();
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for c in value.chars() {
+        let mut encoded = String::new();
+
+        match c {
+            ' ' => encoded.push('_'),
+            c if c.is_ascii_alphanumeric() || "!*+-/".contains(c) => encoded.push(c),
+            c => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    encoded.push_str(&format!("={:02X}", byte));
+                }
+            }
+        }
+
+        if word.len() + encoded.len() > max_encoded_len {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.push_str(&encoded);
+    }
+
+    words.push(word);
+
+    words
+        .into_iter()
+        .map(|word| format!("=?utf-8?Q?{}?=", word))
+        .collect::<Vec<_>>()
+        .join("\n ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_non_ascii_headers() {
+        assert_eq!(encode_header("Plain title"), "Plain title");
+        assert_eq!(
+            encode_header("Café au lait"),
+            "=?utf-8?Q?Caf=C3=A9_au_lait?="
+        );
+
+        let long = "é".repeat(30);
+        let encoded = encode_header(&long);
+        assert!(encoded.lines().all(|line| line.trim().len() <= 75));
+        assert_eq!(encoded.lines().count(), 3);
+    }
+
+    #[test]
+    fn it_keeps_feed_folders_to_one_directory() {
+        let mut feed = crate::rss::Feed {
+            id: 3,
+            title: Some("../Some/Feed".to_string()),
+            custom_title: None,
+            feed_link: None,
+            link: None,
+            feed_kind: crate::rss::FeedKind::Rss,
+            refreshed_at: None,
+            inserted_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            fetch_full_content: false,
+            entry_sort: crate::rss::EntrySort::PubDateDesc,
+            refresh_interval: None,
+            paused: false,
+            failures: 0,
+            last_failed_at: None,
+            last_error: None,
+            next_refresh_at: None,
+            certificate_fingerprint: None,
+            guid: None,
+            pinned: false,
+        };
+        assert_eq!(folder_name(&feed), "_Some_Feed");
+
+        feed.title = Some("...".to_string());
+        assert_eq!(folder_name(&feed), "feed-3");
+    }
+}
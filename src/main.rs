@@ -5,44 +5,309 @@ use anyhow::Result;
 use app::App;
 use clap::Parser;
 use crossterm::event;
-use crossterm::event::{Event as CEvent, KeyCode, KeyModifiers};
+use crossterm::event::{
+    DisableBracketedPaste, EnableBracketedPaste, Event as CEvent, KeyCode, KeyModifiers,
+};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use std::io::stdout;
-use std::path::PathBuf;
-use std::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::{thread, time};
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 mod app;
+mod code;
+mod command;
+mod config;
+mod dates;
+mod export;
+mod finder;
+mod history;
+mod hooks;
+mod http;
+mod hyperlink;
+mod images;
+mod import;
+mod input;
+mod jumps;
+mod keyring;
+mod language;
+mod links;
+mod lint;
+mod loader;
+mod macros;
+mod maildir;
+mod markup;
+mod mastodon;
 mod modes;
+mod newsletters;
+mod parse;
+mod pipe;
+mod player;
+mod pocket;
+mod readability;
+mod recovery;
+mod remote;
 mod rss;
+mod scripting;
+mod sites;
+mod sources;
+mod speech;
+mod stats;
+mod translate;
 mod ui;
 mod util;
+mod viewer;
+mod wallabag;
+mod youtube;
 
 pub enum Event<I> {
     Input(I),
+    /// what was pasted, all at once, with bracketed paste
+    Paste(String),
+    /// the terminal was resized, so redraw right away
+    Resize,
+    /// SIGINT, SIGTERM, or SIGHUP, so quit cleanly
+    Quit,
+    /// SIGTSTP, so give the terminal back before stopping, like for `ctrl-z`
+    Suspend,
     Tick,
 }
 
 #[derive(Clone, Debug, Parser)]
 #[clap(author, version, about, name = "russ")]
 pub struct Options {
-    /// feed database path
-    #[clap(short, long)]
-    database_path: PathBuf,
+    /// feed database path, defaults to `$XDG_DATA_HOME/russ/russ.db`
+    #[clap(short, long, alias = "database")]
+    database_path: Option<PathBuf>,
+    /// use the named profile's database, `$XDG_DATA_HOME/russ/profiles/<PROFILE>.db`
+    #[clap(short, long, conflicts_with = "database-path")]
+    profile: Option<String>,
     /// time in ms between two ticks
     #[clap(short, long, default_value = "250")]
     tick_rate: u64,
     /// number of seconds to show the flash message before clearing it
     #[clap(short, long, default_value = "4", parse(try_from_str = parse_seconds))]
     flash_display_duration_seconds: time::Duration,
-    /// RSS/Atom network request timeout in seconds
+    /// network timeout in seconds, for connecting, and then for each read
     #[clap(short, long, default_value = "5", parse(try_from_str = parse_seconds))]
     network_timeout: time::Duration,
+    /// seconds that a feed's `exec:` command, or the newsletters' sync command,
+    /// can run before it's killed
+    #[clap(long, default_value = "60", parse(try_from_str = parse_seconds))]
+    command_timeout: time::Duration,
+    /// the User-Agent header to send, for servers that turn away ones they don't know,
+    /// defaults to `russ/<version> (+https://github.com/ckampfe/russ)`
+    #[clap(long)]
+    user_agent: Option<String>,
+    /// how much to log to `$XDG_STATE_HOME/russ/russ.log`:
+    /// off, error, warn, info, debug, or trace
+    #[clap(short, long, default_value = "warn")]
+    log_level: log::LevelFilter,
+    /// draw charts with finer unicode block characters, which not every font has,
+    /// and images in entries, in terminals that can draw them with kitty's, sixel,
+    /// or iTerm2's protocol
+    #[clap(long)]
+    enhanced_graphics: bool,
+    /// the database is encrypted with SQLCipher, which needs russ to be built with
+    /// `--features sqlcipher`. Its passphrase is taken from `$RUSS_DATABASE_PASSPHRASE`,
+    /// or the keyring's `database-passphrase`, or asked for
+    #[clap(long)]
+    encrypted: bool,
+    /// config file path, defaults to `$XDG_CONFIG_HOME/russ/config.toml`
+    #[clap(short, long = "config", value_name = "CONFIG")]
+    config_path: Option<PathBuf>,
+    /// loaded from `config_path` by `main`
+    #[clap(skip)]
+    config: crate::config::Config,
+    /// found by `main`, with `encrypted`
+    #[clap(skip)]
+    database_passphrase: Option<String>,
+    #[clap(subcommand)]
+    subcommand: Option<Subcommand>,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum Subcommand {
+    /// subscribe to the feeds from another feed reader, then exit
+    Import {
+        /// a newsboat `urls` file, like `~/.newsboat/urls`
+        #[clap(long, value_name = "URLS_FILE")]
+        newsboat: PathBuf,
+    },
+    /// write entries that haven't been exported yet to a maildir,
+    /// one folder per feed, then exit
+    ExportMaildir {
+        /// defaults to the `path` in the config file's `[maildir]` section
+        dir: Option<PathBuf>,
+    },
+    /// subscribe to a feed, through the russ that's running if there is one, then exit
+    Add {
+        #[clap(required_unless_present = "from-file")]
+        url: Option<String>,
+        /// the SHA-256 fingerprint of the certificate to pin the feed's host to
+        fingerprint: Option<String>,
+        /// subscribe to every url in a file instead, one to a line, each followed by its
+        /// fingerprint if it has one, or in stdin with `-`
+        #[clap(long, value_name = "FILE", conflicts_with_all = &["url", "fingerprint"])]
+        from_file: Option<PathBuf>,
+    },
+    /// send a request to the russ that's running, then exit: `add-url <url>`, `refresh`,
+    /// `unread-count`, or `open-feed <title>`
+    Ctl {
+        #[clap(required = true)]
+        request: Vec<String>,
+    },
+    /// print how many entries are unread, in all, then in each feed, then exit
+    Unread {
+        /// print them as JSON, for status bars, with waybar's `text`, `tooltip`, and `class`
+        #[clap(long)]
+        json: bool,
+        /// refresh the feeds that are due first, or if russ is running,
+        /// ask it to, which it does after these counts are printed
+        #[clap(long)]
+        refresh: bool,
+    },
+    /// fetch a feed and say what's wrong with it, if anything, then exit
+    Check { url: String },
+    /// copy the database to a new file, even while russ is running, then exit
+    Backup { path: PathBuf },
+    /// replace the database with a copy made by `russ backup`, then exit
+    Restore { path: PathBuf },
+    /// authorize russ to save entries to Pocket, then exit
+    PocketLogin,
+    /// keep a secret in the keyring rather than the config file, read from stdin, then exit
+    StoreSecret {
+        /// database-passphrase, mastodon-access-token, translation-api-key,
+        /// wallabag-client-secret, or wallabag-password
+        name: String,
+    },
+}
+
+impl Options {
+    /// only valid after `main` has resolved the default path
+    fn database_path(&self) -> &Path {
+        self.database_path
+            .as_deref()
+            .expect("database path is resolved before it is used")
+    }
+}
+
+/// the name of the profile that uses the default database
+const DEFAULT_PROFILE: &str = "default";
+
+/// how often to look for feeds whose refresh intervals have passed
+const SCHEDULER_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+/// the database path given on the command line,
+/// or the profile's, creating its directory if need be
+fn resolve_database_path(database_path: Option<PathBuf>, profile: Option<&str>) -> Result<PathBuf> {
+    match database_path {
+        Some(database_path) => {
+            if let Some(parent) = database_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            Ok(database_path)
+        }
+        None => profile_database_path(profile),
+    }
+}
+
+/// `None` is the default profile
+fn profile_database_path(profile: Option<&str>) -> Result<PathBuf> {
+    let data_dir = directories::ProjectDirs::from("", "", "russ")
+        .ok_or_else(|| {
+            anyhow::anyhow!("Unable to find a home directory, pass --database-path instead")
+        })?
+        .data_dir()
+        .to_owned();
+
+    let database_path = match profile {
+        None | Some(DEFAULT_PROFILE) => data_dir.join("russ.db"),
+        Some(profile) => {
+            if profile.is_empty() || profile.contains(std::path::is_separator) {
+                return Err(anyhow::anyhow!("{:?} is not a valid profile name", profile));
+            }
+
+            data_dir.join("profiles").join(format!("{}.db", profile))
+        }
+    };
+
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Ok(database_path)
+}
+
+/// with `--encrypted`, the database's passphrase, from the environment, the keyring, or the user
+fn database_passphrase(options: &Options) -> Result<Option<String>> {
+    if !options.encrypted {
+        return Ok(None);
+    }
+
+    if !cfg!(feature = "sqlcipher") {
+        return Err(anyhow::anyhow!(
+            "--encrypted needs russ to be built with `--features sqlcipher`"
+        ));
+    }
+
+    if let Some(passphrase) = std::env::var("RUSS_DATABASE_PASSPHRASE")
+        .ok()
+        .filter(|passphrase| !passphrase.is_empty())
+    {
+        return Ok(Some(passphrase));
+    }
+
+    match crate::keyring::Keyring::open(options.database_path()).get("database-passphrase") {
+        Ok(Some(passphrase)) => return Ok(Some(passphrase)),
+        Ok(None) => (),
+        Err(e) => log::warn!(
+            "unable to get the database passphrase from the keyring: {}",
+            e
+        ),
+    }
+
+    read_passphrase(&format!(
+        "Passphrase for {}: ",
+        options.database_path().display()
+    ))
+    .map(Some)
+}
+
+/// read a line from the terminal without showing it
+fn read_passphrase(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    enable_raw_mode()?;
+
+    let mut passphrase = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(CEvent::Key(key_event)) => match (key_event.code, key_event.modifiers) {
+                (KeyCode::Enter, _) => break Ok(passphrase),
+                (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("No passphrase given"))
+                }
+                (KeyCode::Backspace, _) => {
+                    passphrase.pop();
+                }
+                (KeyCode::Char(c), _) => passphrase.push(c),
+                _ => (),
+            },
+            Ok(_) => (),
+            Err(e) => break Err(e.into()),
+        }
+    };
+
+    disable_raw_mode()?;
+    eprintln!();
+    result
 }
 
 fn parse_seconds(s: &str) -> Result<time::Duration, std::num::ParseIntError> {
@@ -54,7 +319,17 @@ enum IoCommand {
     Break,
     RefreshFeed(crate::rss::FeedId),
     RefreshFeeds(Vec<crate::rss::FeedId>),
-    SubscribeToFeed(String),
+    PreviewFeed(String),
+    /// and where to say how it went, for a request from another russ
+    SubscribeToFeed(String, Option<mpsc::Sender<Result<String>>>),
+    SaveToWallabag(String),
+    SaveToPocket(String),
+    PostToMastodon(String),
+    Translate(crate::rss::EntryId, String),
+    FetchImages(Vec<String>),
+    Play(String),
+    PlayQueue,
+    ReadAloud(Vec<String>),
     ClearFlash,
 }
 
@@ -63,13 +338,24 @@ fn io_loop(
     sx: mpsc::Sender<IoCommand>,
     rx: mpsc::Receiver<IoCommand>,
     options: &Options,
+    script: Option<Arc<crate::scripting::Script>>,
 ) -> Result<()> {
     use IoCommand::*;
 
-    let manager = r2d2_sqlite::SqliteConnectionManager::file(&options.database_path);
-    let connection_pool = r2d2::Pool::new(manager)?;
+    let mut database_path = app.database_path();
+    let manager =
+        crate::rss::connection_manager(&database_path, options.database_passphrase.clone());
+    let mut connection_pool = r2d2::Pool::new(manager)?;
 
     while let Ok(event) = rx.recv() {
+        // the profile may have been switched since the last command
+        if app.database_path() != database_path {
+            database_path = app.database_path();
+            let manager =
+                crate::rss::connection_manager(&database_path, options.database_passphrase.clone());
+            connection_pool = r2d2::Pool::new(manager)?;
+        }
+
         match event {
             Break => break,
             RefreshFeed(feed_id) => {
@@ -78,13 +364,38 @@ fn io_loop(
                 app.set_flash("Refreshing feed...".to_string());
                 app.force_redraw()?;
 
-                refresh_feeds(&app, &connection_pool, &[feed_id], |_app, fetch_result| {
-                    if let Err(e) = fetch_result {
-                        app.push_error_flash(e)
+                let mut refreshed = false;
+                let mut new_entries_len = 0;
+
+                refresh_feeds(
+                    &app,
+                    &connection_pool,
+                    &options.config.hooks,
+                    &options.config.rules,
+                    script.clone(),
+                    &[feed_id],
+                    |app, feed_id, fetch_result| match fetch_result {
+                        Ok(feed_new_entries_len) => {
+                            refreshed = true;
+                            new_entries_len += feed_new_entries_len;
+                        }
+                        Err(e) => app.push_feed_error_flash(feed_id, e),
+                    },
+                )?;
+
+                if refreshed {
+                    if let Err(e) =
+                        crate::hooks::post_refresh(&options.config.hooks, 1, 0, new_entries_len)
+                    {
+                        app.push_error_flash(e);
                     }
-                })?;
+                }
+
+                if let Err(e) = export_maildir_after_refresh(&connection_pool, options) {
+                    app.push_error_flash(e);
+                }
 
-                app.update_current_feed_and_entries()?;
+                app.load_feeds()?;
                 let elapsed = now.elapsed();
                 app.set_flash(format!("Refreshed feed in {:?}", elapsed));
                 app.force_redraw()?;
@@ -98,45 +409,120 @@ fn io_loop(
 
                 let all_feeds_len = feed_ids.len();
                 let mut successfully_refreshed_len = 0usize;
+                let mut new_entries_len = 0;
 
-                refresh_feeds(&app, &connection_pool, &feed_ids, |app, fetch_result| {
-                    match fetch_result {
-                        Ok(_) => successfully_refreshed_len += 1,
-                        Err(e) => app.push_error_flash(e),
-                    }
-                })?;
+                // new senders' feeds are read as they're added, so they aren't refreshed too
+                match subscribe_to_new_senders(&app, &connection_pool, options, script.as_deref()) {
+                    Ok(0) => (),
+                    Ok(senders_len) => log::info!("added feeds for {} new senders", senders_len),
+                    Err(e) => app.push_error_flash(e),
+                }
+
+                let skipped_len = refresh_feeds(
+                    &app,
+                    &connection_pool,
+                    &options.config.hooks,
+                    &options.config.rules,
+                    script.clone(),
+                    &feed_ids,
+                    |app, feed_id, fetch_result| match fetch_result {
+                        Ok(feed_new_entries_len) => {
+                            successfully_refreshed_len += 1;
+                            new_entries_len += feed_new_entries_len;
+                        }
+                        Err(e) => app.push_feed_error_flash(feed_id, e),
+                    },
+                )?;
+
+                if let Err(e) = crate::hooks::post_refresh(
+                    &options.config.hooks,
+                    successfully_refreshed_len,
+                    all_feeds_len - successfully_refreshed_len - skipped_len,
+                    new_entries_len,
+                ) {
+                    app.push_error_flash(e);
+                }
+
+                if let Err(e) = export_maildir_after_refresh(&connection_pool, options) {
+                    app.push_error_flash(e);
+                }
 
                 {
-                    app.update_current_feed_and_entries()?;
+                    app.load_feeds()?;
 
                     let elapsed = now.elapsed();
-                    app.set_flash(format!(
-                        "Refreshed {}/{} feeds in {:?}",
-                        successfully_refreshed_len, all_feeds_len, elapsed
-                    ));
+                    app.set_flash(if skipped_len > 0 {
+                        format!(
+                            "Cancelled, refreshed {}/{} feeds in {:?}, skipped {}",
+                            successfully_refreshed_len, all_feeds_len, elapsed, skipped_len
+                        )
+                    } else {
+                        format!(
+                            "Refreshed {}/{} feeds in {:?}",
+                            successfully_refreshed_len, all_feeds_len, elapsed
+                        )
+                    });
                     app.force_redraw()?;
                 }
 
                 clear_flash_after(sx.clone(), options.flash_display_duration_seconds);
             }
-            SubscribeToFeed(feed_subscription_input) => {
+            PreviewFeed(feed_subscription_input) => {
+                app.set_flash("Fetching feed...".to_string());
+                app.force_redraw()?;
+
+                let input = crate::sites::resolve(&options.config.sites, &feed_subscription_input);
+                match crate::rss::preview_feed(&app.http_client(), &input) {
+                    Ok(feed_preview) => app.set_feed_preview(feed_preview),
+                    Err(e) => app.push_error_flash(e),
+                }
+
+                app.clear_flash();
+                app.force_redraw()?;
+            }
+            SubscribeToFeed(feed_subscription_input, reply) => {
                 let now = std::time::Instant::now();
+                let reply = |result: Result<String>| {
+                    // the other russ may have given up waiting
+                    if let Some(reply) = &reply {
+                        let _ = reply.send(result);
+                    }
+                };
 
                 app.set_flash("Subscribing to feed...".to_string());
                 app.force_redraw()?;
 
-                let mut conn = connection_pool.get()?;
+                let mut conn = match connection_pool.get() {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        reply(Err(anyhow::anyhow!("{:#}", e)));
+                        return Err(e.into());
+                    }
+                };
+                let on_error = |e| app.push_error_flash(e);
+                let input = crate::sites::resolve(&options.config.sites, &feed_subscription_input);
                 let r = crate::rss::subscribe_to_feed(
                     &app.http_client(),
                     &mut conn,
-                    &feed_subscription_input,
+                    &crate::hooks::NewEntryRules {
+                        script: script.as_deref(),
+                        rules: &options.config.rules,
+                        on_error: &on_error,
+                    },
+                    &input,
                 );
 
                 if let Err(e) = r {
+                    reply(Err(anyhow::anyhow!("{:#}", e)));
                     app.push_error_flash(e);
                     continue;
                 }
 
+                reply(Ok(format!(
+                    "Subscribed to {}",
+                    crate::rss::subscription_input_url(&input)
+                )));
+
                 match crate::rss::get_feeds(&conn) {
                     Ok(feeds) => {
                         {
@@ -158,6 +544,225 @@ fn io_loop(
                     }
                 }
             }
+            SaveToWallabag(url) => {
+                let wallabag_config = match &options.config.wallabag {
+                    Some(wallabag_config) => wallabag_config,
+                    None => {
+                        app.push_error_flash(anyhow::anyhow!(
+                            "Add a [wallabag] section to the config file to save to Wallabag"
+                        ));
+                        continue;
+                    }
+                };
+
+                let wallabag_config = match wallabag_config
+                    .with_secrets(&crate::keyring::Keyring::open(&database_path))
+                {
+                    Ok(wallabag_config) => wallabag_config,
+                    Err(e) => {
+                        app.push_error_flash(e);
+                        continue;
+                    }
+                };
+
+                app.set_flash("Saving to Wallabag...".to_string());
+                app.force_redraw()?;
+
+                match crate::wallabag::save_url(&app.http_client(), &wallabag_config, &url) {
+                    Ok(()) => {
+                        app.set_flash("Saved to Wallabag".to_string());
+                        clear_flash_after(sx.clone(), options.flash_display_duration_seconds);
+                    }
+                    Err(e) => {
+                        app.clear_flash();
+                        app.push_error_flash(e);
+                    }
+                }
+
+                app.force_redraw()?;
+            }
+            SaveToPocket(url) => {
+                let pocket_config = match &options.config.pocket {
+                    Some(pocket_config) => pocket_config,
+                    None => {
+                        app.push_error_flash(anyhow::anyhow!(
+                            "Add a [pocket] section to the config file to save to Pocket"
+                        ));
+                        continue;
+                    }
+                };
+
+                let access_token = match crate::keyring::Keyring::open(&database_path)
+                    .get_credential(&*connection_pool.get()?, crate::pocket::CREDENTIAL_SERVICE)
+                {
+                    Ok(Some(access_token)) => access_token,
+                    Ok(None) => {
+                        app.push_error_flash(anyhow::anyhow!(
+                            "Run `russ pocket-login` to save to Pocket"
+                        ));
+                        continue;
+                    }
+                    Err(e) => {
+                        app.push_error_flash(e);
+                        continue;
+                    }
+                };
+
+                app.set_flash("Saving to Pocket...".to_string());
+                app.force_redraw()?;
+
+                match crate::pocket::save_url(
+                    &app.http_client(),
+                    pocket_config,
+                    &access_token,
+                    &url,
+                ) {
+                    Ok(()) => {
+                        app.set_flash("Saved to Pocket".to_string());
+                        clear_flash_after(sx.clone(), options.flash_display_duration_seconds);
+                    }
+                    Err(e) => {
+                        app.clear_flash();
+                        app.push_error_flash(e);
+                    }
+                }
+
+                app.force_redraw()?;
+            }
+            PostToMastodon(status) => {
+                let mastodon_config = match &options.config.mastodon {
+                    Some(mastodon_config) => mastodon_config,
+                    None => {
+                        app.push_error_flash(anyhow::anyhow!(
+                            "Add a [mastodon] section to the config file to share to Mastodon"
+                        ));
+                        continue;
+                    }
+                };
+
+                let mastodon_config = match mastodon_config
+                    .with_secrets(&crate::keyring::Keyring::open(&database_path))
+                {
+                    Ok(mastodon_config) => mastodon_config,
+                    Err(e) => {
+                        app.push_error_flash(e);
+                        continue;
+                    }
+                };
+
+                app.set_flash("Posting to Mastodon...".to_string());
+                app.force_redraw()?;
+
+                match crate::mastodon::post_status(&app.http_client(), &mastodon_config, &status) {
+                    Ok(()) => {
+                        app.set_flash("Posted to Mastodon".to_string());
+                        clear_flash_after(sx.clone(), options.flash_display_duration_seconds);
+                    }
+                    Err(e) => {
+                        app.clear_flash();
+                        app.push_error_flash(e);
+                    }
+                }
+
+                app.force_redraw()?;
+            }
+            Translate(entry_id, html) => {
+                let translation_config = match &options.config.translation {
+                    Some(translation_config) => translation_config,
+                    None => {
+                        app.push_error_flash(anyhow::anyhow!(
+                            "Add a [translation] section to the config file to translate entries"
+                        ));
+                        continue;
+                    }
+                };
+
+                let translation_config = match translation_config
+                    .with_secrets(&crate::keyring::Keyring::open(&database_path))
+                {
+                    Ok(translation_config) => translation_config,
+                    Err(e) => {
+                        app.push_error_flash(e);
+                        continue;
+                    }
+                };
+
+                app.set_flash("Translating...".to_string());
+                app.force_redraw()?;
+
+                match crate::translate::translate(&app.http_client(), &translation_config, &html)
+                    .and_then(|translation| app.set_translation(entry_id, translation))
+                {
+                    Ok(()) => clear_flash_after(sx.clone(), options.flash_display_duration_seconds),
+                    Err(e) => {
+                        app.clear_flash();
+                        app.push_error_flash(e);
+                    }
+                }
+
+                app.force_redraw()?;
+            }
+            FetchImages(urls) => {
+                // images can be slow, and shouldn't hold up refreshing
+                let app = app.clone();
+                thread::spawn(move || {
+                    let http_client = app.http_client();
+
+                    for url in &urls {
+                        // a missing image just shows its alt text
+                        if let Err(e) = crate::images::fetch(&http_client, url) {
+                            log::warn!("unable to fetch image {}: {}", url, e);
+                        }
+                    }
+
+                    if let Err(e) = app.render_current_entry() {
+                        app.push_error_flash(e);
+                    }
+                    // the main thread may have already quit
+                    let _ = app.force_redraw();
+                });
+            }
+            Play(link) => {
+                app.set_flash("Playing...".to_string());
+                app.force_redraw()?;
+                clear_flash_after(sx.clone(), options.flash_display_duration_seconds);
+
+                // the player runs for as long as the video does
+                let app = app.clone();
+                let player_config = options.config.player.clone();
+                thread::spawn(move || {
+                    if let Err(e) = crate::player::play(&player_config, &link) {
+                        app.push_error_flash(e);
+                        // the main thread may have already quit
+                        let _ = app.force_redraw();
+                    }
+                });
+            }
+            PlayQueue => {
+                let app = app.clone();
+                let connection_pool = connection_pool.clone();
+                let player_config = options.config.player.clone();
+                thread::spawn(move || {
+                    if let Err(e) = play_queue(&app, &connection_pool, &player_config) {
+                        app.push_error_flash(e);
+                    }
+                    app.finish_playing_queue();
+                    // the main thread may have already quit
+                    let _ = app.force_redraw();
+                });
+            }
+            ReadAloud(paragraphs) => {
+                let app = app.clone();
+                let speech_config = options.config.speech.clone();
+                thread::spawn(move || {
+                    if let Err(e) = read_aloud(&app, &speech_config, &paragraphs) {
+                        app.push_error_flash(e);
+                    }
+                    app.finish_speech();
+                    // the main thread may have already quit
+                    let _ = app.force_redraw();
+                });
+            }
             ClearFlash => {
                 app.clear_flash();
             }
@@ -167,15 +772,106 @@ fn io_loop(
     Ok(())
 }
 
+/// Read `paragraphs` aloud, one after another, until they're all read, the command fails,
+/// or the reading is stopped. Pausing cuts the paragraph off, and it's read again from its start.
+fn read_aloud(
+    app: &App,
+    speech_config: &crate::config::SpeechConfig,
+    paragraphs: &[String],
+) -> Result<()> {
+    let poll_interval = time::Duration::from_millis(100);
+    let mut paragraph = 0;
+
+    while paragraph < paragraphs.len() {
+        match app.speech_progress() {
+            Some(progress) if progress.stopping => break,
+            Some(progress) if progress.paused => {
+                thread::sleep(poll_interval);
+                continue;
+            }
+            Some(_) => (),
+            None => break,
+        }
+
+        app.set_speech_paragraph(paragraph);
+        app.force_redraw()?;
+
+        let mut speaking = crate::speech::speak(speech_config, &paragraphs[paragraph])?;
+        loop {
+            if speaking.finish()? {
+                paragraph += 1;
+                break;
+            }
+
+            if app
+                .speech_progress()
+                .map(|progress| progress.paused || progress.stopping)
+                .unwrap_or(true)
+            {
+                speaking.stop()?;
+                app.force_redraw()?;
+                break;
+            }
+
+            thread::sleep(poll_interval);
+        }
+    }
+
+    Ok(())
+}
+
+/// Play the queue from the top, one entry after another, taking each out once it has played,
+/// until it is empty, a player fails, or it is stopped.
+fn play_queue(
+    app: &App,
+    connection_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
+    player_config: &crate::config::PlayerConfig,
+) -> Result<()> {
+    let conn = connection_pool.get()?;
+
+    while !app.queue_is_stopping() {
+        let queued_entry = match crate::rss::get_queue(&conn)?.into_iter().next() {
+            Some(queued_entry) => queued_entry,
+            None => break,
+        };
+
+        app.set_queue_now_playing(
+            queued_entry
+                .title
+                .clone()
+                .unwrap_or_else(|| queued_entry.link.clone()),
+        );
+        app.update_queue()?;
+        app.force_redraw()?;
+
+        crate::player::play(player_config, &queued_entry.link)?;
+
+        crate::rss::dequeue(&conn, queued_entry.entry_id)?;
+        app.update_queue()?;
+    }
+
+    Ok(())
+}
+
+/// the handler is given how many new entries each feed had,
+/// after the script's `transform` has had its say about them, the rules have been applied to them,
+/// as they are however entries are added, the `on_new_entry` hook has been run,
+/// and the webhooks posted to, for them.
+/// Returns how many feeds were skipped because the refresh was cancelled.
 fn refresh_feeds<F>(
     app: &App,
     connection_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
+    hooks: &crate::config::Hooks,
+    rules: &[crate::config::Rule],
+    script: Option<Arc<crate::scripting::Script>>,
     feed_ids: &[crate::rss::FeedId],
     mut refresh_result_handler: F,
-) -> Result<()>
+) -> Result<usize>
 where
-    F: FnMut(&App, anyhow::Result<()>),
+    F: FnMut(&App, crate::rss::FeedId, anyhow::Result<usize>),
 {
+    app.start_refresh();
+
     let min_number_of_threads = num_cpus::get() * 2;
     let chunk_size = feed_ids.len() / min_number_of_threads;
     // due to usize floor division, it's possible chunk_size would be 0,
@@ -188,30 +884,94 @@ where
             let pool_get_result = connection_pool.get();
             let http = app.http_client();
             let chunk_feed_ids = chunk_feed_ids.to_owned();
+            let app = app.clone();
+            let hooks = hooks.clone();
+            let rules = rules.to_vec();
+            let script = script.clone();
+            let keyring = crate::keyring::Keyring::open(&app.database_path());
 
-            thread::spawn(move || -> Result<Vec<Result<(), anyhow::Error>>> {
-                let mut results = vec![];
-                let mut conn = pool_get_result?;
+            thread::spawn(
+                move || -> Result<Vec<(crate::rss::FeedId, Result<usize, anyhow::Error>)>> {
+                    let mut results = vec![];
+                    let mut conn = pool_get_result?;
 
-                for feed_id in chunk_feed_ids.into_iter() {
-                    results.push(crate::rss::refresh_feed(&http, &mut conn, feed_id))
-                }
+                    for feed_id in chunk_feed_ids.into_iter() {
+                        if app.refresh_is_cancelled() {
+                            break;
+                        }
 
-                Ok::<Vec<Result<(), anyhow::Error>>, anyhow::Error>(results)
-            })
+                        let on_error = |e| app.push_feed_error_flash(feed_id, e);
+                        let new_entry_rules = crate::hooks::NewEntryRules {
+                            script: script.as_deref(),
+                            rules: &rules,
+                            on_error: &on_error,
+                        };
+                        let result = crate::rss::refresh_feed(
+                            &http,
+                            &mut conn,
+                            &keyring,
+                            &new_entry_rules,
+                            feed_id,
+                        );
+
+                        let has_hooks = hooks.on_new_entry.is_some() || !hooks.webhooks.is_empty();
+                        if let (Ok(new_entries), true) = (&result, has_hooks) {
+                            // a failing hook doesn't mean the refresh failed
+                            if let Err(e) = crate::rss::get_feed(&conn, feed_id).and_then(|feed| {
+                                let ran = crate::hooks::on_new_entries(&hooks, &feed, new_entries);
+                                let posted =
+                                    crate::hooks::post_webhooks(&http, &hooks, &feed, new_entries);
+                                ran.and(posted)
+                            }) {
+                                app.push_feed_error_flash(feed_id, e);
+                            }
+                        }
+
+                        results.push((feed_id, result.map(|new_entries| new_entries.len())))
+                    }
+
+                    Ok(results)
+                },
+            )
         })
         .collect();
 
+    let mut refreshed_len = 0;
+    // the refresh has to finish, and every thread be joined, before any of them fails it
+    let mut chunk_error = None;
+
     for join_handle in join_handles {
         let chunk_results = join_handle
             .join()
-            .expect("unable to join worker thread to io thread");
-        for chunk_result in chunk_results? {
-            refresh_result_handler(app, chunk_result)
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("A refresh thread panicked")));
+        match chunk_results {
+            Ok(chunk_results) => {
+                for (feed_id, chunk_result) in chunk_results {
+                    refreshed_len += 1;
+                    refresh_result_handler(app, feed_id, chunk_result)
+                }
+            }
+            Err(e) => {
+                chunk_error.get_or_insert(e);
+            }
         }
     }
 
-    Ok(())
+    app.finish_refresh();
+
+    if let Err(e) = connection_pool
+        .get()
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| crate::rss::record_refresh(&conn, refreshed_len))
+    {
+        app.push_error_flash(e);
+    }
+
+    if let Some(e) = chunk_error {
+        return Err(e);
+    }
+
+    Ok(feed_ids.len() - refreshed_len)
 }
 
 fn clear_flash_after(sx: mpsc::Sender<IoCommand>, duration: time::Duration) {
@@ -222,48 +982,820 @@ fn clear_flash_after(sx: mpsc::Sender<IoCommand>, duration: time::Duration) {
     });
 }
 
-fn main() -> Result<()> {
-    let options: Options = Options::parse();
+fn export_maildir(options: &Options, dir: Option<PathBuf>) -> Result<()> {
+    let dir = dir
+        .or_else(|| {
+            options
+                .config
+                .maildir
+                .as_ref()
+                .map(|maildir_config| crate::util::expand_tilde(&maildir_config.path))
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!("Give a directory, or add a [maildir] section to the config file")
+        })?;
 
-    enable_raw_mode()?;
+    let mut conn = crate::rss::open_db(
+        options.database_path(),
+        options.database_passphrase.as_deref(),
+    )?;
+    crate::rss::initialize_db(&mut conn)?;
 
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    let exported_len = crate::maildir::export(&conn, &dir)?;
+    println!("Exported {} entries to {}", exported_len, dir.display());
 
-    let backend = CrosstermBackend::new(stdout);
+    Ok(())
+}
 
-    let mut terminal = Terminal::new(backend)?;
-    terminal.hide_cursor()?;
+/// before refreshing all the feeds, if the config has a maildir of newsletters,
+/// returning how many senders there were feeds added for
+fn subscribe_to_new_senders(
+    app: &App,
+    connection_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
+    options: &Options,
+    script: Option<&crate::scripting::Script>,
+) -> Result<usize> {
+    let newsletters_config = match &options.config.newsletters {
+        Some(newsletters_config) => newsletters_config,
+        None => return Ok(0),
+    };
 
-    // Setup input handling
-    let (tx, rx) = mpsc::channel();
-    let tx_clone = tx.clone();
+    if let Some(sync_command) = &newsletters_config.sync_command {
+        log::debug!("syncing newsletters with {}", sync_command);
+        crate::pipe::run_with_timeout(
+            crate::pipe::shell_command(sync_command),
+            &[],
+            crate::sources::command_timeout(),
+        )
+        .map_err(|e| {
+            e.context(format!(
+                "The newsletters' sync command {} failed",
+                sync_command
+            ))
+        })?;
+    }
 
-    let tick_rate = time::Duration::from_millis(options.tick_rate);
-    thread::spawn(move || {
-        let mut last_tick = time::Instant::now();
-        loop {
-            // poll for tick rate duration, if no events, sent tick event.
-            if event::poll(tick_rate - last_tick.elapsed())
-                .expect("Unable to poll for Crossterm event")
-            {
-                if let CEvent::Key(key) = event::read().expect("Unable to read Crossterm event") {
-                    tx.send(Event::Input(key))
-                        .expect("Unable to send Crossterm Key input event");
-                }
-            }
-            if last_tick.elapsed() >= tick_rate {
-                tx.send(Event::Tick).expect("Unable to send tick");
-                last_tick = time::Instant::now();
-            }
-        }
-    });
+    crate::newsletters::subscribe_to_new_senders(
+        &app.http_client(),
+        &mut *connection_pool.get()?,
+        &crate::hooks::NewEntryRules {
+            script,
+            rules: &options.config.rules,
+            on_error: &|e| app.push_error_flash(e),
+        },
+        &newsletters_config.maildir,
+    )
+}
+
+/// after a refresh, if the config asks for it
+fn export_maildir_after_refresh(
+    connection_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
+    options: &Options,
+) -> Result<()> {
+    match &options.config.maildir {
+        Some(maildir_config) if maildir_config.export_after_refresh => {
+            let exported_len = crate::maildir::export(
+                &*connection_pool.get()?,
+                &crate::util::expand_tilde(&maildir_config.path),
+            )?;
+            log::info!("exported {} entries to the maildir", exported_len);
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// a file of urls, or stdin if it's `-`
+fn read_url_list(path: &Path) -> Result<String> {
+    use anyhow::Context;
+    use std::io::Read;
+
+    let mut urls = String::new();
+    if path == Path::new("-") {
+        std::io::stdin().read_to_string(&mut urls)?;
+    } else {
+        urls = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+    }
+
+    Ok(urls)
+}
+
+/// subscribe to each url, with its fingerprint if it has one
+fn add(options: &Options, inputs: &[String]) -> Result<()> {
+    // the russ that's running has the database to itself
+    let instance_lock = crate::remote::lock(options.database_path())?;
+
+    let mut conn = crate::rss::open_db(
+        options.database_path(),
+        options.database_passphrase.as_deref(),
+    )?;
+    if instance_lock.is_some() {
+        crate::rss::initialize_db(&mut conn)?;
+    }
+
+    let subscribed_urls = crate::rss::get_feeds(&conn)?
+        .into_iter()
+        .flat_map(|feed| feed.feed_link)
+        .map(|feed_link| crate::rss::normalize_feed_url(&feed_link))
+        .collect();
+
+    match instance_lock {
+        Some(_instance_lock) => {
+            let http_client =
+                crate::http::client(options.network_timeout, options.user_agent.as_deref());
+            let script = load_script(&options.config)?;
+            let new_entry_rules = crate::hooks::NewEntryRules {
+                script: script.as_deref(),
+                rules: &options.config.rules,
+                on_error: &|e| eprintln!("{:#}", e),
+            };
+
+            crate::import::subscribe_to_urls(subscribed_urls, inputs, |input| {
+                let input = crate::sites::resolve(&options.config.sites, input);
+                crate::rss::subscribe_to_feed(&http_client, &mut conn, &new_entry_rules, &input)?;
+                Ok(format!(
+                    "Subscribed to {}",
+                    crate::rss::subscription_input_url(&input)
+                ))
+            })
+        }
+        None => crate::import::subscribe_to_urls(subscribed_urls, inputs, |input| {
+            crate::remote::send(
+                options.database_path(),
+                &crate::remote::Request::Add(input.to_owned()),
+            )
+        }),
+    }
+}
+
+fn ctl(options: &Options, request: &[String]) -> Result<()> {
+    let request = request.join(" ").parse::<crate::remote::Request>()?;
+
+    if crate::remote::lock(options.database_path())?.is_some() {
+        return Err(anyhow::anyhow!(
+            "Russ isn't running with {}",
+            options.database_path().display()
+        ));
+    }
+
+    println!(
+        "{}",
+        crate::remote::send(options.database_path(), &request)?
+    );
+
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct UnreadCounts {
+    /// the total, for status bars to show
+    text: String,
+    /// a line for each feed
+    tooltip: String,
+    /// `unread`, or `read` when there's nothing left to read
+    class: &'static str,
+    total: usize,
+    feeds: Vec<FeedUnreadCount>,
+}
+
+#[derive(serde::Serialize)]
+struct FeedUnreadCount {
+    id: crate::rss::FeedId,
+    title: Option<String>,
+    unread: usize,
+}
+
+fn unread(options: &Options, json: bool, refresh: bool) -> Result<()> {
+    let instance_lock = crate::remote::lock(options.database_path())?;
+
+    let mut conn = crate::rss::open_db(
+        options.database_path(),
+        options.database_passphrase.as_deref(),
+    )?;
+    // it may be from an older russ, unless the russ that's running has already migrated it
+    if instance_lock.is_some() {
+        crate::rss::initialize_db(&mut conn)?;
+    }
+
+    if refresh {
+        match &instance_lock {
+            Some(_) => {
+                let http_client =
+                    crate::http::client(options.network_timeout, options.user_agent.as_deref());
+                let keyring = crate::keyring::Keyring::open(options.database_path());
+                let script = load_script(&options.config)?;
+                let new_entry_rules = crate::hooks::NewEntryRules {
+                    script: script.as_deref(),
+                    rules: &options.config.rules,
+                    ..Default::default()
+                };
+
+                for feed_id in crate::rss::get_due_feed_ids(&conn)? {
+                    if let Err(e) = crate::rss::refresh_feed(
+                        &http_client,
+                        &mut conn,
+                        &keyring,
+                        &new_entry_rules,
+                        feed_id,
+                    ) {
+                        log::warn!("unable to refresh feed {}: {:?}", feed_id, e);
+                    }
+                }
+            }
+            None => {
+                crate::remote::send(options.database_path(), &crate::remote::Request::Refresh)?;
+            }
+        }
+    }
+
+    let unread_counts = crate::rss::get_unread_counts(&conn)?;
+    let mut feeds = crate::rss::get_feeds(&conn)?
+        .into_iter()
+        .filter_map(|feed| {
+            unread_counts.get(&feed.id).map(|unread| FeedUnreadCount {
+                id: feed.id,
+                title: feed.title,
+                unread: *unread,
+            })
+        })
+        .collect::<Vec<_>>();
+    feeds.sort_by_key(|feed| std::cmp::Reverse(feed.unread));
+
+    let total = feeds.iter().map(|feed| feed.unread).sum::<usize>();
+    let feed_title =
+        |feed: &FeedUnreadCount| feed.title.clone().unwrap_or_else(|| "No title".to_string());
+    if json {
+        let unread_counts = UnreadCounts {
+            text: total.to_string(),
+            tooltip: feeds
+                .iter()
+                .map(|feed| format!("{}: {}", feed_title(feed), feed.unread))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            class: if total == 0 { "read" } else { "unread" },
+            total,
+            feeds,
+        };
+        println!("{}", serde_json::to_string(&unread_counts)?);
+    } else {
+        println!("{}", total);
+        for feed in &feeds {
+            println!("{}\t{}", feed.unread, feed_title(feed));
+        }
+    }
+
+    Ok(())
+}
+
+fn check(options: &Options, url: &str) -> Result<()> {
+    let http_client = crate::http::client_without_redirects(
+        options.network_timeout,
+        options.user_agent.as_deref(),
+    );
+    let report = crate::lint::check(&http_client, url)?;
+
+    println!(
+        "{}, {} {}, {} KB",
+        report.kind.unwrap_or("Unknown"),
+        report.entries_len,
+        if report.entries_len == 1 {
+            "entry"
+        } else {
+            "entries"
+        },
+        report.len / 1024
+    );
+    for problem in &report.problems {
+        println!("{}", problem);
+    }
+
+    let errors_len = report
+        .problems
+        .iter()
+        .filter(|problem| problem.severity == crate::lint::Severity::Error)
+        .count();
+
+    match (errors_len, report.problems.len()) {
+        (0, 0) => {
+            println!("No problems found");
+            Ok(())
+        }
+        (0, _) => Ok(()),
+        (_, _) => Err(anyhow::anyhow!(
+            "Russ can't subscribe to {}, because of the errors above",
+            url
+        )),
+    }
+}
+
+fn backup(options: &Options, path: &Path) -> Result<()> {
+    let conn = crate::rss::open_db(
+        options.database_path(),
+        options.database_passphrase.as_deref(),
+    )?;
+
+    crate::rss::backup_db(&conn, path, options.database_passphrase.as_deref())?;
+    println!(
+        "Backed up {} to {}",
+        options.database_path().display(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+fn restore(options: &Options, path: &Path) -> Result<()> {
+    let mut conn = crate::rss::open_db(
+        options.database_path(),
+        options.database_passphrase.as_deref(),
+    )?;
+
+    crate::rss::restore_db(&mut conn, path, options.database_passphrase.as_deref())?;
+    println!(
+        "Restored {} from {}",
+        options.database_path().display(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// If the database is corrupted, ask whether to restore it from the latest automatic backup,
+/// salvage what's left of it, or leave it be and quit.
+fn check_database(options: &Options) -> Result<()> {
+    let database_path = options.database_path();
+    let passphrase = options.database_passphrase.as_deref();
+
+    let problem = match crate::recovery::check(database_path, passphrase)? {
+        Some(problem) => problem,
+        None => return Ok(()),
+    };
+
+    log::error!("{} is corrupted: {}", database_path.display(), problem);
+    eprintln!(
+        "The database at {} is corrupted:\n{}\n",
+        database_path.display(),
+        problem
+    );
+
+    let backup = crate::recovery::latest_backup(database_path)?;
+    if let Some(backup) = &backup {
+        eprintln!("r: restore it from the backup {}", backup.display());
+    }
+    eprintln!("s: salvage what can still be read from it");
+    eprintln!("q: quit, and leave it as it is");
+    eprint!("> ");
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    let corrupt_path = match (answer.trim(), &backup) {
+        ("r", Some(backup)) => crate::recovery::restore_backup(database_path, passphrase, backup)?,
+        ("s", _) => {
+            let salvaged = crate::recovery::salvage(database_path, passphrase)?;
+            for (table, rows) in salvaged.tables {
+                match rows {
+                    Ok(crate::recovery::SalvagedTable {
+                        rows,
+                        unreadable_rows: 0,
+                    }) => eprintln!("Salvaged {} rows of {}", rows, table),
+                    Ok(crate::recovery::SalvagedTable {
+                        rows,
+                        unreadable_rows,
+                    }) => eprintln!(
+                        "Salvaged {} rows of {}, {} couldn't be read",
+                        rows, table, unreadable_rows
+                    ),
+                    Err(e) => eprintln!("Unable to salvage {}: {:#}", table, e),
+                }
+            }
+            salvaged.corrupt_path
+        }
+        _ => return Err(anyhow::anyhow!("{} is corrupted", database_path.display())),
+    };
+
+    eprintln!(
+        "The corrupted database is now at {}",
+        corrupt_path.display()
+    );
+
+    Ok(())
+}
+
+fn pocket_login(options: &Options) -> Result<()> {
+    let pocket_config = options.config.pocket.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("Add a [pocket] section with your consumer_key to the config file first")
+    })?;
+
+    let keyring = crate::keyring::Keyring::open(options.database_path());
+    let http_client = crate::http::client(options.network_timeout, options.user_agent.as_deref());
+
+    crate::pocket::login(&http_client, pocket_config, &keyring)
+}
+
+/// read a secret from stdin, so it isn't left in the shell's history, and keep it in the keyring
+fn store_secret(options: &Options, name: &str) -> Result<()> {
+    if !crate::keyring::SECRET_NAMES.contains(&name) {
+        return Err(anyhow::anyhow!(
+            "Unknown secret {}, it can be one of {}",
+            name,
+            crate::keyring::SECRET_NAMES.join(", ")
+        ));
+    }
+
+    let mut secret = String::new();
+    std::io::stdin().read_line(&mut secret)?;
+    let secret = secret.trim_end_matches(['\r', '\n']);
+
+    if secret.is_empty() {
+        return Err(anyhow::anyhow!("No secret given on stdin"));
+    }
+
+    crate::keyring::Keyring::open(options.database_path()).set(name, secret)?;
+    println!("Stored {}", name);
+
+    Ok(())
+}
+
+/// piping to a command like `espeak` can take a while,
+/// so it runs on its own thread, and the UI carries on
+fn run_pipe(
+    app: App,
+    pipe: crate::pipe::Pipe,
+    io_s: mpsc::Sender<IoCommand>,
+    flash_display_duration: time::Duration,
+) {
+    thread::spawn(move || {
+        match pipe.run() {
+            Ok(output) => {
+                // the first line of output, for commands that report something
+                let flash = match output.lines().find(|line| !line.trim().is_empty()) {
+                    Some(line) => format!("{}: {}", pipe.command, line.trim()),
+                    None => format!("Piped entry to {}", pipe.command),
+                };
+                app.set_flash(flash);
+                clear_flash_after(io_s, flash_display_duration);
+            }
+            Err(e) => app.push_error_flash(e),
+        }
+
+        // the main thread may have already quit
+        let _ = app.force_redraw();
+    });
+}
+
+/// Call the script's action `function` with `entry`, on a thread of its own,
+/// as it can take a while, showing what it returns.
+fn run_action(
+    app: App,
+    script: Arc<crate::scripting::Script>,
+    function: String,
+    entry: serde_json::Value,
+    io_s: mpsc::Sender<IoCommand>,
+    flash_display_duration: time::Duration,
+) {
+    thread::spawn(move || {
+        match script.run_action(&function, &entry) {
+            Ok(flash) => {
+                app.set_flash(flash);
+                clear_flash_after(io_s, flash_display_duration);
+            }
+            Err(e) => app.push_error_flash(e),
+        }
+
+        // the main thread may have already quit
+        let _ = app.force_redraw();
+    });
+}
+
+/// The config's script, checking that it has every function the actions call,
+/// so a typo shows up now rather than on pressing the key.
+fn load_script(config: &crate::config::Config) -> Result<Option<Arc<crate::scripting::Script>>> {
+    let script = match &config.script {
+        Some(script_config) => crate::scripting::Script::load(&script_config.path)?,
+        None if config.actions.is_empty() => return Ok(None),
+        None => {
+            return Err(anyhow::anyhow!(
+                "[[actions]] call the script's functions, so the config needs a [script] too"
+            ))
+        }
+    };
+
+    if let Some(action) = config
+        .actions
+        .iter()
+        .find(|action| !script.has_function(&action.function))
+    {
+        return Err(anyhow::anyhow!(
+            "The script has no {} function for the {} action",
+            action.function,
+            action.key
+        ));
+    }
+
+    Ok(Some(Arc::new(script)))
+}
+
+/// log to `$XDG_STATE_HOME/russ/russ.log`,
+/// or the data directory on platforms without a state directory
+fn init_logging(log_level: log::LevelFilter) -> Result<()> {
+    if log_level == log::LevelFilter::Off {
+        return Ok(());
+    }
+
+    let project_dirs = directories::ProjectDirs::from("", "", "russ")
+        .ok_or_else(|| anyhow::anyhow!("Unable to find a home directory for the log file"))?;
+
+    let log_dir = project_dirs
+        .state_dir()
+        .unwrap_or_else(|| project_dirs.data_dir());
+
+    std::fs::create_dir_all(log_dir)?;
+
+    let log_file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_dir.join("russ.log"))?;
+
+    simplelog::WriteLogger::init(log_level, simplelog::Config::default(), log_file)?;
+
+    Ok(())
+}
+
+/// Without this, a panic leaves the terminal in raw mode on the alternate screen,
+/// where the panic message is unreadable and disappears.
+fn restore_terminal_on_panic() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |panic_info| {
+        // the terminal may already be restored, so ignore errors
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            stdout(),
+            DisableBracketedPaste,
+            LeaveAlternateScreen,
+            crossterm::cursor::Show
+        );
+        default_hook(panic_info);
+    }));
+}
+
+/// Hand the terminal over to another program, like a pager, until `run` returns.
+/// The input thread stops reading keys meanwhile, so they go to the program.
+fn suspend_terminal<T>(
+    app: &App,
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    input_paused: &AtomicBool,
+    tick_rate: time::Duration,
+    run: impl FnOnce() -> T,
+) -> Result<T> {
+    input_paused.store(true, Ordering::SeqCst);
+    // let a poll for input that's already underway finish
+    thread::sleep(tick_rate);
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen,
+        crossterm::cursor::Show
+    )?;
+
+    let result = run();
+
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableBracketedPaste,
+        crossterm::cursor::Hide
+    )?;
+    app.clear(terminal)?;
+    input_paused.store(false, Ordering::SeqCst);
+
+    Ok(result)
+}
+
+/// Stop, giving the terminal back to the shell, until it's brought back with `fg`.
+#[cfg(unix)]
+fn suspend_to_shell(
+    app: &App,
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    input_paused: &AtomicBool,
+    tick_rate: time::Duration,
+) -> Result<()> {
+    // SIGSTOP, as SIGTSTP is caught, and it's all the same to the shell
+    suspend_terminal(app, terminal, input_paused, tick_rate, || {
+        signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)
+    })??;
+
+    Ok(())
+}
+
+/// Other platforms have no job control to give the terminal back to.
+#[cfg(not(unix))]
+fn suspend_to_shell(
+    _app: &App,
+    _terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    _input_paused: &AtomicBool,
+    _tick_rate: time::Duration,
+) -> Result<()> {
+    Ok(())
+}
+
+fn quit(
+    app: &App,
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    io_s: &mpsc::Sender<IoCommand>,
+) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
+    terminal.show_cursor()?;
+    io_s.send(IoCommand::Break)?;
+    app.save_session()?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let mut options: Options = Options::parse();
+    options.config = crate::config::Config::load(options.config_path.as_deref())?;
+    options.database_path = Some(resolve_database_path(
+        options.database_path.take(),
+        options.profile.as_deref(),
+    )?);
+
+    init_logging(options.log_level)?;
+    crate::sources::set_command_timeout(options.command_timeout);
+    log::info!(
+        "starting russ {} with database {}",
+        env!("CARGO_PKG_VERSION"),
+        options.database_path().display()
+    );
+
+    if !matches!(
+        options.subcommand,
+        Some(Subcommand::StoreSecret { .. } | Subcommand::Ctl { .. })
+    ) {
+        options.database_passphrase = database_passphrase(&options)?;
+    }
+
+    match &options.subcommand {
+        Some(Subcommand::Import { newsboat }) => {
+            let script = load_script(&options.config)?;
+            return crate::import::import_newsboat(
+                options.database_path(),
+                options.database_passphrase.as_deref(),
+                options.network_timeout,
+                options.user_agent.as_deref(),
+                &crate::hooks::NewEntryRules {
+                    script: script.as_deref(),
+                    rules: &options.config.rules,
+                    on_error: &|e| eprintln!("{:#}", e),
+                },
+                newsboat,
+            );
+        }
+        Some(Subcommand::ExportMaildir { dir }) => return export_maildir(&options, dir.clone()),
+        Some(Subcommand::Add {
+            url,
+            fingerprint,
+            from_file,
+        }) => {
+            let inputs = match (url, from_file) {
+                (_, Some(from_file)) => crate::import::parse_url_list(&read_url_list(from_file)?),
+                (Some(url), None) => vec![match fingerprint {
+                    Some(fingerprint) => format!("{} {}", url, fingerprint),
+                    None => url.to_owned(),
+                }],
+                (None, None) => unreachable!("clap requires a url without --from-file"),
+            };
+
+            return add(&options, &inputs);
+        }
+        Some(Subcommand::Ctl { request }) => return ctl(&options, request),
+        Some(Subcommand::Unread { json, refresh }) => return unread(&options, *json, *refresh),
+        Some(Subcommand::Check { url }) => return check(&options, url),
+        Some(Subcommand::Backup { path }) => return backup(&options, path),
+        Some(Subcommand::Restore { path }) => return restore(&options, path),
+        Some(Subcommand::PocketLogin) => return pocket_login(&options),
+        Some(Subcommand::StoreSecret { name }) => return store_secret(&options, name),
+        None => (),
+    }
+
+    let instance_lock = crate::remote::lock(options.database_path())?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Russ is already running with {}",
+            options.database_path().display()
+        )
+    })?;
+
+    check_database(&options)?;
+
+    let script = load_script(&options.config)?;
+
+    let backup_database_path = options.database_path().to_owned();
+    let backup_passphrase = options.database_passphrase.clone();
+    let keep_backups = options.config.backups.keep;
+    thread::spawn(move || {
+        if let Err(e) = crate::recovery::back_up_daily(
+            &backup_database_path,
+            backup_passphrase.as_deref(),
+            keep_backups,
+        ) {
+            log::warn!("unable to back up the database: {:?}", e);
+        }
+    });
+
+    enable_raw_mode()?;
+    restore_terminal_on_panic();
+
+    let mut stdout = stdout();
+    // so pasting is one event, not a key for each character
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
+
+    let backend = CrosstermBackend::new(stdout);
+
+    let mut terminal = Terminal::new(backend)?;
+    terminal.hide_cursor()?;
+
+    // Setup input handling
+    let (tx, rx) = mpsc::channel();
+    let tx_clone = tx.clone();
+
+    // in raw mode, ctrl-c is a key press rather than a signal,
+    // but signals can still come from elsewhere
+    let tx_signal = tx.clone();
+    ctrlc::set_handler(move || {
+        // the main thread may have already quit
+        let _ = tx_signal.send(Event::Quit);
+    })?;
+
+    // and so is ctrl-z, but a SIGTSTP from elsewhere would stop russ in raw mode
+    #[cfg(unix)]
+    {
+        let tx_signal = tx.clone();
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGTSTP])?;
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                if tx_signal.send(Event::Suspend).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let tick_rate = time::Duration::from_millis(options.tick_rate);
+    // while another program has the terminal, see `suspend_terminal`
+    let input_paused = Arc::new(AtomicBool::new(false));
+    let input_thread_paused = input_paused.clone();
+    thread::spawn(move || {
+        let mut last_tick = time::Instant::now();
+        loop {
+            if input_thread_paused.load(Ordering::SeqCst) {
+                thread::sleep(tick_rate);
+                last_tick = time::Instant::now();
+                continue;
+            }
+
+            // poll for tick rate duration, if no events, sent tick event.
+            if event::poll(tick_rate - last_tick.elapsed())
+                .expect("Unable to poll for Crossterm event")
+            {
+                match event::read().expect("Unable to read Crossterm event") {
+                    CEvent::Key(key) => tx
+                        .send(Event::Input(key))
+                        .expect("Unable to send Crossterm Key input event"),
+                    CEvent::Resize(_, _) => tx
+                        .send(Event::Resize)
+                        .expect("Unable to send Crossterm resize event"),
+                    CEvent::Paste(text) => tx
+                        .send(Event::Paste(text))
+                        .expect("Unable to send Crossterm paste event"),
+                    _ => (),
+                }
+            }
+            if last_tick.elapsed() >= tick_rate {
+                tx.send(Event::Tick).expect("Unable to send tick");
+                last_tick = time::Instant::now();
+            }
+        }
+    });
 
     let options_clone = options.clone();
+    let flash_display_duration = options.flash_display_duration_seconds;
+    let viewer_config = options.config.viewer.clone();
+    let actions = options.config.actions.clone();
 
     let app = App::new(options, tx_clone)?;
 
     let cloned_app = app.clone();
+    let io_script = script.clone();
 
     terminal.clear()?;
 
@@ -273,19 +1805,133 @@ fn main() -> Result<()> {
 
     // spawn this thread to handle receiving messages to performing blocking network and db IO
     let io_thread = thread::spawn(move || -> Result<()> {
-        io_loop(cloned_app, io_s_clone, io_r, &options_clone)
+        io_loop(cloned_app, io_s_clone, io_r, &options_clone, io_script)
+    });
+
+    // for other russes, like `russ add` and `russ ctl`, to hand their commands to this one
+    let remote_app = app.clone();
+    let remote_s = io_s.clone();
+    let remote_handler: crate::remote::Handler = Arc::new(move |request| match request {
+        crate::remote::Request::Add(input) => {
+            // answered once it's subscribed, or not, so `russ add` can say which
+            let (reply_s, reply_r) = mpsc::channel();
+            remote_s.send(IoCommand::SubscribeToFeed(input, Some(reply_s)))?;
+            reply_r.recv()?
+        }
+        crate::remote::Request::Refresh => {
+            let feed_ids = remote_app.due_feed_ids()?;
+            let feed_ids_len = feed_ids.len();
+            remote_s.send(IoCommand::RefreshFeeds(feed_ids))?;
+            Ok(format!("Refreshing {} feeds", feed_ids_len))
+        }
+        crate::remote::Request::UnreadCount => Ok(remote_app.unread_count()?.to_string()),
+        crate::remote::Request::OpenFeed(title) => {
+            let title = remote_app.open_feed(&title)?;
+            remote_app.force_redraw()?;
+            Ok(format!("Opened {}", title))
+        }
+    });
+    app.listen(instance_lock, remote_handler)?;
+
+    // refresh the feeds that have their own refresh intervals as they come due
+    let scheduler_app = app.clone();
+    let scheduler_s = io_s.clone();
+    thread::spawn(move || loop {
+        thread::sleep(SCHEDULER_INTERVAL);
+
+        match scheduler_app.scheduled_feed_ids() {
+            Ok(feed_ids) if feed_ids.is_empty() => (),
+            Ok(feed_ids) => {
+                // the IO thread is gone once russ quits
+                if scheduler_s.send(IoCommand::RefreshFeeds(feed_ids)).is_err() {
+                    break;
+                }
+            }
+            Err(e) => scheduler_app.push_error_flash(e),
+        }
     });
 
     // MAIN THREAD IS DRAW THREAD
     loop {
+        // a macro's keys are replayed one after another, drawing once they're done,
+        // or while they wait for the loader thread, which a tick comes after
+        let replayed_key = app.next_replayed_key();
+
         let mode = {
-            app.draw(&mut terminal)?;
+            if replayed_key.is_none() {
+                app.draw(&mut terminal)?;
+            }
             app.mode()
         };
 
+        let image_urls = app.take_pending_images();
+        if !image_urls.is_empty() {
+            io_s.send(IoCommand::FetchImages(image_urls))?;
+        }
+
+        let event = match replayed_key {
+            Some(key) => Event::Input(key),
+            None => {
+                let event = rx.recv()?;
+                if let Event::Input(key) = event {
+                    app.record_key(key);
+                }
+                event
+            }
+        };
+
+        if let Event::Quit = event {
+            quit(&app, &mut terminal, &io_s)?;
+            break;
+        }
+
+        if let Event::Paste(text) = &event {
+            app.paste(text)?;
+            continue;
+        }
+
+        let is_ctrl_z = matches!(
+            &event,
+            Event::Input(key) if key.code == KeyCode::Char('z') && key.modifiers == KeyModifiers::CONTROL
+        );
+        if is_ctrl_z || matches!(event, Event::Suspend) {
+            suspend_to_shell(&app, &mut terminal, &input_paused, tick_rate)?;
+            continue;
+        }
+
         match mode {
-            Mode::Normal => match rx.recv()? {
+            Mode::Normal => match event {
                 Event::Input(event) => match (event.code, event.modifiers) {
+                    // the letter after `m` or `'` is the mark's, whatever it would do otherwise
+                    (keycode, modifiers) if app.mark_is_pending() => {
+                        if let Err(e) = app.on_key(keycode, modifiers) {
+                            app.push_error_flash(e);
+                        }
+                    }
+                    // the config's actions take their keys' places
+                    (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT)
+                        if actions.iter().any(|action| action.key == c) =>
+                    {
+                        app.take_pending_count();
+
+                        let action = actions
+                            .iter()
+                            .find(|action| action.key == c)
+                            .expect("the action was just found");
+                        match (app.action_entry(), &script) {
+                            (Ok(entry), Some(script)) => run_action(
+                                app.clone(),
+                                script.clone(),
+                                action.function.clone(),
+                                entry,
+                                io_s.clone(),
+                                flash_display_duration,
+                            ),
+                            (Err(e), _) => app.push_error_flash(e),
+                            // `load_script` makes sure there is one
+                            (Ok(_), None) => (),
+                        }
+                    }
                     // These first few keycodes are handled inline
                     // because they talk to either the IO thread or the terminal.
                     // All other keycodes are handled in the final `on_key`
@@ -293,25 +1939,127 @@ fn main() -> Result<()> {
                     (KeyCode::Char('q'), _)
                     | (KeyCode::Char('c'), KeyModifiers::CONTROL)
                     | (KeyCode::Esc, _) => {
-                        if !app.error_flash_is_empty() {
+                        if app.take_pending_count().is_some() {
+                            // a count followed by esc is just cancelled
+                        } else if event.code != KeyCode::Char('q') && app.cancel_refresh() {
+                            // esc and ctrl-c cancel a refresh before anything else
+                        } else if !app.error_flash_is_empty() {
                             app.clear_error_flash();
+                        } else if app.error_log_is_shown() {
+                            app.close_error_log();
+                        } else if app.stats_are_shown() {
+                            app.close_stats();
+                        } else if app.queue_is_shown() {
+                            app.close_queue();
+                        } else if !app.visual_is_empty() {
+                            app.exit_visual();
+                        } else if !app.search_is_empty() {
+                            app.clear_search()?;
                         } else {
-                            disable_raw_mode()?;
-                            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-                            terminal.show_cursor()?;
-                            io_s.send(IoCommand::Break)?;
+                            quit(&app, &mut terminal, &io_s)?;
                             break;
                         }
                     }
-                    (KeyCode::Char('r'), KeyModifiers::NONE) => match &app.selected() {
-                        Selected::Feeds => {
-                            let feed_id = app.selected_feed_id();
-                            io_s.send(IoCommand::RefreshFeed(feed_id))?;
+                    (KeyCode::Char('r'), KeyModifiers::NONE) => {
+                        // counts only apply to movement
+                        app.take_pending_count();
+
+                        match &app.selected() {
+                            Selected::Feeds => {
+                                if let Some(feed_id) = app.selected_feed_id() {
+                                    io_s.send(IoCommand::RefreshFeed(feed_id))?;
+                                }
+                            }
+                            _ => app.toggle_read()?,
                         }
-                        _ => app.toggle_read()?,
-                    },
+                    }
+                    (KeyCode::Char('Y'), _) => {
+                        app.take_pending_count();
+
+                        match app.toggle_translation() {
+                            Ok(Some((entry_id, html))) => {
+                                io_s.send(IoCommand::Translate(entry_id, html))?
+                            }
+                            Ok(None) => clear_flash_after(io_s.clone(), flash_display_duration),
+                            Err(e) => app.push_error_flash(e),
+                        }
+                    }
+                    (KeyCode::Char('I'), _) => {
+                        app.take_pending_count();
+
+                        match app.add_feed_from_clipboard() {
+                            Ok(input) => io_s.send(IoCommand::PreviewFeed(input))?,
+                            Err(e) => app.push_error_flash(e),
+                        }
+                    }
+                    (KeyCode::Char('w'), KeyModifiers::NONE) => {
+                        app.take_pending_count();
+
+                        if let Some(link) = app.current_entry_link() {
+                            io_s.send(IoCommand::SaveToWallabag(link))?;
+                        }
+                    }
+                    (KeyCode::Char('P'), _) => {
+                        app.take_pending_count();
+
+                        if let Some(link) = app.current_entry_link() {
+                            io_s.send(IoCommand::SaveToPocket(link))?;
+                        }
+                    }
+                    (KeyCode::Char('W'), _) => {
+                        app.take_pending_count();
+
+                        match app.current_entry_media_link() {
+                            Ok(Some(link)) => io_s.send(IoCommand::Play(link))?,
+                            Ok(None) => (),
+                            Err(e) => app.push_error_flash(e),
+                        }
+                    }
+                    (KeyCode::Char('M'), _) => {
+                        app.take_pending_count();
+
+                        if app.toggle_queue_playback()? {
+                            io_s.send(IoCommand::PlayQueue)?;
+                        }
+                    }
+                    (KeyCode::Char('V'), _) => {
+                        app.take_pending_count();
+
+                        match app.toggle_speech() {
+                            Ok(Some(paragraphs)) => io_s.send(IoCommand::ReadAloud(paragraphs))?,
+                            Ok(None) => clear_flash_after(io_s.clone(), flash_display_duration),
+                            Err(e) => app.push_error_flash(e),
+                        }
+                    }
+                    (KeyCode::Char('X'), _) => {
+                        app.take_pending_count();
+                        app.stop_speech();
+                    }
+                    (KeyCode::Char('O'), _) => {
+                        app.take_pending_count();
+
+                        let result = app
+                            .entry_for_viewer()
+                            .and_then(|(text, html)| {
+                                crate::viewer::View::new(&viewer_config, text, html)
+                            })
+                            .and_then(|view| {
+                                suspend_terminal(
+                                    &app,
+                                    &mut terminal,
+                                    &input_paused,
+                                    tick_rate,
+                                    || view.run(),
+                                )?
+                            });
+
+                        if let Err(e) = result {
+                            app.push_error_flash(e);
+                        }
+                    }
                     (KeyCode::Char('x'), KeyModifiers::NONE) => {
-                        let feed_ids = app.feed_ids()?;
+                        app.take_pending_count();
+                        let feed_ids = app.due_feed_ids()?;
                         io_s.send(IoCommand::RefreshFeeds(feed_ids))?;
                     }
                     // handle all other normal-mode keycodes here
@@ -325,34 +2073,190 @@ fn main() -> Result<()> {
                         }
                     }
                 },
-                Event::Tick => (),
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
             },
-            Mode::Editing => match rx.recv()? {
+            Mode::Command => match event {
                 Event::Input(event) => match event.code {
                     KeyCode::Enter => {
-                        let feed_subscription_input = { app.feed_subscription_input() };
-                        io_s.send(IoCommand::SubscribeToFeed(feed_subscription_input))?;
+                        use crate::command::Command;
+
+                        match app.take_command() {
+                            Ok(Command::Add(url)) => {
+                                io_s.send(IoCommand::SubscribeToFeed(url, None))?
+                            }
+                            Ok(Command::Quit) => {
+                                quit(&app, &mut terminal, &io_s)?;
+                                break;
+                            }
+                            Ok(Command::Refresh) => {
+                                if let Some(feed_id) = app.current_feed_id() {
+                                    io_s.send(IoCommand::RefreshFeed(feed_id))?;
+                                }
+                            }
+                            Ok(Command::RefreshAll) => {
+                                let feed_ids = app.due_feed_ids()?;
+                                io_s.send(IoCommand::RefreshFeeds(feed_ids))?;
+                            }
+                            Ok(command) => {
+                                if let Err(e) = app.run_command(command) {
+                                    app.push_error_flash(e);
+                                }
+                            }
+                            Err(e) => app.push_error_flash(e),
+                        }
+                    }
+                    KeyCode::Tab => app.complete_command(),
+                    KeyCode::Char('r') if event.modifiers == KeyModifiers::CONTROL => {
+                        app.search_command_history()
+                    }
+                    KeyCode::Up => app.previous_command(),
+                    KeyCode::Down => app.next_command(),
+                    KeyCode::Char(c) => app.push_command_input(c),
+                    KeyCode::Backspace => app.pop_command_input(),
+                    KeyCode::Esc => app.cancel_command(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Finder => match event {
+                Event::Input(event) => match (event.code, event.modifiers) {
+                    (KeyCode::Enter, _) => {
+                        if let Err(e) = app.confirm_finder() {
+                            app.push_error_flash(e);
+                        }
                     }
-                    KeyCode::Char(c) => {
-                        app.push_feed_subscription_input(c);
+                    (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                        app.next_finder_match()
                     }
-                    KeyCode::Backspace => app.pop_feed_subscription_input(),
+                    (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                        app.previous_finder_match()
+                    }
+                    (KeyCode::Char(c), _) => app.push_finder_input(c),
+                    (KeyCode::Backspace, _) => app.pop_finder_input(),
+                    (KeyCode::Esc, _) => app.cancel_finder(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Editing => match event {
+                Event::Input(event) => match event.code {
+                    // subscribe to the feed once it's been previewed
+                    KeyCode::Enter => match app.take_feed_preview() {
+                        Some(feed_preview) => {
+                            io_s.send(IoCommand::SubscribeToFeed(feed_preview.input, None))?
+                        }
+                        None => io_s.send(IoCommand::PreviewFeed(app.feed_subscription_input()))?,
+                    },
                     KeyCode::Delete => {
                         app.delete_feed()?;
                     }
-                    KeyCode::Esc => {
-                        app.set_mode(Mode::Normal);
+                    KeyCode::Esc => match app.take_feed_preview() {
+                        Some(_) => (),
+                        None => {
+                            app.cancel_delete_feed();
+                            app.set_mode(Mode::Normal);
+                        }
+                    },
+                    // moving around and editing, like a shell
+                    keycode => app.edit_feed_subscription_input(keycode, event.modifiers),
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Note => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => {
+                        if let Err(e) = app.save_note() {
+                            app.push_error_flash(e);
+                        }
+                    }
+                    KeyCode::Char(c) => app.push_note_input(c),
+                    KeyCode::Backspace => app.pop_note_input(),
+                    KeyCode::Esc => app.cancel_note(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::FeedEdit => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => {
+                        if let Err(e) = app.save_feed_edit() {
+                            app.push_error_flash(e);
+                        }
+                    }
+                    KeyCode::Tab => app.switch_feed_edit_field(),
+                    KeyCode::Char(c) => app.push_feed_edit_input(c),
+                    KeyCode::Backspace => app.pop_feed_edit_input(),
+                    KeyCode::Esc => app.cancel_feed_edit(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Pipe => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => match app.take_pipe() {
+                        Ok(pipe) => {
+                            run_pipe(app.clone(), pipe, io_s.clone(), flash_display_duration)
+                        }
+                        Err(e) => app.push_error_flash(e),
+                    },
+                    KeyCode::Char(c) => app.push_pipe_input(c),
+                    KeyCode::Backspace => app.pop_pipe_input(),
+                    KeyCode::Esc => app.cancel_pipe(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Share => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => match app.take_share() {
+                        Ok(status) => io_s.send(IoCommand::PostToMastodon(status))?,
+                        Err(e) => app.push_error_flash(e),
+                    },
+                    KeyCode::Char(c) => app.push_share_input(c),
+                    KeyCode::Backspace => app.pop_share_input(),
+                    KeyCode::Esc => app.cancel_share(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Tag => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => {
+                        if let Err(e) = app.confirm_tag() {
+                            app.push_error_flash(e);
+                        }
+                    }
+                    KeyCode::Tab => app.complete_tag(),
+                    KeyCode::Char(c) => app.push_tag_input(c),
+                    KeyCode::Backspace => app.pop_tag_input(),
+                    KeyCode::Esc => app.cancel_tag(),
+                    _ => {}
+                },
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
+            },
+            Mode::Search => match event {
+                Event::Input(event) => match event.code {
+                    KeyCode::Enter => app.confirm_search()?,
+                    KeyCode::Char('r') if event.modifiers == KeyModifiers::CONTROL => {
+                        app.search_past_searches()?
                     }
+                    KeyCode::Up => app.previous_search()?,
+                    KeyCode::Down => app.next_search()?,
+                    KeyCode::Char(c) => app.push_search_input(c)?,
+                    KeyCode::Backspace => app.pop_search_input()?,
+                    KeyCode::Esc => app.clear_search()?,
                     _ => {}
                 },
-                Event::Tick => (),
+                Event::Quit | Event::Resize | Event::Tick | Event::Paste(_) | Event::Suspend => (),
             },
         }
     }
 
-    io_thread
+    let joined = io_thread
         .join()
-        .expect("Unable to join IO thread to main thread")?;
+        .expect("Unable to join IO thread to main thread");
 
-    Ok(())
+    app.release_instance_lock();
+
+    joined
 }
@@ -0,0 +1,137 @@
+//! Styling the structure html2text leaves in the text it renders:
+//! `#` headings, `*` list bullets, `>` blockquotes, and the borders of tables.
+
+use crate::code::CodeRegion;
+use tui::style::{Color, Modifier, Style};
+
+/// Swap html2text's `*` list bullets for `•`, and work out how each line should look,
+/// leaving code alone. Returns the text, and the style of each line that has one.
+pub fn decorate(text: &str, code_regions: &[CodeRegion]) -> (String, Vec<(usize, Style)>) {
+    let mut lines = vec![];
+    let mut styles = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        if code_regions.iter().any(|region| region.lines.contains(&i)) {
+            lines.push(line.to_owned());
+            continue;
+        }
+
+        let quote_prefix_len = quote_prefix_len(line);
+        let (quote_prefix, rest) = line.split_at(quote_prefix_len);
+
+        let mut style = match heading_level(rest) {
+            Some(1) => Some(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ),
+            Some(_) => Some(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            None if is_table_border(rest) => Some(Style::default().fg(Color::DarkGray)),
+            None => None,
+        };
+
+        if quote_prefix_len > 0 {
+            style = Some(
+                style
+                    .unwrap_or_default()
+                    .add_modifier(Modifier::DIM | Modifier::ITALIC),
+            );
+        }
+
+        if let Some(style) = style {
+            styles.push((i, style));
+        }
+
+        let indent_len = rest.len() - rest.trim_start().len();
+        match rest[indent_len..].strip_prefix("* ") {
+            Some(item) => lines.push(format!("{}{}• {}", quote_prefix, &rest[..indent_len], item)),
+            None => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+
+    (text, styles)
+}
+
+/// how much of `line` is `> ` markers, for however many blockquotes it's in
+fn quote_prefix_len(line: &str) -> usize {
+    let mut len = 0;
+
+    while let Some(rest) = line[len..].strip_prefix('>') {
+        len += 1;
+        if rest.starts_with(' ') {
+            len += 1;
+        }
+    }
+
+    len
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+fn is_table_border(line: &str) -> bool {
+    !line.trim().is_empty() && line.trim().chars().all(|c| "─┬┼┴".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_styles_headings_lists_and_quotes() {
+        let text =
+            "# Big\n\n## Small\n\n* one\n  * nested\n\n> quoted\n> > deeper\n\n───┼──\n#hashtag\n";
+        let (text, styles) = decorate(text, &[]);
+
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            vec![
+                "# Big",
+                "",
+                "## Small",
+                "",
+                "• one",
+                "  • nested",
+                "",
+                "> quoted",
+                "> > deeper",
+                "",
+                "───┼──",
+                "#hashtag",
+            ]
+        );
+        assert_eq!(
+            styles.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 2, 7, 8, 10]
+        );
+        assert!(styles[0].1.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(styles[2].1.add_modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn it_leaves_code_alone() {
+        let regions = [CodeRegion {
+            lines: 1..2,
+            prefix: String::new(),
+            block_idx: 0,
+        }];
+        let (text, styles) = decorate("* item\n# a comment\n", &regions);
+
+        assert_eq!(text, "• item\n# a comment\n");
+        assert!(styles.is_empty());
+    }
+}
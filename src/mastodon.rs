@@ -0,0 +1,31 @@
+//! Sharing entries to Mastodon, or anything else with Mastodon's API.
+
+use crate::config::MastodonConfig;
+use anyhow::{Context, Result};
+
+/// Mastodon's default, some instances allow more
+pub const MAX_STATUS_LENGTH: usize = 500;
+
+/// post `status` publicly, as the owner of the configured access token
+pub fn post_status(http_client: &ureq::Agent, config: &MastodonConfig, status: &str) -> Result<()> {
+    http_client
+        .post(&format!(
+            "{}/api/v1/statuses",
+            config.url.trim_end_matches('/')
+        ))
+        .set("Authorization", &format!("Bearer {}", config.access_token))
+        .send_form(&[("status", status)])
+        .context("Unable to post to Mastodon")?;
+
+    Ok(())
+}
+
+/// what the share input starts out as, to be edited before posting
+pub fn default_status(title: Option<&str>, link: Option<&str>) -> String {
+    match (title, link) {
+        (Some(title), Some(link)) => format!("{} {}", title, link),
+        (Some(title), None) => title.to_owned(),
+        (None, Some(link)) => link.to_owned(),
+        (None, None) => String::new(),
+    }
+}
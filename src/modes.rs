@@ -2,14 +2,113 @@
 pub enum Selected {
     Feeds,
     Entries,
-    Entry(crate::rss::EntryMeta),
+    Entry(Box<crate::rss::EntryMeta>),
     None,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum Mode {
+    Command,
     Editing,
+    FeedEdit,
+    Finder,
     Normal,
+    Note,
+    Pipe,
+    Search,
+    Share,
+    Tag,
+}
+
+/// what confirming the tag input does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagAction {
+    /// add or remove tags on the selected entries
+    Toggle,
+    /// list the entries with a tag, across all feeds
+    Browse,
+}
+
+/// what the letter after `m`, `'`, `Z`, or `@` does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkAction {
+    /// put the mark on the selected feed or entry
+    Set,
+    /// go to the feed or entry with the mark
+    Jump,
+    /// record the keys pressed next into the letter's register
+    RecordMacro,
+    /// replay the letter's register this many times
+    ReplayMacro(usize),
+}
+
+/// which part of a feed is being edited
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeedField {
+    Title,
+    Url,
+    /// the fingerprint of the certificate the feed's host is pinned to
+    Certificate,
+}
+
+/// which of an entry's fields is shown, or shown first when they say different things
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentField {
+    /// the fetched full content, or the feed's content
+    Content,
+    Description,
+}
+
+/// the feeds listed above the rest, of every feed's entries from a stretch of time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VirtualFeed {
+    Today,
+    LastWeek,
+    /// the entries that have been read, most recently read first
+    History,
+}
+
+impl VirtualFeed {
+    /// in the order they're listed
+    pub const ALL: [VirtualFeed; 3] = [
+        VirtualFeed::Today,
+        VirtualFeed::LastWeek,
+        VirtualFeed::History,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            VirtualFeed::Today => "Today",
+            VirtualFeed::LastWeek => "Last 7 days",
+            VirtualFeed::History => "History",
+        }
+    }
+
+    /// when the stretch of time its entries are from starts, if it is one,
+    /// today being since midnight where `now` is
+    pub fn since(
+        &self,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let since = match self {
+            VirtualFeed::Today => {
+                let midnight = now
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .expect("midnight is a time");
+                // midnight may be skipped by daylight saving time, where it's an hour later
+                midnight
+                    .and_local_timezone(chrono::Local)
+                    .earliest()
+                    .unwrap_or(now - chrono::Duration::hours(24))
+                    .with_timezone(&chrono::Utc)
+            }
+            VirtualFeed::LastWeek => (now - chrono::Duration::days(7)).with_timezone(&chrono::Utc),
+            VirtualFeed::History => return None,
+        };
+
+        Some(since)
+    }
 }
 
 #[derive(Clone, Debug)]
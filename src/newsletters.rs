@@ -0,0 +1,652 @@
+//! Newsletters, read from a maildir, as feeds: one for each sender, subscribed to as
+//! `maildir:<path>?from=<address>`. Russ only reads the maildir, leaving the messages where
+//! they are, so whatever delivers them, like `mbsync` or `getmail` for IMAP, can keep on doing so.
+//!
+//! Messages are parsed only as far as newsletters need: their headers, including encoded
+//! words, and their HTML part, or their text part if they have no HTML, in UTF-8 or Latin-1.
+//! Who sent each one is remembered for as long as russ runs, so a message is only read again
+//! when it changes, and only read for its feed if its entry isn't stored yet.
+
+use crate::rss::{Entry, Feed, FeedKind};
+use crate::sources::{FeedSource, SourcedFeed};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// what a feed's url starts with when it's a sender's newsletters
+pub const MAILDIR_PREFIX: &str = "maildir:";
+
+/// the maildir and sender's address of a feed's url, if it's a sender's newsletters
+pub fn maildir_sender(url: &str) -> Option<(PathBuf, String)> {
+    let (path, address) = url
+        .trim()
+        .strip_prefix(MAILDIR_PREFIX)?
+        .rsplit_once("?from=")?;
+
+    Some((
+        crate::util::expand_tilde(path),
+        address.trim().to_lowercase(),
+    ))
+    .filter(|(_, address)| !address.is_empty())
+}
+
+fn feed_url(maildir: &str, address: &str) -> String {
+    format!("{}{}?from={}", MAILDIR_PREFIX, maildir, address)
+}
+
+/// the messages in the maildir, new and seen
+fn message_paths(maildir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+
+    for subfolder in ["new", "cur"] {
+        let folder = maildir.join(subfolder);
+        let dir_entries = std::fs::read_dir(&folder)
+            .with_context(|| format!("Unable to read maildir {}", folder.display()))?;
+
+        for dir_entry in dir_entries {
+            let path = dir_entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// who sent a message, and the link its entry has, as of when it was modified
+#[derive(Clone, Debug)]
+struct Summary {
+    modified: SystemTime,
+    from: Option<(Option<String>, String)>,
+    link: Option<String>,
+}
+
+/// the summaries of the messages that have been read, by their unique names in their maildirs
+static SUMMARIES: Mutex<BTreeMap<PathBuf, Summary>> = Mutex::new(BTreeMap::new());
+
+/// A message, or `None` if it's gone, like when it's been moved from new to cur since
+/// the maildir was listed.
+fn read_message(path: &Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Unable to read message {}", path.display())),
+    }
+}
+
+/// The summary of the message at `path`, read only if it's changed since it was last read,
+/// or `None` if it's gone.
+fn summarize(maildir: &Path, path: &Path) -> Result<Option<Summary>> {
+    let modified = match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // the same message, whichever folder it's in and whatever its flags are
+    let unique_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy())
+        .unwrap_or_default();
+    let key = maildir.join(unique_name.split(':').next().unwrap_or_default());
+
+    if let Some(summary) = SUMMARIES.lock().unwrap().get(&key) {
+        if summary.modified == modified {
+            return Ok(Some(summary.clone()));
+        }
+    }
+
+    let bytes = match read_message(path)? {
+        Some(bytes) => bytes,
+        None => return Ok(None),
+    };
+    let message = Message::parse(&bytes);
+    let summary = Summary {
+        modified,
+        from: message.from(),
+        link: message.link(),
+    };
+
+    SUMMARIES.lock().unwrap().insert(key, summary.clone());
+    Ok(Some(summary))
+}
+
+/// Subscribe to each sender in the maildir at `maildir` that isn't subscribed to yet,
+/// returning how many were.
+pub fn subscribe_to_new_senders(
+    http_client: &ureq::Agent,
+    conn: &mut rusqlite::Connection,
+    new_entry_rules: &crate::hooks::NewEntryRules,
+    maildir: &str,
+) -> Result<usize> {
+    let subscribed_senders = crate::rss::get_feeds(conn)?
+        .into_iter()
+        .flat_map(|feed| feed.feed_link)
+        .filter_map(|feed_link| maildir_sender(&feed_link).map(|(_, address)| address))
+        .collect::<BTreeSet<_>>();
+
+    let mut senders = BTreeSet::new();
+    let maildir_path = crate::util::expand_tilde(maildir);
+    for path in message_paths(&maildir_path)? {
+        if let Some((_, address)) =
+            summarize(&maildir_path, &path)?.and_then(|summary| summary.from)
+        {
+            senders.insert(address);
+        }
+    }
+
+    let mut subscribed_len = 0;
+    for address in senders.difference(&subscribed_senders) {
+        crate::rss::subscribe_to_feed(
+            http_client,
+            conn,
+            new_entry_rules,
+            &feed_url(maildir, address),
+        )?;
+        subscribed_len += 1;
+    }
+
+    Ok(subscribed_len)
+}
+
+/// A sender's messages in a maildir, read when the feed is.
+pub struct MaildirSource {
+    url: String,
+    maildir: PathBuf,
+    address: String,
+    /// the links of the entries that are already stored, whose messages needn't be read
+    known_links: HashSet<String>,
+}
+
+impl MaildirSource {
+    pub fn new(url: &str) -> Option<MaildirSource> {
+        let (maildir, address) = maildir_sender(url)?;
+
+        Some(MaildirSource {
+            url: url.trim().to_owned(),
+            maildir,
+            address,
+            known_links: HashSet::new(),
+        })
+    }
+}
+
+impl FeedSource for MaildirSource {
+    fn read(&self, on_entries: &mut dyn FnMut(Vec<Entry>) -> Result<()>) -> Result<SourcedFeed> {
+        let mut sender_name = None;
+        let mut batch = vec![];
+
+        for path in message_paths(&self.maildir)? {
+            let summary = match summarize(&self.maildir, &path)? {
+                Some(summary) => summary,
+                None => continue,
+            };
+
+            match summary.from {
+                Some((name, address)) if address == self.address => {
+                    sender_name = sender_name.or(name);
+                }
+                _ => continue,
+            }
+            if let Some(link) = &summary.link {
+                if self.known_links.contains(link) {
+                    continue;
+                }
+            }
+
+            if let Some(bytes) = read_message(&path)? {
+                batch.extend(Message::parse(&bytes).to_entry());
+            }
+
+            if batch.len() >= crate::parse::BATCH_SIZE {
+                on_entries(std::mem::take(&mut batch))?;
+            }
+        }
+
+        if !batch.is_empty() {
+            on_entries(batch)?;
+        }
+
+        let mut feed = Feed::new(
+            Some(sender_name.unwrap_or_else(|| self.address.clone())),
+            None,
+            FeedKind::Mail,
+        );
+        feed.feed_link = Some(self.url.clone());
+
+        Ok(SourcedFeed {
+            feed,
+            description: Some(format!("Newsletters from {}", self.address)),
+        })
+    }
+
+    fn known_links(&mut self, links: &HashSet<String>) {
+        self.known_links = links.clone();
+    }
+}
+
+/// an email, or one of its parts
+#[derive(Debug)]
+struct Message<'a> {
+    /// lowercased names, with their values unfolded
+    headers: BTreeMap<String, String>,
+    body: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    fn parse(bytes: &'a [u8]) -> Message<'a> {
+        let (head, body) = match find(bytes, b"\r\n\r\n") {
+            Some(i) => (&bytes[..i], &bytes[i + 4..]),
+            None => match find(bytes, b"\n\n") {
+                Some(i) => (&bytes[..i], &bytes[i + 2..]),
+                None => (bytes, &bytes[bytes.len()..]),
+            },
+        };
+
+        let mut headers = BTreeMap::new();
+        let mut current: Option<(String, String)> = None;
+
+        for line in String::from_utf8_lossy(head).lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = &mut current {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+
+            if let Some((name, value)) = current.take() {
+                headers.entry(name).or_insert(value);
+            }
+            current = line
+                .split_once(':')
+                .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_owned()));
+        }
+        if let Some((name, value)) = current {
+            headers.entry(name).or_insert(value);
+        }
+
+        Message { headers, body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// the sender's name, if there is one, and address, lowercased
+    fn from(&self) -> Option<(Option<String>, String)> {
+        let from = decode_words(self.header("from")?);
+
+        let (name, address) = match (from.rfind('<'), from.rfind('>')) {
+            (Some(start), Some(end)) if start < end => {
+                (from[..start].trim(), from[start + 1..end].trim())
+            }
+            _ => ("", from.trim()),
+        };
+        let name = name.trim_matches('"').trim();
+
+        Some((
+            Some(name.to_owned()).filter(|name| !name.is_empty()),
+            address.to_lowercase(),
+        ))
+        .filter(|(_, address)| address.contains('@'))
+    }
+
+    /// the value of the content type, like `text/html`, and its parameters, like `charset`
+    fn content_type(&self) -> (String, BTreeMap<String, String>) {
+        let content_type = self.header("content-type").unwrap_or("text/plain");
+        let mut parts = content_type.split(';');
+        let value = parts.next().unwrap_or_default().trim().to_lowercase();
+
+        let parameters = parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .map(|(name, value)| {
+                (
+                    name.trim().to_lowercase(),
+                    value.trim().trim_matches('"').to_owned(),
+                )
+            })
+            .collect();
+
+        (value, parameters)
+    }
+
+    /// the body, decoded, as text
+    fn text(&self) -> String {
+        let encoding = self
+            .header("content-transfer-encoding")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        let bytes = match encoding.as_str() {
+            "base64" => {
+                let encoded = self
+                    .body
+                    .iter()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .copied()
+                    .collect::<Vec<_>>();
+                base64::decode(encoded).unwrap_or_else(|_| self.body.to_vec())
+            }
+            "quoted-printable" => decode_quoted_printable(self.body, false),
+            _ => self.body.to_vec(),
+        };
+
+        let (_, parameters) = self.content_type();
+        decode_charset(&bytes, parameters.get("charset").map(String::as_str))
+    }
+
+    /// the part to show: the HTML one, or the text one, as HTML
+    fn html(&self) -> Option<String> {
+        let (content_type, parameters) = self.content_type();
+
+        if let Some(boundary) = content_type
+            .starts_with("multipart/")
+            .then(|| parameters.get("boundary"))
+            .flatten()
+        {
+            // the first part that can be shown, unless there's an HTML one after it
+            let mut shown = None;
+
+            for part in split_parts(self.body, boundary) {
+                let part = Message::parse(part);
+                let (part_type, _) = part.content_type();
+
+                match part.html() {
+                    Some(html) if part_type == "text/html" => return Some(html),
+                    html => shown = shown.or(html),
+                }
+            }
+
+            return shown;
+        }
+
+        match content_type.as_str() {
+            "text/html" => Some(self.text()),
+            "text/plain" => Some(format!("<pre>{}</pre>", escape_html(&self.text()))),
+            _ => None,
+        }
+    }
+
+    /// where the newsletter is on the web, if it says, or else the message, by its id
+    fn link(&self) -> Option<String> {
+        let message_id = self
+            .header("message-id")?
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_owned();
+
+        let link = self
+            .header("archived-at")
+            .map(|archived_at| {
+                archived_at
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_owned()
+            })
+            .unwrap_or_else(|| format!("mid:{}", message_id));
+
+        Some(crate::links::strip_tracking_params(&link))
+    }
+
+    fn to_entry(&self) -> Option<Entry> {
+        Some(Entry {
+            id: -1,
+            feed_id: -1,
+            title: self.header("subject").map(decode_words),
+            author: self.from().map(|(name, address)| name.unwrap_or(address)),
+            pub_date: self.header("date").and_then(crate::dates::parse),
+            description: None,
+            content: self.html(),
+            link: Some(self.link()?),
+            comments_link: None,
+            enclosure: None,
+            modified_at: None,
+            categories: vec![],
+            read_at: None,
+            inserted_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// a multipart body's parts, between its `--boundary` lines
+fn split_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = vec![];
+    let mut rest = body;
+    let mut started = false;
+
+    while let Some(i) = find(rest, delimiter.as_bytes()) {
+        if started {
+            parts.push(&rest[..i]);
+        }
+        started = true;
+        rest = &rest[i + delimiter.len()..];
+
+        if rest.starts_with(b"--") {
+            break;
+        }
+        // the rest of the boundary's line
+        rest = match rest.iter().position(|b| *b == b'\n') {
+            Some(i) => &rest[i + 1..],
+            None => &rest[rest.len()..],
+        };
+    }
+
+    parts
+}
+
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    match charset.map(|charset| charset.to_lowercase()).as_deref() {
+        Some("iso-8859-1" | "latin1" | "windows-1252" | "cp1252") => {
+            bytes.iter().map(|b| *b as char).collect()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// `in_header`, for encoded words' `Q` encoding, where `_` is a space
+fn decode_quoted_printable(bytes: &[u8], in_header: bool) -> Vec<u8> {
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'=' if bytes[i + 1..].starts_with(b"\r\n") => i += 3,
+            b'=' if bytes[i + 1..].starts_with(b"\n") => i += 2,
+            b'=' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(b) => {
+                        decoded.push(b);
+                        i += 3;
+                    }
+                    None => {
+                        decoded.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            b'_' if in_header => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    decoded
+}
+
+/// a header's RFC 2047 encoded words, like `=?utf-8?Q?caf=C3=A9?=`, decoded
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    // the whitespace between two encoded words isn't part of the value
+    let mut after_word = false;
+
+    while let Some(start) = rest.find("=?") {
+        let word = rest[start + 2..].splitn(3, '?').collect::<Vec<_>>();
+        let (charset, encoding, text) = match word.as_slice() {
+            [charset, encoding, text] => match text.find("?=") {
+                Some(end) => (*charset, *encoding, &text[..end]),
+                None => break,
+            },
+            _ => break,
+        };
+
+        let bytes = match encoding.to_ascii_lowercase().as_str() {
+            "b" => base64::decode(text).ok(),
+            "q" => Some(decode_quoted_printable(text.as_bytes(), true)),
+            _ => None,
+        };
+
+        let before = &rest[..start];
+        if !(after_word && before.trim().is_empty()) {
+            decoded.push_str(before);
+        }
+
+        let word_len = start + 2 + charset.len() + 1 + encoding.len() + 1 + text.len() + 2;
+        match bytes {
+            Some(bytes) => {
+                decoded.push_str(&decode_charset(&bytes, Some(charset)));
+                after_word = true;
+            }
+            None => {
+                decoded.push_str(&rest[start..word_len]);
+                after_word = false;
+            }
+        }
+        rest = &rest[word_len..];
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEWSLETTER: &str = "From: =?utf-8?Q?Caf=C3=A9?= Weekly <news@Example.com>\r
+Subject: =?utf-8?B?SXNzdWUg?= =?utf-8?Q?=E2=84=961?=\r
+Date: Tue, 02 Jan 2024 03:04:05 +0000\r
+Message-ID: <abc@example.com>\r
+Content-Type: multipart/alternative;\r
+ boundary=\"b1\"\r
+\r
+--b1\r
+Content-Type: text/plain; charset=utf-8\r
+\r
+Plain\r
+--b1\r
+Content-Type: text/html; charset=utf-8\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+<p>Hello, caf=C3=A9 =\r
+readers</p>\r
+--b1--\r
+";
+
+    #[test]
+    fn it_reads_newsletters() {
+        let message = Message::parse(NEWSLETTER.as_bytes());
+        assert_eq!(
+            message.from(),
+            Some((
+                Some("Café Weekly".to_string()),
+                "news@example.com".to_string()
+            ))
+        );
+
+        let entry = message.to_entry().unwrap();
+        assert_eq!(entry.title.as_deref(), Some("Issue №1"));
+        assert_eq!(entry.author.as_deref(), Some("Café Weekly"));
+        assert_eq!(entry.link.as_deref(), Some("mid:abc@example.com"));
+        assert_eq!(
+            entry.content.as_deref().map(str::trim),
+            Some("<p>Hello, café readers</p>")
+        );
+        assert!(entry.pub_date.is_some());
+
+        let plain = Message::parse(
+            b"From: someone@example.com\nMessage-ID: <1@x>\nContent-Transfer-Encoding: base64\n\nPGI+\n",
+        );
+        assert_eq!(
+            plain.from(),
+            Some((None, "someone@example.com".to_string()))
+        );
+        assert_eq!(plain.html().as_deref(), Some("<pre>&lt;b&gt;</pre>"));
+        assert!(Message::parse(b"Subject: no id\n\nhi").to_entry().is_none());
+    }
+
+    #[test]
+    fn it_reads_a_senders_newsletters_from_a_maildir() {
+        let maildir = std::env::temp_dir().join(format!("russ-newsletters-{}", std::process::id()));
+        for subfolder in ["new", "cur", "tmp"] {
+            std::fs::create_dir_all(maildir.join(subfolder)).unwrap();
+        }
+        std::fs::write(maildir.join("new/1"), NEWSLETTER).unwrap();
+        std::fs::write(
+            maildir.join("cur/2:2,S"),
+            "From: other@example.com\nMessage-ID: <2@x>\n\nhi",
+        )
+        .unwrap();
+
+        let url = feed_url(&maildir.display().to_string(), "news@example.com");
+        let mut source = MaildirSource::new(&url).unwrap();
+        let read = |source: &MaildirSource| {
+            let mut entries = vec![];
+            let sourced = source
+                .read(&mut |batch| {
+                    entries.extend(batch);
+                    Ok(())
+                })
+                .unwrap();
+            (sourced.feed.title, entries)
+        };
+
+        let (title, entries) = read(&source);
+        assert_eq!(title.as_deref(), Some("Café Weekly"));
+        assert_eq!(entries.len(), 1);
+
+        // seen, and so moved to cur, it's still the same message
+        std::fs::rename(maildir.join("new/1"), maildir.join("cur/1:2,S")).unwrap();
+        assert_eq!(read(&source).1.len(), 1);
+
+        // once it's stored, it isn't read again, but the sender's name is still known
+        source.known_links(&entries.into_iter().flat_map(|entry| entry.link).collect());
+        let (title, entries) = read(&source);
+        assert_eq!(title.as_deref(), Some("Café Weekly"));
+        assert!(entries.is_empty());
+        assert_eq!(maildir_sender(&url).unwrap().1, "news@example.com");
+        assert!(maildir_sender("maildir:~/Mail").is_none());
+
+        std::fs::remove_dir_all(&maildir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! Parsing feeds an entry at a time, rather than all at once,
+//! so a feed with years of entries in it doesn't have to fit in memory.
+//!
+//! quick-xml splits the document into its entries, and each is parsed on its own by
+//! `rss` or `atom_syndication`, wrapped in the elements it was in, namespaces and all.
+//! Whatever's left, like the feed's title, is parsed last, as the feed itself.
+
+use crate::rss::Entry;
+use anyhow::Result;
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::io::BufRead;
+
+/// how many entries are handed over at a time
+pub const BATCH_SIZE: usize = 100;
+
+/// a feed, without its entries
+pub enum Channel {
+    Atom(Box<atom_syndication::Feed>),
+    Rss(Box<rss::Channel>),
+}
+
+impl Channel {
+    /// what the feed says it is, Atom's subtitle or RSS's description
+    pub fn description(&self) -> Option<String> {
+        let description = match self {
+            Channel::Atom(feed) => feed.subtitle().map(|subtitle| subtitle.as_str()),
+            Channel::Rss(channel) => Some(channel.description()),
+        };
+
+        description
+            .map(|description| description.trim().to_owned())
+            .filter(|description| !description.is_empty())
+    }
+
+    /// what the feed calls itself: Atom's id, or the self link RSS feeds have with Atom's `link`
+    pub fn guid(&self) -> Option<String> {
+        let guid = match self {
+            Channel::Atom(feed) => Some(feed.id().to_owned()),
+            Channel::Rss(channel) => channel
+                .extensions()
+                .get("atom")
+                .and_then(|atom| atom.get("link"))
+                .into_iter()
+                .flatten()
+                .find(|link| link.attrs().get("rel").map(String::as_str) == Some("self"))
+                .and_then(|link| link.attrs().get("href").cloned()),
+        };
+
+        guid.map(|guid| guid.trim().to_owned())
+            .filter(|guid| !guid.is_empty())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Atom,
+    Rss,
+}
+
+/// Parse the feed `reader` reads, handing its entries to `on_entries`
+/// `BATCH_SIZE` at a time, in the order they're in.
+pub fn parse<R: BufRead>(
+    reader: R,
+    mut on_entries: impl FnMut(Vec<Entry>) -> Result<()>,
+) -> Result<Channel> {
+    let mut reader = Reader::from_reader(reader);
+    let mut buf = vec![];
+
+    // the document without its entries
+    let mut rest = Writer::new(vec![]);
+    // like the XML declaration, which each entry is parsed with too
+    let mut prolog = vec![];
+    // the elements the reader is in, from the root down
+    let mut ancestors: Vec<BytesStart<'static>> = vec![];
+    let mut kind = Kind::Rss;
+    // the entry being read, and how many of its elements the reader is in
+    let mut entry: Option<(Writer<Vec<u8>>, usize)> = None;
+    let mut batch = vec![];
+    // whether the reader is in one of Atom's dates
+    let mut in_date = false;
+
+    loop {
+        let event = match reader.read_event_into(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(start) => {
+                in_date = kind == Kind::Atom && is_date(&start);
+                Event::Start(start)
+            }
+            Event::End(end) => {
+                in_date = false;
+                Event::End(end)
+            }
+            // `atom_syndication` won't read a feed with a date it can't read in it,
+            // so they're read here, and written back as RFC 3339, or left out
+            Event::Text(text) if in_date => {
+                let date = crate::dates::parse(&text.unescape()?)
+                    .map(|date| date.to_rfc3339())
+                    .unwrap_or_default();
+                Event::Text(BytesText::new(&date).into_owned())
+            }
+            event => event,
+        };
+
+        let finished_entry = match &mut entry {
+            Some((writer, depth)) => {
+                match &event {
+                    Event::Start(_) => *depth += 1,
+                    Event::End(_) => *depth -= 1,
+                    _ => (),
+                }
+                writer.write_event(event)?;
+
+                if *depth == 0 {
+                    entry.take().map(|(writer, _)| writer.into_inner())
+                } else {
+                    None
+                }
+            }
+            None => match event {
+                Event::Start(start) if is_entry(kind, &ancestors, &start) => {
+                    let mut writer = Writer::new(vec![]);
+                    writer.write_event(Event::Start(start))?;
+                    entry = Some((writer, 1));
+                    None
+                }
+                Event::Empty(start) if is_entry(kind, &ancestors, &start) => {
+                    let mut writer = Writer::new(vec![]);
+                    writer.write_event(Event::Empty(start))?;
+                    Some(writer.into_inner())
+                }
+                Event::Start(start) => {
+                    if ancestors.is_empty() {
+                        if start.local_name().as_ref() == b"feed" {
+                            kind = Kind::Atom;
+                        }
+                        prolog = rest.get_ref().clone();
+                    }
+                    ancestors.push(start.clone().into_owned());
+                    rest.write_event(Event::Start(start))?;
+                    None
+                }
+                Event::End(end) => {
+                    ancestors.pop();
+                    rest.write_event(Event::End(end))?;
+                    None
+                }
+                event => {
+                    rest.write_event(event)?;
+                    None
+                }
+            },
+        };
+
+        if let Some(entry_xml) = finished_entry {
+            let wrapped = wrap(kind, &prolog, &ancestors, &entry_xml)?;
+            batch.extend(parse_entry(kind, &wrapped)?);
+
+            if batch.len() >= BATCH_SIZE {
+                on_entries(std::mem::take(&mut batch))?;
+            }
+        }
+
+        buf.clear();
+    }
+
+    if !batch.is_empty() {
+        on_entries(batch)?;
+    }
+
+    let rest = rest.into_inner();
+    let channel = match kind {
+        Kind::Atom => Channel::Atom(Box::new(atom_syndication::Feed::read_from(&rest[..])?)),
+        Kind::Rss => Channel::Rss(Box::new(rss::Channel::read_from(&rest[..])?)),
+    };
+
+    Ok(channel)
+}
+
+/// Atom's entries are the root's, and RSS's items are the channel's,
+/// or the root's in RSS 1.0
+fn is_entry(kind: Kind, ancestors: &[BytesStart], start: &BytesStart) -> bool {
+    let name = match kind {
+        Kind::Atom => b"entry".as_slice(),
+        Kind::Rss => b"item".as_slice(),
+    };
+
+    !ancestors.is_empty() && start.local_name().as_ref() == name
+}
+
+fn is_date(start: &BytesStart) -> bool {
+    matches!(start.local_name().as_ref(), b"published" | b"updated")
+}
+
+/// the entry, in the elements it was in, as a document of its own
+fn wrap(kind: Kind, prolog: &[u8], ancestors: &[BytesStart], entry_xml: &[u8]) -> Result<Vec<u8>> {
+    let mut writer = Writer::new(prolog.to_vec());
+
+    for ancestor in ancestors {
+        writer.write_event(Event::Start(ancestor.borrow()))?;
+    }
+    // RSS 1.0's items are beside its channel, which `rss` won't do without
+    if kind == Kind::Rss && ancestors.len() == 1 {
+        writer.write_event(Event::Empty(BytesStart::new("channel")))?;
+    }
+    writer.get_mut().extend_from_slice(entry_xml);
+    for ancestor in ancestors.iter().rev() {
+        writer.write_event(Event::End(ancestor.to_end()))?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+fn parse_entry(kind: Kind, document: &[u8]) -> Result<Option<Entry>> {
+    let entry = match kind {
+        Kind::Atom => atom_syndication::Feed::read_from(document)?
+            .entries()
+            .first()
+            .map(Entry::from),
+        Kind::Rss => rss::Channel::read_from(document)?
+            .items()
+            .first()
+            .map(Entry::from),
+    };
+
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(document: &str) -> (Channel, Vec<Vec<String>>) {
+        let mut batches = vec![];
+        let channel = parse(document.as_bytes(), |batch| {
+            batches.push(batch.into_iter().flat_map(|entry| entry.title).collect());
+            Ok(())
+        })
+        .unwrap();
+
+        (channel, batches)
+    }
+
+    #[test]
+    fn it_parses_entries_a_batch_at_a_time() {
+        let items = (0..BATCH_SIZE + 1)
+            .map(|i| {
+                format!(
+                    "<item><title>{}</title><dc:creator>someone</dc:creator></item>",
+                    i
+                )
+            })
+            .collect::<String>();
+        let document = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <channel>
+                <title>Test</title>
+                {}
+                <ttl>60</ttl>
+              </channel>
+            </rss>"#,
+            items
+        );
+
+        let (channel, batches) = titles(&document);
+        assert_eq!(
+            batches.iter().map(|batch| batch.len()).collect::<Vec<_>>(),
+            vec![BATCH_SIZE, 1]
+        );
+        assert_eq!(batches[1], vec![BATCH_SIZE.to_string()]);
+
+        match channel {
+            Channel::Rss(channel) => {
+                assert_eq!(channel.title(), "Test");
+                assert_eq!(channel.ttl(), Some("60"));
+                assert!(channel.items().is_empty());
+            }
+            Channel::Atom(_) => panic!("expected RSS"),
+        }
+    }
+
+    #[test]
+    fn it_parses_atom_and_rss_1_0() {
+        let (channel, batches) = titles(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Atom</title>
+              <entry><title type="html">a &amp; b</title></entry>
+              <entry><title>c</title></entry>
+            </feed>"#,
+        );
+        assert!(matches!(&channel, Channel::Atom(feed) if feed.title().as_str() == "Atom"));
+        assert_eq!(channel.guid(), None);
+        assert_eq!(batches, vec![vec!["a & b", "c"]]);
+
+        let mut entries = vec![];
+        parse(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Atom</title>
+              <updated>sometime</updated>
+              <entry><title>a</title><published>Tue, 01 Jan 2024 10:00:00 CEST</published></entry>
+              <entry><title>b</title><updated>2024-01-02T10:00:00Z</updated></entry>
+              <entry><title>c</title><published>never</published></entry>
+            </feed>"#
+                .as_bytes(),
+            |batch| {
+                entries.extend(batch);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.pub_date.map(|date| date.to_rfc3339()))
+                .collect::<Vec<_>>(),
+            vec![
+                Some("2024-01-01T08:00:00+00:00".to_string()),
+                Some("2024-01-02T10:00:00+00:00".to_string()),
+                None
+            ]
+        );
+
+        let (_, batches) = titles(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/">
+              <channel><title>RDF</title></channel>
+              <item><title>d</title></item>
+            </rdf:RDF>"#,
+        );
+        assert_eq!(batches, vec![vec!["d"]]);
+
+        let (channel, _) = titles(
+            r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+              <channel>
+                <title>RSS</title>
+                <description> An RSS feed </description>
+                <atom:link href="https://example.com/feed.xml" rel="self" type="application/rss+xml"/>
+              </channel>
+            </rss>"#,
+        );
+        assert_eq!(
+            channel.guid().as_deref(),
+            Some("https://example.com/feed.xml")
+        );
+        assert_eq!(channel.description().as_deref(), Some("An RSS feed"));
+
+        assert!(parse("<html><body></body></html>".as_bytes(), |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Running shell commands with russ's data on their stdin,
+//! so entries can go anywhere without russ knowing about each destination.
+
+use anyhow::{anyhow, Result};
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// how often a command with a timeout is checked on
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// the open entry, on its way to a shell command
+#[derive(Clone, Debug)]
+pub struct Pipe {
+    pub command: String,
+    /// the entry's text, as it is shown
+    pub text: String,
+    pub title: Option<String>,
+    pub link: Option<String>,
+}
+
+impl Pipe {
+    /// Run the command with the entry's text on stdin,
+    /// and its title and link in `RUSS_ENTRY_TITLE` and `RUSS_ENTRY_LINK`.
+    /// Returns what the command printed to stdout.
+    pub fn run(&self) -> Result<String> {
+        let mut command = shell_command(&self.command);
+        command
+            .env(
+                "RUSS_ENTRY_TITLE",
+                self.title.as_deref().unwrap_or_default(),
+            )
+            .env("RUSS_ENTRY_LINK", self.link.as_deref().unwrap_or_default());
+
+        run_with_stdin(command, self.text.as_bytes())
+    }
+}
+
+/// `command` run by the platform's shell, so pipes, quoting, and `$VARS` work
+pub fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Output is captured rather than shown, as it would draw over the UI.
+/// A failing command's stderr becomes the error.
+pub fn run_with_stdin(command: Command, stdin: &[u8]) -> Result<String> {
+    run(command, stdin, None)
+}
+
+/// Like `run_with_stdin`, but the command, and everything it started, is killed
+/// if it hasn't finished in `timeout`, so one that hangs can't hang russ with it.
+pub fn run_with_timeout(command: Command, stdin: &[u8], timeout: Duration) -> Result<String> {
+    run(command, stdin, Some(timeout))
+}
+
+fn run(mut command: Command, stdin: &[u8], timeout: Option<Duration>) -> Result<String> {
+    // so the whole group can be killed when it times out, not just the shell
+    #[cfg(unix)]
+    if timeout.is_some() {
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut child_stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Unable to get stdin handle for command"))?;
+
+    // write from another thread, so a command that prints a lot
+    // before reading all of its input doesn't deadlock
+    let stdin = stdin.to_owned();
+    let writer = std::thread::spawn(move || match child_stdin.write_all(&stdin) {
+        // plenty of commands never read their input
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    });
+
+    // and read from others, so the command isn't stuck on a full pipe while it's waited for
+    let stdout = child.stdout.take().map(read_on_thread);
+    let stderr = child.stderr.take().map(read_on_thread);
+
+    let status = match timeout {
+        Some(timeout) => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    kill_group(&mut child)?;
+                    return Err(anyhow!("Command timed out after {:?}", timeout));
+                }
+                std::thread::sleep(TIMEOUT_POLL_INTERVAL);
+            }
+        }
+        None => child.wait()?,
+    };
+
+    writer
+        .join()
+        .map_err(|_| anyhow!("Unable to write to the command's stdin"))??;
+    let stdout = join_output(stdout)?;
+    let stderr = join_output(stderr)?;
+
+    if status.success() {
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let stderr = stderr.trim();
+
+        if stderr.is_empty() {
+            Err(anyhow!("Command failed with {}", status))
+        } else {
+            Err(anyhow!("Command failed with {}: {}", status, stderr))
+        }
+    }
+}
+
+fn read_on_thread(mut output: impl Read + Send + 'static) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut bytes = vec![];
+        output.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    })
+}
+
+fn join_output(output: Option<JoinHandle<std::io::Result<Vec<u8>>>>) -> Result<Vec<u8>> {
+    match output {
+        Some(output) => Ok(output
+            .join()
+            .map_err(|_| anyhow!("Unable to read the command's output"))??),
+        None => Ok(vec![]),
+    }
+}
+
+/// kill the command and everything it started, which are in its group
+fn kill_group(child: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    {
+        // the command leads its group, so the group has its id
+        let group = nix::unistd::Pid::from_raw(child.id() as i32);
+        match nix::sys::signal::killpg(group, nix::sys::signal::Signal::SIGKILL) {
+            // everything in it has already exited
+            Ok(()) | Err(nix::errno::Errno::ESRCH) => (),
+            Err(e) => return Err(anyhow!("Unable to kill the command: {}", e)),
+        }
+    }
+    #[cfg(not(unix))]
+    child.kill()?;
+
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_pipes_text_and_link() {
+        let pipe = Pipe {
+            command: "tr a-z A-Z; echo \"$RUSS_ENTRY_LINK\"".to_string(),
+            text: "some text\n".to_string(),
+            title: None,
+            link: Some("https://example.com".to_string()),
+        };
+
+        assert_eq!(pipe.run().unwrap(), "SOME TEXT\nhttps://example.com\n");
+    }
+
+    #[test]
+    fn it_fails_with_stderr() {
+        let e = run_with_stdin(shell_command("echo oops >&2; exit 3"), b"").unwrap_err();
+        assert!(e.to_string().ends_with(": oops"));
+    }
+
+    #[test]
+    fn it_kills_commands_that_take_too_long() {
+        let marker = std::env::temp_dir().join(format!("russ-pipe-{}", std::process::id()));
+        let command = format!("(sleep 1; touch {}) | cat", marker.display());
+
+        let started = Instant::now();
+        let e =
+            run_with_timeout(shell_command(&command), b"", Duration::from_millis(100)).unwrap_err();
+        assert!(e.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // what it started was killed along with it
+        std::thread::sleep(Duration::from_millis(1200));
+        assert!(!marker.exists());
+
+        assert_eq!(
+            run_with_timeout(shell_command("echo hi"), b"", Duration::from_secs(5)).unwrap(),
+            "hi\n"
+        );
+    }
+}
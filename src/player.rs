@@ -0,0 +1,51 @@
+//! Playing entries' links, like YouTube videos, with a media player rather than the browser.
+
+use crate::config::PlayerConfig;
+use crate::pipe::shell_command;
+use anyhow::{anyhow, Result};
+use std::process::Stdio;
+
+/// Run the player on `link`, which is added to the end of the command, waiting for it to quit.
+/// What the player prints is thrown away, as it would draw over the UI,
+/// except for its stderr, which becomes the error if it fails.
+pub fn play(config: &PlayerConfig, link: &str) -> Result<()> {
+    let command = config.command.as_deref().unwrap_or("mpv");
+    let command = if cfg!(windows) {
+        format!("{} \"%RUSS_ENTRY_LINK%\"", command)
+    } else {
+        format!("{} \"$RUSS_ENTRY_LINK\"", command)
+    };
+
+    let output = shell_command(&command)
+        .env("RUSS_ENTRY_LINK", link)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Player failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_plays_the_link() {
+        let config = PlayerConfig {
+            command: Some("test https://example.com/v =".to_string()),
+        };
+        assert!(play(&config, "https://example.com/v").is_ok());
+
+        let error = play(&config, "https://example.com/other").unwrap_err();
+        assert!(error.to_string().starts_with("Player failed"));
+    }
+}
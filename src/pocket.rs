@@ -0,0 +1,109 @@
+//! Saving entries to Pocket, to read later.
+//!
+//! Pocket's API needs an access token as well as the app's consumer key,
+//! which `russ pocket-login` gets by having the user authorize russ in their browser.
+
+use crate::config::PocketConfig;
+use anyhow::{anyhow, Context, Result};
+use std::io::BufRead;
+
+/// the name the access token is stored under in the keyring
+pub const CREDENTIAL_SERVICE: &str = "pocket";
+
+/// Pocket sends the browser here after authorizing,
+/// but russ checks for itself, so it can be anything
+const REDIRECT_URI: &str = "https://github.com/ckampfe/russ";
+
+pub fn save_url(
+    http_client: &ureq::Agent,
+    config: &PocketConfig,
+    access_token: &str,
+    url: &str,
+) -> Result<()> {
+    post(
+        http_client,
+        "https://getpocket.com/v3/add",
+        serde_json::json!({
+            "url": url,
+            "consumer_key": config.consumer_key,
+            "access_token": access_token,
+        }),
+    )
+    .context("Unable to save to Pocket")?;
+
+    Ok(())
+}
+
+/// Pocket's OAuth flow, on the command line,
+/// storing the access token in the keyring
+pub fn login(
+    http_client: &ureq::Agent,
+    config: &PocketConfig,
+    keyring: &crate::keyring::Keyring,
+) -> Result<()> {
+    let request_token = post(
+        http_client,
+        "https://getpocket.com/v3/oauth/request",
+        serde_json::json!({
+            "consumer_key": config.consumer_key,
+            "redirect_uri": REDIRECT_URI,
+        }),
+    )
+    .context("Unable to start logging in to Pocket")?["code"]
+        .as_str()
+        .map(|code| code.to_owned())
+        .ok_or_else(|| anyhow!("Pocket did not return a request token"))?;
+
+    let authorize_url = url::Url::parse_with_params(
+        "https://getpocket.com/auth/authorize",
+        [
+            ("request_token", request_token.as_str()),
+            ("redirect_uri", REDIRECT_URI),
+        ],
+    )?;
+
+    println!("Authorize russ in your browser at:\n\n{}\n", authorize_url);
+    // it's fine if there's no browser, the url is right there
+    let _ = webbrowser::open(authorize_url.as_str());
+    println!("then press Enter to finish logging in");
+
+    std::io::stdin().lock().read_line(&mut String::new())?;
+
+    let authorization = post(
+        http_client,
+        "https://getpocket.com/v3/oauth/authorize",
+        serde_json::json!({
+            "consumer_key": config.consumer_key,
+            "code": request_token,
+        }),
+    )
+    .context("Unable to finish logging in to Pocket, was russ authorized?")?;
+
+    let access_token = authorization["access_token"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Pocket did not return an access token"))?;
+
+    keyring.set(CREDENTIAL_SERVICE, access_token)?;
+
+    println!(
+        "Logged in to Pocket as {}",
+        authorization["username"].as_str().unwrap_or("you")
+    );
+
+    Ok(())
+}
+
+/// Pocket takes and returns JSON, but only returns it when asked
+fn post(
+    http_client: &ureq::Agent,
+    url: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let resp = http_client
+        .post(url)
+        .set("Content-Type", "application/json; charset=UTF-8")
+        .set("X-Accept", "application/json")
+        .send_string(&body.to_string())?;
+
+    Ok(serde_json::from_reader(resp.into_reader())?)
+}
@@ -0,0 +1,149 @@
+//! A small readability-style article extractor.
+//!
+//! This is a simplified version of the Arc90 readability algorithm:
+//! every paragraph-ish element awards points to its parent and grandparent
+//! based on how much prose it contains, those points are adjusted by the
+//! `class` and `id` of the candidate, and the highest scoring element is
+//! taken to be the article body.
+
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+
+const NEGATIVE_HINTS: &[&str] = &[
+    "ad-", "banner", "combx", "comment", "footer", "footnote", "header", "menu", "meta", "nav",
+    "related", "share", "shoutbox", "sidebar", "social", "sponsor", "widget",
+];
+
+/// paragraphs shorter than this are too short to say anything about
+/// whether their parent is the article body
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Extract the HTML of the main article body from a full HTML document,
+/// returning `None` if nothing in the document looks like an article.
+pub fn extract(document: &str) -> Option<String> {
+    let html = Html::parse_document(document);
+    let paragraphs = Selector::parse("p, pre, td").expect("static selector is valid");
+
+    let mut scores = HashMap::new();
+
+    for paragraph in html.select(&paragraphs) {
+        let text = paragraph.text().collect::<String>();
+        let text = text.trim();
+
+        if text.len() < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+
+        let parent = paragraph.parent().and_then(ElementRef::wrap);
+        let grandparent = parent
+            .and_then(|parent| parent.parent())
+            .and_then(ElementRef::wrap);
+
+        if let Some(parent) = parent {
+            *scores
+                .entry(parent.id())
+                .or_insert_with(|| class_weight(&parent)) += score;
+        }
+
+        if let Some(grandparent) = grandparent {
+            *scores
+                .entry(grandparent.id())
+                .or_insert_with(|| class_weight(&grandparent)) += score / 2.0;
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = html.tree.get(id).and_then(ElementRef::wrap)?;
+            // penalize candidates that are mostly links, like navigation lists
+            Some((element, score * (1.0 - link_density(&element))))
+        })
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(element, _)| element.inner_html())
+}
+
+fn class_weight(element: &ElementRef) -> f64 {
+    let mut weight = 0.0;
+
+    for hint_source in [element.value().attr("class"), element.value().id()]
+        .into_iter()
+        .flatten()
+    {
+        let hint_source = hint_source.to_lowercase();
+
+        if POSITIVE_HINTS.iter().any(|hint| hint_source.contains(hint)) {
+            weight += 25.0;
+        }
+
+        if NEGATIVE_HINTS.iter().any(|hint| hint_source.contains(hint)) {
+            weight -= 25.0;
+        }
+    }
+
+    match element.value().name() {
+        "article" | "main" => weight + 10.0,
+        "div" => weight + 5.0,
+        "blockquote" | "pre" | "td" => weight + 3.0,
+        "form" | "li" | "ol" | "ul" => weight - 3.0,
+        _ => weight,
+    }
+}
+
+fn link_density(element: &ElementRef) -> f64 {
+    let links = Selector::parse("a").expect("static selector is valid");
+
+    let text_len = element.text().map(|t| t.len()).sum::<usize>();
+
+    if text_len == 0 {
+        return 0.0;
+    }
+
+    let link_text_len = element
+        .select(&links)
+        .flat_map(|link| link.text())
+        .map(|t| t.len())
+        .sum::<usize>();
+
+    link_text_len as f64 / text_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_extracts_the_article_body() {
+        let document = r#"
+        <html>
+          <body>
+            <nav class="nav"><a href="/">Home</a> <a href="/about">About</a></nav>
+            <div class="sidebar"><p>Subscribe to the newsletter, it's great, really.</p></div>
+            <div class="post-content">
+              <p>This is the first paragraph of the post, and it has a few commas, like this.</p>
+              <p>This is the second paragraph of the post, which is also long enough to count.</p>
+            </div>
+            <footer><p>Copyright whoever, all rights reserved, forever and ever.</p></footer>
+          </body>
+        </html>
+        "#;
+
+        let extracted = extract(document).unwrap();
+
+        assert!(extracted.contains("first paragraph"));
+        assert!(extracted.contains("second paragraph"));
+        assert!(!extracted.contains("newsletter"));
+        assert!(!extracted.contains("Copyright"));
+    }
+
+    #[test]
+    fn it_returns_none_when_there_is_no_prose() {
+        assert!(extract("<html><body><a href=\"/\">Home</a></body></html>").is_none());
+    }
+}
@@ -0,0 +1,431 @@
+//! Looking after the database: backing it up once a day, and checking it when russ starts,
+//! so that if it's corrupted it can be restored from a backup, or salvaged,
+//! rather than russ failing with whatever SQLite said.
+//!
+//! The backups go in a `backups` directory beside the database,
+//! named for the database and the day, like `russ-2024-01-01.db`.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+
+/// What's wrong with the database, if anything,
+/// from SQLite's `PRAGMA quick_check`, or from not being able to open it at all.
+pub fn check(database_path: &Path, passphrase: Option<&str>) -> Result<Option<String>> {
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    let quick_check = crate::rss::open_db(database_path, passphrase).and_then(|conn| {
+        let mut statement = conn.prepare("PRAGMA quick_check")?;
+        let problems = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(problems)
+    });
+
+    match quick_check {
+        Ok(problems) if problems == ["ok"] => Ok(None),
+        Ok(problems) => Ok(Some(problems.join("\n"))),
+        Err(e) if has_error_code(&e, rusqlite::ErrorCode::DatabaseCorrupt) => {
+            Ok(Some(format!("{:#}", e)))
+        }
+        // it's what SQLCipher says when the passphrase is wrong, too,
+        // and setting aside a healthy database for that would be the wrong fix
+        Err(e) if has_error_code(&e, rusqlite::ErrorCode::NotADatabase) => Err(match passphrase {
+            Some(_) => e.context(format!(
+                "Unable to read {}, is the passphrase right?",
+                database_path.display()
+            )),
+            None => e.context(format!(
+                "{} isn't a database, or it's encrypted and needs its passphrase",
+                database_path.display()
+            )),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+fn has_error_code(e: &anyhow::Error, code: rusqlite::ErrorCode) -> bool {
+    e.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(error, _)) if error.code == code
+        )
+    })
+}
+
+fn backups_dir(database_path: &Path) -> PathBuf {
+    database_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("backups")
+}
+
+fn database_name(database_path: &Path) -> String {
+    database_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "russ".to_string())
+}
+
+/// the database's backups, oldest first
+fn backups(database_path: &Path) -> Result<Vec<PathBuf>> {
+    let prefix = format!("{}-", database_name(database_path));
+
+    let mut backups = match std::fs::read_dir(backups_dir(database_path)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix(&prefix)?.strip_suffix(".db"))
+                    .map(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e.into()),
+    };
+
+    // the dates sort the same as the names
+    backups.sort();
+    Ok(backups)
+}
+
+pub fn latest_backup(database_path: &Path) -> Result<Option<PathBuf>> {
+    Ok(backups(database_path)?.pop())
+}
+
+/// Back the database up, unless it's already been backed up today,
+/// keeping only the latest `keep` backups. Nothing is backed up if `keep` is 0.
+pub fn back_up_daily(database_path: &Path, passphrase: Option<&str>, keep: usize) -> Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    let backups_dir = backups_dir(database_path);
+    let backup_path = backups_dir.join(format!(
+        "{}-{}.db",
+        database_name(database_path),
+        Utc::now().format("%Y-%m-%d")
+    ));
+
+    if !backup_path.exists() {
+        std::fs::create_dir_all(&backups_dir)?;
+
+        // so a backup that was cut short isn't mistaken for a whole one
+        let partial_path = backup_path.with_extension("db.partial");
+        let _ = std::fs::remove_file(&partial_path);
+
+        let conn = crate::rss::open_db(database_path, passphrase)?;
+        crate::rss::backup_db(&conn, &partial_path, passphrase)?;
+        std::fs::rename(&partial_path, &backup_path)?;
+        log::info!("backed up the database to {}", backup_path.display());
+    }
+
+    let backups = backups(database_path)?;
+    for old_backup in &backups[..backups.len().saturating_sub(keep)] {
+        std::fs::remove_file(old_backup)?;
+        log::info!("removed the old backup {}", old_backup.display());
+    }
+
+    Ok(())
+}
+
+/// Move the corrupted database out of the way, beside itself,
+/// with its write-ahead log, returning where it went.
+fn set_aside(database_path: &Path) -> Result<PathBuf> {
+    let suffix = format!("corrupt-{}", Utc::now().format("%Y%m%d%H%M%S"));
+    let corrupt_path = PathBuf::from(format!("{}.{}", database_path.display(), suffix));
+
+    std::fs::rename(database_path, &corrupt_path)
+        .with_context(|| format!("Unable to move {} out of the way", database_path.display()))?;
+
+    for extension in ["-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{}", database_path.display(), extension));
+        if path.exists() {
+            std::fs::rename(&path, format!("{}{}", corrupt_path.display(), extension))?;
+        }
+    }
+
+    Ok(corrupt_path)
+}
+
+/// Replace the corrupted database with `backup`, returning where the corrupted one went.
+pub fn restore_backup(
+    database_path: &Path,
+    passphrase: Option<&str>,
+    backup: &Path,
+) -> Result<PathBuf> {
+    let corrupt_path = set_aside(database_path)?;
+
+    let mut conn = crate::rss::open_db(database_path, passphrase)?;
+    crate::rss::restore_db(&mut conn, backup, passphrase)?;
+
+    Ok(corrupt_path)
+}
+
+/// how many rowids are copied at a time, so a bad page only loses the rows around it
+const SALVAGE_RANGE: i64 = 256;
+
+pub struct Salvaged {
+    /// where the corrupted database went
+    pub corrupt_path: PathBuf,
+    /// what was salvaged of each table, or why none of it could be
+    pub tables: Vec<(String, Result<SalvagedTable>)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SalvagedTable {
+    pub rows: usize,
+    /// the rows that were on pages that couldn't be read
+    pub unreadable_rows: usize,
+}
+
+/// Replace the corrupted database with a new one,
+/// copying every table's rows into it that can still be read.
+pub fn salvage(database_path: &Path, passphrase: Option<&str>) -> Result<Salvaged> {
+    let corrupt_path = set_aside(database_path)?;
+
+    let mut conn = crate::rss::open_db(database_path, passphrase)?;
+    crate::rss::initialize_db(&mut conn)?;
+
+    match passphrase {
+        Some(passphrase) => conn.execute(
+            "ATTACH DATABASE ?1 AS corrupt KEY ?2",
+            rusqlite::params![corrupt_path.to_string_lossy(), passphrase],
+        )?,
+        None => conn.execute(
+            "ATTACH DATABASE ?1 AS corrupt",
+            [corrupt_path.to_string_lossy()],
+        )?,
+    };
+
+    let tables = conn
+        .prepare(
+            "SELECT name FROM main.sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name",
+        )?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tables = tables
+        .into_iter()
+        .map(|table| {
+            let rows = salvage_table(&conn, &table);
+            (table, rows)
+        })
+        .collect();
+
+    conn.execute("DETACH DATABASE corrupt", [])?;
+
+    Ok(Salvaged {
+        corrupt_path,
+        tables,
+    })
+}
+
+/// Copy the columns the table has in both databases, as they may be from different versions,
+/// a range of rowids at a time, and the rows of a range that can't be read one at a time,
+/// skipping the ones that still can't be.
+fn salvage_table(conn: &rusqlite::Connection, table: &str) -> Result<SalvagedTable> {
+    let columns = |schema: &str| -> Result<Vec<String>> {
+        let columns = conn
+            .prepare(&format!("PRAGMA {}.table_info(\"{}\")", schema, table))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    };
+
+    let corrupt_columns = columns("corrupt")?;
+    let columns = columns("main")?
+        .into_iter()
+        .filter(|column| corrupt_columns.contains(column))
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if columns.is_empty() {
+        return Err(anyhow!("it isn't in the corrupted database"));
+    }
+
+    // separately, so each only reads the pages down to one end of the table
+    let bound = |aggregate: &str| {
+        conn.query_row(
+            &format!("SELECT {}(rowid) FROM corrupt.\"{}\"", aggregate, table),
+            [],
+            |row| row.get::<_, Option<i64>>(0),
+        )
+    };
+    let (first, last) = (bound("MIN")?, bound("MAX")?);
+
+    let mut salvaged = SalvagedTable {
+        rows: 0,
+        unreadable_rows: 0,
+    };
+    let (first, last) = match (first, last) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(salvaged),
+    };
+
+    let mut copy = conn.prepare(&format!(
+        "INSERT OR IGNORE INTO main.\"{table}\" ({columns})
+        SELECT {columns} FROM corrupt.\"{table}\" WHERE rowid BETWEEN ?1 AND ?2",
+        table = table,
+        columns = columns
+    ))?;
+
+    let mut start = first;
+    while start <= last {
+        let end = start.saturating_add(SALVAGE_RANGE - 1).min(last);
+
+        match copy.execute([start, end]) {
+            Ok(rows) => salvaged.rows += rows,
+            Err(_) => {
+                for rowid in start..=end {
+                    match copy.execute([rowid, rowid]) {
+                        Ok(rows) => salvaged.rows += rows,
+                        Err(_) => salvaged.unreadable_rows += 1,
+                    }
+                }
+            }
+        }
+
+        match end.checked_add(1) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+
+    Ok(salvaged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupted_databases_are_restored_or_salvaged() {
+        let dir = std::env::temp_dir().join(format!("russ-recovery-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let database_path = dir.join("russ.db");
+
+        let mut conn = crate::rss::open_db(&database_path, None).unwrap();
+        crate::rss::initialize_db(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO feeds (title, feed_link, feed_kind) VALUES ('a', 'https://a', 'RSS')",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        assert_eq!(check(&database_path, None).unwrap(), None);
+
+        back_up_daily(&database_path, None, 1).unwrap();
+        let backup = latest_backup(&database_path).unwrap().unwrap();
+        std::fs::write(
+            backups_dir(&database_path).join("russ-2000-01-01.db"),
+            "old",
+        )
+        .unwrap();
+        back_up_daily(&database_path, None, 1).unwrap();
+        assert_eq!(backups(&database_path).unwrap(), vec![backup.clone()]);
+
+        // what a wrong passphrase looks like, too, so it isn't taken for corruption
+        std::fs::write(&database_path, "not a database, not anymore").unwrap();
+        assert!(check(&database_path, None).is_err());
+
+        // a page of the feeds table overwritten
+        std::fs::copy(&backup, &database_path).unwrap();
+        let conn = crate::rss::open_db(&database_path, None).unwrap();
+        let feeds_page = conn
+            .query_row(
+                "SELECT rootpage FROM sqlite_master WHERE name = 'feeds'",
+                [],
+                |row| row.get::<_, u64>(0),
+            )
+            .unwrap();
+        let page_size = conn
+            .query_row("PRAGMA page_size", [], |row| row.get::<_, u64>(0))
+            .unwrap();
+        drop(conn);
+        let mut database = std::fs::read(&database_path).unwrap();
+        let page = ((feeds_page - 1) * page_size) as usize;
+        database[page..page + page_size as usize].fill(0xff);
+        std::fs::write(&database_path, database).unwrap();
+        assert!(check(&database_path, None).unwrap().is_some());
+
+        let corrupt_path = restore_backup(&database_path, None, &backup).unwrap();
+        assert!(corrupt_path.exists());
+        assert_eq!(check(&database_path, None).unwrap(), None);
+        let conn = crate::rss::open_db(&database_path, None).unwrap();
+        assert_eq!(crate::rss::get_feeds(&conn).unwrap().len(), 1);
+        drop(conn);
+
+        // a database that can be read, but is corrupted, is salvaged a table at a time
+        std::fs::copy(&backup, &database_path).unwrap();
+        let salvaged = salvage(&database_path, None).unwrap();
+        assert!(salvaged
+            .tables
+            .iter()
+            .any(|(table, salvaged)| table == "feeds"
+                && salvaged.as_ref().ok()
+                    == Some(&SalvagedTable {
+                        rows: 1,
+                        unreadable_rows: 0
+                    })));
+        let conn = crate::rss::open_db(&database_path, None).unwrap();
+        assert_eq!(crate::rss::get_feeds(&conn).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn salvaging_skips_the_rows_that_cant_be_read() {
+        let dir = std::env::temp_dir().join(format!("russ-salvage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let database_path = dir.join("russ.db");
+
+        let mut conn = crate::rss::open_db(&database_path, None).unwrap();
+        crate::rss::initialize_db(&mut conn).unwrap();
+        for i in 0..500 {
+            conn.execute(
+                "INSERT INTO feeds (title, feed_link, feed_kind) VALUES (?1, ?2, 'RSS')",
+                [format!("{:0>100}", i), format!("https://{}", i)],
+            )
+            .unwrap();
+        }
+        let (leaf_page, page_size) = conn
+            .query_row(
+                "SELECT pageno, pgsize FROM dbstat
+                WHERE name = 'feeds' AND pagetype = 'leaf'
+                ORDER BY pageno LIMIT 1 OFFSET 3",
+                [],
+                |row| Ok((row.get::<_, usize>(0)?, row.get::<_, usize>(1)?)),
+            )
+            .unwrap();
+        drop(conn);
+
+        let mut database = std::fs::read(&database_path).unwrap();
+        let page = (leaf_page - 1) * page_size;
+        database[page..page + page_size].fill(0xff);
+        std::fs::write(&database_path, database).unwrap();
+
+        let salvaged = salvage(&database_path, None).unwrap();
+        let feeds = salvaged
+            .tables
+            .into_iter()
+            .find(|(table, _)| table == "feeds")
+            .unwrap()
+            .1
+            .unwrap();
+        assert!(feeds.unreadable_rows > 0);
+        assert_eq!(feeds.rows + feeds.unreadable_rows, 500);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+//! One russ at a time for each database. The first one to start locks it, and listens
+//! on a socket beside it, so that any others, like `russ add` run from a browser or a keybinding,
+//! hand their commands over to it rather than fighting over the database.
+//! The socket is only on unix; elsewhere, the others just can't start.
+//!
+//! Requests are a line of text, like `add-url <url>`, and `russ ctl` sends them from the shell,
+//! for window manager keybindings and browser extensions. The running russ answers with
+//! `ok` or `error`, and what it had to say about it.
+
+use anyhow::{anyhow, Result};
+use std::fmt::Display;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// a command for the russ that's running
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    /// subscribe to a feed, by its url, and the fingerprint to pin it to, if any,
+    /// answered once it's subscribed
+    Add(String),
+    /// refresh the feeds that are due, like `x`
+    Refresh,
+    /// how many unread entries there are, in every feed
+    UnreadCount,
+    /// select a feed, by its title
+    OpenFeed(String),
+}
+
+impl FromStr for Request {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, argument) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((s.trim(), ""));
+
+        match (name, argument) {
+            ("add-url", "") => Err(anyhow!("add-url needs a url")),
+            ("add-url", input) => Ok(Request::Add(input.to_owned())),
+            ("refresh", "") => Ok(Request::Refresh),
+            ("unread-count", "") => Ok(Request::UnreadCount),
+            ("open-feed", "") => Err(anyhow!("open-feed needs a feed's title")),
+            ("open-feed", title) => Ok(Request::OpenFeed(title.to_owned())),
+            ("refresh" | "unread-count", _) => Err(anyhow!("{} doesn't take anything", name)),
+            _ => Err(anyhow!("{:?} is not a request russ knows", s.trim())),
+        }
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Request::Add(input) => write!(f, "add-url {}", input),
+            Request::Refresh => write!(f, "refresh"),
+            Request::UnreadCount => write!(f, "unread-count"),
+            Request::OpenFeed(title) => write!(f, "open-feed {}", title),
+        }
+    }
+}
+
+fn lock_path(database_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.lock", database_path.display()))
+}
+
+fn socket_path(database_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.sock", database_path.display()))
+}
+
+/// answers other russes' requests, for whichever database is open
+pub type Handler = Arc<dyn Fn(Request) -> Result<String> + Send + Sync>;
+
+/// held by the russ that's running, for as long as it runs with the database
+pub struct InstanceLock {
+    _file: File,
+    socket_path: PathBuf,
+    /// what requests are answered with, once it's listening
+    handle: Option<Handler>,
+    /// tells the thread listening on the socket to stop, once it's woken up
+    stop_listening: Option<Arc<AtomicBool>>,
+}
+
+impl std::fmt::Debug for InstanceLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstanceLock")
+            .field("socket_path", &self.socket_path)
+            .field("listening", &self.handle.is_some())
+            .finish()
+    }
+}
+
+/// Lock the database for this russ, or `None` if another one already has.
+/// The lock goes with the process, so one that crashes doesn't leave it locked.
+pub fn lock(database_path: &Path) -> Result<Option<InstanceLock>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(database_path))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(InstanceLock {
+            _file: file,
+            socket_path: socket_path(database_path),
+            handle: None,
+            stop_listening: None,
+        })),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+impl InstanceLock {
+    /// Answer other russes' requests with `handle`, on a thread of its own,
+    /// until the lock is dropped.
+    #[cfg(unix)]
+    pub fn listen(&mut self, handle: Handler) -> Result<()> {
+        // with the lock, no other russ can be listening, so it's left over from one that crashed
+        let _ = std::fs::remove_file(&self.socket_path);
+        let listener = std::os::unix::net::UnixListener::bind(&self.socket_path)?;
+
+        let stop_listening = Arc::new(AtomicBool::new(false));
+        self.stop_listening = Some(stop_listening.clone());
+        self.handle = Some(handle.clone());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_listening.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let answered = stream
+                    .map_err(anyhow::Error::from)
+                    .and_then(|stream| answer(&stream, handle.as_ref()));
+
+                if let Err(e) = answered {
+                    log::warn!("unable to answer a request: {:?}", e);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen(&mut self, handle: Handler) -> Result<()> {
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Lock `database_path` too, listening beside it the way this lock is, for switching to it.
+    /// `None` if another russ has it. This lock is let go of when it's dropped.
+    pub fn move_to(&self, database_path: &Path) -> Result<Option<InstanceLock>> {
+        let mut instance_lock = match lock(database_path)? {
+            Some(instance_lock) => instance_lock,
+            None => return Ok(None),
+        };
+
+        if let Some(handle) = &self.handle {
+            instance_lock.listen(handle.clone())?;
+        }
+
+        Ok(Some(instance_lock))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // the listening thread is waiting for a connection, so it's sent one to stop it
+        #[cfg(unix)]
+        if let Some(stop_listening) = &self.stop_listening {
+            stop_listening.store(true, Ordering::SeqCst);
+            let _ = std::os::unix::net::UnixStream::connect(&self.socket_path);
+        }
+
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+#[cfg(unix)]
+fn answer(
+    stream: &std::os::unix::net::UnixStream,
+    handle: &dyn Fn(Request) -> Result<String>,
+) -> Result<()> {
+    use std::io::{BufRead, Write};
+
+    let mut line = String::new();
+    std::io::BufReader::new(stream).read_line(&mut line)?;
+
+    let mut stream = stream;
+    match line.parse::<Request>().and_then(handle) {
+        Ok(response) => writeln!(stream, "ok {}", response)?,
+        Err(e) => writeln!(stream, "error {:#}", e)?,
+    }
+
+    Ok(())
+}
+
+/// Hand `request` to the russ running with the database, returning what it had to say.
+#[cfg(unix)]
+pub fn send(database_path: &Path, request: &Request) -> Result<String> {
+    use anyhow::Context;
+    use std::io::{Read, Write};
+
+    let mut stream = std::os::unix::net::UnixStream::connect(socket_path(database_path))
+        .context("Russ is already running, but it isn't listening for requests")?;
+    writeln!(stream, "{}", request)?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    match response.trim_end().split_once(' ') {
+        Some(("ok", response)) => Ok(response.to_owned()),
+        Some(("error", e)) => Err(anyhow!("{}", e)),
+        _ if response.trim_end() == "ok" => Ok(String::new()),
+        _ => Err(anyhow!("Russ answered with {:?}", response)),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn send(_database_path: &Path, _request: &Request) -> Result<String> {
+    Err(anyhow!(
+        "Russ is already running, and can only be sent requests on unix"
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_handed_to_the_running_russ() {
+        let database_path =
+            std::env::temp_dir().join(format!("russ-remote-{}.db", std::process::id()));
+
+        let mut instance_lock = lock(&database_path).unwrap().unwrap();
+        assert!(lock(&database_path).unwrap().is_none());
+
+        instance_lock
+            .listen(Arc::new(|request| match request {
+                Request::Add(input) if input.starts_with("https://") => {
+                    Ok(format!("Subscribing to {}", input))
+                }
+                Request::Add(_) => Err(anyhow!("not a url")),
+                Request::UnreadCount => Ok("3".to_string()),
+                _ => Err(anyhow!("not now")),
+            }))
+            .unwrap();
+
+        assert_eq!(
+            send(&database_path, &Request::Add("https://a".to_string())).unwrap(),
+            "Subscribing to https://a"
+        );
+        assert!(send(&database_path, &Request::Add("a".to_string())).is_err());
+        assert_eq!(send(&database_path, &Request::UnreadCount).unwrap(), "3");
+        assert!("add-url".parse::<Request>().is_err());
+        assert!("refresh now".parse::<Request>().is_err());
+        assert!("remove https://a".parse::<Request>().is_err());
+        assert_eq!(
+            " open-feed  A Blog ".parse::<Request>().unwrap(),
+            Request::OpenFeed("A Blog".to_string())
+        );
+        for request in [Request::Add("https://a b".to_string()), Request::Refresh] {
+            assert_eq!(request.to_string().parse::<Request>().unwrap(), request);
+        }
+
+        drop(instance_lock);
+        assert!(!socket_path(&database_path).exists());
+        assert!(send(&database_path, &Request::UnreadCount).is_err());
+        assert!(lock(&database_path).unwrap().is_some());
+        std::fs::remove_file(lock_path(&database_path)).unwrap();
+    }
+
+    #[test]
+    fn listening_moves_with_the_lock() {
+        let database_path = |name: &str| {
+            std::env::temp_dir().join(format!("russ-remote-{}-{}.db", name, std::process::id()))
+        };
+        let mut first_lock = lock(&database_path("first")).unwrap().unwrap();
+        first_lock
+            .listen(Arc::new(|_| Ok("3".to_string())))
+            .unwrap();
+
+        // like switching profiles
+        let second_lock = first_lock
+            .move_to(&database_path("second"))
+            .unwrap()
+            .unwrap();
+        assert!(first_lock
+            .move_to(&database_path("second"))
+            .unwrap()
+            .is_none());
+        drop(first_lock);
+
+        assert!(send(&database_path("first"), &Request::UnreadCount).is_err());
+        assert_eq!(
+            send(&database_path("second"), &Request::UnreadCount).unwrap(),
+            "3"
+        );
+        assert!(lock(&database_path("first")).unwrap().is_some());
+        assert!(lock(&database_path("second")).unwrap().is_none());
+
+        drop(second_lock);
+        for name in ["first", "second"] {
+            std::fs::remove_file(lock_path(&database_path(name))).unwrap();
+        }
+    }
+}
@@ -1,21 +1,66 @@
+use crate::http::Fingerprint;
 use crate::modes::ReadMode;
 use anyhow::{Context, Result};
 use atom_syndication as atom;
 use chrono::prelude::{DateTime, Utc};
 use rss::Channel;
-use rusqlite::params;
 use rusqlite::types::ToSqlOutput;
-use std::collections::HashSet;
+use rusqlite::{params, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::io::Seek;
 use std::str::FromStr;
 
-type EntryId = i64;
+pub type EntryId = i64;
 pub type FeedId = i64;
 
+impl rusqlite::types::FromSql for ReadMode {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_str()? {
+            "show_read" => Ok(ReadMode::ShowRead),
+            "show_unread" => Ok(ReadMode::ShowUnread),
+            "all" => Ok(ReadMode::All),
+            s => Err(rusqlite::types::FromSqlError::Other(
+                anyhow::anyhow!("{} is not a valid ReadMode", s).into(),
+            )),
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for ReadMode {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        let s = match self {
+            ReadMode::ShowRead => "show_read",
+            ReadMode::ShowUnread => "show_unread",
+            ReadMode::All => "all",
+        };
+        Ok(ToSqlOutput::from(s))
+    }
+}
+
+/// where the user was when they last quit
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub feed_id: Option<FeedId>,
+    pub entry_id: Option<EntryId>,
+    /// whether the entry was open, rather than just selected
+    pub entry_open: bool,
+    pub read_mode: ReadMode,
+    pub entry_scroll_position: u16,
+    /// `None` until the feeds pane has been resized
+    pub feeds_pane_percent: Option<u16>,
+    pub feeds_pane_hidden: bool,
+    pub feed_sort: FeedSort,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum FeedKind {
     Atom,
     Rss,
+    /// a JSON Feed, printed by a command
+    Json,
+    /// a sender's newsletters, from a maildir
+    Mail,
 }
 
 impl rusqlite::types::FromSql for FeedKind {
@@ -40,6 +85,8 @@ impl Display for FeedKind {
         let out = match self {
             FeedKind::Atom => "Atom",
             FeedKind::Rss => "RSS",
+            FeedKind::Json => "JSON",
+            FeedKind::Mail => "Mail",
         };
 
         write!(f, "{}", out)
@@ -53,21 +100,307 @@ impl FromStr for FeedKind {
         match s {
             "Atom" => Ok(FeedKind::Atom),
             "RSS" => Ok(FeedKind::Rss),
+            "JSON" => Ok(FeedKind::Json),
+            "Mail" => Ok(FeedKind::Mail),
             _ => Err(anyhow::anyhow!(format!("{} is not a valid FeedKind", s))),
         }
     }
 }
 
+/// the order a feed's entries are listed in
+#[derive(Clone, Copy, Debug)]
+pub enum EntrySort {
+    PubDateDesc,
+    PubDateAsc,
+    Title,
+    /// most recently read first, for the history, rather than for feeds
+    ReadAtDesc,
+}
+
+impl EntrySort {
+    pub fn next(&self) -> Self {
+        match self {
+            EntrySort::PubDateDesc => EntrySort::PubDateAsc,
+            EntrySort::PubDateAsc => EntrySort::Title,
+            EntrySort::Title | EntrySort::ReadAtDesc => EntrySort::PubDateDesc,
+        }
+    }
+
+    /// a human-readable description, for the UI
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EntrySort::PubDateDesc => "newest first",
+            EntrySort::PubDateAsc => "oldest first",
+            EntrySort::Title => "title",
+            EntrySort::ReadAtDesc => "recently read first",
+        }
+    }
+
+    fn order_by(&self) -> &'static str {
+        // entries without a date, or with one that couldn't be read, go by when they were added,
+        // rather than all going after the rest, as do entries dated after they were added,
+        // rather than staying at the top until then. Inserted at keeps the order stable
+        match self {
+            EntrySort::PubDateDesc => {
+                "\nORDER BY MIN(COALESCE(pub_date, inserted_at), inserted_at) DESC, inserted_at DESC"
+            }
+            EntrySort::PubDateAsc => "\nORDER BY MIN(COALESCE(pub_date, inserted_at), inserted_at) ASC, inserted_at ASC",
+            EntrySort::Title => {
+                "\nORDER BY lower(title) ASC, MIN(COALESCE(pub_date, inserted_at), inserted_at) DESC, inserted_at DESC"
+            }
+            EntrySort::ReadAtDesc => "\nORDER BY read_at DESC, MIN(COALESCE(pub_date, inserted_at), inserted_at) DESC",
+        }
+    }
+}
+
+/// the order the feeds list is in, after the pinned feeds, which always come first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeedSort {
+    /// the order they've been put in with `J` and `K`, then by title
+    Manual,
+    Title,
+    /// the feeds with the newest entries first
+    Recent,
+    /// the feeds with the most unread entries first
+    Unread,
+}
+
+impl FeedSort {
+    pub fn next(&self) -> Self {
+        match self {
+            FeedSort::Manual => FeedSort::Title,
+            FeedSort::Title => FeedSort::Recent,
+            FeedSort::Recent => FeedSort::Unread,
+            FeedSort::Unread => FeedSort::Manual,
+        }
+    }
+
+    /// a human-readable description, for the UI
+    pub fn describe(&self) -> &'static str {
+        match self {
+            FeedSort::Manual => "your order",
+            FeedSort::Title => "title",
+            FeedSort::Recent => "newest entries first",
+            FeedSort::Unread => "most unread first",
+        }
+    }
+
+    fn order_by(&self) -> String {
+        match self {
+            FeedSort::Manual => "pinned DESC, sort_order IS NULL, sort_order,".to_string(),
+            FeedSort::Title => "pinned DESC,".to_string(),
+            FeedSort::Recent => format!(
+                "pinned DESC,
+                (SELECT MAX(MIN(COALESCE(pub_date, inserted_at), inserted_at)) FROM entries
+                WHERE feed_id = feeds.id AND deleted_at IS NULL AND {}) DESC,",
+                NOT_LEFT_OUT
+            ),
+            FeedSort::Unread => format!(
+                "pinned DESC,
+                (SELECT COUNT(*) FROM entries
+                WHERE feed_id = feeds.id AND read_at IS NULL AND deleted_at IS NULL AND {}) DESC,",
+                NOT_LEFT_OUT
+            ),
+        }
+    }
+}
+
+impl rusqlite::types::FromSql for FeedSort {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_str()? {
+            "manual" => Ok(FeedSort::Manual),
+            "title" => Ok(FeedSort::Title),
+            "recent" => Ok(FeedSort::Recent),
+            "unread" => Ok(FeedSort::Unread),
+            s => Err(rusqlite::types::FromSqlError::Other(
+                anyhow::anyhow!("{} is not a valid FeedSort", s).into(),
+            )),
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for FeedSort {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        let s = match self {
+            FeedSort::Manual => "manual",
+            FeedSort::Title => "title",
+            FeedSort::Recent => "recent",
+            FeedSort::Unread => "unread",
+        };
+        Ok(ToSqlOutput::from(s))
+    }
+}
+
+/// the color a rule lists the entries it matches in
+#[derive(Clone, Copy, Debug, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Highlight {
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Magenta,
+}
+
+impl Highlight {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Highlight::Red => "red",
+            Highlight::Yellow => "yellow",
+            Highlight::Green => "green",
+            Highlight::Cyan => "cyan",
+            Highlight::Blue => "blue",
+            Highlight::Magenta => "magenta",
+        }
+    }
+}
+
+impl rusqlite::types::FromSql for Highlight {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_str()? {
+            "red" => Ok(Highlight::Red),
+            "yellow" => Ok(Highlight::Yellow),
+            "green" => Ok(Highlight::Green),
+            "cyan" => Ok(Highlight::Cyan),
+            "blue" => Ok(Highlight::Blue),
+            "magenta" => Ok(Highlight::Magenta),
+            s => Err(rusqlite::types::FromSqlError::Other(
+                anyhow::anyhow!("{} is not a valid Highlight", s).into(),
+            )),
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for Highlight {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl rusqlite::types::FromSql for EntrySort {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        match EntrySort::from_str(s) {
+            Ok(entry_sort) => Ok(entry_sort),
+            Err(e) => Err(rusqlite::types::FromSqlError::Other(e.into())),
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for EntrySort {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        let s = self.to_string();
+        Ok(ToSqlOutput::from(s))
+    }
+}
+
+impl Display for EntrySort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let out = match self {
+            EntrySort::PubDateDesc => "pub_date_desc",
+            EntrySort::PubDateAsc => "pub_date_asc",
+            EntrySort::Title => "title",
+            EntrySort::ReadAtDesc => "read_at_desc",
+        };
+
+        write!(f, "{}", out)
+    }
+}
+
+impl FromStr for EntrySort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pub_date_desc" => Ok(EntrySort::PubDateDesc),
+            "pub_date_asc" => Ok(EntrySort::PubDateAsc),
+            "title" => Ok(EntrySort::Title),
+            "read_at_desc" => Ok(EntrySort::ReadAtDesc),
+            _ => Err(anyhow::anyhow!(format!("{} is not a valid EntrySort", s))),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Feed {
     pub id: FeedId,
+    /// the custom title, if there is one, otherwise the feed's own title
     pub title: Option<String>,
+    /// the title set by the user, which refreshing the feed leaves alone
+    pub custom_title: Option<String>,
     pub feed_link: Option<String>,
     pub link: Option<String>,
     pub feed_kind: FeedKind,
     pub refreshed_at: Option<chrono::DateTime<Utc>>,
     pub inserted_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
+    pub fetch_full_content: bool,
+    pub entry_sort: EntrySort,
+    /// how long to wait between refreshes, for feeds that are refreshed on a schedule
+    pub refresh_interval: Option<chrono::Duration>,
+    /// paused feeds keep their entries, but are left alone when refreshing all feeds
+    pub paused: bool,
+    /// how many times in a row refreshing the feed has failed
+    pub failures: u32,
+    pub last_failed_at: Option<chrono::DateTime<Utc>>,
+    /// why refreshing it failed the last time it did, if it hasn't refreshed since
+    pub last_error: Option<String>,
+    /// until when the feed says it won't have changed, from its `<ttl>`,
+    /// its syndication module update period, or its `Cache-Control: max-age`
+    pub next_refresh_at: Option<chrono::DateTime<Utc>>,
+    /// the SHA-256 of the only certificate accepted from the feed's host, for self-signed ones
+    pub certificate_fingerprint: Option<String>,
+    /// what the feed calls itself, its Atom id, or its RSS self link,
+    /// which stays the same whatever url it's fetched from
+    pub guid: Option<String>,
+    /// pinned feeds are listed before the rest
+    pub pinned: bool,
+}
+
+/// the most a feed can ask to be left alone for, in case it asks for something silly
+const MAX_REFRESH_HINT_DAYS: i64 = 1;
+
+/// how many times in a row a feed can fail before refreshing all feeds starts skipping it
+const BACKOFF_AFTER_FAILURES: u32 = 2;
+
+/// how long a feed is skipped after `failures` failures in a row,
+/// doubling with each failure from 15 minutes, up to a day
+fn backoff(failures: u32) -> Option<chrono::Duration> {
+    if failures < BACKOFF_AFTER_FAILURES {
+        return None;
+    }
+
+    let doublings = (failures - BACKOFF_AFTER_FAILURES).min(16);
+    Some((chrono::Duration::minutes(15) * 2i32.pow(doublings)).min(chrono::Duration::days(1)))
+}
+
+impl Feed {
+    /// Whether refreshing all feeds should refresh this one:
+    /// it isn't paused, and its refresh interval, if it has one, has passed.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        if self.paused {
+            return false;
+        }
+
+        if matches!(self.backoff_until(), Some(backoff_until) if backoff_until > now) {
+            return false;
+        }
+
+        if matches!(self.next_refresh_at, Some(next_refresh_at) if next_refresh_at > now) {
+            return false;
+        }
+
+        match (self.refresh_interval, self.refreshed_at) {
+            (Some(refresh_interval), Some(refreshed_at)) => refreshed_at + refresh_interval <= now,
+            _ => true,
+        }
+    }
+
+    /// when a feed that keeps failing to refresh is tried again by refreshing all feeds
+    pub fn backoff_until(&self) -> Option<DateTime<Utc>> {
+        Some(self.last_failed_at? + backoff(self.failures)?)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -80,11 +413,44 @@ pub struct Entry {
     pub description: Option<String>,
     pub content: Option<String>,
     pub link: Option<String>,
+    /// where the entry is discussed, like its Hacker News thread
+    pub comments_link: Option<String>,
+    /// a podcast's audio, or other media that comes with the entry
+    pub enclosure: Option<String>,
+    /// when the feed says the entry was last changed, if it says
+    pub modified_at: Option<chrono::DateTime<Utc>>,
+    pub categories: Vec<String>,
     pub read_at: Option<chrono::DateTime<Utc>>,
     pub inserted_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+impl Entry {
+    /// the language the entry is in, guessed from its title and text
+    pub fn language(&self) -> Option<&'static str> {
+        language_of(
+            self.title.as_deref(),
+            self.content.as_deref().or(self.description.as_deref()),
+        )
+    }
+
+    /// whether the entry is hidden, for linking to a blocked domain,
+    /// or being in a muted language, like `NOT_LEFT_OUT`
+    fn is_left_out(&self, blocked_domains: &[String], muted_languages: &[String]) -> bool {
+        let is_blocked = match &self.link {
+            Some(link) => crate::links::is_blocked(link, blocked_domains),
+            None => false,
+        };
+
+        is_blocked
+            || (!muted_languages.is_empty()
+                && self
+                    .language()
+                    .map(|language| muted_languages.iter().any(|muted| muted == language))
+                    .unwrap_or(false))
+    }
+}
+
 impl From<&atom::Entry> for Entry {
     fn from(entry: &atom::Entry) -> Self {
         Self {
@@ -92,10 +458,41 @@ impl From<&atom::Entry> for Entry {
             feed_id: -1,
             title: Some(entry.title().to_string()),
             author: entry.authors().get(0).map(|author| author.name.to_owned()),
-            pub_date: entry.published().map(|date| date.with_timezone(&Utc)),
+            // plenty of feeds only say when their entries were updated, which will have to do
+            pub_date: entry
+                .published()
+                .or_else(|| Some(entry.updated()).filter(|updated| updated.timestamp() != 0))
+                .map(|date| date.with_timezone(&Utc)),
             description: None,
             content: entry.content().and_then(|content| content.value.to_owned()),
-            link: entry.links().get(0).map(|link| link.href().to_string()),
+            // the first link might be the comments, so prefer the one that says it's the entry
+            link: entry
+                .links()
+                .iter()
+                .find(|link| link.rel() == "alternate")
+                .or_else(|| entry.links().get(0))
+                .map(|link| crate::links::strip_tracking_params(link.href())),
+            comments_link: entry
+                .links()
+                .iter()
+                .find(|link| link.rel() == "replies")
+                .map(|link| link.href().to_string()),
+            enclosure: entry
+                .links()
+                .iter()
+                .find(|link| link.rel() == "enclosure")
+                .map(|link| link.href().to_string()),
+            modified_at: Some(entry.updated().with_timezone(&Utc)),
+            categories: entry
+                .categories()
+                .iter()
+                .map(|category| {
+                    category
+                        .label()
+                        .unwrap_or_else(|| category.term())
+                        .to_owned()
+                })
+                .collect(),
             read_at: None,
             inserted_at: Utc::now(),
             updated_at: Utc::now(),
@@ -110,12 +507,32 @@ impl From<&rss::Item> for Entry {
             feed_id: -1,
             title: entry.title().map(|title| title.to_owned()),
             author: entry.author().map(|author| author.to_owned()),
-            pub_date: entry.pub_date().and_then(parse_datetime),
+            // Dublin Core's date, for feeds that only have that
+            pub_date: entry
+                .pub_date()
+                .into_iter()
+                .chain(
+                    entry
+                        .dublin_core_ext()
+                        .and_then(|dublin_core| dublin_core.dates().first())
+                        .map(String::as_str),
+                )
+                .find_map(crate::dates::parse),
             description: entry
                 .description()
                 .map(|description| description.to_owned()),
             content: entry.content().map(|content| content.to_owned()),
-            link: entry.link().map(|link| link.to_owned()),
+            link: entry.link().map(crate::links::strip_tracking_params),
+            comments_link: entry.comments().map(|comments| comments.to_owned()),
+            enclosure: entry
+                .enclosure()
+                .map(|enclosure| enclosure.url().to_owned()),
+            modified_at: None,
+            categories: entry
+                .categories()
+                .iter()
+                .map(|category| category.name().to_owned())
+                .collect(),
             read_at: None,
             inserted_at: Utc::now(),
             updated_at: Utc::now(),
@@ -134,9 +551,29 @@ pub struct EntryMeta {
     pub read_at: Option<chrono::DateTime<Utc>>,
     pub inserted_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
+    pub starred_at: Option<chrono::DateTime<Utc>>,
+    pub tags: Vec<String>,
+    /// when the feed says the entry was last changed, if it says
+    pub modified_at: Option<chrono::DateTime<Utc>>,
+    /// the feed's categories for the entry, unlike `tags`, which are the user's
+    pub categories: Vec<String>,
+    /// how many characters long the entry's content is, markup and all
+    pub content_len: usize,
+    pub comments_link: Option<String>,
+    /// what a rule highlighted it with, when it was added
+    pub highlight: Option<Highlight>,
+    /// the language it was guessed to be in, when it was added, as its code
+    pub language: Option<String>,
 }
 
 impl EntryMeta {
+    /// whether the feed dated the entry after it was added, by more than its clock being a little off
+    pub fn is_future_dated(&self) -> bool {
+        self.pub_date
+            .map(|pub_date| pub_date > self.inserted_at + FUTURE_DATE_LEEWAY)
+            .unwrap_or(false)
+    }
+
     pub fn toggle_read(&self, conn: &rusqlite::Connection) -> Result<()> {
         if self.read_at.is_none() {
             self.mark_as_read(conn)
@@ -158,576 +595,3859 @@ impl EntryMeta {
     }
 }
 
+/// how far ahead of ours a feed's clock can be before its dates are bogus
+const FUTURE_DATE_LEEWAY: chrono::Duration = chrono::Duration::hours(1);
+
+#[derive(Debug)]
 pub struct EntryContent {
     pub content: Option<String>,
     pub description: Option<String>,
+    pub full_content: Option<String>,
 }
 
-fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
-    diligent_date_parser::parse_date(s).map(|dt| dt.with_timezone(&Utc))
-}
+impl Feed {
+    /// a feed that isn't in the database yet
+    pub fn new(title: Option<String>, link: Option<String>, feed_kind: FeedKind) -> Feed {
+        Feed {
+            id: 0,
+            title,
+            custom_title: None,
+            feed_link: None,
+            link,
+            feed_kind,
+            refreshed_at: None,
+            inserted_at: Utc::now(),
+            updated_at: Utc::now(),
+            fetch_full_content: false,
+            entry_sort: EntrySort::PubDateDesc,
+            refresh_interval: None,
+            paused: false,
+            failures: 0,
+            last_failed_at: None,
+            last_error: None,
+            next_refresh_at: None,
+            certificate_fingerprint: None,
+            guid: None,
+            pinned: false,
+        }
+    }
 
-struct FeedAndEntries {
-    pub feed: Feed,
-    pub entries: Vec<Entry>,
-}
+    fn from_channel(channel: &crate::parse::Channel) -> Feed {
+        let mut feed = match channel {
+            crate::parse::Channel::Atom(atom_feed) => Feed::new(
+                Some(atom_feed.title.to_string()),
+                atom_feed.links.get(0).map(|link| link.href().to_string()),
+                FeedKind::Atom,
+            ),
+            crate::parse::Channel::Rss(channel) => {
+                let mut feed = Feed::new(
+                    Some(channel.title().to_string()),
+                    Some(channel.link().to_string()),
+                    FeedKind::Rss,
+                );
+                if let Some(hint) = channel_refresh_hint(channel) {
+                    feed.hold_off(hint);
+                }
+                feed
+            }
+        };
+        feed.guid = channel.guid();
+        feed
+    }
 
-impl FeedAndEntries {
-    pub fn set_feed_link(&mut self, url: &str) {
-        self.feed.feed_link = Some(url.to_owned());
+    /// Leave the feed alone for `hint` from now, if that's longer than it already is.
+    fn hold_off(&mut self, hint: chrono::Duration) {
+        let next_refresh_at = Utc::now() + hint.min(chrono::Duration::days(MAX_REFRESH_HINT_DAYS));
+
+        // `None` is less than any `Some`
+        if self.next_refresh_at < Some(next_refresh_at) {
+            self.next_refresh_at = Some(next_refresh_at);
+        }
     }
 }
 
-impl FromStr for FeedAndEntries {
-    type Err = anyhow::Error;
+/// how long an RSS channel says it can be cached for, with `<ttl>` or the syndication module
+fn channel_refresh_hint(channel: &Channel) -> Option<chrono::Duration> {
+    use rss::extension::syndication::UpdatePeriod;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match atom::Feed::from_str(s) {
-            Ok(atom_feed) => {
-                let feed = Feed {
-                    id: 0,
-                    title: Some(atom_feed.title.to_string()),
-                    feed_link: None,
-                    link: atom_feed.links.get(0).map(|link| link.href().to_string()),
-                    feed_kind: FeedKind::Atom,
-                    refreshed_at: None,
-                    inserted_at: Utc::now(),
-                    updated_at: Utc::now(),
-                };
-
-                let entries = atom_feed
-                    .entries()
-                    .iter()
-                    .map(|entry| entry.into())
-                    .collect::<Vec<_>>();
+    let ttl = channel
+        .ttl()
+        .and_then(|ttl| ttl.trim().parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .map(chrono::Duration::minutes);
 
-                Ok(FeedAndEntries { feed, entries })
-            }
+    let update_period = channel.syndication_ext().map(|syndication| {
+        let period = match syndication.period() {
+            UpdatePeriod::Hourly => chrono::Duration::hours(1),
+            UpdatePeriod::Daily => chrono::Duration::days(1),
+            UpdatePeriod::Weekly => chrono::Duration::weeks(1),
+            UpdatePeriod::Monthly => chrono::Duration::days(30),
+            UpdatePeriod::Yearly => chrono::Duration::days(365),
+        };
+        // updating `frequency` times a period
+        period / syndication.frequency().max(1) as i32
+    });
 
-            Err(_e) => match Channel::from_str(s) {
-                Ok(channel) => {
-                    let feed = Feed {
-                        id: 0,
-                        title: Some(channel.title().to_string()),
-                        feed_link: None,
-                        link: Some(channel.link().to_string()),
-                        feed_kind: FeedKind::Rss,
-                        refreshed_at: None,
-                        inserted_at: Utc::now(),
-                        updated_at: Utc::now(),
-                    };
-
-                    let entries = channel
-                        .items()
-                        .iter()
-                        .map(|item| item.into())
-                        .collect::<Vec<_>>();
-
-                    Ok(FeedAndEntries { feed, entries })
-                }
-                Err(e) => Err(e.into()),
-            },
-        }
+    ttl.into_iter().chain(update_period).max()
+}
+
+/// the `max-age` of a `Cache-Control` header, unless it also says not to cache
+fn cache_control_max_age(cache_control: &str) -> Option<chrono::Duration> {
+    let directives = cache_control
+        .split(',')
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+
+    if directives
+        .iter()
+        .any(|directive| directive == "no-cache" || directive == "no-store")
+    {
+        return None;
     }
+
+    directives
+        .iter()
+        .find_map(|directive| directive.strip_prefix("max-age=")?.parse::<i64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(chrono::Duration::seconds)
 }
 
+/// `input` is the feed's url, optionally followed by the fingerprint
+/// of the certificate to pin its host to
 pub fn subscribe_to_feed(
     http_client: &ureq::Agent,
     conn: &mut rusqlite::Connection,
-    url: &str,
+    new_entry_rules: &crate::hooks::NewEntryRules,
+    input: &str,
 ) -> Result<FeedId> {
-    let feed_and_entries: FeedAndEntries = fetch_feed(http_client, url)?;
+    let (url, fingerprint) = subscription_url(http_client, input)?;
+    refuse_duplicate_feed(conn, &url, None, None)?;
+    let subscription_pin = crate::http::SubscriptionPin::new(http_client, &url, fingerprint)?;
+    let source = fetch_source(http_client, &url, &[])?;
+
+    let mut tags = vec![];
+    let mut added = vec![];
     let feed_id = in_transaction(conn, |tx| {
-        let feed_id = create_feed(tx, &feed_and_entries.feed)?;
-        add_entries_to_feed(tx, feed_id, &feed_and_entries.entries)?;
+        let blocked_domains = get_blocked_domains(tx)?;
+        let muted_languages = get_muted_languages(tx)?;
+
+        // the feed's own title and link are only known once it's all been read
+        let mut feed = Feed::new(None, None, FeedKind::Rss);
+        feed.feed_link = Some(url.clone());
+        feed.certificate_fingerprint = fingerprint.map(|fingerprint| fingerprint.to_string());
+        let feed_id = create_feed(tx, &feed)?;
+        feed.id = feed_id;
+
+        let remote_feed =
+            source
+                .read(&mut |mut entries| {
+                    new_entry_rules.transform(&feed, &mut entries, &mut tags);
+                    add_entries_to_feed(tx, feed_id, &entries)?;
+                    // hidden ones aren't new to anyone, like when refreshing
+                    if !new_entry_rules.rules.is_empty() {
+                        added.extend(entries.into_iter().filter(|entry| {
+                            !entry.is_left_out(&blocked_domains, &muted_languages)
+                        }));
+                    }
+                    Ok(())
+                })?
+                .feed;
+        // the feed's own guid is only known now, and it's all rolled back if it's a duplicate
+        refuse_duplicate_feed(tx, &url, remote_feed.guid.as_deref(), Some(feed_id))?;
+        update_remote_feed(tx, feed_id, &remote_feed)?;
         Ok(feed_id)
     })?;
 
+    new_entry_rules.tag(conn, feed_id, &tags);
+    new_entry_rules.apply(conn, feed_id, &added);
+
+    crate::http::pin_certificate(
+        http_client,
+        &url,
+        crate::http::PinnedBy::Feed(feed_id),
+        fingerprint,
+    )?;
+    drop(subscription_pin);
+
     Ok(feed_id)
 }
 
-fn fetch_feed(http_client: &ureq::Agent, url: &str) -> Result<FeedAndEntries> {
-    let resp = http_client.get(url).call()?.into_string()?;
-    let mut feed = FeedAndEntries::from_str(&resp)?;
-    feed.set_feed_link(url);
+/// A feed's url, for telling whether two urls are the same feed's: without its scheme,
+/// so http and https are the same, fragment, default port, or trailing slash,
+/// and with its host lowercased.
+pub fn normalize_feed_url(url: &str) -> String {
+    let url = url.trim();
+    if let Some(url) = crate::sources::normalize_local_url(url) {
+        return url;
+    }
+    let url = url.split_once('#').map(|(url, _)| url).unwrap_or(url);
+
+    let rest = match url.split_once("://") {
+        Some((scheme, rest))
+            if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+        {
+            rest
+        }
+        Some(_) => return url.to_owned(),
+        None => url,
+    };
+
+    let (host, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let host = host.to_lowercase();
+    let host = host
+        .strip_suffix(":80")
+        .or_else(|| host.strip_suffix(":443"))
+        .unwrap_or(&host);
+
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+    let path = path.trim_end_matches('/');
 
-    Ok(feed)
+    match query {
+        Some(query) => format!("{}{}?{}", host, path, query),
+        None => format!("{}{}", host, path),
+    }
 }
 
-/// fetches the feed and stores the new entries
-/// uses the link as the uniqueness key.
-/// TODO hash the content to see if anything changed, and update that way.
-pub fn refresh_feed(
-    client: &ureq::Agent,
-    conn: &mut rusqlite::Connection,
-    feed_id: FeedId,
+/// Err if a feed other than `except` is already subscribed to at `url`,
+/// allowing for the differences `normalize_feed_url` ignores, or has the same `guid`.
+fn refuse_duplicate_feed(
+    conn: &rusqlite::Connection,
+    url: &str,
+    guid: Option<&str>,
+    except: Option<FeedId>,
 ) -> Result<()> {
-    let feed_url = get_feed_url(conn, feed_id).with_context(|| {
-        format!(
-            "Unable to get url for feed id {} from the database",
-            feed_id
-        )
-    })?;
+    let urls = std::iter::once(url)
+        .chain(guid)
+        .map(normalize_feed_url)
+        .collect::<Vec<_>>();
 
-    let remote_feed: FeedAndEntries = fetch_feed(client, &feed_url)
-        .with_context(|| format!("Failed to fetch feed {}", feed_url))?;
+    let duplicate = get_feeds(conn)?.into_iter().find(|feed| {
+        Some(feed.id) != except
+            && (feed
+                .feed_link
+                .as_deref()
+                .map(|feed_link| urls.contains(&normalize_feed_url(feed_link)))
+                .unwrap_or(false)
+                || (guid.is_some() && feed.guid.as_deref() == guid))
+    });
 
-    let remote_items = remote_feed.entries;
-    let remote_items_links = remote_items
-        .iter()
-        .flat_map(|item| &item.link)
-        .cloned()
-        .collect::<HashSet<String>>();
+    match duplicate {
+        Some(feed) => Err(anyhow::anyhow!(
+            "Already subscribed to {}, as {}",
+            url,
+            feed.title
+                .or(feed.feed_link)
+                .unwrap_or_else(|| "a feed with no title".to_string())
+        )),
+        None => Ok(()),
+    }
+}
 
-    let local_entries_links = get_entries_links(conn, &ReadMode::All, feed_id)?
-        .into_iter()
-        .flatten()
-        .collect::<HashSet<_>>();
+/// what was typed to subscribe, without the fingerprint after it, if any
+pub fn subscription_input_url(input: &str) -> &str {
+    match crate::sources::normalize_local_url(input) {
+        Some(_) => input.trim(),
+        None => input.split_whitespace().next().unwrap_or_default(),
+    }
+}
 
-    let difference = remote_items_links
-        .difference(&local_entries_links)
-        .cloned()
-        .collect::<HashSet<_>>();
+/// the url of the feed to subscribe to, for what was typed, a url
+/// and the fingerprint to pin its host's certificate to, if any
+fn subscription_url(
+    http_client: &ureq::Agent,
+    input: &str,
+) -> Result<(String, Option<Fingerprint>)> {
+    // a command or a maildir has no certificate to pin, and can have spaces
+    if let Some(url) = crate::sources::normalize_local_url(input) {
+        return Ok((url, None));
+    }
 
-    let items_to_add = remote_items
-        .into_iter()
-        .filter(|item| match &item.link {
-            Some(link) => difference.contains(link.as_str()),
-            None => false,
-        })
-        .collect::<Vec<_>>();
+    let (url, fingerprint) = match input.trim().split_once(char::is_whitespace) {
+        Some((url, fingerprint)) => (url, Some(fingerprint.parse::<Fingerprint>()?)),
+        None => (input.trim(), None),
+    };
 
-    in_transaction(conn, |tx| {
-        add_entries_to_feed(tx, feed_id, &items_to_add)?;
-        update_feed_refreshed_at(tx, feed_id)?;
-        Ok(())
-    })?;
+    let url = crate::youtube::feed_url(http_client, url)?.unwrap_or_else(|| url.to_owned());
 
-    Ok(())
+    Ok((url, fingerprint))
 }
 
-pub fn initialize_db(conn: &mut rusqlite::Connection) -> Result<()> {
-    in_transaction(conn, |tx| {
-        tx.execute(
-            "CREATE TABLE IF NOT EXISTS feeds (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        title TEXT,
-        feed_link TEXT,
-        link TEXT,
-        feed_kind TEXT,
-        refreshed_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-            [],
-        )?;
+/// how many of its newest entries a feed's preview shows
+pub const PREVIEW_ENTRIES_LEN: usize = 5;
 
-        tx.execute(
-            "CREATE TABLE IF NOT EXISTS entries (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        feed_id INTEGER,
-        title TEXT,
-        author TEXT,
-        pub_date TIMESTAMP,
-        description TEXT,
-        content TEXT,
-        link TEXT,
-        read_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-            [],
-        )?;
+/// what a feed looks like, to decide whether to subscribe to it
+#[derive(Clone, Debug)]
+pub struct FeedPreview {
+    /// what was typed, to subscribe with
+    pub input: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// the newest, newest first
+    pub entries: Vec<Entry>,
+}
 
-        tx.execute(
-            "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index
-        ON entries (feed_id, pub_date, inserted_at)",
-            [],
-        )?;
+/// Fetch the feed that subscribing to `input` would, without adding anything to the database.
+pub fn preview_feed(http_client: &ureq::Agent, input: &str) -> Result<FeedPreview> {
+    let (url, fingerprint) = subscription_url(http_client, input)?;
+    let _pin = crate::http::SubscriptionPin::new(http_client, &url, fingerprint)?;
+    let source = fetch_source(http_client, &url, &[])?;
 
+    let mut entries: Vec<Entry> = vec![];
+    let sourced = source.read(&mut |batch| {
+        entries.extend(batch);
+        // entries without dates go last, staying in the order the feed has them
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.pub_date));
+        entries.truncate(PREVIEW_ENTRIES_LEN);
         Ok(())
+    })?;
+
+    Ok(FeedPreview {
+        input: input.to_owned(),
+        title: sourced.feed.title,
+        description: sourced.description,
+        entries,
     })
 }
 
-fn create_feed(tx: &rusqlite::Transaction, feed: &Feed) -> Result<FeedId> {
-    let feed_id = tx.query_row::<FeedId, _, _>(
-        "INSERT INTO feeds (title, link, feed_link, feed_kind)
-        VALUES (?1, ?2, ?3, ?4)
-        RETURNING id",
-        params![feed.title, feed.link, feed.feed_link, feed.feed_kind],
-        |r| r.get(0),
-    )?;
+/// A feed's response, downloaded to a temporary file rather than kept in memory,
+/// to be parsed from there without holding the database's write lock while it downloads.
+struct FetchedFeed {
+    url: String,
+    body: Download,
+    max_age: Option<chrono::Duration>,
+}
 
-    Ok(feed_id)
+impl crate::sources::FeedSource for FetchedFeed {
+    fn read(
+        &self,
+        on_entries: &mut dyn FnMut(Vec<Entry>) -> Result<()>,
+    ) -> Result<crate::sources::SourcedFeed> {
+        let channel = crate::parse::parse(std::io::BufReader::new(&self.body.file), on_entries)
+            .map_err(|e| {
+                log::warn!("unable to parse feed {}: {:?}", self.url, e);
+                e
+            })?;
+
+        let mut feed = Feed::from_channel(&channel);
+        feed.feed_link = Some(self.url.clone());
+        if let Some(max_age) = self.max_age {
+            feed.hold_off(max_age);
+        }
+
+        Ok(crate::sources::SourcedFeed {
+            feed,
+            description: channel.description(),
+        })
+    }
 }
 
-pub fn delete_feed(conn: &mut rusqlite::Connection, feed_id: FeedId) -> Result<()> {
-    in_transaction(conn, |tx| {
-        tx.execute("DELETE FROM feeds WHERE id = ?1", [feed_id])?;
-        tx.execute("DELETE FROM entries WHERE feed_id = ?1", [feed_id])?;
-        Ok(())
+/// a temporary file, removed when it's dropped, if it hasn't been already
+struct Download {
+    path: std::path::PathBuf,
+    file: std::fs::File,
+}
+
+impl Download {
+    fn new() -> Result<Download> {
+        // feeds are downloaded from several threads at once
+        static DOWNLOADS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let n = DOWNLOADS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        let path = std::env::temp_dir().join(format!("russ-feed-{}-{}.xml", std::process::id(), n));
+        // `create_new`, so there's no writing through a link someone else put there
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        // where it can be, it's removed right away, so it's gone even if russ is killed
+        if cfg!(unix) {
+            std::fs::remove_file(&path)?;
+        }
+
+        Ok(Download { path, file })
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// the feed at `url`, whether it's fetched or local, ready to be read
+fn fetch_source(
+    http_client: &ureq::Agent,
+    url: &str,
+    headers: &[(String, String)],
+) -> Result<Box<dyn crate::sources::FeedSource>> {
+    match crate::sources::local_source(url) {
+        Some(source) => source,
+        None => Ok(Box::new(fetch_feed(http_client, url, headers)?)),
+    }
+}
+
+/// `headers` are sent along with the request, like ones the feed needs to let us in
+fn fetch_feed(
+    http_client: &ureq::Agent,
+    url: &str,
+    headers: &[(String, String)],
+) -> Result<FetchedFeed> {
+    let now = std::time::Instant::now();
+    log::debug!("GET {}", url);
+
+    let request = headers
+        .iter()
+        .fold(http_client.get(url), |request, (name, value)| {
+            request.set(name, value)
+        });
+
+    let resp = request.call().map_err(|e| {
+        log::warn!("GET {} failed: {}", url, e);
+        e
+    })?;
+    let max_age = resp.header("Cache-Control").and_then(cache_control_max_age);
+
+    let mut body = Download::new()?;
+    let len = std::io::copy(&mut resp.into_reader(), &mut body.file)?;
+    body.file.rewind()?;
+
+    log::debug!("GET {} returned {} bytes in {:?}", url, len, now.elapsed());
+
+    Ok(FetchedFeed {
+        url: url.to_owned(),
+        body,
+        max_age,
     })
 }
 
-fn add_entries_to_feed(
+/// keep the feed's title and link, and when it asks to be refreshed, up to date with the feed's own
+fn update_remote_feed(
     tx: &rusqlite::Transaction,
     feed_id: FeedId,
-    entries: &[Entry],
+    remote_feed: &Feed,
 ) -> Result<()> {
-    if !entries.is_empty() {
-        let now = Utc::now();
+    // a custom title is its own column, so this never overwrites it
+    tx.execute(
+        "UPDATE feeds SET title = ?2, link = ?3, feed_kind = ?4, next_refresh_at = ?5, guid = ?6
+        WHERE id = ?1",
+        params![
+            feed_id,
+            remote_feed.title,
+            remote_feed.link,
+            remote_feed.feed_kind,
+            remote_feed.next_refresh_at,
+            remote_feed.guid
+        ],
+    )?;
 
-        let columns = [
-            "feed_id",
-            "title",
-            "author",
-            "pub_date",
-            "description",
-            "content",
-            "link",
-            "updated_at",
-        ];
+    Ok(())
+}
 
-        let mut entries_values = Vec::with_capacity(entries.len() * columns.len());
+/// fetches the feed and stores the new entries, returning them
+/// uses the link as the uniqueness key.
+/// TODO hash the content to see if anything changed, and update that way.
+pub fn refresh_feed(
+    client: &ureq::Agent,
+    conn: &mut rusqlite::Connection,
+    keyring: &crate::keyring::Keyring,
+    new_entry_rules: &crate::hooks::NewEntryRules,
+    feed_id: FeedId,
+) -> Result<Vec<Entry>> {
+    let feed_url = get_feed_url(conn, feed_id).with_context(|| {
+        format!(
+            "Unable to get url for feed id {} from the database",
+            feed_id
+        )
+    })?;
 
-        for entry in entries {
-            let values = params![
-                feed_id,
-                entry.title,
-                entry.author,
-                entry.pub_date,
-                entry.description,
-                entry.content,
-                entry.link,
-                now,
-            ];
-            entries_values.extend_from_slice(values);
+    let headers = get_feed_headers(conn, keyring, feed_id)?;
+    // from the feed's row every time, so its pin follows its url, and goes when its fingerprint does
+    let fingerprint = get_feed_certificate_fingerprint(conn, feed_id)?;
+    if crate::sources::normalize_local_url(&feed_url).is_none() {
+        crate::http::pin_certificate(
+            client,
+            &feed_url,
+            crate::http::PinnedBy::Feed(feed_id),
+            fingerprint,
+        )?;
+    }
+
+    let mut source = match fetch_source(client, &feed_url, &headers) {
+        Ok(source) => source,
+        Err(e) => {
+            record_feed_failure(conn, feed_id, &e)?;
+            return Err(e.context(format!("Failed to fetch feed {}", feed_url)));
         }
+    };
 
-        let query = build_bulk_insert_query("entries", &columns, entries);
+    let feed = get_feed(conn, feed_id)?;
+    let mut tags = vec![];
 
-        tx.execute(&query, entries_values.as_slice())?;
+    let refreshed = in_transaction(conn, |tx| {
+        // entries from before their links were stripped of tracking parameters are the same entries
+        let mut links = get_entries_links(tx, &ReadMode::All, feed_id)?
+            .into_iter()
+            .flatten()
+            .map(|link| crate::links::strip_tracking_params(&link))
+            .collect::<HashSet<_>>();
+        source.known_links(&links);
+        let blocked_domains = get_blocked_domains(tx)?;
+        let muted_languages = get_muted_languages(tx)?;
+
+        let mut items_to_add = vec![];
+        let remote_feed = source
+            .read(&mut |entries| {
+                let mut new_entries = entries
+                    .into_iter()
+                    .filter(|entry| match &entry.link {
+                        Some(link) => links.insert(link.clone()),
+                        None => false,
+                    })
+                    .collect::<Vec<_>>();
+                new_entry_rules.transform(&feed, &mut new_entries, &mut tags);
+
+                // hidden entries are kept, for if they're unblocked or unmuted,
+                // but they aren't new to anyone
+                add_entries_to_feed(tx, feed_id, &new_entries)?;
+                items_to_add.extend(
+                    new_entries
+                        .into_iter()
+                        .filter(|entry| !entry.is_left_out(&blocked_domains, &muted_languages)),
+                );
+                Ok(())
+            })?
+            .feed;
+
+        update_remote_feed(tx, feed_id, &remote_feed)?;
+        update_feed_refreshed_at(tx, feed_id)?;
+        Ok(items_to_add)
+    });
+
+    let items_to_add = match refreshed {
+        Ok(items_to_add) => items_to_add,
+        Err(e) => {
+            record_feed_failure(conn, feed_id, &e)?;
+            return Err(e.context(format!("Failed to fetch feed {}", feed_url)));
+        }
+    };
+
+    new_entry_rules.tag(conn, feed_id, &tags);
+    new_entry_rules.apply(conn, feed_id, &items_to_add);
+
+    log::info!(
+        "refreshed feed {} ({}), {} new entries",
+        feed_id,
+        feed_url,
+        items_to_add.len()
+    );
+
+    if get_feed_fetch_full_content(conn, feed_id)? {
+        fetch_full_content(client, conn, feed_id)?;
     }
 
-    Ok(())
+    Ok(items_to_add)
 }
 
-fn build_bulk_insert_query<C: AsRef<str>, R>(table: &str, columns: &[C], rows: &[R]) -> String {
-    let idxs = (1..(rows.len() * columns.len() + 1)).collect::<Vec<_>>();
+/// for feeds that only provide a summary, download the page each unread entry links to,
+/// extract the article body, and store it so the entry can be read in full offline.
+/// entries whose page cannot be fetched or does not look like an article are skipped,
+/// and will be tried again on the next refresh.
+fn fetch_full_content(
+    client: &ureq::Agent,
+    conn: &mut rusqlite::Connection,
+    feed_id: FeedId,
+) -> Result<()> {
+    let entries_to_fetch = {
+        let mut statement = conn.prepare(&format!(
+            "SELECT id, link FROM entries
+            WHERE feed_id = ?1
+            AND full_content IS NULL
+            AND read_at IS NULL
+            AND deleted_at IS NULL
+            AND {}
+            AND link IS NOT NULL",
+            NOT_LEFT_OUT
+        ))?;
 
-    let values_groups_string = idxs
-        .chunks(columns.len())
-        .map(|chunk| {
-            let values_string = chunk
-                .iter()
-                .map(|i| format!("?{}", i))
-                .collect::<Vec<_>>()
-                .join(", ");
-            ["(", &values_string, ")"].concat()
-        })
-        .collect::<Vec<_>>()
-        .join(", ");
+        let mut entries_to_fetch: Vec<(EntryId, String)> = vec![];
+        for entry in statement.query_map([feed_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            entries_to_fetch.push(entry?);
+        }
+        entries_to_fetch
+    };
 
-    let columns_strs = columns
-        .iter()
-        .map(|column| column.as_ref())
-        .collect::<Vec<&str>>();
+    let full_contents = entries_to_fetch
+        .into_iter()
+        .filter_map(|(entry_id, link)| {
+            log::debug!("GET {}", link);
 
-    let columns_joined = columns_strs.join(", ");
+            let page = match client.get(&link).call().map(|resp| resp.into_string()) {
+                Ok(Ok(page)) => page,
+                Ok(Err(e)) => {
+                    log::warn!("unable to read {}: {}", link, e);
+                    return None;
+                }
+                Err(e) => {
+                    log::warn!("GET {} failed: {}", link, e);
+                    return None;
+                }
+            };
 
-    let mut query = String::with_capacity(
-        "INSERT INTO ".len()
-            + table.len()
-            + 1 // '(' is a char
-            + columns_joined.len()
-            + ") ".len()
-            + "VALUES ".len()
-            + values_groups_string.len(),
+            let full_content = crate::readability::extract(&page);
+
+            if full_content.is_none() {
+                log::debug!("{} does not look like an article", link);
+            }
+
+            Some((entry_id, full_content?))
+        })
+        .collect::<Vec<_>>();
+
+    in_transaction(conn, |tx| {
+        for (entry_id, full_content) in &full_contents {
+            tx.execute(
+                "UPDATE entries SET full_content = ?2 WHERE id = ?1",
+                params![entry_id, full_content],
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Each migration is run exactly once, in order,
+/// and the database's `user_version` records how many have been run.
+/// Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: readability-style full content extraction
+    "ALTER TABLE feeds ADD COLUMN fetch_full_content INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE entries ADD COLUMN full_content TEXT;",
+    // 2: per-feed entry sorting
+    "ALTER TABLE feeds ADD COLUMN entry_sort TEXT NOT NULL DEFAULT 'pub_date_desc';",
+    // 3: starring and deleting entries
+    "ALTER TABLE entries ADD COLUMN starred_at TIMESTAMP;
+    ALTER TABLE entries ADD COLUMN deleted_at TIMESTAMP;",
+    // 4: per-entry notes
+    "CREATE TABLE notes (
+        entry_id INTEGER PRIMARY KEY,
+        note TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 5: entry tags
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE entry_tags (
+        entry_id INTEGER NOT NULL,
+        tag_id INTEGER NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (entry_id, tag_id)
     );
+    CREATE INDEX IF NOT EXISTS entry_tags_tag_id_idx ON entry_tags (tag_id);",
+    // 6: session state, a single row
+    "CREATE TABLE session (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        feed_id INTEGER,
+        entry_id INTEGER,
+        entry_open INTEGER NOT NULL DEFAULT 0,
+        read_mode TEXT NOT NULL DEFAULT 'show_unread',
+        entry_scroll_position INTEGER NOT NULL DEFAULT 0,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 7: user-set feed titles
+    "ALTER TABLE feeds ADD COLUMN custom_title TEXT;",
+    // 8: access tokens for saving entries elsewhere
+    "CREATE TABLE credentials (
+        service TEXT PRIMARY KEY,
+        token TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 9: when feeds were refreshed, for the statistics view
+    "CREATE TABLE refreshes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feeds_len INTEGER NOT NULL,
+        refreshed_at TIMESTAMP NOT NULL
+    );",
+    // 10: more of what feeds say about their entries, for the entry header
+    "ALTER TABLE entries ADD COLUMN modified_at TIMESTAMP;
+    ALTER TABLE entries ADD COLUMN categories TEXT;",
+    // 11: the size of the feeds pane, kept with the rest of the session
+    "ALTER TABLE session ADD COLUMN feeds_pane_percent INTEGER;
+    ALTER TABLE session ADD COLUMN feeds_pane_hidden INTEGER NOT NULL DEFAULT 0;",
+    // 12: where entries are discussed
+    "ALTER TABLE entries ADD COLUMN comments_link TEXT;",
+    // 13: the playback queue, and the media it plays
+    "ALTER TABLE entries ADD COLUMN enclosure TEXT;
+    CREATE TABLE queue (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        entry_id INTEGER NOT NULL UNIQUE,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 14: refreshing feeds on their own schedules, or not at all
+    "ALTER TABLE feeds ADD COLUMN refresh_interval INTEGER;
+    ALTER TABLE feeds ADD COLUMN paused INTEGER NOT NULL DEFAULT 0;",
+    // 15: backing off from feeds that keep failing
+    "ALTER TABLE feeds ADD COLUMN failures INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE feeds ADD COLUMN last_failed_at TIMESTAMP;",
+    // 16: how long feeds ask to be left alone for
+    "ALTER TABLE feeds ADD COLUMN next_refresh_at TIMESTAMP;",
+    // 17: extra request headers for feeds that need them,
+    // their values now being kept in the keyring, and left empty here
+    "CREATE TABLE feed_headers (
+        feed_id INTEGER NOT NULL,
+        name TEXT NOT NULL COLLATE NOCASE,
+        value TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (feed_id, name)
+    );",
+    // 18: certificates pinned for feeds on servers with self-signed ones
+    "ALTER TABLE feeds ADD COLUMN certificate_fingerprint TEXT;",
+    // 19: telling feeds apart by what they call themselves, not only by their urls
+    "ALTER TABLE feeds ADD COLUMN guid TEXT;",
+    // 20: feeds put in order by hand, and pinned to the top
+    "ALTER TABLE feeds ADD COLUMN sort_order INTEGER;
+    ALTER TABLE feeds ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;",
+    // 21: the order the feeds list is in, kept with the rest of the session
+    "ALTER TABLE session ADD COLUMN feed_sort TEXT NOT NULL DEFAULT 'manual';",
+    // 22: single-letter marks on feeds and entries, to come back to
+    "CREATE TABLE marks (
+        name TEXT PRIMARY KEY,
+        feed_id INTEGER NOT NULL,
+        entry_id INTEGER,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 23: domains whose entries are hidden
+    "CREATE TABLE blocked_domains (
+        domain TEXT PRIMARY KEY,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 24: entries highlighted by the rules they matched when they were added
+    "ALTER TABLE entries ADD COLUMN highlight TEXT;",
+    // 25: the languages entries are in, and the ones whose entries are hidden,
+    // filled in for the entries already there by `set_entries_languages`
+    "ALTER TABLE entries ADD COLUMN language TEXT;
+    CREATE TABLE muted_languages (
+        language TEXT PRIMARY KEY,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 26: entries are listed by their dates, or when they were added if they have none,
+    // or if their dates are after that
+    "CREATE INDEX entries_feed_id_and_date_index
+    ON entries (feed_id, MIN(COALESCE(pub_date, inserted_at), inserted_at));",
+    // 27: what's been typed into the command and search inputs, newest last
+    "CREATE TABLE history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        input TEXT NOT NULL,
+        UNIQUE (kind, input)
+    );",
+    // 28: the hosts entries link to, for hiding the ones on blocked domains,
+    // filled in for the entries already there by `set_entries_hosts`
+    "ALTER TABLE entries ADD COLUMN host TEXT;",
+    // 29: why feeds last failed to refresh
+    "ALTER TABLE feeds ADD COLUMN last_error TEXT;",
+];
 
-    query.push_str("INSERT INTO ");
-    query.push_str(table);
-    query.push('(');
-    query.push_str(&columns_joined);
-    query.push_str(") ");
-    query.push_str("VALUES ");
-    query.push_str(&values_groups_string);
+/// the migration that adds `entries.language`, counting from 1, like the comments on `MIGRATIONS`
+const ENTRIES_LANGUAGE_MIGRATION: usize = 25;
 
-    query
+/// the migration that adds `entries.host`
+const ENTRIES_HOST_MIGRATION: usize = 28;
+
+/// Entries linking to a blocked domain, or any of its subdomains, and entries in a muted language,
+/// are hidden from everything that lists or counts them, until it's unblocked or unmuted.
+/// For the `WHERE` clauses of queries on `entries`.
+const NOT_LEFT_OUT: &str = "(host IS NULL OR NOT EXISTS (SELECT 1 FROM blocked_domains
+        WHERE host = domain OR substr(host, -length(domain) - 1) = '.' || domain))
+    AND (language IS NULL OR language NOT IN (SELECT language FROM muted_languages))";
+
+fn migrate(tx: &rusqlite::Transaction) -> Result<()> {
+    let user_version: usize = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(user_version) {
+        tx.execute_batch(migration)?;
+
+        if i + 1 == ENTRIES_LANGUAGE_MIGRATION {
+            set_entries_languages(tx)?;
+        }
+        if i + 1 == ENTRIES_HOST_MIGRATION {
+            set_entries_hosts(tx)?;
+        }
+    }
+
+    tx.execute_batch(&format!("PRAGMA user_version = {}", MIGRATIONS.len()))?;
+
+    Ok(())
 }
 
-pub fn get_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Feed> {
-    let s = conn.query_row(
-        "SELECT id, title, feed_link, link, feed_kind, refreshed_at, inserted_at, updated_at FROM feeds WHERE id=?1",
-        [feed_id],
-        |row| {
-            let feed_kind_str: String = row.get(4)?;
-            let feed_kind: FeedKind = FeedKind::from_str(&feed_kind_str)
-                .unwrap_or_else(|_| panic!("FeedKind must be Atom or RSS, got {}", feed_kind_str));
+/// fill in `entries.host`, which is parsed from the link, so it can't be done in SQL
+fn set_entries_hosts(tx: &rusqlite::Transaction) -> Result<()> {
+    let links = tx
+        .prepare("SELECT id, link FROM entries WHERE link IS NOT NULL")?
+        .query_map([], |row| {
+            Ok((row.get::<_, EntryId>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
 
-            Ok(Feed {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                feed_link: row.get(2)?,
-                link: row.get(3)?,
-                feed_kind,
-                refreshed_at: row.get(5)?,
-                inserted_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
+    let mut statement = tx.prepare("UPDATE entries SET host = ?2 WHERE id = ?1")?;
+    for (entry_id, link) in links {
+        statement.execute(params![entry_id, crate::links::host(&link)])?;
+    }
+
+    Ok(())
+}
+
+/// the language an entry with `title` and `text` is in, if it can be guessed
+fn language_of(title: Option<&str>, text: Option<&str>) -> Option<&'static str> {
+    crate::language::detect(&format!(
+        "{} {}",
+        title.unwrap_or_default(),
+        text.unwrap_or_default()
+    ))
+}
+
+/// fill in `entries.language`, which is guessed from the entry's text, so it can't be done in SQL
+fn set_entries_languages(tx: &rusqlite::Transaction) -> Result<()> {
+    let texts = tx
+        .prepare("SELECT id, title, content, description FROM entries")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, EntryId>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = tx.prepare("UPDATE entries SET language = ?2 WHERE id = ?1")?;
+    for (entry_id, title, content, description) in texts {
+        if let Some(language) = language_of(title.as_deref(), content.or(description).as_deref()) {
+            statement.execute(params![entry_id, language])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// how long a connection waits for another one to finish writing,
+/// like a refresh, before giving up with `SQLITE_BUSY`
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// enough for every query that runs on every redraw or refresh, with room to spare
+const PREPARED_STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// Backing up and restoring copy this many pages at a time,
+/// pausing for `BACKUP_PAUSE` in between for anything else using the database
+const BACKUP_PAGES_PER_STEP: i32 = 100;
+const BACKUP_PAUSE: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Open the database, unlocking it with `passphrase` if it's encrypted with SQLCipher,
+/// which needs russ to be built with the `sqlcipher` feature.
+pub fn open_db(path: &std::path::Path, passphrase: Option<&str>) -> Result<rusqlite::Connection> {
+    let conn = rusqlite::Connection::open(path)?;
+
+    if let Some(passphrase) = passphrase {
+        unlock_db(&conn, passphrase).with_context(|| {
+            format!(
+                "Unable to unlock {}, is the passphrase right?",
+                path.display()
+            )
+        })?;
+    }
+
+    configure_db(&conn)?;
+
+    Ok(conn)
+}
+
+/// like `open_db`, for a pool of connections
+pub fn connection_manager(
+    path: &std::path::Path,
+    passphrase: Option<String>,
+) -> r2d2_sqlite::SqliteConnectionManager {
+    r2d2_sqlite::SqliteConnectionManager::file(path).with_init(move |conn| {
+        if let Some(passphrase) = &passphrase {
+            unlock_db(conn, passphrase)?;
+        }
+        configure_db(conn)
+    })
+}
+
+fn configure_db(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // SQLite's own `lower` only lowercases ASCII
+    conn.create_scalar_function(
+        "lower_unicode",
+        1,
+        rusqlite::functions::FunctionFlags::SQLITE_UTF8
+            | rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
+        |context| Ok(context.get::<Option<String>>(0)?.map(|s| s.to_lowercase())),
+    )?;
+    // with a write-ahead log, reading doesn't wait for writing, or writing for reading,
+    // so the UI carries on while a refresh writes. It sticks, but setting it again is cheap
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.set_prepared_statement_cache_capacity(PREPARED_STATEMENT_CACHE_CAPACITY);
+    Ok(())
+}
+
+fn unlock_db(conn: &rusqlite::Connection, passphrase: &str) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "key", passphrase)?;
+    // SQLCipher only finds out whether the key is right when the database is first read
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+}
+
+/// Copy the database to a new file at `path`, encrypted with the same `passphrase`, if any.
+/// It's copied a bit at a time, with SQLite's online backup,
+/// so a russ that's running with the database only waits now and then.
+pub fn backup_db(
+    conn: &rusqlite::Connection,
+    path: &std::path::Path,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow::anyhow!("{} already exists", path.display()));
+    }
+
+    let mut backup = rusqlite::Connection::open(path)?;
+    if let Some(passphrase) = passphrase {
+        backup.pragma_update(None, "key", passphrase)?;
+    }
+
+    rusqlite::backup::Backup::new(conn, &mut backup)?.run_to_completion(
+        BACKUP_PAGES_PER_STEP,
+        BACKUP_PAUSE,
+        None,
+    )?;
+
+    Ok(())
+}
+
+/// Replace everything in the database with the backup at `path`, made by `backup_db`,
+/// bringing it up to date if it was made by an older russ.
+pub fn restore_db(
+    conn: &mut rusqlite::Connection,
+    path: &std::path::Path,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!("{} does not exist", path.display()));
+    }
+
+    let backup = rusqlite::Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    if let Some(passphrase) = passphrase {
+        unlock_db(&backup, passphrase)
+            .with_context(|| format!("Unable to unlock {}", path.display()))?;
+    }
+
+    let has_feeds = backup
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'feeds'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .with_context(|| format!("{} is not a russ database", path.display()))?
+        .is_some();
+    if !has_feeds {
+        return Err(anyhow::anyhow!("{} is not a russ database", path.display()));
+    }
+
+    rusqlite::backup::Backup::new(&backup, conn)?.run_to_completion(
+        BACKUP_PAGES_PER_STEP,
+        BACKUP_PAUSE,
+        None,
     )?;
 
-    Ok(s)
-}
+    initialize_db(conn)
+}
+
+pub fn initialize_db(conn: &mut rusqlite::Connection) -> Result<()> {
+    in_transaction(conn, |tx| {
+        tx.execute(
+            "CREATE TABLE IF NOT EXISTS feeds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT,
+        feed_link TEXT,
+        link TEXT,
+        feed_kind TEXT,
+        refreshed_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+            [],
+        )?;
+
+        tx.execute(
+            "CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER,
+        title TEXT,
+        author TEXT,
+        pub_date TIMESTAMP,
+        description TEXT,
+        content TEXT,
+        link TEXT,
+        read_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+            [],
+        )?;
+
+        tx.execute(
+            "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index
+        ON entries (feed_id, pub_date, inserted_at)",
+            [],
+        )?;
+
+        migrate(tx)?;
+
+        Ok(())
+    })
+}
+
+fn create_feed(tx: &rusqlite::Transaction, feed: &Feed) -> Result<FeedId> {
+    let feed_id = tx.query_row::<FeedId, _, _>(
+        "INSERT INTO feeds (title, link, feed_link, feed_kind, next_refresh_at, certificate_fingerprint)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        RETURNING id",
+        params![
+            feed.title,
+            feed.link,
+            feed.feed_link,
+            feed.feed_kind,
+            feed.next_refresh_at,
+            feed.certificate_fingerprint
+        ],
+        |r| r.get(0),
+    )?;
+
+    Ok(feed_id)
+}
+
+/// A copy of some rows of a table, so they can be put back exactly as they were
+#[derive(Debug)]
+struct TableSnapshot {
+    table: &'static str,
+    columns: Vec<String>,
+    rows: Vec<Vec<rusqlite::types::Value>>,
+}
+
+impl TableSnapshot {
+    /// `predicate` is a `WHERE` clause with a single parameter, `?1`, which is `id`
+    fn take(
+        tx: &rusqlite::Transaction,
+        table: &'static str,
+        predicate: &str,
+        id: i64,
+    ) -> Result<Self> {
+        let mut statement = tx.prepare(&format!("SELECT * FROM {} WHERE {}", table, predicate))?;
+
+        let columns = statement
+            .column_names()
+            .into_iter()
+            .map(|column| column.to_owned())
+            .collect::<Vec<_>>();
+
+        let mut rows = vec![];
+        for row in statement.query_map([id], |row| {
+            (0..columns.len())
+                .map(|i| row.get(i))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })? {
+            rows.push(row?);
+        }
+
+        Ok(TableSnapshot {
+            table,
+            columns,
+            rows,
+        })
+    }
+
+    fn restore(&self, tx: &rusqlite::Transaction) -> Result<()> {
+        // stay well under SQLite's limit on the number of bound parameters
+        let rows_per_insert = (999 / self.columns.len().max(1)).max(1);
+
+        for rows in self.rows.chunks(rows_per_insert) {
+            let query = build_bulk_insert_query(self.table, &self.columns, rows);
+            let values = rows
+                .iter()
+                .flatten()
+                .map(|value| value as &dyn rusqlite::ToSql)
+                .collect::<Vec<_>>();
+
+            tx.execute(&query, values.as_slice())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Everything removed from the database when a feed was deleted
+#[derive(Debug)]
+pub struct DeletedFeed {
+    pub feed_id: FeedId,
+    snapshots: Vec<TableSnapshot>,
+}
+
+pub fn delete_feed(conn: &mut rusqlite::Connection, feed_id: FeedId) -> Result<DeletedFeed> {
+    let deleted_feed = in_transaction(conn, |tx| {
+        let entries_predicate = "entry_id IN (SELECT id FROM entries WHERE feed_id = ?1)";
+
+        let snapshots = vec![
+            TableSnapshot::take(tx, "feeds", "id = ?1", feed_id)?,
+            TableSnapshot::take(tx, "entries", "feed_id = ?1", feed_id)?,
+            TableSnapshot::take(tx, "notes", entries_predicate, feed_id)?,
+            TableSnapshot::take(tx, "entry_tags", entries_predicate, feed_id)?,
+            TableSnapshot::take(tx, "queue", entries_predicate, feed_id)?,
+            TableSnapshot::take(tx, "feed_headers", "feed_id = ?1", feed_id)?,
+            TableSnapshot::take(tx, "marks", "feed_id = ?1", feed_id)?,
+        ];
+
+        for table in ["notes", "entry_tags", "queue"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE {}", table, entries_predicate),
+                [feed_id],
+            )?;
+        }
+        tx.execute("DELETE FROM feeds WHERE id = ?1", [feed_id])?;
+        tx.execute("DELETE FROM entries WHERE feed_id = ?1", [feed_id])?;
+        tx.execute("DELETE FROM feed_headers WHERE feed_id = ?1", [feed_id])?;
+        tx.execute("DELETE FROM marks WHERE feed_id = ?1", [feed_id])?;
+
+        Ok(DeletedFeed { feed_id, snapshots })
+    })?;
+
+    // refreshing it pins it again, if it's restored
+    crate::http::unpin_certificate(crate::http::PinnedBy::Feed(feed_id));
+
+    Ok(deleted_feed)
+}
+
+/// put a deleted feed and its entries back, with their original ids
+pub fn restore_feed(conn: &mut rusqlite::Connection, deleted_feed: &DeletedFeed) -> Result<()> {
+    in_transaction(conn, |tx| {
+        for snapshot in &deleted_feed.snapshots {
+            snapshot.restore(tx)?;
+        }
+        Ok(())
+    })
+}
+
+/// Marks every unread entry in a feed as read, or only those published before `before`,
+/// or added before it if they have no date, or are dated after they were added,
+/// returning the ids of the entries that changed.
+pub fn mark_feed_as_read(
+    conn: &mut rusqlite::Connection,
+    feed_id: FeedId,
+    before: Option<DateTime<Utc>>,
+) -> Result<Vec<EntryId>> {
+    let predicate = format!(
+        "feed_id = ?1 AND read_at IS NULL AND deleted_at IS NULL AND {}
+        AND (?2 IS NULL OR MIN(COALESCE(pub_date, inserted_at), inserted_at) < ?2)",
+        NOT_LEFT_OUT
+    );
+
+    in_transaction(conn, |tx| {
+        let mut statement = tx.prepare(&format!("SELECT id FROM entries WHERE {}", predicate))?;
+        let mut entry_ids = vec![];
+        for entry_id in statement.query_map(params![feed_id, before], |row| row.get(0))? {
+            entry_ids.push(entry_id?);
+        }
+
+        tx.execute(
+            &format!("UPDATE entries SET read_at = ?3 WHERE {}", predicate),
+            params![feed_id, before, Utc::now()],
+        )?;
+
+        Ok(entry_ids)
+    })
+}
+
+/// The per-entry states that are stored as the timestamp they were set at
+#[derive(Clone, Copy, Debug)]
+pub enum EntryFlag {
+    Read,
+    Starred,
+    /// deleted entries are hidden rather than removed,
+    /// so refreshing the feed does not bring them back
+    Deleted,
+}
+
+impl EntryFlag {
+    fn column(&self) -> &'static str {
+        match self {
+            EntryFlag::Read => "read_at",
+            EntryFlag::Starred => "starred_at",
+            EntryFlag::Deleted => "deleted_at",
+        }
+    }
+}
+
+/// set a flag on many entries at once, for example to put them back the way they were
+pub fn set_entries_flag(
+    conn: &mut rusqlite::Connection,
+    entry_flag: EntryFlag,
+    entries_flag: &[(EntryId, Option<chrono::DateTime<Utc>>)],
+) -> Result<()> {
+    in_transaction(conn, |tx| {
+        let mut statement = tx.prepare(&format!(
+            "UPDATE entries SET {} = ?2 WHERE id = ?1",
+            entry_flag.column()
+        ))?;
+        for (entry_id, flagged_at) in entries_flag {
+            statement.execute(params![entry_id, flagged_at])?;
+        }
+        Ok(())
+    })
+}
+
+fn add_entries_to_feed(
+    tx: &rusqlite::Transaction,
+    feed_id: FeedId,
+    entries: &[Entry],
+) -> Result<()> {
+    if !entries.is_empty() {
+        let now = Utc::now();
+
+        let columns = [
+            "feed_id",
+            "title",
+            "author",
+            "pub_date",
+            "description",
+            "content",
+            "link",
+            "comments_link",
+            "enclosure",
+            "modified_at",
+            "categories",
+            "updated_at",
+            "language",
+            "host",
+        ];
+
+        let mut entries_values = Vec::with_capacity(entries.len() * columns.len());
+
+        let categories = entries
+            .iter()
+            .map(|entry| join_categories(&entry.categories))
+            .collect::<Vec<_>>();
+        let languages = entries.iter().map(Entry::language).collect::<Vec<_>>();
+        let hosts = entries
+            .iter()
+            .map(|entry| entry.link.as_deref().and_then(crate::links::host))
+            .collect::<Vec<_>>();
+
+        for (i, entry) in entries.iter().enumerate() {
+            let values = params![
+                feed_id,
+                entry.title,
+                entry.author,
+                entry.pub_date,
+                entry.description,
+                entry.content,
+                entry.link,
+                entry.comments_link,
+                entry.enclosure,
+                entry.modified_at,
+                categories[i],
+                now,
+                languages[i],
+                hosts[i],
+            ];
+            entries_values.extend_from_slice(values);
+        }
+
+        let query = build_bulk_insert_query("entries", &columns, entries);
+
+        tx.execute(&query, entries_values.as_slice())?;
+    }
+
+    Ok(())
+}
+
+fn build_bulk_insert_query<C: AsRef<str>, R>(table: &str, columns: &[C], rows: &[R]) -> String {
+    let idxs = (1..(rows.len() * columns.len() + 1)).collect::<Vec<_>>();
+
+    let values_groups_string = idxs
+        .chunks(columns.len())
+        .map(|chunk| {
+            let values_string = chunk
+                .iter()
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ["(", &values_string, ")"].concat()
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let columns_strs = columns
+        .iter()
+        .map(|column| column.as_ref())
+        .collect::<Vec<&str>>();
+
+    let columns_joined = columns_strs.join(", ");
+
+    let mut query = String::with_capacity(
+        "INSERT INTO ".len()
+            + table.len()
+            + 1 // '(' is a char
+            + columns_joined.len()
+            + ") ".len()
+            + "VALUES ".len()
+            + values_groups_string.len(),
+    );
+
+    query.push_str("INSERT INTO ");
+    query.push_str(table);
+    query.push('(');
+    query.push_str(&columns_joined);
+    query.push_str(") ");
+    query.push_str("VALUES ");
+    query.push_str(&values_groups_string);
+
+    query
+}
+
+pub fn get_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Feed> {
+    let s = conn.query_row(
+        "SELECT id, COALESCE(custom_title, title), feed_link, link, feed_kind, refreshed_at, inserted_at, updated_at, fetch_full_content, entry_sort, custom_title, refresh_interval, paused, failures, last_failed_at, next_refresh_at, certificate_fingerprint, guid, pinned, last_error FROM feeds WHERE id=?1",
+        [feed_id],
+        |row| {
+            let feed_kind_str: String = row.get(4)?;
+            let feed_kind: FeedKind = FeedKind::from_str(&feed_kind_str)
+                .unwrap_or_else(|_| panic!("FeedKind must be Atom or RSS, got {}", feed_kind_str));
+
+            Ok(Feed {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                feed_link: row.get(2)?,
+                link: row.get(3)?,
+                feed_kind,
+                refreshed_at: row.get(5)?,
+                inserted_at: row.get(6)?,
+                updated_at: row.get(7)?,
+                fetch_full_content: row.get(8)?,
+                entry_sort: row.get(9)?,
+                custom_title: row.get(10)?,
+                refresh_interval: row
+                    .get::<_, Option<i64>>(11)?
+                    .map(chrono::Duration::seconds),
+                paused: row.get(12)?,
+                failures: row.get(13)?,
+                last_failed_at: row.get(14)?,
+                next_refresh_at: row.get(15)?,
+                certificate_fingerprint: row.get(16)?,
+                guid: row.get(17)?,
+                pinned: row.get(18)?,
+                last_error: row.get(19)?,
+            })
+        },
+    )?;
+
+    Ok(s)
+}
+
+fn update_feed_refreshed_at(tx: &rusqlite::Transaction, feed_id: FeedId) -> Result<()> {
+    tx.execute(
+        "UPDATE feeds SET refreshed_at = ?2, failures = 0, last_failed_at = NULL, last_error = NULL
+        WHERE id = ?1",
+        params![feed_id, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+fn record_feed_failure(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    error: &anyhow::Error,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET failures = failures + 1, last_failed_at = ?2, last_error = ?3
+        WHERE id = ?1",
+        params![feed_id, Utc::now(), format!("{:#}", error)],
+    )?;
+
+    Ok(())
+}
+
+fn get_feed_fetch_full_content(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<bool> {
+    let fetch_full_content = conn.query_row(
+        "SELECT fetch_full_content FROM feeds WHERE id=?1",
+        [feed_id],
+        |row| row.get(0),
+    )?;
+
+    Ok(fetch_full_content)
+}
+
+pub fn toggle_feed_fetch_full_content(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET fetch_full_content = NOT fetch_full_content, updated_at = ?2 WHERE id = ?1",
+        params![feed_id, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+pub fn set_feed_entry_sort(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    entry_sort: EntrySort,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET entry_sort = ?2, updated_at = ?3 WHERE id = ?1",
+        params![feed_id, entry_sort, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// an empty title removes the custom title, going back to the feed's own
+pub fn set_feed_custom_title(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    custom_title: &str,
+) -> Result<()> {
+    let custom_title = custom_title.trim();
+    let custom_title = if custom_title.is_empty() {
+        None
+    } else {
+        Some(custom_title)
+    };
+
+    conn.execute(
+        "UPDATE feeds SET custom_title = ?2, updated_at = ?3 WHERE id = ?1",
+        params![feed_id, custom_title, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// Pin the feed's host to the certificate with `fingerprint`, or unpin it if it's empty.
+pub fn set_feed_certificate_fingerprint(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    fingerprint: &str,
+) -> Result<Option<Fingerprint>> {
+    let fingerprint = if fingerprint.trim().is_empty() {
+        None
+    } else {
+        Some(fingerprint.parse::<Fingerprint>()?)
+    };
+
+    conn.execute(
+        "UPDATE feeds SET certificate_fingerprint = ?2, updated_at = ?3 WHERE id = ?1",
+        params![
+            feed_id,
+            fingerprint.map(|fingerprint| fingerprint.to_string()),
+            Utc::now()
+        ],
+    )?;
+
+    Ok(fingerprint)
+}
+
+fn get_feed_certificate_fingerprint(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+) -> Result<Option<Fingerprint>> {
+    let fingerprint: Option<String> = conn.query_row(
+        "SELECT certificate_fingerprint FROM feeds WHERE id=?1",
+        [feed_id],
+        |row| row.get(0),
+    )?;
+
+    fingerprint
+        .map(|fingerprint| fingerprint.parse())
+        .transpose()
+}
+
+/// `None` to only refresh the feed with the rest of them
+pub fn set_feed_refresh_interval(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    refresh_interval: Option<chrono::Duration>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET refresh_interval = ?2, updated_at = ?3 WHERE id = ?1",
+        params![
+            feed_id,
+            refresh_interval.map(|refresh_interval| refresh_interval.num_seconds()),
+            Utc::now()
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn toggle_feed_paused(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET paused = NOT paused, updated_at = ?2 WHERE id = ?1",
+        params![feed_id, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+pub fn toggle_feed_pinned(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET pinned = NOT pinned, updated_at = ?2 WHERE id = ?1",
+        params![feed_id, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// Swap two feeds' places in the feeds list. Every feed is given its place in the list,
+/// so the ones that were only in order by title stay where they were.
+pub fn swap_feeds(conn: &mut rusqlite::Connection, a: FeedId, b: FeedId) -> Result<()> {
+    let tx = conn.transaction()?;
+
+    let mut feed_ids = get_feeds(&tx)?
+        .into_iter()
+        .map(|feed| feed.id)
+        .collect::<Vec<_>>();
+
+    if let (Some(a_idx), Some(b_idx)) = (
+        feed_ids.iter().position(|feed_id| *feed_id == a),
+        feed_ids.iter().position(|feed_id| *feed_id == b),
+    ) {
+        feed_ids.swap(a_idx, b_idx);
+    }
+
+    {
+        let mut statement = tx.prepare("UPDATE feeds SET sort_order = ?2 WHERE id = ?1")?;
+        for (sort_order, feed_id) in feed_ids.into_iter().enumerate() {
+            statement.execute(params![feed_id, sort_order])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// the names of the extra headers the feed is fetched with
+pub fn get_feed_header_names(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Vec<String>> {
+    let mut statement =
+        conn.prepare("SELECT name FROM feed_headers WHERE feed_id = ?1 ORDER BY name")?;
+
+    let mut names = vec![];
+    for name in statement.query_map([feed_id], |row| row.get(0))? {
+        names.push(name?);
+    }
+
+    Ok(names)
+}
+
+/// the extra headers the feed is fetched with, by name,
+/// their values, like `Authorization`'s, being kept in the keyring
+pub fn get_feed_headers(
+    conn: &rusqlite::Connection,
+    keyring: &crate::keyring::Keyring,
+    feed_id: FeedId,
+) -> Result<Vec<(String, String)>> {
+    move_feed_headers_to_keyring(conn, keyring)?;
+
+    let mut headers = vec![];
+    for name in get_feed_header_names(conn, feed_id)? {
+        match keyring.get(&feed_header_secret(feed_id, &name))? {
+            Some(value) => headers.push((name, value)),
+            None => log::warn!("feed {}'s {} header isn't in the keyring", feed_id, name),
+        }
+    }
+
+    Ok(headers)
+}
+
+/// Move the values of headers set before they were kept in the keyring out of the database.
+pub fn move_feed_headers_to_keyring(
+    conn: &rusqlite::Connection,
+    keyring: &crate::keyring::Keyring,
+) -> Result<()> {
+    let mut statement =
+        conn.prepare("SELECT feed_id, name, value FROM feed_headers WHERE value != ''")?;
+    let headers = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, FeedId>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (feed_id, name, value) in headers {
+        keyring.set(&feed_header_secret(feed_id, &name), &value)?;
+        conn.execute(
+            "UPDATE feed_headers SET value = '' WHERE feed_id = ?1 AND name = ?2",
+            params![feed_id, name],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// what the keyring knows a feed's header by, whatever the case of its name
+fn feed_header_secret(feed_id: FeedId, name: &str) -> String {
+    format!("feed-{}-header-{}", feed_id, name.to_ascii_lowercase())
+}
+
+/// Send the header with every request for the feed, replacing any with the same name,
+/// or stop sending it if `value` is `None`.
+/// Only its name goes in the database, its value goes in the keyring.
+/// A deleted feed's are left there, so undoing the delete sends them again.
+pub fn set_feed_header(
+    conn: &rusqlite::Connection,
+    keyring: &crate::keyring::Keyring,
+    feed_id: FeedId,
+    name: &str,
+    value: Option<&str>,
+) -> Result<()> {
+    let name = name.trim();
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?={}".contains(c))
+    {
+        return Err(anyhow::anyhow!("{:?} is not a valid header name", name));
+    }
+
+    match value {
+        Some(value) => {
+            keyring.set(&feed_header_secret(feed_id, name), value.trim())?;
+            conn.execute(
+                "INSERT INTO feed_headers (feed_id, name, value) VALUES (?1, ?2, '')
+                ON CONFLICT (feed_id, name) DO UPDATE SET name = excluded.name, value = ''",
+                params![feed_id, name],
+            )?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM feed_headers WHERE feed_id = ?1 AND name = ?2",
+                params![feed_id, name],
+            )?;
+            keyring.delete(&feed_header_secret(feed_id, name))?;
+        }
+    };
+
+    Ok(())
+}
+
+/// a feed's entry, by its link, which is how a refresh tells entries apart
+pub fn get_entry_id_by_link(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    link: &str,
+) -> Result<Option<EntryId>> {
+    let entry_id = conn
+        .query_row(
+            "SELECT id FROM entries WHERE feed_id = ?1 AND link = ?2",
+            params![feed_id, link],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(entry_id)
+}
+
+/// Highlight a feed's entry, which is told apart from the rest by its link,
+/// as the entries a refresh adds don't have their ids.
+pub fn set_entry_highlight(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    link: &str,
+    highlight: Highlight,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE entries SET highlight = ?3 WHERE feed_id = ?1 AND link = ?2",
+        params![feed_id, link, highlight],
+    )?;
+
+    Ok(())
+}
+
+/// Hide entries linking to `domain`, or any of its subdomains, until it's unblocked,
+/// returning the domain as it was blocked.
+pub fn block_domain(conn: &rusqlite::Connection, domain: &str) -> Result<String> {
+    let domain = crate::links::normalize_domain(domain)
+        .ok_or_else(|| anyhow::anyhow!("{:?} is not a domain", domain))?;
+
+    conn.execute(
+        "INSERT INTO blocked_domains (domain) VALUES (?1) ON CONFLICT (domain) DO NOTHING",
+        [&domain],
+    )?;
+
+    Ok(domain)
+}
+
+/// Show the domain's entries again, returning whether it was blocked.
+pub fn unblock_domain(conn: &rusqlite::Connection, domain: &str) -> Result<bool> {
+    let domain = crate::links::normalize_domain(domain).unwrap_or_default();
+
+    Ok(conn.execute("DELETE FROM blocked_domains WHERE domain = ?1", [domain])? > 0)
+}
+
+pub fn get_blocked_domains(conn: &rusqlite::Connection) -> Result<Vec<String>> {
+    let domains = conn
+        .prepare_cached("SELECT domain FROM blocked_domains ORDER BY domain")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(domains)
+}
+
+/// Hide entries in `language` until it's unmuted,
+/// returning the language's code.
+pub fn mute_language(conn: &rusqlite::Connection, language: &str) -> Result<String> {
+    let language = crate::language::normalize(language)
+        .ok_or_else(|| anyhow::anyhow!("{:?} is not a language, like de or German", language))?;
+
+    conn.execute(
+        "INSERT INTO muted_languages (language) VALUES (?1) ON CONFLICT (language) DO NOTHING",
+        [&language],
+    )?;
+
+    Ok(language)
+}
+
+/// Show the language's entries again, returning whether it was muted.
+pub fn unmute_language(conn: &rusqlite::Connection, language: &str) -> Result<bool> {
+    let language = crate::language::normalize(language).unwrap_or_default();
+
+    Ok(conn.execute(
+        "DELETE FROM muted_languages WHERE language = ?1",
+        [language],
+    )? > 0)
+}
+
+pub fn get_muted_languages(conn: &rusqlite::Connection) -> Result<Vec<String>> {
+    let languages = conn
+        .prepare_cached("SELECT language FROM muted_languages ORDER BY language")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(languages)
+}
+
+/// the entries, in every feed, in `language`, that aren't deleted
+pub fn get_entry_ids_in_language(
+    conn: &rusqlite::Connection,
+    language: &str,
+) -> Result<Vec<EntryId>> {
+    let entry_ids = conn
+        .prepare("SELECT id FROM entries WHERE deleted_at IS NULL AND language = ?1")?
+        .query_map([language], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(entry_ids)
+}
+
+/// Add `input` to the `kind` history, like `command` or `search`, as its newest,
+/// forgetting the oldest past `crate::history::HISTORY_LIMIT`.
+pub fn add_to_history(conn: &rusqlite::Connection, kind: &str, input: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM history WHERE kind = ?1 AND input = ?2",
+        [kind, input],
+    )?;
+    conn.execute(
+        "INSERT INTO history (kind, input) VALUES (?1, ?2)",
+        [kind, input],
+    )?;
+    conn.execute(
+        "DELETE FROM history
+        WHERE kind = ?1
+        AND id NOT IN (SELECT id FROM history WHERE kind = ?1 ORDER BY id DESC LIMIT ?2)",
+        rusqlite::params![kind, crate::history::HISTORY_LIMIT],
+    )?;
+
+    Ok(())
+}
+
+/// the `kind` history, oldest first
+pub fn get_history(conn: &rusqlite::Connection, kind: &str) -> Result<Vec<String>> {
+    let inputs = conn
+        .prepare_cached("SELECT input FROM history WHERE kind = ?1 ORDER BY id")?
+        .query_map([kind], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(inputs)
+}
+
+/// the entries, in every feed, that link to `domain` or its subdomains, and aren't deleted
+pub fn get_entry_ids_on_domain(conn: &rusqlite::Connection, domain: &str) -> Result<Vec<EntryId>> {
+    let domains = [domain.to_owned()];
+    let mut entry_ids = vec![];
+
+    let mut statement = conn.prepare(
+        "SELECT id, link FROM entries
+        WHERE deleted_at IS NULL AND instr(lower(link), ?1) > 0",
+    )?;
+    for entry in statement.query_map([domain], |row| {
+        Ok((row.get::<_, EntryId>(0)?, row.get::<_, String>(1)?))
+    })? {
+        let (entry_id, link) = entry?;
+        if crate::links::is_blocked(&link, &domains) {
+            entry_ids.push(entry_id);
+        }
+    }
+
+    Ok(entry_ids)
+}
+
+/// for when a feed moves, the new url is used from the next refresh on
+pub fn set_feed_url(conn: &rusqlite::Connection, feed_id: FeedId, url: &str) -> Result<()> {
+    let url = url.trim();
+
+    if url.is_empty() {
+        return Err(anyhow::anyhow!("A feed's url can't be empty"));
+    }
+
+    conn.execute(
+        "UPDATE feeds SET feed_link = ?2, updated_at = ?3 WHERE id = ?1",
+        params![feed_id, url, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_feed_url(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<String> {
+    let s: String = conn.query_row(
+        "SELECT feed_link FROM feeds WHERE id=?1",
+        [feed_id],
+        |row| row.get(0),
+    )?;
+
+    Ok(s)
+}
+
+pub fn get_feeds(conn: &rusqlite::Connection) -> Result<Vec<Feed>> {
+    get_sorted_feeds(conn, FeedSort::Manual)
+}
+
+pub fn get_sorted_feeds(conn: &rusqlite::Connection, feed_sort: FeedSort) -> Result<Vec<Feed>> {
+    let mut statement = conn.prepare_cached(&format!(
+        "SELECT 
+          id, 
+          COALESCE(custom_title, title), 
+          feed_link, 
+          link, 
+          feed_kind, 
+          refreshed_at, 
+          inserted_at, 
+          updated_at, 
+          fetch_full_content, 
+          entry_sort, 
+          custom_title, 
+          refresh_interval, 
+          paused, 
+          failures, 
+          last_failed_at, 
+          next_refresh_at, 
+          certificate_fingerprint, 
+          guid, 
+          pinned, 
+          last_error 
+        FROM feeds
+        ORDER BY {} lower(COALESCE(custom_title, title)) ASC",
+        feed_sort.order_by()
+    ))?;
+    let mut feeds = vec![];
+    for feed in statement.query_map([], |row| {
+        Ok(Feed {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            feed_link: row.get(2)?,
+            link: row.get(3)?,
+            feed_kind: row.get(4)?,
+            refreshed_at: row.get(5)?,
+            inserted_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            fetch_full_content: row.get(8)?,
+            entry_sort: row.get(9)?,
+            custom_title: row.get(10)?,
+            refresh_interval: row
+                .get::<_, Option<i64>>(11)?
+                .map(chrono::Duration::seconds),
+            paused: row.get(12)?,
+            failures: row.get(13)?,
+            last_failed_at: row.get(14)?,
+            next_refresh_at: row.get(15)?,
+            certificate_fingerprint: row.get(16)?,
+            guid: row.get(17)?,
+            pinned: row.get(18)?,
+            last_error: row.get(19)?,
+        })
+    })? {
+        feeds.push(feed?)
+    }
+
+    Ok(feeds)
+}
+
+/// the number of unread entries in every feed
+pub fn get_unread_count(conn: &rusqlite::Connection) -> Result<usize> {
+    let count = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FROM entries WHERE read_at IS NULL AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_row([], |row| row.get(0))?;
+
+    Ok(count)
+}
+
+/// the number of unread entries in each feed that has any
+pub fn get_unread_counts(conn: &rusqlite::Connection) -> Result<HashMap<FeedId, usize>> {
+    let counts = conn
+        .prepare_cached(&format!(
+            "SELECT feed_id, COUNT(*) FROM entries
+            WHERE read_at IS NULL AND deleted_at IS NULL AND {}
+            GROUP BY feed_id",
+            NOT_LEFT_OUT
+        ))?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    Ok(counts)
+}
+
+/// the feeds that have at least one unread entry
+pub fn get_unread_feed_ids(conn: &rusqlite::Connection) -> Result<HashSet<FeedId>> {
+    let feed_ids = conn
+        .prepare_cached(&format!(
+            "SELECT DISTINCT feed_id FROM entries
+            WHERE read_at IS NULL AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    Ok(feed_ids)
+}
+
+/// the feeds refreshing all feeds refreshes, see `Feed::is_due`
+pub fn get_due_feed_ids(conn: &rusqlite::Connection) -> Result<Vec<FeedId>> {
+    let now = Utc::now();
+
+    Ok(get_feeds(conn)?
+        .into_iter()
+        .filter(|feed| feed.is_due(now))
+        .map(|feed| feed.id)
+        .collect())
+}
+
+/// the feeds with refresh intervals that are due, to be refreshed without being asked to
+pub fn get_scheduled_feed_ids(conn: &rusqlite::Connection) -> Result<Vec<FeedId>> {
+    let now = Utc::now();
+
+    Ok(get_feeds(conn)?
+        .into_iter()
+        .filter(|feed| feed.refresh_interval.is_some() && feed.is_due(now))
+        .map(|feed| feed.id)
+        .collect())
+}
+
+/// the id, feed id, and title of every titled entry in every feed,
+/// which is all the fuzzy finder needs
+pub fn get_entries_titles(conn: &rusqlite::Connection) -> Result<Vec<(EntryId, FeedId, String)>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT id, feed_id, title FROM entries
+        WHERE title IS NOT NULL
+        AND deleted_at IS NULL
+        AND {}
+        ORDER BY MIN(COALESCE(pub_date, inserted_at), inserted_at) DESC, inserted_at DESC",
+        NOT_LEFT_OUT
+    ))?;
+
+    let titles = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(titles)
+}
+
+pub fn get_entry_meta(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryMeta> {
+    let result = conn.query_row(
+        "SELECT 
+          id, 
+          feed_id, 
+          title, 
+          author, 
+          pub_date, 
+          link, 
+          read_at, 
+          inserted_at, 
+          updated_at, 
+          starred_at, 
+          modified_at, 
+          categories, 
+          (SELECT GROUP_CONCAT(tags.name) FROM entry_tags
+            JOIN tags ON tags.id = entry_tags.tag_id
+            WHERE entry_tags.entry_id = entries.id),
+          LENGTH(COALESCE(full_content, content, description, '')),
+          comments_link,
+          highlight,
+          language
+        FROM entries WHERE id=?1",
+        [entry_id],
+        |row| {
+            Ok(EntryMeta {
+                id: row.get(0)?,
+                feed_id: row.get(1)?,
+                title: row.get(2)?,
+                author: row.get(3)?,
+                pub_date: row.get(4)?,
+                link: row.get(5)?,
+                read_at: row.get(6)?,
+                inserted_at: row.get(7)?,
+                updated_at: row.get(8)?,
+                starred_at: row.get(9)?,
+                modified_at: row.get(10)?,
+                categories: split_categories(row.get(11)?),
+                tags: split_tags(row.get(12)?),
+                content_len: row.get(13)?,
+                comments_link: row.get(14)?,
+                highlight: row.get(15)?,
+                language: row.get(16)?,
+            })
+        },
+    )?;
+
+    Ok(result)
+}
+
+pub fn get_entry_content(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryContent> {
+    let result = conn.query_row(
+        "SELECT content, description, full_content FROM entries WHERE id=?1",
+        [entry_id],
+        |row| {
+            Ok(EntryContent {
+                content: row.get(0)?,
+                description: row.get(1)?,
+                full_content: row.get(2)?,
+            })
+        },
+    )?;
+
+    Ok(result)
+}
+
+pub fn get_session(conn: &rusqlite::Connection) -> Result<Option<Session>> {
+    let session = conn
+        .query_row(
+            "SELECT feed_id, entry_id, entry_open, read_mode, entry_scroll_position,
+            feeds_pane_percent, feeds_pane_hidden, feed_sort
+            FROM session WHERE id = 1",
+            [],
+            |row| {
+                Ok(Session {
+                    feed_id: row.get(0)?,
+                    entry_id: row.get(1)?,
+                    entry_open: row.get(2)?,
+                    read_mode: row.get(3)?,
+                    entry_scroll_position: row.get(4)?,
+                    feeds_pane_percent: row.get(5)?,
+                    feeds_pane_hidden: row.get(6)?,
+                    feed_sort: row.get(7)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(session)
+}
+
+pub fn save_session(conn: &rusqlite::Connection, session: &Session) -> Result<()> {
+    conn.execute(
+        "INSERT INTO session (id, feed_id, entry_id, entry_open, read_mode, entry_scroll_position,
+            feeds_pane_percent, feeds_pane_hidden, feed_sort, updated_at)
+        VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT (id) DO UPDATE SET
+            feed_id = ?1,
+            entry_id = ?2,
+            entry_open = ?3,
+            read_mode = ?4,
+            entry_scroll_position = ?5,
+            feeds_pane_percent = ?6,
+            feeds_pane_hidden = ?7,
+            feed_sort = ?8,
+            updated_at = ?9",
+        params![
+            session.feed_id,
+            session.entry_id,
+            session.entry_open,
+            session.read_mode,
+            session.entry_scroll_position,
+            session.feeds_pane_percent,
+            session.feeds_pane_hidden,
+            session.feed_sort,
+            Utc::now()
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// put the mark `name` on a feed, or an entry in it, moving it there if it was elsewhere
+pub fn set_mark(
+    conn: &rusqlite::Connection,
+    name: char,
+    feed_id: FeedId,
+    entry_id: Option<EntryId>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO marks (name, feed_id, entry_id) VALUES (?1, ?2, ?3)
+        ON CONFLICT (name) DO UPDATE SET
+            feed_id = excluded.feed_id,
+            entry_id = excluded.entry_id,
+            inserted_at = CURRENT_TIMESTAMP",
+        params![name.to_string(), feed_id, entry_id],
+    )?;
+
+    Ok(())
+}
+
+/// the feed, and the entry, if it's on one, that the mark `name` is on
+pub fn get_mark(
+    conn: &rusqlite::Connection,
+    name: char,
+) -> Result<Option<(FeedId, Option<EntryId>)>> {
+    let mark = conn
+        .query_row(
+            "SELECT feed_id, entry_id FROM marks WHERE name = ?1",
+            [name.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    Ok(mark)
+}
+
+pub fn get_entry_note(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<Option<String>> {
+    let note = conn
+        .query_row(
+            "SELECT note FROM notes WHERE entry_id=?1",
+            [entry_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(note)
+}
+
+/// an empty note removes the entry's note
+pub fn set_entry_note(conn: &rusqlite::Connection, entry_id: EntryId, note: &str) -> Result<()> {
+    if note.trim().is_empty() {
+        conn.execute("DELETE FROM notes WHERE entry_id = ?1", [entry_id])?;
+    } else {
+        conn.execute(
+            "INSERT INTO notes (entry_id, note) VALUES (?1, ?2)
+            ON CONFLICT (entry_id) DO UPDATE SET note = ?2, updated_at = ?3",
+            params![entry_id, note, Utc::now()],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// an entry waiting to be played
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedEntry {
+    pub entry_id: EntryId,
+    pub title: Option<String>,
+    /// the entry's enclosure, or its link if it has none
+    pub link: String,
+}
+
+/// what there is to play in an entry: its enclosure, or its link if it has none
+pub fn get_media_link(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<Option<String>> {
+    let link = conn.query_row(
+        "SELECT COALESCE(enclosure, link) FROM entries WHERE id=?1",
+        [entry_id],
+        |row| row.get(0),
+    )?;
+
+    Ok(link)
+}
+
+/// the queue, in the order it plays in, without the entries that have been deleted since
+pub fn get_queue(conn: &rusqlite::Connection) -> Result<Vec<QueuedEntry>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT entries.id, entries.title, COALESCE(entries.enclosure, entries.link)
+        FROM queue
+        JOIN entries ON entries.id = queue.entry_id
+        WHERE entries.deleted_at IS NULL
+        AND {}
+        AND COALESCE(entries.enclosure, entries.link) IS NOT NULL
+        ORDER BY queue.id",
+        NOT_LEFT_OUT
+    ))?;
+
+    let mut queue = vec![];
+    for queued_entry in statement.query_map([], |row| {
+        Ok(QueuedEntry {
+            entry_id: row.get(0)?,
+            title: row.get(1)?,
+            link: row.get(2)?,
+        })
+    })? {
+        queue.push(queued_entry?)
+    }
+
+    Ok(queue)
+}
+
+/// Add the entry to the end of the queue, or take it out if it is already there.
+/// Returns whether it is in the queue now.
+pub fn toggle_queued(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<bool> {
+    if conn.execute("DELETE FROM queue WHERE entry_id=?1", [entry_id])? > 0 {
+        Ok(false)
+    } else {
+        conn.execute("INSERT INTO queue (entry_id) VALUES (?1)", [entry_id])?;
+        Ok(true)
+    }
+}
+
+pub fn dequeue(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<()> {
+    conn.execute("DELETE FROM queue WHERE entry_id=?1", [entry_id])?;
+    Ok(())
+}
+
+/// the token russ was given for a service like Pocket, from before tokens were kept in the keyring
+pub fn get_credential(conn: &rusqlite::Connection, service: &str) -> Result<Option<String>> {
+    let token = conn
+        .query_row(
+            "SELECT token FROM credentials WHERE service=?1",
+            [service],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(token)
+}
+
+/// once the token has been moved to the keyring
+pub fn delete_credential(conn: &rusqlite::Connection, service: &str) -> Result<()> {
+    conn.execute("DELETE FROM credentials WHERE service=?1", [service])?;
+
+    Ok(())
+}
+
+/// record that `feeds_len` feeds were just refreshed, at once
+pub fn record_refresh(conn: &rusqlite::Connection, feeds_len: usize) -> Result<()> {
+    conn.execute(
+        "INSERT INTO refreshes (feeds_len, refreshed_at) VALUES (?1, ?2)",
+        params![feeds_len, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// every time feeds were refreshed, oldest first
+pub fn get_refreshed_ats(conn: &rusqlite::Connection) -> Result<Vec<DateTime<Utc>>> {
+    let refreshed_ats = conn
+        .prepare("SELECT refreshed_at FROM refreshes ORDER BY refreshed_at ASC")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(refreshed_ats)
+}
+
+/// a stretch of an entries list, for loading a long one a bit at a time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    pub const ALL: Page = Page {
+        offset: 0,
+        limit: usize::MAX,
+    };
+}
+
+/// which of the entries to list, of the ones a list is for
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntriesFilter {
+    pub date_range: DateRange,
+    /// only the entries whose titles have this in them, ignoring case
+    pub title: Option<String>,
+}
+
+/// a window of days to list entries from, by when they were published,
+/// or added if they have no date
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DateRange {
+    /// from the start of this day
+    pub since: Option<chrono::NaiveDate>,
+    /// up to the start of this day
+    pub until: Option<chrono::NaiveDate>,
+}
+
+impl DateRange {
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// a human-readable description, for the UI
+    pub fn describe(&self) -> String {
+        let mut description = vec![];
+        if let Some(since) = self.since {
+            description.push(format!("since {}", since));
+        }
+        if let Some(until) = self.until {
+            description.push(format!("until {}", until));
+        }
+        description.join(" ")
+    }
+
+    /// the start of `date`, where russ is
+    fn start_of(date: chrono::NaiveDate) -> DateTime<Utc> {
+        date.and_hms_opt(0, 0, 0)
+            .expect("midnight")
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| date.and_hms_opt(0, 0, 0).expect("midnight").and_utc())
+    }
+}
+
+pub fn get_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    feed_id: FeedId,
+) -> Result<Vec<EntryMeta>> {
+    get_entries_metas_page(
+        conn,
+        read_mode,
+        entry_sort,
+        feed_id,
+        &EntriesFilter::default(),
+        Page::ALL,
+    )
+}
+
+pub fn get_entries_metas_page(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    feed_id: FeedId,
+    filter: &EntriesFilter,
+    page: Page,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        "feed_id=?1",
+        &[&feed_id],
+        filter,
+        page,
+    )
+}
+
+/// entries with the given tag, across all feeds
+pub fn get_tagged_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    tag: &str,
+) -> Result<Vec<EntryMeta>> {
+    get_tagged_entries_metas_page(
+        conn,
+        read_mode,
+        entry_sort,
+        tag,
+        &EntriesFilter::default(),
+        Page::ALL,
+    )
+}
+
+pub fn get_tagged_entries_metas_page(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    tag: &str,
+    filter: &EntriesFilter,
+    page: Page,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        "id IN (SELECT entry_id FROM entry_tags
+            JOIN tags ON tags.id = entry_tags.tag_id
+            WHERE tags.name = ?1)",
+        &[&tag],
+        filter,
+        page,
+    )
+}
+
+/// entries across all feeds, published since `since`, or added since, if they have no date
+pub fn get_entries_metas_since_page(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    since: DateTime<Utc>,
+    filter: &EntriesFilter,
+    page: Page,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        EntrySort::PubDateDesc,
+        "MIN(COALESCE(pub_date, inserted_at), inserted_at) >= ?1",
+        &[&since],
+        filter,
+        page,
+    )
+}
+
+/// entries across all feeds that have been read, most recently read first
+pub fn get_read_entries_metas_page(
+    conn: &rusqlite::Connection,
+    filter: &EntriesFilter,
+    page: Page,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        &ReadMode::All,
+        EntrySort::ReadAtDesc,
+        "read_at IS NOT NULL",
+        &[],
+        filter,
+        page,
+    )
+}
+
+/// starred entries across all feeds
+pub fn get_starred_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        "starred_at IS NOT NULL",
+        &[],
+        &EntriesFilter::default(),
+        Page::ALL,
+    )
+}
+
+/// `predicate` is a `WHERE` clause whose parameters are `params`
+fn query_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    predicate: &str,
+    params: &[&dyn rusqlite::ToSql],
+    filter: &EntriesFilter,
+    page: Page,
+) -> Result<Vec<EntryMeta>> {
+    let read_at_predicate = match read_mode {
+        ReadMode::ShowUnread => "\nAND read_at IS NULL",
+        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
+        ReadMode::All => "\n",
+    };
+
+    let mut query = format!(
+        "SELECT 
+        id, 
+        feed_id, 
+        title, 
+        author, 
+        pub_date, 
+        link, 
+        read_at, 
+        inserted_at, 
+        updated_at, 
+        starred_at, 
+        modified_at, 
+        categories, 
+        (SELECT GROUP_CONCAT(tags.name) FROM entry_tags
+          JOIN tags ON tags.id = entry_tags.tag_id
+          WHERE entry_tags.entry_id = entries.id),
+        LENGTH(COALESCE(full_content, content, description, '')),
+        comments_link,
+        highlight,
+        language
+        FROM entries 
+        WHERE {}
+        AND deleted_at IS NULL
+        AND {}",
+        predicate, NOT_LEFT_OUT
+    );
+
+    query.push_str(read_at_predicate);
+
+    let since = filter.date_range.since.map(DateRange::start_of);
+    let until = filter.date_range.until.map(DateRange::start_of);
+    let title = filter.title.as_ref().map(|title| title.to_lowercase());
+    let mut params = params.to_vec();
+    if let Some(since) = &since {
+        params.push(since);
+        query.push_str(&format!(
+            "\nAND MIN(COALESCE(pub_date, inserted_at), inserted_at) >= ?{}",
+            params.len()
+        ));
+    }
+    if let Some(until) = &until {
+        params.push(until);
+        query.push_str(&format!(
+            "\nAND MIN(COALESCE(pub_date, inserted_at), inserted_at) < ?{}",
+            params.len()
+        ));
+    }
+    if let Some(title) = &title {
+        params.push(title);
+        query.push_str(&format!(
+            "\nAND instr(lower_unicode(title), ?{}) > 0",
+            params.len()
+        ));
+    }
+
+    query.push_str(entry_sort.order_by());
+    // the page is bound rather than written into the query, so it doesn't need a statement of its own
+    query.push_str(&format!(
+        "\nLIMIT ?{} OFFSET ?{}",
+        params.len() + 1,
+        params.len() + 2
+    ));
+
+    let limit = i64::try_from(page.limit).unwrap_or(i64::MAX);
+    let offset = i64::try_from(page.offset).unwrap_or(i64::MAX);
+    params.push(&limit);
+    params.push(&offset);
+
+    // there are only a few predicates, read modes, and sorts, so each is its own cached statement
+    let mut statement = conn.prepare_cached(&query)?;
+    let mut entries = vec![];
+    for entry in statement.query_map(params.as_slice(), |row| {
+        Ok(EntryMeta {
+            id: row.get(0)?,
+            feed_id: row.get(1)?,
+            title: row.get(2)?,
+            author: row.get(3)?,
+            pub_date: row.get(4)?,
+            link: row.get(5)?,
+            read_at: row.get(6)?,
+            inserted_at: row.get(7)?,
+            updated_at: row.get(8)?,
+            starred_at: row.get(9)?,
+            modified_at: row.get(10)?,
+            categories: split_categories(row.get(11)?),
+            tags: split_tags(row.get(12)?),
+            content_len: row.get(13)?,
+            comments_link: row.get(14)?,
+            highlight: row.get(15)?,
+            language: row.get(16)?,
+        })
+    })? {
+        entries.push(entry?)
+    }
+
+    Ok(entries)
+}
+
+/// tags come out of the database as a single comma-separated string,
+/// which is why tags cannot contain commas
+/// Categories can have commas in them, unlike tags, so they are kept one per line.
+/// `None` when there are none, so entries without categories stay `NULL`.
+fn join_categories(categories: &[String]) -> Option<String> {
+    if categories.is_empty() {
+        None
+    } else {
+        Some(categories.join("\n"))
+    }
+}
+
+fn split_categories(categories: Option<String>) -> Vec<String> {
+    categories
+        .map(|categories| {
+            categories
+                .lines()
+                .map(|category| category.to_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    let mut tags = tags
+        .map(|tags| {
+            tags.split(',')
+                .map(|tag| tag.to_owned())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    tags.sort();
+    tags
+}
+
+/// every tag that is on at least one entry
+pub fn get_tags(conn: &rusqlite::Connection) -> Result<Vec<String>> {
+    let mut statement = conn.prepare(
+        "SELECT name FROM tags
+        WHERE EXISTS (SELECT 1 FROM entry_tags WHERE entry_tags.tag_id = tags.id)
+        ORDER BY name",
+    )?;
+
+    let tags = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(tags)
+}
+
+/// add or remove `tag` on all of `entry_ids`
+pub fn set_entries_tag(
+    conn: &mut rusqlite::Connection,
+    entry_ids: &[EntryId],
+    tag: &str,
+    tagged: bool,
+) -> Result<()> {
+    in_transaction(conn, |tx| {
+        tx.execute(
+            "INSERT INTO tags (name) VALUES (?1) ON CONFLICT (name) DO NOTHING",
+            [tag],
+        )?;
+
+        let tag_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", [tag], |row| {
+            row.get(0)
+        })?;
+
+        let query = if tagged {
+            "INSERT INTO entry_tags (entry_id, tag_id) VALUES (?1, ?2)
+            ON CONFLICT (entry_id, tag_id) DO NOTHING"
+        } else {
+            "DELETE FROM entry_tags WHERE entry_id = ?1 AND tag_id = ?2"
+        };
+
+        let mut statement = tx.prepare(query)?;
+
+        for entry_id in entry_ids {
+            statement.execute([*entry_id, tag_id])?;
+        }
+
+        Ok(())
+    })
+}
+
+/// the number of unread entries and the total number of entries with a tag
+pub fn get_tag_entry_counts(conn: &rusqlite::Connection, tag: &str) -> Result<(usize, usize)> {
+    let counts = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FILTER (WHERE read_at IS NULL), COUNT(*) FROM entries
+            WHERE id IN (SELECT entry_id FROM entry_tags
+                JOIN tags ON tags.id = entry_tags.tag_id
+                WHERE tags.name = ?1)
+            AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_row([tag], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(counts)
+}
+
+/// the number of unread entries and the total number of entries across all feeds since `since`
+pub fn get_entry_counts_since(
+    conn: &rusqlite::Connection,
+    since: DateTime<Utc>,
+) -> Result<(usize, usize)> {
+    let counts = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FILTER (WHERE read_at IS NULL), COUNT(*) FROM entries
+            WHERE MIN(COALESCE(pub_date, inserted_at), inserted_at) >= ?1
+            AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_row([since], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(counts)
+}
+
+/// the number of entries that have been read, in every feed
+pub fn get_read_entry_count(conn: &rusqlite::Connection) -> Result<usize> {
+    let count = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FROM entries WHERE read_at IS NOT NULL AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_row([], |row| row.get(0))?;
+
+    Ok(count)
+}
+
+/// the number of unread entries and the total number of entries in a feed
+pub fn get_feed_entry_counts(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+) -> Result<(usize, usize)> {
+    let counts = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FILTER (WHERE read_at IS NULL), COUNT(*) FROM entries
+            WHERE feed_id=?1 AND deleted_at IS NULL AND {}",
+            NOT_LEFT_OUT
+        ))?
+        .query_row([feed_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(counts)
+}
+
+pub fn get_entries_links(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    feed_id: FeedId,
+) -> Result<Vec<Option<String>>> {
+    let read_at_predicate = match read_mode {
+        ReadMode::ShowUnread => "\nAND read_at IS NULL",
+        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
+        ReadMode::All => "\n",
+    };
+
+    let mut query = "SELECT link FROM entries WHERE feed_id=?1".to_string();
+
+    query.push_str(read_at_predicate);
+    query.push_str(EntrySort::PubDateDesc.order_by());
+
+    let mut links = vec![];
+    let mut statement = conn.prepare_cached(&query)?;
+
+    for link in statement.query_map([feed_id], |row| row.get(0))? {
+        links.push(link?);
+    }
+
+    Ok(links)
+}
+
+/// run `f` in a transaction, committing if `f` returns an `Ok` value,
+/// otherwise rolling back.
+fn in_transaction<F, R>(conn: &mut rusqlite::Connection, f: F) -> Result<R>
+where
+    F: FnOnce(&rusqlite::Transaction) -> Result<R>,
+{
+    let tx = conn.transaction()?;
+
+    let result = f(&tx)?;
+
+    tx.commit()?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const ZCT: &str = "https://zeroclarkthirty.com/feed";
+
+    struct FeedAndEntries {
+        feed: Feed,
+        entries: Vec<Entry>,
+    }
+
+    impl FromStr for FeedAndEntries {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut entries = vec![];
+            let channel = crate::parse::parse(s.as_bytes(), |batch| {
+                entries.extend(batch);
+                Ok(())
+            })?;
+
+            Ok(FeedAndEntries {
+                feed: Feed::from_channel(&channel),
+                entries,
+            })
+        }
+    }
+
+    #[test]
+    fn it_fetches() {
+        let http_client = ureq::AgentBuilder::new()
+            .timeout_read(std::time::Duration::from_secs(5))
+            .build();
+        let mut entries_len = 0;
+        fetch_source(&http_client, ZCT, &[])
+            .unwrap()
+            .read(&mut |entries| {
+                entries_len += entries.len();
+                Ok(())
+            })
+            .unwrap();
+        assert!(entries_len > 0)
+    }
+
+    #[test]
+    fn it_subscribes_to_a_feed() {
+        let http_client = ureq::AgentBuilder::new()
+            .timeout_read(std::time::Duration::from_secs(5))
+            .build();
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        subscribe_to_feed(&http_client, &mut conn, &Default::default(), ZCT).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .unwrap();
+
+        assert!(count > 50)
+    }
+
+    #[test]
+    fn refresh_feed_does_not_add_any_items_if_there_are_no_new_items() {
+        let http_client = ureq::AgentBuilder::new()
+            .timeout_read(std::time::Duration::from_secs(5))
+            .build();
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        subscribe_to_feed(&http_client, &mut conn, &Default::default(), ZCT).unwrap();
+        let feed_id = 1;
+        let old_entries = get_entries_metas(
+            &conn,
+            &ReadMode::ShowUnread,
+            EntrySort::PubDateDesc,
+            feed_id,
+        )
+        .unwrap();
+        let keyring = crate::keyring::Keyring::in_file(
+            std::env::temp_dir().join("russ-test-refresh.secrets"),
+            "test",
+        );
+        refresh_feed(
+            &http_client,
+            &mut conn,
+            &keyring,
+            &Default::default(),
+            feed_id,
+        )
+        .unwrap();
+        let e = get_entry_meta(&conn, 1).unwrap();
+        e.mark_as_read(&conn).unwrap();
+        let new_entries = get_entries_metas(
+            &conn,
+            &ReadMode::ShowUnread,
+            EntrySort::PubDateDesc,
+            feed_id,
+        )
+        .unwrap();
+
+        assert_eq!(new_entries.len(), old_entries.len() - 1);
+    }
+
+    /// subscribe to a small feed without going to the network
+    fn create_test_feed(conn: &mut rusqlite::Connection) -> FeedId {
+        let feed_and_entries = FeedAndEntries::from_str(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Test</title>
+                <link>https://example.com</link>
+                <description>test</description>
+                <item>
+                  <title>b</title>
+                  <link>https://example.com/b</link>
+                  <comments>https://example.com/b#comments</comments>
+                  <enclosure url="https://example.com/b.mp3" length="1" type="audio/mpeg"/>
+                  <category>rust</category>
+                  <category>tea, coffee</category>
+                  <pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate>
+                </item>
+                <item>
+                  <title>a</title>
+                  <link>https://example.com/a</link>
+                  <pubDate>Tue, 02 Jan 2024 00:00:00 +0000</pubDate>
+                </item>
+              </channel>
+            </rss>"#,
+        )
+        .unwrap();
+
+        in_transaction(conn, |tx| {
+            let feed_id = create_feed(tx, &feed_and_entries.feed)?;
+            add_entries_to_feed(tx, feed_id, &feed_and_entries.entries)?;
+            Ok(feed_id)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn entry_categories_are_kept() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        let entries =
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::PubDateAsc, feed_id).unwrap();
+
+        assert_eq!(entries[0].categories, vec!["rust", "tea, coffee"]);
+        assert!(entries[1].categories.is_empty());
+    }
+
+    #[test]
+    fn comments_links_are_kept() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        let entries =
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::PubDateAsc, feed_id).unwrap();
+
+        assert_eq!(
+            entries[0].comments_link.as_deref(),
+            Some("https://example.com/b#comments")
+        );
+        assert_eq!(entries[1].comments_link, None);
+
+        let feed_and_entries = FeedAndEntries::from_str(
+            r#"<?xml version="1.0"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Test</title>
+              <id>urn:test</id>
+              <updated>2024-01-01T00:00:00Z</updated>
+              <entry>
+                <title>a</title>
+                <id>urn:test:a</id>
+                <updated>2024-01-01T00:00:00Z</updated>
+                <link rel="replies" href="https://example.com/a/comments"/>
+                <link href="https://example.com/a"/>
+              </entry>
+            </feed>"#,
+        )
+        .unwrap();
+
+        let entry = &feed_and_entries.entries[0];
+        assert_eq!(entry.link.as_deref(), Some("https://example.com/a"));
+        assert_eq!(
+            entry.comments_link.as_deref(),
+            Some("https://example.com/a/comments")
+        );
+    }
+
+    #[test]
+    fn the_queue_plays_enclosures_in_order() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let entries =
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::PubDateAsc, feed_id).unwrap();
+
+        assert!(toggle_queued(&conn, entries[1].id).unwrap());
+        assert!(toggle_queued(&conn, entries[0].id).unwrap());
+
+        let links = |conn: &rusqlite::Connection| {
+            get_queue(conn)
+                .unwrap()
+                .into_iter()
+                .map(|queued_entry| queued_entry.link)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            links(&conn),
+            vec!["https://example.com/a", "https://example.com/b.mp3"]
+        );
+
+        assert!(!toggle_queued(&conn, entries[1].id).unwrap());
+        dequeue(&conn, entries[0].id).unwrap();
+        assert!(links(&conn).is_empty());
+    }
+
+    #[test]
+    fn paused_feeds_and_feeds_with_intervals_wait() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        assert_eq!(get_due_feed_ids(&conn).unwrap(), vec![feed_id]);
+        assert!(get_scheduled_feed_ids(&conn).unwrap().is_empty());
+
+        set_feed_refresh_interval(&conn, feed_id, Some(chrono::Duration::hours(1))).unwrap();
+        assert_eq!(get_scheduled_feed_ids(&conn).unwrap(), vec![feed_id]);
+
+        in_transaction(&mut conn, |tx| update_feed_refreshed_at(tx, feed_id)).unwrap();
+        assert!(get_due_feed_ids(&conn).unwrap().is_empty());
+        assert!(get_scheduled_feed_ids(&conn).unwrap().is_empty());
+
+        set_feed_refresh_interval(&conn, feed_id, None).unwrap();
+        assert_eq!(get_due_feed_ids(&conn).unwrap(), vec![feed_id]);
+
+        toggle_feed_paused(&conn, feed_id).unwrap();
+        assert!(get_feed(&conn, feed_id).unwrap().paused);
+        assert!(get_due_feed_ids(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn failing_feeds_are_backed_off() {
+        assert_eq!(backoff(1), None);
+        assert_eq!(backoff(2), Some(chrono::Duration::minutes(15)));
+        assert_eq!(backoff(4), Some(chrono::Duration::hours(1)));
+        assert_eq!(backoff(100), Some(chrono::Duration::days(1)));
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        record_feed_failure(&conn, feed_id, &anyhow::anyhow!("timed out")).unwrap();
+        assert_eq!(get_due_feed_ids(&conn).unwrap(), vec![feed_id]);
+
+        record_feed_failure(&conn, feed_id, &anyhow::anyhow!("not found")).unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.failures, 2);
+        assert_eq!(feed.last_error.as_deref(), Some("not found"));
+        assert!(feed.backoff_until().is_some());
+        assert!(get_due_feed_ids(&conn).unwrap().is_empty());
+
+        in_transaction(&mut conn, |tx| update_feed_refreshed_at(tx, feed_id)).unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.backoff_until(), None);
+        assert_eq!(feed.last_error, None);
+        assert_eq!(get_due_feed_ids(&conn).unwrap(), vec![feed_id]);
+    }
+
+    #[test]
+    fn feeds_that_say_how_long_to_cache_them_are_left_alone() {
+        assert_eq!(
+            cache_control_max_age("public, max-age=3600"),
+            Some(chrono::Duration::hours(1))
+        );
+        assert_eq!(cache_control_max_age("max-age=3600, no-cache"), None);
+        assert_eq!(cache_control_max_age("private"), None);
+
+        let feed_and_entries = FeedAndEntries::from_str(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+              <channel>
+                <title>Test</title>
+                <link>https://example.com</link>
+                <description>test</description>
+                <ttl>60</ttl>
+                <sy:updatePeriod>daily</sy:updatePeriod>
+                <sy:updateFrequency>2</sy:updateFrequency>
+              </channel>
+            </rss>"#,
+        )
+        .unwrap();
+        let next_refresh_at = feed_and_entries.feed.next_refresh_at.unwrap();
+        assert!(next_refresh_at > Utc::now() + chrono::Duration::hours(11));
+        assert!(next_refresh_at <= Utc::now() + chrono::Duration::hours(12));
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id =
+            in_transaction(&mut conn, |tx| create_feed(tx, &feed_and_entries.feed)).unwrap();
+        assert!(get_due_feed_ids(&conn).unwrap().is_empty());
+        assert_eq!(
+            get_feed(&conn, feed_id).unwrap().next_refresh_at,
+            Some(next_refresh_at)
+        );
+    }
+
+    #[test]
+    fn feed_headers_are_set_and_removed() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let path =
+            std::env::temp_dir().join(format!("russ-test-headers-{}.secrets", std::process::id()));
+        let keyring = crate::keyring::Keyring::in_file(path.clone(), "test");
+
+        set_feed_header(&conn, &keyring, feed_id, "Authorization", Some("Bearer a")).unwrap();
+        set_feed_header(&conn, &keyring, feed_id, "accept-language", Some("en")).unwrap();
+        set_feed_header(&conn, &keyring, feed_id, "authorization", Some("Bearer b")).unwrap();
+        assert_eq!(
+            get_feed_headers(&conn, &keyring, feed_id).unwrap(),
+            vec![
+                ("accept-language".to_string(), "en".to_string()),
+                ("authorization".to_string(), "Bearer b".to_string()),
+            ]
+        );
+        // only the names are in the database
+        let values: Vec<String> = conn
+            .prepare("SELECT value FROM feed_headers")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values, vec!["".to_string(), "".to_string()]);
+
+        set_feed_header(&conn, &keyring, feed_id, "Accept-Language", None).unwrap();
+        assert_eq!(
+            get_feed_header_names(&conn, feed_id).unwrap(),
+            vec!["authorization"]
+        );
+        assert_eq!(
+            keyring
+                .get(&feed_header_secret(feed_id, "accept-language"))
+                .unwrap(),
+            None
+        );
+        assert!(set_feed_header(&conn, &keyring, feed_id, "Bad Name", Some("x")).is_err());
+
+        let deleted_feed = delete_feed(&mut conn, feed_id).unwrap();
+        assert!(get_feed_headers(&conn, &keyring, feed_id)
+            .unwrap()
+            .is_empty());
+        restore_feed(&mut conn, &deleted_feed).unwrap();
+        assert_eq!(
+            get_feed_headers(&conn, &keyring, feed_id).unwrap(),
+            vec![("authorization".to_string(), "Bearer b".to_string())]
+        );
+
+        // from before the values were kept in the keyring
+        conn.execute(
+            "INSERT INTO feed_headers (feed_id, name, value) VALUES (?1, 'Cookie', 'a=b')",
+            [feed_id],
+        )
+        .unwrap();
+        assert_eq!(
+            get_feed_headers(&conn, &keyring, feed_id).unwrap(),
+            vec![
+                ("authorization".to_string(), "Bearer b".to_string()),
+                ("Cookie".to_string(), "a=b".to_string()),
+            ]
+        );
+        assert_eq!(
+            conn.query_row(
+                "SELECT COUNT(*) FROM feed_headers WHERE value != ''",
+                [],
+                |row| { row.get::<_, i64>(0) }
+            )
+            .unwrap(),
+            0
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn feed_certificates_are_pinned_and_unpinned() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let fingerprint = "ab".repeat(32);
+
+        let pinned = set_feed_certificate_fingerprint(&conn, feed_id, &fingerprint).unwrap();
+        assert_eq!(
+            get_feed_certificate_fingerprint(&conn, feed_id).unwrap(),
+            pinned
+        );
+        assert_eq!(
+            get_feed(&conn, feed_id)
+                .unwrap()
+                .certificate_fingerprint
+                .as_deref(),
+            Some(vec!["AB"; 32].join(":").as_str())
+        );
+
+        assert!(set_feed_certificate_fingerprint(&conn, feed_id, "abc").is_err());
+        set_feed_certificate_fingerprint(&conn, feed_id, " ").unwrap();
+        assert_eq!(
+            get_feed_certificate_fingerprint(&conn, feed_id).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn databases_are_opened_with_a_write_ahead_log() {
+        let path = std::env::temp_dir().join(format!("russ-test-{}.db", std::process::id()));
+        let mut conn = open_db(&path, None).unwrap();
+        initialize_db(&mut conn).unwrap();
+
+        let journal_mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        let busy_timeout: i64 = conn
+            .pragma_query_value(None, "busy_timeout", |row| row.get(0))
+            .unwrap();
+        assert_eq!(busy_timeout, BUSY_TIMEOUT.as_millis() as i64);
+
+        drop(conn);
+        for extension in ["db", "db-wal", "db-shm"] {
+            let _ = std::fs::remove_file(path.with_extension(extension));
+        }
+    }
+
+    #[test]
+    fn databases_are_backed_up_and_restored() {
+        let path = std::env::temp_dir().join(format!("russ-backup-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        backup_db(&conn, &path, None).unwrap();
+        assert!(backup_db(&conn, &path, None).is_err());
+
+        delete_feed(&mut conn, feed_id).unwrap();
+        assert!(get_feeds(&conn).unwrap().is_empty());
+
+        restore_db(&mut conn, &path, None).unwrap();
+        assert_eq!(get_feeds(&conn).unwrap().len(), 1);
+        assert_eq!(get_feed_entry_counts(&conn, feed_id).unwrap(), (2, 2));
+
+        std::fs::write(&path, "not a database").unwrap();
+        assert!(restore_db(&mut conn, &path, None).is_err());
+        assert_eq!(get_feeds(&conn).unwrap().len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn feeds_can_be_retitled_and_moved() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        set_feed_custom_title(&conn, feed_id, " Better title ").unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Better title"));
+        assert_eq!(feed.custom_title.as_deref(), Some("Better title"));
+        assert_eq!(get_feeds(&conn).unwrap()[0].title, feed.title);
+
+        set_feed_custom_title(&conn, feed_id, "").unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Test"));
+        assert_eq!(feed.custom_title, None);
+
+        set_feed_url(&conn, feed_id, "https://example.org/feed").unwrap();
+        assert_eq!(
+            get_feed_url(&conn, feed_id).unwrap(),
+            "https://example.org/feed"
+        );
+        assert!(set_feed_url(&conn, feed_id, " ").is_err());
+    }
+
+    #[test]
+    fn feeds_can_be_put_in_order_and_pinned() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        for title in ["c", "a", "b"] {
+            conn.execute(
+                "INSERT INTO feeds (title, feed_link, feed_kind) VALUES (?1, ?1, 'RSS')",
+                [title],
+            )
+            .unwrap();
+        }
+
+        let titles = |conn: &rusqlite::Connection| {
+            get_feeds(conn)
+                .unwrap()
+                .into_iter()
+                .flat_map(|feed| feed.title)
+                .collect::<Vec<_>>()
+        };
+        let feed_id = |conn: &rusqlite::Connection, title: &str| {
+            get_feeds(conn)
+                .unwrap()
+                .into_iter()
+                .find(|feed| feed.title.as_deref() == Some(title))
+                .unwrap()
+                .id
+        };
+        assert_eq!(titles(&conn), vec!["a", "b", "c"]);
+
+        let (b, c) = (feed_id(&conn, "b"), feed_id(&conn, "c"));
+        swap_feeds(&mut conn, c, b).unwrap();
+        assert_eq!(titles(&conn), vec!["a", "c", "b"]);
+
+        // new feeds go after the ones in order
+        conn.execute(
+            "INSERT INTO feeds (title, feed_link, feed_kind) VALUES ('0', '0', 'RSS')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(titles(&conn), vec!["a", "c", "b", "0"]);
+
+        toggle_feed_pinned(&conn, b).unwrap();
+        assert!(get_feed(&conn, b).unwrap().pinned);
+        assert_eq!(titles(&conn), vec!["b", "a", "c", "0"]);
+    }
+
+    #[test]
+    fn feeds_are_sorted_by_activity() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES
+                (1, 'a', 'a', 'RSS'), (2, 'b', 'b', 'RSS'), (3, 'c', 'c', 'RSS');
+            INSERT INTO entries (feed_id, title, pub_date, read_at) VALUES
+                (2, 'x', '2024-01-01 00:00:00+00:00', NULL),
+                (2, 'y', '2024-01-02 00:00:00+00:00', NULL),
+                (3, 'z', '2024-02-01 00:00:00+00:00', NULL),
+                (1, 'w', '2023-01-01 00:00:00+00:00', '2023-01-02 00:00:00+00:00');",
+        )
+        .unwrap();
+
+        let titles = |feed_sort| {
+            get_sorted_feeds(&conn, feed_sort)
+                .unwrap()
+                .into_iter()
+                .flat_map(|feed| feed.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(FeedSort::Title), vec!["a", "b", "c"]);
+        assert_eq!(titles(FeedSort::Recent), vec!["c", "b", "a"]);
+        assert_eq!(titles(FeedSort::Unread), vec!["b", "c", "a"]);
+
+        toggle_feed_pinned(&conn, 1).unwrap();
+        assert_eq!(titles(FeedSort::Unread), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn virtual_feeds_list_entries_across_feeds() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let now = Utc::now();
+        conn.execute_batch(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES
+                (1, 'a', 'a', 'RSS'), (2, 'b', 'b', 'RSS');",
+        )
+        .unwrap();
+        for (feed_id, title, pub_date) in [
+            (1, "old", Some(now - chrono::Duration::days(10))),
+            (1, "new", Some(now - chrono::Duration::hours(1))),
+            (2, "newer", Some(now - chrono::Duration::minutes(1))),
+            (2, "undated", None),
+        ] {
+            conn.execute(
+                "INSERT INTO entries (feed_id, title, pub_date, inserted_at) VALUES (?1, ?2, ?3, ?4)",
+                params![feed_id, title, pub_date, now],
+            )
+            .unwrap();
+        }
+
+        let since = now - chrono::Duration::days(7);
+        let titles = get_entries_metas_since_page(
+            &conn,
+            &ReadMode::All,
+            since,
+            &EntriesFilter::default(),
+            Page::ALL,
+        )
+        .unwrap()
+        .into_iter()
+        .flat_map(|entry| entry.title)
+        .collect::<Vec<_>>();
+        assert_eq!(titles.len(), 3);
+        assert!(!titles.contains(&"old".to_string()));
+        assert_eq!(get_entry_counts_since(&conn, since).unwrap(), (3, 3));
+
+        for (entry_id, read_at) in [(1, now - chrono::Duration::days(1)), (3, now)] {
+            conn.execute(
+                "UPDATE entries SET read_at = ?2 WHERE id = ?1",
+                params![entry_id, read_at],
+            )
+            .unwrap();
+        }
+        let titles = get_read_entries_metas_page(&conn, &EntriesFilter::default(), Page::ALL)
+            .unwrap()
+            .into_iter()
+            .flat_map(|entry| entry.title)
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["newer", "old"]);
+        assert_eq!(get_read_entry_count(&conn).unwrap(), 2);
+
+        // digging through a feed's archive, a few days at a time
+        let five_days_ago = (now - chrono::Duration::days(5))
+            .with_timezone(&chrono::Local)
+            .date_naive();
+        let titles = |date_range| {
+            get_entries_metas_page(
+                &conn,
+                &ReadMode::All,
+                EntrySort::PubDateDesc,
+                1,
+                &EntriesFilter {
+                    date_range,
+                    title: None,
+                },
+                Page::ALL,
+            )
+            .unwrap()
+            .into_iter()
+            .flat_map(|entry| entry.title)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            titles(DateRange {
+                since: None,
+                until: Some(five_days_ago)
+            }),
+            vec!["old"]
+        );
+        assert_eq!(
+            titles(DateRange {
+                since: Some(five_days_ago),
+                until: None
+            }),
+            vec!["new"]
+        );
+        assert!(titles(DateRange {
+            since: Some(five_days_ago),
+            until: Some(five_days_ago)
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn get_entries_metas_sorts_entries() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+
+        let titles = |entry_sort| {
+            get_entries_metas(&conn, &ReadMode::All, entry_sort, feed_id)
+                .unwrap()
+                .into_iter()
+                .flat_map(|entry| entry.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(EntrySort::PubDateDesc), vec!["a", "b"]);
+        assert_eq!(titles(EntrySort::PubDateAsc), vec!["b", "a"]);
+        assert_eq!(titles(EntrySort::Title), vec!["a", "b"]);
+
+        // undated, and dated years after it was added, but it was added between a and b
+        conn.execute(
+            "INSERT INTO entries (feed_id, title, inserted_at) VALUES (?1, 'undated', ?2)",
+            params![feed_id, "2024-01-01 13:00:00"],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO entries (feed_id, title, pub_date, inserted_at) VALUES (?1, 'future', ?2, ?3)",
+            params![feed_id, "2099-01-01 00:00:00+00:00", "2024-01-01 12:00:00"],
+        )
+        .unwrap();
+        assert_eq!(
+            titles(EntrySort::PubDateDesc),
+            vec!["a", "undated", "future", "b"]
+        );
+
+        let entries =
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::PubDateDesc, feed_id).unwrap();
+        assert!(!entries[0].is_future_dated());
+        assert!(entries[2].is_future_dated());
+    }
+
+    #[test]
+    fn entries_are_listed_a_page_at_a_time() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
 
-fn update_feed_refreshed_at(tx: &rusqlite::Transaction, feed_id: FeedId) -> Result<()> {
-    tx.execute(
-        "UPDATE feeds SET refreshed_at = ?2 WHERE id = ?1",
-        params![feed_id, Utc::now()],
-    )?;
+        let titles = |offset, limit| {
+            get_entries_metas_page(
+                &conn,
+                &ReadMode::All,
+                EntrySort::PubDateAsc,
+                feed_id,
+                &EntriesFilter::default(),
+                Page { offset, limit },
+            )
+            .unwrap()
+            .into_iter()
+            .flat_map(|entry| entry.title)
+            .collect::<Vec<_>>()
+        };
 
-    Ok(())
-}
+        assert_eq!(titles(0, 1), vec!["b"]);
+        assert_eq!(titles(1, 1), vec!["a"]);
+        assert_eq!(titles(1, 10), vec!["a"]);
+        assert!(titles(2, 10).is_empty());
+        assert_eq!(titles(0, usize::MAX), vec!["b", "a"]);
+    }
 
-pub fn get_feed_url(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<String> {
-    let s: String = conn.query_row(
-        "SELECT feed_link FROM feeds WHERE id=?1",
-        [feed_id],
-        |row| row.get(0),
-    )?;
+    #[test]
+    fn entries_are_filtered_by_title_before_they_are_paged() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        configure_db(&conn).unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        conn.execute(
+            "INSERT INTO entries (feed_id, title) VALUES (?1, 'Über Straße'), (?1, 'ÜBER')",
+            [feed_id],
+        )
+        .unwrap();
 
-    Ok(s)
-}
+        let titles = |title: &str, offset| {
+            get_entries_metas_page(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Title,
+                feed_id,
+                &EntriesFilter {
+                    date_range: DateRange::default(),
+                    title: Some(title.to_string()),
+                },
+                Page { offset, limit: 1 },
+            )
+            .unwrap()
+            .into_iter()
+            .flat_map(|entry| entry.title)
+            .collect::<Vec<_>>()
+        };
 
-pub fn get_feeds(conn: &rusqlite::Connection) -> Result<Vec<Feed>> {
-    let mut statement = conn.prepare(
-        "SELECT 
-          id, 
-          title, 
-          feed_link, 
-          link, 
-          feed_kind, 
-          refreshed_at, 
-          inserted_at, 
-          updated_at 
-        FROM feeds ORDER BY lower(title) ASC",
-    )?;
-    let mut feeds = vec![];
-    for feed in statement.query_map([], |row| {
-        Ok(Feed {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            feed_link: row.get(2)?,
-            link: row.get(3)?,
-            feed_kind: row.get(4)?,
-            refreshed_at: row.get(5)?,
-            inserted_at: row.get(6)?,
-            updated_at: row.get(7)?,
-        })
-    })? {
-        feeds.push(feed?)
+        // the first page has a match even though the feed's first entry doesn't
+        assert_eq!(titles("über", 0), vec!["ÜBER"]);
+        assert_eq!(titles("über", 1), vec!["Über Straße"]);
+        assert!(titles("über", 2).is_empty());
+        assert_eq!(titles("STRASSE", 0), Vec::<String>::new());
+        assert_eq!(titles("straße", 0), vec!["Über Straße"]);
     }
 
-    Ok(feeds)
-}
+    #[test]
+    fn duplicate_feeds_are_refused() {
+        assert_eq!(
+            normalize_feed_url("HTTPS://Example.com:443/feed/#top"),
+            normalize_feed_url("http://example.com/feed")
+        );
+        assert_eq!(
+            normalize_feed_url("https://example.com/?feed=rss2"),
+            "example.com?feed=rss2"
+        );
+        assert_ne!(
+            normalize_feed_url("https://example.com/feed"),
+            normalize_feed_url("https://example.com/Feed")
+        );
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_and_entries = FeedAndEntries::from_str(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <id>urn:uuid:60a76c80-d399-11d9-b91C-0003939e0af6</id>
+              <title>Atom</title>
+            </feed>"#,
+        )
+        .unwrap();
+        let mut feed = feed_and_entries.feed;
+        feed.feed_link = Some("https://example.com/feed/".to_string());
+        let feed_id = in_transaction(&mut conn, |tx| create_feed(tx, &feed)).unwrap();
+        in_transaction(&mut conn, |tx| update_remote_feed(tx, feed_id, &feed)).unwrap();
 
-pub fn get_feed_ids(conn: &rusqlite::Connection) -> Result<Vec<FeedId>> {
-    let mut statement = conn.prepare("SELECT id FROM feeds ORDER BY lower(title) ASC")?;
-    let mut ids = vec![];
-    for id in statement.query_map([], |row| row.get(0))? {
-        ids.push(id?)
+        assert!(refuse_duplicate_feed(&conn, "http://EXAMPLE.com/feed", None, None).is_err());
+        assert!(
+            refuse_duplicate_feed(&conn, "https://example.com/feed", None, Some(feed_id)).is_ok()
+        );
+        assert!(refuse_duplicate_feed(&conn, "https://example.org/feed", None, None).is_ok());
+        assert!(refuse_duplicate_feed(
+            &conn,
+            "https://example.org/feed",
+            feed.guid.as_deref(),
+            None
+        )
+        .is_err());
     }
 
-    Ok(ids)
-}
+    #[test]
+    fn unread_entries_are_counted() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let other_feed_id = create_test_feed(&mut conn);
+        assert_eq!(get_unread_count(&conn).unwrap(), 4);
 
-pub fn get_entry_meta(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryMeta> {
-    let result = conn.query_row(
-        "SELECT 
-          id, 
-          feed_id, 
-          title, 
-          author, 
-          pub_date, 
-          link, 
-          read_at, 
-          inserted_at, 
-          updated_at 
-        FROM entries WHERE id=?1",
-        [entry_id],
-        |row| {
-            Ok(EntryMeta {
-                id: row.get(0)?,
-                feed_id: row.get(1)?,
-                title: row.get(2)?,
-                author: row.get(3)?,
-                pub_date: row.get(4)?,
-                link: row.get(5)?,
-                read_at: row.get(6)?,
-                inserted_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        },
-    )?;
+        mark_feed_as_read(&mut conn, other_feed_id, None).unwrap();
+        assert_eq!(get_unread_count(&conn).unwrap(), 2);
+        assert_eq!(
+            get_unread_counts(&conn).unwrap(),
+            HashMap::from([(feed_id, 2)])
+        );
 
-    Ok(result)
-}
+        // only what's older than the 2nd, like after being away
+        let before = "2024-01-02T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            mark_feed_as_read(&mut conn, feed_id, Some(before))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            get_unread_counts(&conn).unwrap(),
+            HashMap::from([(feed_id, 1)])
+        );
+    }
 
-pub fn get_entry_content(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryContent> {
-    let result = conn.query_row(
-        "SELECT content, description FROM entries WHERE id=?1",
-        [entry_id],
-        |row| {
-            Ok(EntryContent {
-                content: row.get(0)?,
-                description: row.get(1)?,
-            })
-        },
-    )?;
+    #[test]
+    fn entries_on_blocked_domains_are_hidden_until_unblocked() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let entries_len = |conn: &rusqlite::Connection| {
+            get_entries_metas(conn, &ReadMode::All, EntrySort::Title, feed_id)
+                .unwrap()
+                .len()
+        };
 
-    Ok(result)
-}
+        assert_eq!(
+            block_domain(&conn, "https://www.Example.com/").unwrap(),
+            "example.com"
+        );
+        assert!(block_domain(&conn, " ").is_err());
+        assert_eq!(get_blocked_domains(&conn).unwrap(), vec!["example.com"]);
+        assert_eq!(
+            get_entry_ids_on_domain(&conn, "example.com").unwrap().len(),
+            2
+        );
+        assert!(get_entry_ids_on_domain(&conn, "ample.com")
+            .unwrap()
+            .is_empty());
+        assert_eq!(entries_len(&conn), 0);
+        assert_eq!(get_unread_count(&conn).unwrap(), 0);
 
-pub fn get_entries_metas(
-    conn: &rusqlite::Connection,
-    read_mode: &ReadMode,
-    feed_id: FeedId,
-) -> Result<Vec<EntryMeta>> {
-    let read_at_predicate = match read_mode {
-        ReadMode::ShowUnread => "\nAND read_at IS NULL",
-        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
-        ReadMode::All => "\n",
-    };
+        assert!(unblock_domain(&conn, "example.com").unwrap());
+        assert!(!unblock_domain(&conn, "example.com").unwrap());
+        assert_eq!(entries_len(&conn), 2);
 
-    // we get weird pubDate formats from feeds,
-    // so sort by inserted at as this as a stable order at least
-    let mut query = "SELECT 
-        id, 
-        feed_id, 
-        title, 
-        author, 
-        pub_date, 
-        link, 
-        read_at, 
-        inserted_at, 
-        updated_at 
-        FROM entries 
-        WHERE feed_id=?1"
-        .to_string();
+        // entries from before hosts were kept get theirs when the database is migrated
+        conn.execute("UPDATE entries SET host = NULL", []).unwrap();
+        block_domain(&conn, "sub.example.com").unwrap();
+        assert_eq!(entries_len(&conn), 2);
+        in_transaction(&mut conn, set_entries_hosts).unwrap();
+        assert_eq!(entries_len(&conn), 2);
+        block_domain(&conn, "example.com").unwrap();
+        assert_eq!(entries_len(&conn), 0);
+    }
 
-    query.push_str(read_at_predicate);
-    query.push_str("\nORDER BY pub_date DESC, inserted_at DESC");
+    #[test]
+    fn migrating_fills_in_the_hosts_of_entries_already_there() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        create_test_feed(&mut conn);
 
-    let mut statement = conn.prepare(&query)?;
-    let mut entries = vec![];
-    for entry in statement.query_map([feed_id], |row| {
-        Ok(EntryMeta {
-            id: row.get(0)?,
-            feed_id: row.get(1)?,
-            title: row.get(2)?,
-            author: row.get(3)?,
-            pub_date: row.get(4)?,
-            link: row.get(5)?,
-            read_at: row.get(6)?,
-            inserted_at: row.get(7)?,
-            updated_at: row.get(8)?,
-        })
-    })? {
-        entries.push(entry?)
+        // like a database from before hosts were kept
+        conn.execute_batch(&format!(
+            "ALTER TABLE entries DROP COLUMN host;
+            ALTER TABLE feeds DROP COLUMN last_error;
+            PRAGMA user_version = {};",
+            ENTRIES_HOST_MIGRATION - 1
+        ))
+        .unwrap();
+
+        initialize_db(&mut conn).unwrap();
+
+        let hosts = conn
+            .prepare("SELECT host FROM entries")
+            .unwrap()
+            .query_map([], |row| row.get::<_, Option<String>>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(hosts.len(), 2);
+        assert!(hosts
+            .iter()
+            .all(|host| host.as_deref() == Some("example.com")));
     }
 
-    Ok(entries)
-}
+    #[test]
+    fn entries_in_muted_languages_are_hidden_until_unmuted() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
 
-pub fn get_entries_links(
-    conn: &rusqlite::Connection,
-    read_mode: &ReadMode,
-    feed_id: FeedId,
-) -> Result<Vec<Option<String>>> {
-    let read_at_predicate = match read_mode {
-        ReadMode::ShowUnread => "\nAND read_at IS NULL",
-        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
-        ReadMode::All => "\n",
-    };
+        let mut entry = Entry::from(&rss::Item::default());
+        entry.link = Some("https://example.com/de".to_string());
+        entry.title = Some("Der Fuchs und der Hund".to_string());
+        entry.description =
+            Some("Das ist nicht das Ende, und es ist auch nicht schlimm.".to_string());
+        in_transaction(&mut conn, |tx| {
+            add_entries_to_feed(tx, feed_id, &[entry.clone()])
+        })
+        .unwrap();
 
-    // we get weird pubDate formats from feeds,
-    // so sort by inserted at as this as a stable order at least
-    let mut query = "SELECT link FROM entries WHERE feed_id=?1".to_string();
+        assert_eq!(mute_language(&conn, "German").unwrap(), "de");
+        assert!(mute_language(&conn, "not a language").is_err());
+        assert_eq!(get_muted_languages(&conn).unwrap(), vec!["de"]);
+        assert!(entry.is_left_out(&[], &get_muted_languages(&conn).unwrap()));
+        let entries_len = |conn: &rusqlite::Connection| {
+            get_entries_metas(conn, &ReadMode::All, EntrySort::Title, feed_id)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(entries_len(&conn), 2);
 
-    query.push_str(read_at_predicate);
-    query.push_str("\nORDER BY pub_date DESC, inserted_at DESC");
+        let entry_ids = get_entry_ids_in_language(&conn, "de").unwrap();
+        assert_eq!(entry_ids.len(), 1);
+        assert_eq!(
+            get_entry_meta(&conn, entry_ids[0])
+                .unwrap()
+                .language
+                .as_deref(),
+            Some("de")
+        );
 
-    let mut links = vec![];
-    let mut statement = conn.prepare(&query)?;
+        assert!(unmute_language(&conn, "de").unwrap());
+        assert!(!unmute_language(&conn, "de").unwrap());
+        assert_eq!(entries_len(&conn), 3);
 
-    for link in statement.query_map([feed_id], |row| row.get(0))? {
-        links.push(link?);
+        // entries from before languages were kept get theirs when the database is migrated
+        conn.execute("UPDATE entries SET language = NULL", [])
+            .unwrap();
+        mute_language(&conn, "de").unwrap();
+        assert_eq!(entries_len(&conn), 3);
+        in_transaction(&mut conn, set_entries_languages).unwrap();
+        assert_eq!(entries_len(&conn), 2);
     }
 
-    Ok(links)
-}
+    #[test]
+    fn it_keeps_histories() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
 
-/// run `f` in a transaction, committing if `f` returns an `Ok` value,
-/// otherwise rolling back.
-fn in_transaction<F, R>(conn: &mut rusqlite::Connection, f: F) -> Result<R>
-where
-    F: Fn(&rusqlite::Transaction) -> Result<R>,
-{
-    let tx = conn.transaction()?;
+        add_to_history(&conn, "command", "tag rust").unwrap();
+        add_to_history(&conn, "command", "set width=80").unwrap();
+        add_to_history(&conn, "search", "rust").unwrap();
+        add_to_history(&conn, "command", "tag rust").unwrap();
 
-    let result = f(&tx)?;
+        assert_eq!(
+            get_history(&conn, "command").unwrap(),
+            vec!["set width=80", "tag rust"]
+        );
+        assert_eq!(get_history(&conn, "search").unwrap(), vec!["rust"]);
 
-    tx.commit()?;
+        for i in 0..crate::history::HISTORY_LIMIT {
+            add_to_history(&conn, "search", &i.to_string()).unwrap();
+        }
+        let history = get_history(&conn, "search").unwrap();
+        assert_eq!(history.len(), crate::history::HISTORY_LIMIT);
+        assert_eq!(history[0], "0");
+    }
 
-    Ok(result)
-}
+    #[test]
+    fn deleted_feeds_can_be_restored() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&mut conn).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        mark_feed_as_read(&mut conn, feed_id, None).unwrap();
+        let entry_id =
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::Title, feed_id).unwrap()[0].id;
+        set_entry_note(&conn, entry_id, "a note").unwrap();
+        toggle_queued(&conn, entry_id).unwrap();
+        set_mark(&conn, 'a', feed_id, None).unwrap();
+        set_mark(&conn, 'a', feed_id, Some(entry_id)).unwrap();
+        assert_eq!(
+            get_mark(&conn, 'a').unwrap(),
+            Some((feed_id, Some(entry_id)))
+        );
+        assert_eq!(get_mark(&conn, 'b').unwrap(), None);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    const ZCT: &str = "https://zeroclarkthirty.com/feed";
+        let deleted_feed = delete_feed(&mut conn, feed_id).unwrap();
+        assert!(get_feeds(&conn).unwrap().is_empty());
+        assert_eq!(get_entry_note(&conn, entry_id).unwrap(), None);
+        assert_eq!(get_mark(&conn, 'a').unwrap(), None);
+        let queued: i64 = conn
+            .query_row("SELECT COUNT(*) FROM queue", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(queued, 0);
 
-    #[test]
-    fn it_fetches() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
-        let feed_and_entries = fetch_feed(&http_client, ZCT).unwrap();
-        assert!(feed_and_entries.entries.len() > 0)
+        restore_feed(&mut conn, &deleted_feed).unwrap();
+        let feeds = get_feeds(&conn).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].id, feed_id);
+
+        let entries = get_entries_metas(&conn, &ReadMode::All, EntrySort::Title, feed_id).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.read_at.is_some()));
+        assert_eq!(
+            get_entry_note(&conn, entry_id).unwrap(),
+            Some("a note".to_string())
+        );
+        assert_eq!(
+            get_mark(&conn, 'a').unwrap(),
+            Some((feed_id, Some(entry_id)))
+        );
+        assert!(!toggle_queued(&conn, entry_id).unwrap());
+
+        set_entry_note(&conn, entry_id, "").unwrap();
+        assert_eq!(get_entry_note(&conn, entry_id).unwrap(), None);
     }
 
     #[test]
-    fn it_subscribes_to_a_feed() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
+    fn sessions_are_saved() {
         let mut conn = rusqlite::Connection::open_in_memory().unwrap();
         initialize_db(&mut conn).unwrap();
-        subscribe_to_feed(&http_client, &mut conn, ZCT).unwrap();
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+        assert!(get_session(&conn).unwrap().is_none());
+
+        for entry_scroll_position in [3, 7] {
+            save_session(
+                &conn,
+                &Session {
+                    feed_id: Some(1),
+                    entry_id: Some(2),
+                    entry_open: true,
+                    read_mode: ReadMode::ShowRead,
+                    entry_scroll_position,
+                    feeds_pane_percent: Some(45),
+                    feeds_pane_hidden: true,
+                    feed_sort: FeedSort::Unread,
+                },
+            )
             .unwrap();
+        }
 
-        assert!(count > 50)
+        let session = get_session(&conn).unwrap().unwrap();
+        assert_eq!(session.feed_id, Some(1));
+        assert_eq!(session.entry_id, Some(2));
+        assert!(session.entry_open);
+        assert!(matches!(session.read_mode, ReadMode::ShowRead));
+        assert_eq!(session.entry_scroll_position, 7);
+        assert_eq!(session.feeds_pane_percent, Some(45));
+        assert!(session.feeds_pane_hidden);
+        assert_eq!(session.feed_sort, FeedSort::Unread);
     }
 
     #[test]
-    fn refresh_feed_does_not_add_any_items_if_there_are_no_new_items() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
+    fn entries_can_be_tagged() {
         let mut conn = rusqlite::Connection::open_in_memory().unwrap();
         initialize_db(&mut conn).unwrap();
-        subscribe_to_feed(&http_client, &mut conn, ZCT).unwrap();
-        let feed_id = 1;
-        let old_entries = get_entries_metas(&conn, &ReadMode::ShowUnread, feed_id).unwrap();
-        refresh_feed(&http_client, &mut conn, feed_id).unwrap();
-        let e = get_entry_meta(&conn, 1).unwrap();
-        e.mark_as_read(&conn).unwrap();
-        let new_entries = get_entries_metas(&conn, &ReadMode::ShowUnread, feed_id).unwrap();
+        let feed_id = create_test_feed(&mut conn);
+        let entry_ids = get_entries_metas(&conn, &ReadMode::All, EntrySort::Title, feed_id)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
 
-        assert_eq!(new_entries.len(), old_entries.len() - 1);
+        set_entries_tag(&mut conn, &entry_ids, "rust", true).unwrap();
+        set_entries_tag(&mut conn, &entry_ids[..1], "later", true).unwrap();
+        assert_eq!(get_tags(&conn).unwrap(), vec!["later", "rust"]);
+        assert_eq!(
+            get_entry_meta(&conn, entry_ids[0]).unwrap().tags,
+            vec!["later", "rust"]
+        );
+
+        let tagged =
+            get_tagged_entries_metas(&conn, &ReadMode::All, EntrySort::Title, "later").unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, entry_ids[0]);
+
+        set_entries_tag(&mut conn, &entry_ids, "later", false).unwrap();
+        assert_eq!(get_tags(&conn).unwrap(), vec!["rust"]);
+        assert_eq!(get_tag_entry_counts(&conn, "rust").unwrap(), (2, 2));
     }
 
     #[test]
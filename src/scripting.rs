@@ -0,0 +1,340 @@
+//! The config's script, in [Rhai](https://rhai.rs), for extending russ without forking it.
+//! Its `transform(entry)` function, if it has one, is given every new entry before it's added,
+//! to rewrite its title, tag it, or drop it, and the config's `[[actions]]` call its other
+//! functions with the selected entry, from a key.
+
+use crate::rss::{Entry, Feed};
+use anyhow::{anyhow, Context, Result};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+/// a script that does more than this is stopped, rather than hanging a refresh forever
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+/// what the script's `transform` said to do with an entry it kept
+#[derive(Debug, Default, PartialEq)]
+struct Transform {
+    title: Option<String>,
+    tags: Vec<String>,
+}
+
+impl Script {
+    /// Compile the script at `path`, which can start with `~`.
+    pub fn load(path: &str) -> Result<Script> {
+        let path = crate::util::expand_tilde(path);
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read the script {}", path.display()))?;
+
+        Script::compile(&source)
+            .with_context(|| format!("Unable to compile the script {}", path.display()))
+    }
+
+    fn compile(source: &str) -> Result<Script> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // for actions that reach outside of russ, like archiving a link
+        engine.register_fn("run", run);
+
+        let ast = engine.compile(source)?;
+        Ok(Script { engine, ast })
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name)
+    }
+
+    fn call(&self, name: &str, entry: Map) -> Result<Dynamic> {
+        self.engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, (entry,))
+            .with_context(|| format!("The script's {} function failed", name))
+    }
+
+    /// Call the action `function` with the entry, as `AppImpl::action_entry` has it,
+    /// returning what to show for it.
+    pub fn run_action(&self, function: &str, entry: &serde_json::Value) -> Result<String> {
+        let entry = self.engine.parse_json(entry.to_string(), true)?;
+        let result = self.call(function, entry)?;
+
+        Ok(if result.is_unit() {
+            format!("Ran {}", function)
+        } else {
+            result.to_string()
+        })
+    }
+
+    /// What `transform` does with an entry: `None` if it returns `false`, to drop it,
+    /// the entry, with its `title` or `tags` changed, or anything else, like `true`, to keep it as is.
+    fn transform(&self, entry: Map) -> Result<Option<Transform>> {
+        let title = entry.get("title").map(|title| title.to_string());
+        let result = self.call("transform", entry)?;
+
+        if let Ok(keep) = result.as_bool() {
+            return Ok(if keep {
+                Some(Transform::default())
+            } else {
+                None
+            });
+        }
+
+        let entry = match result.try_cast::<Map>() {
+            Some(entry) => entry,
+            None => return Ok(Some(Transform::default())),
+        };
+
+        let new_title = entry
+            .get("title")
+            .filter(|new_title| new_title.is_string())
+            .map(|new_title| new_title.to_string())
+            .filter(|new_title| Some(new_title) != title.as_ref());
+
+        let tags = entry
+            .get("tags")
+            .and_then(|tags| tags.clone().try_cast::<Array>())
+            .into_iter()
+            .flatten()
+            .filter(|tag| tag.is_string())
+            .map(|tag| tag.to_string().trim().to_owned())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+
+        Ok(Some(Transform {
+            title: new_title,
+            tags,
+        }))
+    }
+}
+
+/// Give each of a feed's new entries to `transform`, before they're added,
+/// rewriting the titles of the ones it keeps, and leaving out the ones it drops,
+/// so they're never added. The tags it gives them go in `tags`, by link,
+/// to tag them with once they've been added. If it fails, the rest are kept as they are.
+pub fn transform_entries(
+    script: &Script,
+    feed: &Feed,
+    entries: &mut Vec<Entry>,
+    tags: &mut Vec<(String, String)>,
+) -> Result<()> {
+    if entries.is_empty() || !script.has_function("transform") {
+        return Ok(());
+    }
+
+    let mut kept = vec![];
+    let mut result = Ok(());
+    let mut untransformed = std::mem::take(entries).into_iter();
+
+    for mut entry in untransformed.by_ref() {
+        match entry_map(script, feed, &entry).and_then(|entry| script.transform(entry)) {
+            Ok(Some(transform)) => {
+                if let Some(title) = transform.title {
+                    entry.title = Some(title);
+                }
+                if let Some(link) = &entry.link {
+                    tags.extend(transform.tags.into_iter().map(|tag| (link.clone(), tag)));
+                }
+                kept.push(entry);
+            }
+            Ok(None) => (),
+            Err(e) => {
+                kept.push(entry);
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    kept.extend(untransformed);
+    *entries = kept;
+    result
+}
+
+/// the entry as the `on_new_entry` hook gets it, with its categories, and no tags yet
+fn entry_map(script: &Script, feed: &Feed, entry: &Entry) -> Result<Map> {
+    let mut json = crate::hooks::entry_json(feed, entry);
+    json["categories"] = serde_json::json!(entry.categories);
+    json["tags"] = serde_json::json!([]);
+
+    Ok(script.engine.parse_json(json.to_string(), true)?)
+}
+
+/// run a shell command, returning what it printed
+fn run(command: &str) -> std::result::Result<String, Box<rhai::EvalAltResult>> {
+    crate::pipe::run_with_stdin(crate::pipe::shell_command(command), &[])
+        .map(|output| output.trim_end().to_owned())
+        .map_err(|e| anyhow!("{} failed: {:#}", command, e).to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> Map {
+        let engine = Engine::new();
+        engine
+            .parse_json(
+                serde_json::json!({ "title": title, "categories": ["Rust"], "tags": [] })
+                    .to_string(),
+                true,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn it_transforms_entries() {
+        let script = Script::compile(
+            r#"
+            fn transform(entry) {
+                if entry.title.contains("Sponsored") {
+                    return false;
+                }
+                if entry.title.starts_with("[") {
+                    return true;
+                }
+                entry.title = entry.title.to_upper();
+                entry.tags += entry.categories;
+                entry.tags.push(" ");
+                entry
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(script.transform(entry("Sponsored: socks")).unwrap(), None);
+        assert_eq!(
+            script.transform(entry("[meta] unchanged")).unwrap(),
+            Some(Transform::default())
+        );
+        assert_eq!(
+            script.transform(entry("rust 2.0")).unwrap(),
+            Some(Transform {
+                title: Some("RUST 2.0".to_string()),
+                tags: vec!["Rust".to_string()],
+            })
+        );
+        // the same title isn't a new one
+        assert_eq!(
+            script.transform(entry("RUST 2.0")).unwrap().unwrap().title,
+            None
+        );
+    }
+
+    #[test]
+    fn it_runs_actions() {
+        let script = Script::compile(
+            r#"
+            fn shout(entry) { `${entry.title}!` }
+            fn quietly(entry) { }
+            fn echo(entry) { run(`echo ${entry.link}`) }
+            fn forever(entry) { loop {} }
+            "#,
+        )
+        .unwrap();
+        let entry = serde_json::json!({ "title": "Hello", "link": "https://example.com" });
+
+        assert!(script.has_function("shout"));
+        assert!(!script.has_function("transform"));
+        assert_eq!(script.run_action("shout", &entry).unwrap(), "Hello!");
+        assert_eq!(script.run_action("quietly", &entry).unwrap(), "Ran quietly");
+        #[cfg(unix)]
+        assert_eq!(
+            script.run_action("echo", &entry).unwrap(),
+            "https://example.com"
+        );
+        assert!(script.run_action("forever", &entry).is_err());
+        assert!(script.run_action("missing", &entry).is_err());
+
+        assert!(Script::compile("fn broken( {").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn entries_are_transformed_before_theyre_added() {
+        let script = Script::compile(
+            r#"
+            fn transform(entry) {
+                if entry.title.contains("Sponsored") {
+                    return false;
+                }
+                entry.title = entry.title.to_upper();
+                entry.tags.push("kept");
+                entry
+            }
+            "#,
+        )
+        .unwrap();
+        // after the transform, so the dropped entry isn't there to highlight
+        let rules = vec![toml::from_str::<crate::config::Rule>(
+            r#"
+            matches = "socks|issue"
+            highlight = "red"
+            "#,
+        )
+        .unwrap()];
+        let new_entry_rules = crate::hooks::NewEntryRules {
+            script: Some(&script),
+            rules: &rules,
+            ..Default::default()
+        };
+
+        let path = std::env::temp_dir().join(format!("russ-transform-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "version": "https://jsonfeed.org/version/1.1",
+                "title": "Transformed",
+                "items": [
+                    { "url": "https://example.com/1", "title": "Issue 1" },
+                    { "url": "https://example.com/2", "title": "Sponsored: socks" },
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::rss::initialize_db(&mut conn).unwrap();
+        let http_client = crate::http::client(std::time::Duration::from_secs(1), None);
+        let titles = |conn: &rusqlite::Connection| -> Vec<String> {
+            conn.prepare("SELECT title FROM entries")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        let feed_id = crate::rss::subscribe_to_feed(
+            &http_client,
+            &mut conn,
+            &new_entry_rules,
+            &format!("exec:cat {}", path.display()),
+        )
+        .unwrap();
+        // the dropped entry was never added, not even as deleted
+        assert_eq!(titles(&conn), vec!["ISSUE 1"]);
+        assert_eq!(crate::rss::get_tags(&conn).unwrap(), vec!["kept"]);
+        assert_eq!(
+            conn.query_row(
+                "SELECT COUNT(*) FROM entries WHERE highlight IS NOT NULL",
+                [],
+                |row| row.get::<_, i64>(0)
+            )
+            .unwrap(),
+            1
+        );
+
+        let keyring = crate::keyring::Keyring::in_file(path.with_extension("secrets"), "test");
+        let new_entries =
+            crate::rss::refresh_feed(&http_client, &mut conn, &keyring, &new_entry_rules, feed_id)
+                .unwrap();
+        assert!(new_entries.is_empty());
+        assert_eq!(titles(&conn), vec!["ISSUE 1"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+//! Subscribing to sites by the links people actually have, like a subreddit's or a Bluesky
+//! profile's, rather than their feeds', which the sites don't link to.
+//!
+//! Each site is a pattern, like `reddit.com/r/{subreddit}`, and the feed url it's rewritten to,
+//! like `https://www.reddit.com/r/{subreddit}/.rss`. A `{name}` matches one part of the path,
+//! and patterns ignore the scheme, a leading `www.`, and anything after the path.
+//! The config's `[[sites]]`, like ones for Nitter instances, are tried before these.
+
+use crate::config::Site;
+
+/// the sites russ knows, as (pattern, feed url)
+const KNOWN_SITES: &[(&str, &str)] = &[
+    (
+        "reddit.com/r/{subreddit}",
+        "https://www.reddit.com/r/{subreddit}/.rss",
+    ),
+    (
+        "old.reddit.com/r/{subreddit}",
+        "https://www.reddit.com/r/{subreddit}/.rss",
+    ),
+    (
+        "reddit.com/user/{user}",
+        "https://www.reddit.com/user/{user}/.rss",
+    ),
+    (
+        "reddit.com/u/{user}",
+        "https://www.reddit.com/user/{user}/.rss",
+    ),
+    (
+        "bsky.app/profile/{handle}",
+        "https://bsky.app/profile/{handle}/rss",
+    ),
+    ("nitter.net/{user}", "https://nitter.net/{user}/rss"),
+];
+
+/// `input` with its url rewritten to its site's feed, if it's on one,
+/// keeping anything after it, like a certificate's fingerprint
+pub fn resolve(sites: &[Site], input: &str) -> String {
+    let input = input.trim();
+    let (url, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+
+    let configured = sites
+        .iter()
+        .map(|site| (site.url.as_str(), site.feed.as_str()));
+
+    match configured
+        .chain(KNOWN_SITES.iter().copied())
+        .find_map(|(pattern, feed)| feed_url(pattern, feed, url))
+    {
+        Some(feed_url) if rest.is_empty() => feed_url,
+        Some(feed_url) => format!("{} {}", feed_url, rest),
+        None => input.to_owned(),
+    }
+}
+
+/// `url` without its scheme, a leading `www.`, or a query or fragment
+fn strip(url: &str) -> &str {
+    let url = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let url = url.strip_prefix("www.").unwrap_or(url);
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    url.trim_end_matches('/')
+}
+
+/// `feed`, with the parts of `url` that `pattern`'s `{name}`s matched, if it matches
+fn feed_url(pattern: &str, feed: &str, url: &str) -> Option<String> {
+    let pattern = strip(pattern);
+    let url = strip(url);
+
+    let pattern_segments = pattern.split('/').collect::<Vec<_>>();
+    let url_segments = url.split('/').collect::<Vec<_>>();
+    if pattern_segments.len() != url_segments.len() {
+        return None;
+    }
+
+    let mut feed = feed.to_owned();
+    for (i, (pattern_segment, url_segment)) in
+        pattern_segments.iter().zip(&url_segments).enumerate()
+    {
+        let is_placeholder = pattern_segment.len() > 2
+            && pattern_segment.starts_with('{')
+            && pattern_segment.ends_with('}');
+
+        if is_placeholder && !url_segment.is_empty() {
+            feed = feed.replace(pattern_segment, url_segment);
+        } else if i == 0 && !pattern_segment.eq_ignore_ascii_case(url_segment) {
+            // hosts are the same whatever their case, unlike paths
+            return None;
+        } else if i > 0 && pattern_segment != url_segment {
+            return None;
+        }
+    }
+
+    Some(feed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_sites_feeds() {
+        assert_eq!(
+            resolve(&[], "https://www.reddit.com/r/rust/"),
+            "https://www.reddit.com/r/rust/.rss"
+        );
+        assert_eq!(
+            resolve(&[], "old.reddit.com/r/rust?sort=new"),
+            "https://www.reddit.com/r/rust/.rss"
+        );
+        assert_eq!(
+            resolve(&[], "https://BSKY.app/profile/someone.bsky.social"),
+            "https://bsky.app/profile/someone.bsky.social/rss"
+        );
+        // only the profile, not its posts
+        assert_eq!(
+            resolve(&[], "https://bsky.app/profile/someone.bsky.social/post/1"),
+            "https://bsky.app/profile/someone.bsky.social/post/1"
+        );
+        assert_eq!(
+            resolve(&[], "https://example.com/feed.xml AB:CD"),
+            "https://example.com/feed.xml AB:CD"
+        );
+
+        let sites = vec![Site {
+            url: "https://x.com/{user}".to_string(),
+            feed: "https://nitter.example.org/{user}/rss".to_string(),
+        }];
+        assert_eq!(
+            resolve(&sites, "x.com/someone AB:CD"),
+            "https://nitter.example.org/someone/rss AB:CD"
+        );
+        assert_eq!(resolve(&sites, "x.com/"), "x.com/");
+    }
+}
@@ -0,0 +1,286 @@
+//! Where feeds come from. Most are fetched over HTTP, and are RSS or Atom,
+//! but a feed can also be a command, subscribed to as `exec:<command>`, that prints a
+//! JSON Feed (https://jsonfeed.org), for newsletters, scrapers, and APIs that have no feed
+//! of their own. Whatever the source, its entries are stored and shown like any other feed's.
+
+use crate::rss::{Entry, Feed, FeedKind};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// what a feed's url starts with when it's a command
+pub const EXEC_PREFIX: &str = "exec:";
+
+/// how long a feed's command can run before it's killed, from `--command-timeout`
+static COMMAND_TIMEOUT: RwLock<Duration> = RwLock::new(Duration::from_secs(60));
+
+pub fn set_command_timeout(timeout: Duration) {
+    *COMMAND_TIMEOUT.write().unwrap() = timeout;
+}
+
+pub fn command_timeout() -> Duration {
+    *COMMAND_TIMEOUT.read().unwrap()
+}
+
+/// a feed that has been fetched, and is ready to be read
+pub trait FeedSource {
+    /// Read the feed, handing its entries to `on_entries` a batch at a time,
+    /// and return the feed itself.
+    fn read(&self, on_entries: &mut dyn FnMut(Vec<Entry>) -> Result<()>) -> Result<SourcedFeed>;
+
+    /// The links of the feed's entries that are already stored, for a source that can tell
+    /// which entries they are without reading them, like a maildir, to skip them.
+    fn known_links(&mut self, _links: &HashSet<String>) {}
+}
+
+/// the feed a source read, without its entries
+pub struct SourcedFeed {
+    pub feed: Feed,
+    /// what the feed says it is, for its preview
+    pub description: Option<String>,
+}
+
+/// The source of a feed that isn't fetched over HTTP, if `url` is one of those:
+/// a command's, or a sender's newsletters.
+pub fn local_source(url: &str) -> Option<Result<Box<dyn FeedSource>>> {
+    if let Some(command) = exec_command(url) {
+        return Some(
+            CommandSource::run(command).map(|source| Box::new(source) as Box<dyn FeedSource>),
+        );
+    }
+
+    crate::newsletters::MaildirSource::new(url)
+        .map(|source| Ok(Box::new(source) as Box<dyn FeedSource>))
+}
+
+/// `url`, if it's a local source's, the same way every time it's typed.
+/// Unlike urls, they can have spaces in them.
+pub fn normalize_local_url(url: &str) -> Option<String> {
+    if let Some(command) = exec_command(url) {
+        return Some(format!("{}{}", EXEC_PREFIX, command));
+    }
+
+    url.trim()
+        .starts_with(crate::newsletters::MAILDIR_PREFIX)
+        .then(|| url.trim().to_owned())
+}
+
+/// the command a feed's url runs, if it's a command's
+pub fn exec_command(url: &str) -> Option<&str> {
+    url.trim()
+        .strip_prefix(EXEC_PREFIX)
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+}
+
+/// What `command` printed, read as a JSON Feed.
+pub struct CommandSource {
+    command: String,
+    output: String,
+}
+
+impl CommandSource {
+    /// Run `command`, now, so it isn't run while the database is locked for writing.
+    pub fn run(command: &str) -> Result<CommandSource> {
+        log::debug!("running feed command {}", command);
+
+        let output = crate::pipe::run_with_timeout(
+            crate::pipe::shell_command(command),
+            &[],
+            command_timeout(),
+        )
+        .with_context(|| format!("The feed command {} failed", command))?;
+
+        Ok(CommandSource {
+            command: command.to_owned(),
+            output,
+        })
+    }
+}
+
+impl FeedSource for CommandSource {
+    fn read(&self, on_entries: &mut dyn FnMut(Vec<Entry>) -> Result<()>) -> Result<SourcedFeed> {
+        let json_feed: JsonFeed = serde_json::from_str(&self.output).with_context(|| {
+            format!("The feed command {} didn't print a JSON Feed", self.command)
+        })?;
+
+        on_entries(
+            json_feed
+                .items
+                .iter()
+                .filter_map(JsonItem::to_entry)
+                .collect(),
+        )?;
+
+        let mut feed = Feed::new(
+            json_feed.title.clone(),
+            json_feed.home_page_url.clone(),
+            FeedKind::Json,
+        );
+        feed.feed_link = Some(format!("{}{}", EXEC_PREFIX, self.command));
+        feed.guid = json_feed.feed_url.clone();
+
+        Ok(SourcedFeed {
+            feed,
+            description: json_feed.description,
+        })
+    }
+}
+
+/// https://jsonfeed.org/version/1.1, the parts of it russ has a use for
+#[derive(Debug, Deserialize)]
+struct JsonFeed {
+    title: Option<String>,
+    home_page_url: Option<String>,
+    feed_url: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    items: Vec<JsonItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonItem {
+    id: Option<serde_json::Value>,
+    url: Option<String>,
+    external_url: Option<String>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    /// 1.1's
+    #[serde(default)]
+    authors: Vec<JsonAuthor>,
+    /// 1.0's
+    author: Option<JsonAuthor>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    attachments: Vec<JsonAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonAuthor {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonAttachment {
+    url: String,
+}
+
+impl JsonItem {
+    /// Entries are told apart by their links, so an item without a url goes by its id,
+    /// and one without either is left out.
+    fn to_entry(&self) -> Option<Entry> {
+        let id = match &self.id {
+            Some(serde_json::Value::String(id)) => Some(id.clone()),
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        };
+        let link = self
+            .url
+            .clone()
+            .or_else(|| self.external_url.clone())
+            .or(id)?;
+
+        let parse_date = |date: &Option<String>| date.as_deref().and_then(crate::dates::parse);
+
+        Some(Entry {
+            id: -1,
+            feed_id: -1,
+            title: self.title.clone(),
+            author: self
+                .authors
+                .iter()
+                .chain(&self.author)
+                .find_map(|author| author.name.clone()),
+            pub_date: parse_date(&self.date_published),
+            description: self.summary.clone(),
+            content: self
+                .content_html
+                .clone()
+                .or_else(|| self.content_text.clone()),
+            link: Some(crate::links::strip_tracking_params(&link)),
+            comments_link: None,
+            enclosure: self
+                .attachments
+                .first()
+                .map(|attachment| attachment.url.clone()),
+            modified_at: parse_date(&self.date_modified),
+            categories: self.tags.clone(),
+            read_at: None,
+            inserted_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_what_a_command_prints_as_a_json_feed() {
+        let source = CommandSource {
+            command: "newsletters".to_string(),
+            output: r#"{
+                "version": "https://jsonfeed.org/version/1.1",
+                "title": "Newsletters",
+                "home_page_url": "https://mail.example.com",
+                "items": [
+                    {
+                        "id": "1",
+                        "url": "https://example.com/issue-1?utm_source=email",
+                        "title": "Issue 1",
+                        "content_html": "<p>Hello</p>",
+                        "date_published": "2024-01-02T03:04:05Z",
+                        "authors": [{"name": "Someone"}],
+                        "tags": ["weekly"]
+                    },
+                    {"id": 2, "content_text": "no url"},
+                    {"title": "neither url nor id"}
+                ]
+            }"#
+            .to_string(),
+        };
+
+        let mut entries = vec![];
+        let sourced = source
+            .read(&mut |batch| {
+                entries.extend(batch);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(sourced.feed.title.as_deref(), Some("Newsletters"));
+        assert_eq!(sourced.feed.feed_link.as_deref(), Some("exec:newsletters"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].link.as_deref(),
+            Some("https://example.com/issue-1")
+        );
+        assert_eq!(entries[0].author.as_deref(), Some("Someone"));
+        assert_eq!(entries[0].categories, vec!["weekly"]);
+        assert!(entries[0].pub_date.is_some());
+        assert_eq!(entries[1].link.as_deref(), Some("2"));
+        assert_eq!(entries[1].content.as_deref(), Some("no url"));
+
+        let not_a_feed = CommandSource {
+            command: "echo".to_string(),
+            output: "<rss></rss>".to_string(),
+        };
+        assert!(not_a_feed.read(&mut |_| Ok(())).is_err());
+
+        assert_eq!(
+            exec_command(" exec: ~/bin/feed --all "),
+            Some("~/bin/feed --all")
+        );
+        assert_eq!(exec_command("exec:"), None);
+        assert_eq!(exec_command("https://example.com/feed"), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Reading the open entry aloud, with a text-to-speech command like `espeak-ng`,
+//! a paragraph at a time, so it can be paused between them, or in the middle of one.
+
+use crate::config::SpeechConfig;
+use crate::pipe::shell_command;
+use anyhow::{anyhow, Result};
+use std::io::{Read, Write};
+use std::process::{Child, Stdio};
+use std::thread::JoinHandle;
+
+/// how far through the entry the reading is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// the paragraph being read, from 0
+    pub paragraph: usize,
+    pub paragraphs: usize,
+    pub paused: bool,
+    /// stop once the paragraph being read is cut off
+    pub stopping: bool,
+}
+
+/// `text`'s paragraphs, each on one line, as it was wrapped to fit the entry pane
+pub fn paragraphs(text: &str) -> Vec<String> {
+    text.split("\n\n")
+        .map(|paragraph| paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+/// The command reading a paragraph, in a process group of its own where there are those,
+/// so stopping it stops what the shell runs for it too, like `piper | aplay`.
+pub struct Speaking {
+    child: Child,
+    /// read as it's written, so the command can't fill the pipe and hang
+    stderr: Option<JoinHandle<String>>,
+}
+
+/// Start the command reading `paragraph`, which it gets on stdin.
+/// What it prints is thrown away, as it would draw over the UI,
+/// except for its stderr, which `finish` makes the error if it fails.
+pub fn speak(config: &SpeechConfig, paragraph: &str) -> Result<Speaking> {
+    let command = config.command.as_deref().unwrap_or("espeak-ng");
+
+    let mut shell_command = shell_command(command);
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell_command, 0);
+
+    let mut child = shell_command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Unable to run the speech command {}: {}", command, e))?;
+
+    let stderr = child.stderr.take().map(|mut child_stderr| {
+        std::thread::spawn(move || {
+            let mut stderr = String::new();
+            let _ = child_stderr.read_to_string(&mut stderr);
+            stderr
+        })
+    });
+
+    let mut child_stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Unable to get stdin handle for command"))?;
+
+    // from another thread, so a long paragraph can't block the pausing,
+    // and the command can be killed before it has read all of it
+    let paragraph = paragraph.to_owned();
+    std::thread::spawn(move || {
+        let _ = child_stdin.write_all(paragraph.as_bytes());
+    });
+
+    Ok(Speaking { child, stderr })
+}
+
+impl Speaking {
+    /// Whether the command is done with the paragraph, and the error if it failed.
+    pub fn finish(&mut self) -> Result<bool> {
+        let status = match self.child.try_wait()? {
+            Some(status) => status,
+            None => return Ok(false),
+        };
+
+        if status.success() {
+            return Ok(true);
+        }
+
+        let stderr = self
+            .stderr
+            .take()
+            .and_then(|stderr| stderr.join().ok())
+            .unwrap_or_default();
+
+        Err(anyhow!(
+            "The speech command failed with {}: {}",
+            status,
+            stderr.trim()
+        ))
+    }
+
+    /// Cut the paragraph off, stopping everything the command started.
+    pub fn stop(&mut self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            // the command leads its group, so the group has its id
+            let group = nix::unistd::Pid::from_raw(self.child.id() as i32);
+            match nix::sys::signal::killpg(group, nix::sys::signal::Signal::SIGKILL) {
+                // everything in it has already exited
+                Ok(()) | Err(nix::errno::Errno::ESRCH) => (),
+                Err(e) => return Err(anyhow!("Unable to stop the speech command: {}", e)),
+            }
+        }
+        #[cfg(not(unix))]
+        self.child.kill()?;
+
+        self.child.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for Speaking {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_a_paragraph_at_a_time() {
+        assert_eq!(
+            paragraphs("Title\n\nA paragraph\nwrapped  over\nlines.\n\n\n\nAnother.\n"),
+            vec!["Title", "A paragraph wrapped over lines.", "Another."]
+        );
+        assert!(paragraphs(" \n\n ").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_speaks_with_the_command() {
+        let config = SpeechConfig {
+            command: Some(r#"test "$(cat)" = "Hello there""#.to_string()),
+        };
+
+        let mut speaking = speak(&config, "Hello there").unwrap();
+        speaking.child.wait().unwrap();
+        assert!(speaking.finish().unwrap());
+
+        let mut speaking = speak(&config, "Goodbye").unwrap();
+        speaking.child.wait().unwrap();
+        assert!(speaking
+            .finish()
+            .unwrap_err()
+            .to_string()
+            .starts_with("The speech command failed"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stopping_stops_what_the_command_started() {
+        let marker = std::env::temp_dir().join(format!("russ-speech-{}", std::process::id()));
+        let config = SpeechConfig {
+            command: Some(format!(
+                "cat >/dev/null; (sleep 1; touch {}) | cat",
+                marker.display()
+            )),
+        };
+
+        let mut speaking = speak(&config, "Hello").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(!speaking.finish().unwrap());
+        speaking.stop().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,269 @@
+//! Numbers about the feeds, and about reading them, for the statistics view.
+
+use crate::rss::{Feed, FeedId};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+/// how many weeks back posts are counted
+pub const WEEKS: usize = 12;
+
+/// how many days back reads are counted
+pub const DAYS: usize = 30;
+
+/// how many weeks back the selected feed's posts are counted, a day at a time
+pub const ACTIVITY_WEEKS: usize = 26;
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    /// most unread first
+    pub feeds: Vec<FeedStats>,
+    pub entries_len: usize,
+    pub unread_len: usize,
+    /// posts across all feeds in each of the last `WEEKS` weeks, oldest first
+    pub posts_per_week: Vec<u64>,
+    /// entries read in each of the last `DAYS` days, oldest first
+    pub reads_per_day: Vec<u64>,
+    /// `None` until feeds have been refreshed at least twice
+    pub mean_refresh_interval: Option<Duration>,
+    /// the selected feed's, if a feed is selected
+    pub feed_activity: Option<FeedActivity>,
+}
+
+/// when a feed has been posting, for noticing one that's gone quiet, or won't stop
+#[derive(Clone, Debug)]
+pub struct FeedActivity {
+    pub title: String,
+    /// the Monday `ACTIVITY_WEEKS` weeks back, where the calendar starts
+    pub first_day: NaiveDate,
+    /// posts on each day from `first_day` to today, where russ is
+    pub posts_per_day: Vec<u64>,
+    pub last_posted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FeedStats {
+    pub title: String,
+    pub entries_len: usize,
+    pub unread_len: usize,
+    /// averaged over the last `WEEKS` weeks
+    pub posts_per_week: f64,
+    /// how many times in a row refreshing the feed has failed
+    pub failures: u32,
+    /// see `Feed::backoff_until`
+    pub backoff_until: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl Stats {
+    pub fn load(conn: &rusqlite::Connection, feed: Option<&Feed>) -> Result<Stats> {
+        let now = Utc::now();
+        let week = Duration::weeks(1);
+
+        let mut feeds = vec![];
+        let mut all_pub_dates = vec![];
+
+        for feed in crate::rss::get_feeds(conn)? {
+            let (unread_len, entries_len) = crate::rss::get_feed_entry_counts(conn, feed.id)?;
+            let pub_dates = get_post_dates(conn, feed.id)?;
+            let recent_posts_len = buckets(now, &pub_dates, week, WEEKS).iter().sum::<u64>();
+
+            feeds.push(FeedStats {
+                failures: feed.failures,
+                backoff_until: feed
+                    .backoff_until()
+                    .filter(|backoff_until| *backoff_until > now),
+                last_error: feed.last_error,
+                title: feed
+                    .title
+                    .or(feed.feed_link)
+                    .unwrap_or_else(|| "untitled".to_string()),
+                entries_len,
+                unread_len,
+                posts_per_week: recent_posts_len as f64 / WEEKS as f64,
+            });
+
+            all_pub_dates.extend(pub_dates);
+        }
+
+        feeds.sort_by_key(|feed| std::cmp::Reverse(feed.unread_len));
+
+        let read_dates = conn
+            .prepare(
+                "SELECT read_at FROM entries WHERE read_at IS NOT NULL AND deleted_at IS NULL",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<DateTime<Utc>>, _>>()?;
+
+        let refreshed_ats = crate::rss::get_refreshed_ats(conn)?;
+
+        Ok(Stats {
+            entries_len: feeds.iter().map(|feed| feed.entries_len).sum(),
+            unread_len: feeds.iter().map(|feed| feed.unread_len).sum(),
+            feeds,
+            posts_per_week: buckets(now, &all_pub_dates, week, WEEKS),
+            reads_per_day: buckets(now, &read_dates, Duration::days(1), DAYS),
+            mean_refresh_interval: mean_interval(&refreshed_ats),
+            feed_activity: feed
+                .map(|feed| FeedActivity::load(conn, feed))
+                .transpose()?,
+        })
+    }
+}
+
+impl FeedActivity {
+    pub fn load(conn: &rusqlite::Connection, feed: &Feed) -> Result<FeedActivity> {
+        let today = chrono::Local::now().date_naive();
+        let first_day = today
+            - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+            - Duration::weeks(ACTIVITY_WEEKS as i64 - 1);
+        let pub_dates = get_post_dates(conn, feed.id)?;
+
+        Ok(FeedActivity {
+            title: feed
+                .title
+                .clone()
+                .or_else(|| feed.feed_link.clone())
+                .unwrap_or_else(|| "untitled".to_string()),
+            first_day,
+            posts_per_day: posts_per_day(
+                first_day,
+                today,
+                pub_dates
+                    .iter()
+                    .map(|date| date.with_timezone(&chrono::Local).date_naive()),
+            ),
+            last_posted_at: pub_dates.into_iter().max(),
+        })
+    }
+}
+
+/// when each of a feed's entries was published, or added if the feed doesn't say
+fn get_post_dates(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Vec<DateTime<Utc>>> {
+    let dates = conn
+        .prepare(
+            "SELECT MIN(COALESCE(pub_date, inserted_at), inserted_at) FROM entries
+            WHERE feed_id=?1 AND deleted_at IS NULL",
+        )?
+        .query_map([feed_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(dates)
+}
+
+/// Count `dates` into `buckets_len` buckets of `bucket_duration` each, ending at `now`,
+/// oldest first. Dates that are older, or in the future, aren't counted.
+fn buckets(
+    now: DateTime<Utc>,
+    dates: &[DateTime<Utc>],
+    bucket_duration: Duration,
+    buckets_len: usize,
+) -> Vec<u64> {
+    let mut buckets = vec![0; buckets_len];
+
+    for date in dates {
+        let age = now - *date;
+
+        if age < Duration::zero() {
+            continue;
+        }
+
+        let buckets_ago = (age.num_seconds() / bucket_duration.num_seconds()) as usize;
+
+        if buckets_ago < buckets_len {
+            buckets[buckets_len - 1 - buckets_ago] += 1;
+        }
+    }
+
+    buckets
+}
+
+/// Count `days` into a bucket for each day from `first_day` to `today`.
+/// Days before or after them aren't counted.
+fn posts_per_day(
+    first_day: NaiveDate,
+    today: NaiveDate,
+    days: impl Iterator<Item = NaiveDate>,
+) -> Vec<u64> {
+    let days_len = usize::try_from((today - first_day).num_days() + 1).unwrap_or(0);
+    let mut posts_per_day = vec![0; days_len];
+
+    for day in days {
+        if let Some(posts) = usize::try_from((day - first_day).num_days())
+            .ok()
+            .and_then(|idx| posts_per_day.get_mut(idx))
+        {
+            *posts += 1;
+        }
+    }
+
+    posts_per_day
+}
+
+/// the average time between consecutive `times`, which are in order
+fn mean_interval(times: &[DateTime<Utc>]) -> Option<Duration> {
+    match times {
+        [first, .., last] => Some((*last - *first) / (times.len() as i32 - 1)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_dates_into_buckets() {
+        let now: DateTime<Utc> = "2024-01-31T12:00:00Z".parse().unwrap();
+        let dates = [
+            "2024-01-31T11:00:00Z",
+            "2024-01-31T01:00:00Z",
+            "2024-01-30T11:00:00Z",
+            "2024-01-29T11:00:00Z",
+            // too old
+            "2024-01-01T00:00:00Z",
+            // in the future
+            "2024-02-01T00:00:00Z",
+        ]
+        .iter()
+        .map(|date| date.parse().unwrap())
+        .collect::<Vec<_>>();
+
+        assert_eq!(buckets(now, &dates, Duration::days(1), 3), vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn it_counts_posts_per_day() {
+        let day = |day: &str| day.parse::<NaiveDate>().unwrap();
+        let days = [
+            "2024-01-28",
+            "2024-01-29",
+            "2024-01-31",
+            "2024-01-31",
+            "2024-02-01",
+        ];
+
+        assert_eq!(
+            posts_per_day(
+                day("2024-01-29"),
+                day("2024-01-31"),
+                days.iter().map(|d| day(d))
+            ),
+            vec![1, 0, 2]
+        );
+    }
+
+    #[test]
+    fn it_averages_intervals() {
+        let times = [
+            "2024-01-01T00:00:00Z",
+            "2024-01-01T01:00:00Z",
+            "2024-01-01T05:00:00Z",
+        ]
+        .iter()
+        .map(|time| time.parse().unwrap())
+        .collect::<Vec<_>>();
+
+        assert_eq!(mean_interval(&times), Some(Duration::minutes(150)));
+        assert_eq!(mean_interval(&times[..1]), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Translating the open entry, with a command, LibreTranslate, or DeepL,
+//! as the config's `[translation]` section says.
+
+use crate::config::{TranslationConfig, TranslationService};
+use anyhow::{anyhow, Context, Result};
+
+/// `html` in the configured language, as HTML
+pub fn translate(
+    http_client: &ureq::Agent,
+    config: &TranslationConfig,
+    html: &str,
+) -> Result<String> {
+    match config.service {
+        TranslationService::Command => {
+            let command = config.command.as_deref().ok_or_else(|| {
+                anyhow!("Add a command to the config's [translation] section to translate entries")
+            })?;
+
+            let mut shell = crate::pipe::shell_command(command);
+            shell.env("RUSS_TRANSLATE_TO", &config.target);
+            let output = crate::pipe::run_with_stdin(shell, html.as_bytes())
+                .with_context(|| format!("The translation command {} failed", command))?;
+
+            Ok(text_to_html(&output))
+        }
+        TranslationService::Libretranslate => {
+            let url = config
+                .url
+                .as_deref()
+                .unwrap_or("https://libretranslate.com");
+
+            let resp = http_client
+                .post(&format!("{}/translate", url.trim_end_matches('/')))
+                .set("Content-Type", "application/json")
+                .send_string(
+                    &serde_json::json!({
+                        "q": html,
+                        "source": "auto",
+                        "target": config.target,
+                        "format": "html",
+                        "api_key": config.api_key,
+                    })
+                    .to_string(),
+                )
+                .context("Unable to translate the entry with LibreTranslate")?;
+            let response: serde_json::Value = serde_json::from_reader(resp.into_reader())?;
+
+            response["translatedText"]
+                .as_str()
+                .map(|translated| translated.to_owned())
+                .ok_or_else(|| anyhow!("LibreTranslate answered with {}", response))
+        }
+        TranslationService::Deepl => {
+            // free accounts' keys end in `:fx`, and have an API of their own
+            let url = config
+                .url
+                .as_deref()
+                .unwrap_or(if config.api_key.ends_with(":fx") {
+                    "https://api-free.deepl.com"
+                } else {
+                    "https://api.deepl.com"
+                });
+
+            let resp = http_client
+                .post(&format!("{}/v2/translate", url.trim_end_matches('/')))
+                .set(
+                    "Authorization",
+                    &format!("DeepL-Auth-Key {}", config.api_key),
+                )
+                .send_form(&[
+                    ("text", html),
+                    ("target_lang", &config.target.to_uppercase()),
+                    ("tag_handling", "html"),
+                ])
+                .context("Unable to translate the entry with DeepL")?;
+            let response: serde_json::Value = serde_json::from_reader(resp.into_reader())?;
+
+            response["translations"][0]["text"]
+                .as_str()
+                .map(|translated| translated.to_owned())
+                .ok_or_else(|| anyhow!("DeepL answered with {}", response))
+        }
+    }
+}
+
+/// Commands like `trans` print text, which would be run together as HTML,
+/// so their lines are kept as paragraphs. HTML is left as it is.
+fn text_to_html(output: &str) -> String {
+    let trimmed = output.trim_start();
+    if trimmed.starts_with('<') {
+        return output.to_owned();
+    }
+
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            format!(
+                "<p>{}</p>",
+                line.replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_translates_with_a_command() {
+        let config = TranslationConfig {
+            service: TranslationService::Command,
+            command: Some(r#"sed "s/Hello/Bonjour ($RUSS_TRANSLATE_TO)/""#.to_string()),
+            url: None,
+            api_key: String::new(),
+            target: "fr".to_string(),
+        };
+        let http_client = ureq::AgentBuilder::new().build();
+
+        assert_eq!(
+            translate(&http_client, &config, "<p>Hello</p>")
+                .unwrap()
+                .trim(),
+            "<p>Bonjour (fr)</p>"
+        );
+        assert_eq!(
+            text_to_html("Bonjour\n\n<a> & b\n"),
+            "<p>Bonjour</p><p>&lt;a&gt; &amp; b</p>"
+        );
+    }
+}
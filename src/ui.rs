@@ -1,35 +1,283 @@
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
-use tui::text::{Span, Text};
-use tui::widgets::{Block, Borders, LineGauge, List, ListItem, Paragraph, Wrap};
+use tui::symbols;
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{
+    BarChart, Block, Borders, Cell, Clear, LineGauge, List, ListItem, ListState, Paragraph, Row,
+    Sparkline, Table, Wrap,
+};
 use tui::Frame;
 
 use crate::app::AppImpl;
-use crate::modes::{Mode, ReadMode, Selected};
-use crate::rss::EntryMeta;
+use crate::config::{DateFormat, EntryColumn, EntryColumnKind, PaneLayout};
+use crate::modes::{FeedField, MarkAction, Mode, ReadMode, Selected, TagAction, VirtualFeed};
+use crate::rss::{EntryMeta, Highlight};
+use crate::util;
+use std::collections::HashMap;
 
 const PINK: Color = Color::Rgb(255, 150, 167);
 
-pub fn predraw<B: Backend>(f: &Frame<B>) -> Vec<Rect> {
-    Layout::default()
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+/// how entries are listed, by whether they have been read or starred
+const UNREAD_ENTRY_STYLE: Style = Style {
+    fg: None,
+    bg: None,
+    add_modifier: Modifier::BOLD,
+    sub_modifier: Modifier::empty(),
+};
+const READ_ENTRY_STYLE: Style = Style {
+    fg: Some(Color::DarkGray),
+    bg: None,
+    add_modifier: Modifier::DIM,
+    sub_modifier: Modifier::empty(),
+};
+const STARRED_GLYPH: &str = "★ ";
+const PINNED_GLYPH: &str = "▴ ";
+const STARRED_GLYPH_STYLE: Style = Style {
+    fg: Some(Color::Yellow),
+    bg: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+
+/// returns the info column, the entries column, the preview column in the three-pane layout,
+/// and the status bar, always last
+pub fn predraw<B: Backend>(f: &Frame<B>, app: &AppImpl) -> Vec<Rect> {
+    let rows = Layout::default()
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .direction(Direction::Vertical)
+        .split(f.size());
+
+    let feeds_percent = if app.feeds_pane_is_shown() {
+        app.feeds_pane_percent
+    } else {
+        0
+    };
+    let rest_percent = 100 - feeds_percent;
+
+    let columns = match app.layout {
+        PaneLayout::TwoPane => vec![
+            Constraint::Percentage(feeds_percent),
+            Constraint::Percentage(rest_percent),
+        ],
+        // the entries and the preview share what's left 3:5
+        PaneLayout::ThreePane => vec![
+            Constraint::Percentage(feeds_percent),
+            Constraint::Percentage(rest_percent * 3 / 8),
+            Constraint::Percentage(rest_percent - rest_percent * 3 / 8),
+        ],
+    };
+
+    let mut chunks = Layout::default()
+        .constraints(columns)
         .direction(Direction::Horizontal)
-        .split(f.size())
+        .split(rows[0]);
+
+    chunks.push(rows[1]);
+
+    chunks
+}
+
+/// the chunk the open entry is drawn in
+pub fn entry_chunk(chunks: &[Rect], layout: PaneLayout) -> Rect {
+    match layout {
+        PaneLayout::TwoPane => chunks[1],
+        PaneLayout::ThreePane => chunks[2],
+    }
 }
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, chunks: Vec<Rect>, app: &mut AppImpl) {
-    draw_info_column(f, chunks[0], app);
+    app.drawn_links.clear();
+    app.drawn_entry_lines.clear();
+    app.drawn_images.clear();
 
-    match &app.selected {
-        Selected::Feeds | Selected::Entries => {
+    if chunks[0].width > 0 {
+        draw_info_column(f, chunks[0], app);
+    }
+    draw_status_bar(f, chunks[chunks.len() - 1], app);
+
+    match app.layout {
+        PaneLayout::TwoPane => match &app.selected {
+            _ if app.show_error_log => draw_error_log(f, chunks[1], app),
+            _ if app.stats.is_some() => draw_stats(f, chunks[1], app),
+            _ if app.queue.is_some() => draw_queue(f, chunks[1], app),
+            Selected::Feeds | Selected::Entries => {
+                draw_entries(f, chunks[1], app);
+            }
+            Selected::Entry(_entry_meta) => {
+                draw_entry(f, chunks[1], app);
+            }
+            Selected::None => draw_entries(f, chunks[1], app),
+        },
+        PaneLayout::ThreePane => {
             draw_entries(f, chunks[1], app);
+
+            match &app.selected {
+                _ if app.show_error_log => draw_error_log(f, chunks[2], app),
+                _ if app.stats.is_some() => draw_stats(f, chunks[2], app),
+                _ if app.queue.is_some() => draw_queue(f, chunks[2], app),
+                Selected::Entry(_) => draw_entry(f, chunks[2], app),
+                _ if app.entries.items.is_empty() || app.current_entry_meta.is_none() => {
+                    f.render_widget(Block::default().borders(Borders::ALL), chunks[2])
+                }
+                _ => draw_entry(f, chunks[2], app),
+            }
         }
-        Selected::Entry(_entry_meta) => {
-            draw_entry(f, chunks[1], app);
+    }
+
+    if app.finder.is_some() {
+        // links and images written over the entry would show through the finder
+        app.drawn_links.clear();
+        app.drawn_images.clear();
+        draw_finder(f, centered_rect(60, 60, f.size()), app);
+    }
+
+    if app.feed_preview.is_some() {
+        app.drawn_links.clear();
+        app.drawn_images.clear();
+        draw_feed_preview(f, centered_rect(60, 50, f.size()), app);
+    }
+}
+
+/// a `percent_x` by `percent_y` rect in the middle of `area`
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(rows[1])[1]
+}
+
+fn draw_finder<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    use crate::finder::FinderTarget;
+
+    let finder = match &app.finder {
+        Some(finder) => finder,
+        None => return,
+    };
+
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .direction(Direction::Vertical)
+        .split(area);
+
+    f.render_widget(Clear, area);
+
+    let input = Paragraph::new(Text::from(finder.input.as_str()))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                format!("Find ({})", finder.matches_len()),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, chunks[0]);
+
+    let feed_title = |feed_id| {
+        app.feeds
+            .items
+            .iter()
+            .find(|feed| feed.id == feed_id)
+            .and_then(|feed| feed.title.as_deref())
+            .unwrap_or_default()
+    };
+
+    // there can be a lot of entries, so only build the items that can be seen
+    let items = finder
+        .matches()
+        .take(finder.selection + chunks[1].height as usize)
+        .map(|item| match item.target {
+            FinderTarget::Feed(_) => ListItem::new(Spans::from(vec![Span::styled(
+                item.text.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )])),
+            FinderTarget::Entry(feed_id, _) => ListItem::new(Spans::from(vec![
+                Span::raw(item.text.as_str()),
+                Span::styled(
+                    format!("  {}", feed_title(feed_id)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])),
+        })
+        .collect::<Vec<_>>();
+
+    let mut state = ListState::default();
+    state.select(Some(finder.selection));
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(Style::default().fg(PINK).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+fn draw_feed_preview<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed_preview = match &app.feed_preview {
+        Some(feed_preview) => feed_preview,
+        None => return,
+    };
+
+    let mut lines = vec![Spans::from(Span::styled(
+        feed_preview.title.as_deref().unwrap_or("No title"),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    if let Some(description) = &feed_preview.description {
+        lines.push(Spans::from(Span::styled(
+            description.as_str(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.push(Spans::from(""));
+
+    if feed_preview.entries.is_empty() {
+        lines.push(Spans::from("No entries"));
+    }
+    for entry in &feed_preview.entries {
+        let mut spans = vec![Span::raw(entry.title.as_deref().unwrap_or("No title"))];
+        if let Some(pub_date) = entry.pub_date {
+            spans.push(Span::styled(
+                format!(
+                    "  {}",
+                    pub_date.with_timezone(&chrono::Local).format("%Y-%m-%d")
+                ),
+                Style::default().fg(Color::DarkGray),
+            ));
         }
-        Selected::None => draw_entries(f, chunks[1], app),
+        lines.push(Spans::from(spans));
     }
+
+    let preview = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default().borders(Borders::ALL).title(Span::styled(
+            "Subscribe? Enter to subscribe, Esc to cancel",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(preview, area);
 }
 
 fn draw_info_column<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
@@ -37,12 +285,23 @@ where
     B: Backend,
 {
     let mut constraints = match &app.mode {
-        Mode::Normal => vec![Constraint::Percentage(70), Constraint::Percentage(20)],
-        Mode::Editing => vec![
-            Constraint::Percentage(60),
-            Constraint::Percentage(20),
-            Constraint::Percentage(10),
-        ],
+        Mode::Normal | Mode::Finder => {
+            vec![Constraint::Percentage(70), Constraint::Percentage(20)]
+        }
+        Mode::Command
+        | Mode::Editing
+        | Mode::FeedEdit
+        | Mode::Note
+        | Mode::Pipe
+        | Mode::Search
+        | Mode::Share
+        | Mode::Tag => {
+            vec![
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+                Constraint::Percentage(10),
+            ]
+        }
     };
 
     if app.show_help {
@@ -67,7 +326,7 @@ where
                     draw_feed_info(f, chunks[1], app);
                 }
             }
-            Selected::None => draw_first_run_helper(f, chunks[1]),
+            Selected::None => draw_first_run_helper(f, chunks[1], app.hidden_feeds_len),
             _ => {
                 if app.current_feed.is_some() {
                     draw_feed_info(f, chunks[1], app);
@@ -76,6 +335,13 @@ where
         }
 
         match (app.mode, app.show_help) {
+            (Mode::Command, true) => {
+                draw_command_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Command, false) => {
+                draw_command_input(f, chunks[2], app);
+            }
             (Mode::Editing, true) => {
                 draw_new_feed_input(f, chunks[2], app);
                 draw_help(f, chunks[3], app);
@@ -83,6 +349,48 @@ where
             (Mode::Editing, false) => {
                 draw_new_feed_input(f, chunks[2], app);
             }
+            (Mode::Note, true) => {
+                draw_note_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Note, false) => {
+                draw_note_input(f, chunks[2], app);
+            }
+            (Mode::FeedEdit, true) => {
+                draw_feed_edit_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::FeedEdit, false) => {
+                draw_feed_edit_input(f, chunks[2], app);
+            }
+            (Mode::Pipe, true) => {
+                draw_pipe_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Pipe, false) => {
+                draw_pipe_input(f, chunks[2], app);
+            }
+            (Mode::Share, true) => {
+                draw_share_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Share, false) => {
+                draw_share_input(f, chunks[2], app);
+            }
+            (Mode::Tag, true) => {
+                draw_tag_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Tag, false) => {
+                draw_tag_input(f, chunks[2], app);
+            }
+            (Mode::Search, true) => {
+                draw_search_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            (Mode::Search, false) => {
+                draw_search_input(f, chunks[2], app);
+            }
             (_, true) => {
                 draw_help(f, chunks[2], app);
             }
@@ -91,14 +399,161 @@ where
     }
 }
 
-fn draw_first_run_helper<B>(f: &mut Frame<B>, area: Rect)
+fn draw_status_bar<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let mode = match app.mode {
+        Mode::Normal if app.visual_anchor.is_some() => " VISUAL ",
+        Mode::Normal => " NORMAL ",
+        Mode::Editing => " EDITING ",
+        Mode::Command => " COMMAND ",
+        Mode::Finder => " FIND ",
+        Mode::FeedEdit => " FEED ",
+        Mode::Note => " NOTE ",
+        Mode::Pipe => " PIPE ",
+        Mode::Share => " SHARE ",
+        Mode::Tag => " TAG ",
+        Mode::Search => " SEARCH ",
+    };
+
+    let mut status = String::new();
+
+    if let Some(profile) = &app.profile {
+        status.push_str(" [");
+        status.push_str(profile);
+        status.push_str("] |");
+    }
+
+    if let Some(tag) = &app.entries_tag {
+        status.push_str(" tagged ");
+        status.push_str(tag);
+        status.push_str(" |");
+    } else if let Some(virtual_feed) = app.virtual_feed {
+        status.push(' ');
+        status.push_str(virtual_feed.title());
+        status.push_str(" |");
+    } else if let Some(feed_title) = app
+        .current_feed
+        .as_ref()
+        .and_then(|feed| feed.title.as_ref())
+    {
+        status.push(' ');
+        status.push_str(feed_title);
+        status.push_str(" |");
+    }
+
+    if app.entries_loading {
+        status.push_str(" loading entries |");
+    } else {
+        let (unread, total) = app.current_feed_entry_counts;
+        status.push_str(&format!(" {}/{} unread |", unread, total));
+    }
+
+    if app.entry_loading {
+        status.push_str(" loading entry |");
+    }
+
+    match app.read_mode {
+        ReadMode::ShowUnread => status.push_str(" showing unread |"),
+        ReadMode::ShowRead => status.push_str(" showing read |"),
+        ReadMode::All => status.push_str(" showing all |"),
+    }
+
+    if let Some(pending_count) = app.pending_count {
+        status.push_str(&format!(" {} |", pending_count));
+    }
+
+    match app.pending_mark {
+        Some(MarkAction::Set) => status.push_str(" m |"),
+        Some(MarkAction::Jump) => status.push_str(" ' |"),
+        Some(MarkAction::RecordMacro) => status.push_str(" Z |"),
+        Some(MarkAction::ReplayMacro(_)) => status.push_str(" @ |"),
+        None => (),
+    }
+
+    if let Some(register) = app.macros.recording() {
+        status.push_str(&format!(" recording @{} |", register));
+    }
+
+    if let Some(now_playing) = &app.queue_now_playing {
+        status.push_str(" ▶ ");
+        status.push_str(now_playing);
+        status.push_str(" |");
+    }
+
+    if let Some(speech) = &app.speech {
+        status.push_str(&format!(
+            " {} reading aloud {}/{} |",
+            if speech.paused { "⏸" } else { "▶" },
+            speech.paragraph + 1,
+            speech.paragraphs
+        ));
+    }
+
+    status.push(' ');
+    status.push_str(status_hints(app));
+
+    let line = Spans::from(vec![
+        Span::styled(
+            mode,
+            Style::default()
+                .fg(Color::Black)
+                .bg(PINK)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(status),
+    ]);
+
+    f.render_widget(Paragraph::new(line), area);
+}
+
+/// a few contextual key hints, for when the help box is hidden
+fn status_hints(app: &AppImpl) -> &'static str {
+    match (app.mode, &app.selected) {
+        (Mode::Editing, _) if app.feed_preview.is_some() => "enter subscribe, esc cancel",
+        (Mode::Editing, _) => "enter preview, del delete feed, esc normal mode",
+        (Mode::Command, _) => "enter run, tab complete, up/down history, esc cancel",
+        (Mode::Note, _) => "enter save, esc cancel",
+        (Mode::Pipe, _) => "enter run, esc cancel",
+        (Mode::Share, _) => "enter post, esc cancel",
+        (Mode::FeedEdit, _) => "enter save, tab title/url/certificate, esc cancel",
+        (Mode::Finder, _) => "enter jump, up/down select, esc cancel",
+        (Mode::Tag, _) => "enter confirm, tab complete, esc cancel",
+        (Mode::Search, _) => "enter confirm, esc clear",
+        (Mode::Normal, Selected::Feeds) => {
+            "r refresh, x refresh all, l entries, i add feed, ? help"
+        }
+        (Mode::Normal, Selected::Entries) if app.visual_anchor.is_some() => {
+            "j/k extend, r read/unread, * star, d delete, esc cancel"
+        }
+        (Mode::Normal, Selected::Entries) => "enter read, r read/unread, / search, h feeds, ? help",
+        (Mode::Normal, Selected::Entry(_)) => "r read/unread, / search, o open, h back, ? help",
+        (Mode::Normal, Selected::None) => "i add feed, q quit",
+    }
+}
+
+fn draw_first_run_helper<B>(f: &mut Frame<B>, area: Rect, hidden_feeds_len: usize)
 where
     B: Backend,
 {
-    let text = "Press 'i', then enter an RSS/Atom feed URL, then hit `Enter`!";
+    let (title, text) = if hidden_feeds_len > 0 {
+        (
+            "ALL CAUGHT UP",
+            format!(
+                "None of your {} feeds have unread entries. Press 'a' to show read entries, or 'x' to refresh.",
+                hidden_feeds_len
+            ),
+        )
+    } else {
+        (
+            "TO SUBSCRIBE TO YOUR FIRST FEED",
+            "Press 'i', then enter an RSS/Atom feed URL, then hit `Enter`!".to_string(),
+        )
+    };
 
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "TO SUBSCRIBE TO YOUR FIRST FEED",
+        title,
         Style::default().fg(PINK).add_modifier(Modifier::BOLD),
     ));
 
@@ -126,6 +581,12 @@ where
         text.push('\n');
     }
 
+    if let Some(item) = &entry_meta.comments_link {
+        text.push_str("Comments: ");
+        text.push_str(item);
+        text.push('\n');
+    }
+
     if let Some(pub_date) = &entry_meta.pub_date {
         text.push_str("Pub. date: ");
         text.push_str(pub_date.to_string().as_str());
@@ -144,6 +605,18 @@ where
         text.push('\n');
     }
 
+    if let Some(starred_at) = &entry_meta.starred_at {
+        text.push_str("Starred at: ");
+        text.push_str(starred_at.to_string().as_str());
+        text.push('\n');
+    }
+
+    if !entry_meta.tags.is_empty() {
+        text.push_str("Tags: ");
+        text.push_str(entry_meta.tags.join(", ").as_str());
+        text.push('\n');
+    }
+
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
         "Info",
         Style::default()
@@ -162,16 +635,39 @@ fn draw_feeds<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let feeds = app
-        .feeds
-        .items
-        .iter()
-        .flat_map(|feed| feed.title.as_ref())
-        .map(Span::raw)
+    let virtual_feeds = VirtualFeed::ALL.iter().map(|virtual_feed| {
+        Spans::from(Span::styled(
+            virtual_feed.title(),
+            Style::default().add_modifier(Modifier::ITALIC),
+        ))
+    });
+
+    let feeds = app.feeds.items.iter().flat_map(|feed| {
+        let title = feed.title.as_ref()?;
+        Some(if feed.pinned {
+            Spans::from(vec![
+                Span::styled(PINNED_GLYPH, Style::default().fg(Color::Cyan)),
+                Span::raw(title),
+            ])
+        } else {
+            Spans::from(Span::raw(title))
+        })
+    });
+
+    let feeds = virtual_feeds
+        .chain(feeds)
         .map(ListItem::new)
         .collect::<Vec<ListItem>>();
 
-    let default_title = String::from("Feeds");
+    let mut default_title = if app.hide_read_feeds && matches!(app.read_mode, ReadMode::ShowUnread)
+    {
+        String::from("Feeds with unread")
+    } else {
+        String::from("Feeds")
+    };
+    if app.feed_sort != crate::rss::FeedSort::Manual {
+        default_title.push_str(&format!(", {}", app.feed_sort.describe()));
+    }
     let title = app.flash.as_ref().unwrap_or(&default_title);
 
     let feeds = List::new(feeds).block(
@@ -190,7 +686,9 @@ where
         _ => feeds,
     };
 
-    f.render_stateful_widget(feeds, area, &mut app.feeds.state);
+    let position = app.feeds_pane_position();
+    app.feeds_pane_state.select(position);
+    f.render_stateful_widget(feeds, area, &mut app.feeds_pane_state);
 }
 
 fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
@@ -251,7 +749,7 @@ where
     match app.read_mode {
         ReadMode::ShowUnread => text.push_str("Unread entries: "),
         ReadMode::ShowRead => text.push_str("Read entries: "),
-        ReadMode::All => unreachable!("ReadMode::All should never be possible from the UI!"),
+        ReadMode::All => text.push_str("Entries: "),
     }
     text.push_str(app.entries.items.len().to_string().as_str());
     text.push('\n');
@@ -262,6 +760,64 @@ where
         text.push('\n');
     }
 
+    if let Some(feed) = &app.current_feed {
+        text.push_str("Sort: ");
+        text.push_str(feed.entry_sort.describe());
+        text.push('\n');
+
+        text.push_str("Fetch full content: ");
+        text.push_str(if feed.fetch_full_content { "yes" } else { "no" });
+        text.push('\n');
+
+        if let Some(refresh_interval) = feed.refresh_interval {
+            text.push_str("Refresh interval: ");
+            text.push_str(&util::format_interval(refresh_interval));
+            text.push('\n');
+        }
+
+        if feed.paused {
+            text.push_str("Paused: yes\n");
+        }
+
+        if feed.pinned {
+            text.push_str("Pinned: yes\n");
+        }
+
+        if feed.failures > 0 {
+            text.push_str(&format!("Failed: {} times in a row\n", feed.failures));
+        }
+
+        if let Some(last_error) = &feed.last_error {
+            text.push_str("Last error: ");
+            text.push_str(last_error);
+            text.push('\n');
+        }
+
+        if let Some(backoff_until) = feed
+            .backoff_until()
+            .filter(|backoff_until| *backoff_until > chrono::Utc::now())
+        {
+            text.push_str("Skipped by refresh all until: ");
+            text.push_str(&backoff_until.to_string());
+            text.push('\n');
+        }
+
+        if let Some(certificate_fingerprint) = &feed.certificate_fingerprint {
+            text.push_str("Pinned certificate: ");
+            text.push_str(certificate_fingerprint);
+            text.push('\n');
+        }
+
+        if let Some(next_refresh_at) = feed
+            .next_refresh_at
+            .filter(|next_refresh_at| *next_refresh_at > chrono::Utc::now())
+        {
+            text.push_str("Asks not to be refreshed until: ");
+            text.push_str(&next_refresh_at.to_string());
+            text.push('\n');
+        }
+    }
+
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
         "Info",
         Style::default()
@@ -284,19 +840,88 @@ where
     match app.selected {
         Selected::Feeds => {
             text.push_str("r - refresh selected feed; x - refresh all feeds\n");
+            text.push_str("R - mark feed read; u - undo\n");
+            text.push_str("f - toggle fetching full content for feed\n");
+            text.push_str("F - edit feed title/url\n");
+            text.push_str("J/K - move feed down/up; p - pin/unpin feed\n");
+            text.push_str("L - sort feeds by title/newest/unread\n");
+            text.push_str("T - browse tags; : - run a command\n");
             text.push_str("c - copy link; o - open link in browser\n")
         }
         _ => {
-            text.push_str("r - mark entry read/un; a - toggle view read/un\n");
-            text.push_str("c - copy link; o - open link in browser\n")
+            text.push_str("r - mark entry read/un; a - view unread/read/all\n");
+            text.push_str("s - cycle entry sort order; u - undo\n");
+            text.push_str("* - star; v - select range; d - delete\n");
+            text.push_str("A - add/edit note; t - tag; T - browse tags\n");
+            text.push_str("| - pipe entry to a command; Y - translate; V - read aloud, pause; X - stop reading\n");
+            text.push_str("b - show content/description; O - open in pager\n");
+            text.push_str("w - save to Wallabag; P - save to Pocket\n");
+            text.push_str("S - share to Mastodon; W - play in mpv\n");
+            text.push_str("+ - add to/remove from the queue\n");
+            text.push_str("Q - show the queue; M - play/stop it\n");
+            text.push_str(": - run a command\n");
+            text.push_str("c - copy link; o - open link in browser\n");
+            text.push_str("C - open comments in browser\n")
         }
     }
     match app.mode {
-        Mode::Normal => text.push_str("i - edit mode; q - exit\n"),
+        Mode::Normal => {
+            match app.selected {
+                Selected::Entries => text.push_str("/ - search entries; n/N - next/prev match\n"),
+                Selected::Entry(_) => text.push_str("/ - search entry; n/N - next/prev match\n"),
+                _ => (),
+            }
+            text.push_str("n - next unread; N/tab - next feed with unread\n");
+            text.push_str("ctrl-p - find feeds and entries\n");
+            text.push_str("ctrl-o/ctrl-i - jump back/forward\n");
+            text.push_str("m<letter> - mark; '<letter> - go to mark\n");
+            text.push_str("Z<letter> - record macro, Z to stop; @<letter> - replay macro\n");
+            text.push_str("5j, 10k, etc. - move or scroll several times\n");
+            text.push_str("E - show/hide the error log; D - statistics\n");
+            text.push_str("</> - shrink/grow the feeds pane; z - hide it\n");
+            text.push_str(
+                "i - edit mode; I - add feed from clipboard; ctrl-z - suspend; q - exit\n",
+            )
+        }
+        Mode::Finder => {
+            text.push_str("enter - jump to match; up/down - select\n");
+            text.push_str("esc - cancel\n")
+        }
         Mode::Editing => {
-            text.push_str("enter - fetch feed; del - delete feed\n");
+            text.push_str("enter - preview feed, again to subscribe; del del - delete feed\n");
+            text.push_str("ctrl-a/e - start/end; ctrl-w - delete back to space; ctrl-u/k - delete to start/end; ctrl-y - paste\n");
             text.push_str("esc - normal mode\n")
         }
+        Mode::Note => {
+            text.push_str("enter - save note (empty to remove)\n");
+            text.push_str("esc - cancel\n")
+        }
+        Mode::Share => {
+            text.push_str("enter - post to Mastodon\n");
+            text.push_str("esc - cancel\n")
+        }
+        Mode::Pipe => {
+            text.push_str("enter - pipe the entry's text to the command\n");
+            text.push_str("esc - cancel\n")
+        }
+        Mode::FeedEdit => {
+            text.push_str("enter - save (empty title to reset); tab - title/url/certificate\n");
+            text.push_str("esc - cancel\n")
+        }
+        Mode::Search => {
+            text.push_str("enter - confirm search\n");
+            text.push_str("up/down - history; ctrl-r - search history\n");
+            text.push_str("esc - clear search\n")
+        }
+        Mode::Tag => {
+            text.push_str("enter - confirm; tab - complete tag\n");
+            text.push_str("esc - cancel\n")
+        }
+        Mode::Command => {
+            text.push_str("enter - run command; tab - complete\n");
+            text.push_str("up/down - history; ctrl-r - search history\n");
+            text.push_str("esc - cancel\n")
+        }
     }
 
     text.push_str("? - show/hide help");
@@ -310,45 +935,454 @@ fn draw_new_feed_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let text = &app.feed_subscription_input;
-    let text = Text::from(text.as_str());
+    let title = if app.confirm_delete_feed {
+        format!(
+            "Press Del again to delete {}",
+            app.current_feed
+                .as_ref()
+                .and_then(|feed| feed.title.as_deref())
+                .unwrap_or("this feed")
+        )
+    } else {
+        "Add a feed".to_string()
+    };
+    // long urls scroll along with the cursor
+    let cursor_column = app.feed_subscription_input.cursor_column() as u16;
+    let inner_width = area.width.saturating_sub(2);
+    let scroll = cursor_column.saturating_sub(inner_width.saturating_sub(1));
+
+    let text = Text::from(app.feed_subscription_input.text());
     let input = Paragraph::new(text)
         .style(Style::default().fg(Color::Yellow))
+        .scroll((0, scroll))
         .block(
             Block::default().borders(Borders::ALL).title(Span::styled(
-                "Add a feed",
+                title,
                 Style::default()
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
             )),
         );
     f.render_widget(input, area);
+
+    if matches!(app.mode, Mode::Editing) && app.feed_preview.is_none() {
+        f.set_cursor(area.x + 1 + cursor_column - scroll, area.y + 1);
+    }
 }
 
-fn draw_entries<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+fn draw_note_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let entries = app
-        .entries
-        .items
-        .iter()
-        .map(|entry| {
-            ListItem::new(Span::raw(entry.title.as_ref().unwrap_or_else(|| {
-                panic!("Unable to get title for entry id {}", entry.id)
-            })))
-        })
-        .collect::<Vec<ListItem>>();
+    let text = &app.note_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                "Note",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
 
-    let default_title = "Entries".to_string();
+fn draw_feed_edit_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let title = match app.feed_edit_field {
+        FeedField::Title => "Feed title (empty to use the feed's own)",
+        FeedField::Url => "Feed url",
+        FeedField::Certificate => "SHA-256 fingerprint of the certificate to pin (empty to unpin)",
+    };
 
-    let title = app
-        .current_feed
-        .as_ref()
-        .and_then(|feed| feed.title.as_ref())
-        .unwrap_or(&default_title);
+    let text = &app.feed_edit_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
 
-    let entries_titles = List::new(entries).block(
+fn draw_share_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let title = format!(
+        "Share to Mastodon ({}/{})",
+        app.share_input.chars().count(),
+        crate::mastodon::MAX_STATUS_LENGTH
+    );
+
+    let text = Text::from(app.share_input.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
+
+fn draw_pipe_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let text = Text::from(Spans::from(vec![
+        Span::raw("| "),
+        Span::raw(app.pipe_input.as_str()),
+    ]));
+
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                "Pipe entry to command ($RUSS_ENTRY_LINK, $RUSS_ENTRY_TITLE)",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
+
+fn draw_command_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let text = Text::from(Spans::from(vec![
+        Span::raw(":"),
+        Span::raw(app.command_input.as_str()),
+        Span::styled(
+            format!("  {}", app.matching_commands().join(" ")),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+
+    let title = match app.command_history.search() {
+        Some(search) => format!("Command (history search: {})", search),
+        None => "Command".to_string(),
+    };
+
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
+
+fn draw_tag_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let title = match app.tag_action {
+        TagAction::Toggle => "Tag (comma-separated, existing tags are removed)",
+        TagAction::Browse => "Show entries tagged",
+    };
+
+    let text = Text::from(Spans::from(vec![
+        Span::raw(app.tag_input.as_str()),
+        Span::styled(
+            format!("  {}", app.matching_tags().join(" ")),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
+
+fn draw_search_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let title = if matches!(app.selected, Selected::Entry(_)) {
+        "Search entry"
+    } else {
+        "Search entries"
+    };
+    let title = match app.search_history.search() {
+        Some(search) => format!("{} (history search: {})", title, search),
+        None => title.to_string(),
+    };
+    let text = &app.search_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    f.render_widget(input, area);
+}
+
+/// like `fit_to_width`, but padded on the left, for right-aligned columns
+fn fit_to_width_right(s: &str, width: usize) -> String {
+    let s_width = util::display_width(s);
+
+    if s_width <= width {
+        format!("{}{}", " ".repeat(width - s_width), s)
+    } else {
+        util::fit_to_width(s, width)
+    }
+}
+
+/// how wide a column is allowed to get from what is in it, rather than a configured width
+const MAX_ENTRY_COLUMN_WIDTH: usize = 24;
+
+/// what goes in `kind`'s column for `entry`, before it is fit to the column's width
+fn entry_cell(
+    app: &AppImpl,
+    entry: &EntryMeta,
+    kind: EntryColumnKind,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    match kind {
+        EntryColumnKind::Read => match entry.read_at {
+            Some(_) => " ".to_string(),
+            None => "•".to_string(),
+        },
+        EntryColumnKind::Title => entry
+            .title
+            .clone()
+            .unwrap_or_else(|| panic!("Unable to get title for entry id {}", entry.id)),
+        // the history is of when entries were read, not when they were published.
+        // Entries without a date have when they were fetched, marked with a `~`,
+        // and those dated after they were fetched are marked with a `!`
+        EntryColumnKind::Date => {
+            let (date, mark) = match app.virtual_feed {
+                Some(VirtualFeed::History) => (entry.read_at, ""),
+                _ if entry.pub_date.is_none() => (Some(entry.inserted_at), "~"),
+                _ if entry.is_future_dated() => (entry.pub_date, "!"),
+                _ => (entry.pub_date, ""),
+            };
+
+            let date = match (&app.date_format, date) {
+                (DateFormat::Relative, Some(date)) => crate::util::relative_time(now, date),
+                (DateFormat::Absolute(format), Some(date)) => date
+                    .with_timezone(&chrono::Local)
+                    .format(format)
+                    .to_string(),
+                (DateFormat::None, _) | (_, None) => return String::new(),
+            };
+
+            format!("{}{}", mark, date)
+        }
+        EntryColumnKind::Feed => app
+            .feed_titles
+            .get(&entry.feed_id)
+            .cloned()
+            .unwrap_or_default(),
+        EntryColumnKind::Author => entry.author.clone().unwrap_or_default(),
+        EntryColumnKind::Length => crate::util::format_size(entry.content_len),
+        EntryColumnKind::Language => entry.language.clone().unwrap_or_default(),
+    }
+}
+
+/// How wide each column is. Columns are as wide as they are configured to be,
+/// or as wide as their widest cell, and the title takes the rest.
+/// A column that ends up with no width isn't drawn at all.
+fn entry_column_widths(
+    columns: &[EntryColumn],
+    cells: &[Vec<String>],
+    line_width: usize,
+) -> Vec<usize> {
+    let mut widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| match (column.kind, column.width) {
+            (_, Some(width)) => width as usize,
+            (EntryColumnKind::Title, None) => 0,
+            (_, None) => cells
+                .iter()
+                .map(|row| util::display_width(&row[i]))
+                .max()
+                .unwrap_or(0)
+                .min(MAX_ENTRY_COLUMN_WIDTH),
+        })
+        .collect::<Vec<_>>();
+
+    let is_flexible =
+        |column: &EntryColumn| column.kind == EntryColumnKind::Title && column.width.is_none();
+
+    let shown_len = columns
+        .iter()
+        .zip(&widths)
+        .filter(|(column, width)| is_flexible(column) || **width > 0)
+        .count();
+    let used_width = widths.iter().sum::<usize>() + shown_len.saturating_sub(1);
+
+    for (column, width) in columns.iter().zip(widths.iter_mut()) {
+        if is_flexible(column) {
+            *width = line_width.saturating_sub(used_width);
+        }
+    }
+
+    widths
+}
+
+fn draw_entries<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let visual_range = app.visual_range();
+    let now = chrono::Utc::now();
+
+    // inside the borders, and the highlight symbol that every entry is indented by
+    let highlight_symbol_width = if matches!(app.selected, Selected::Entries) {
+        2
+    } else {
+        0
+    };
+    let line_width = (area.width as usize).saturating_sub(2 + highlight_symbol_width);
+
+    // every column's text for every entry, so columns can be as wide as what is in them
+    let cells = app
+        .entries
+        .items
+        .iter()
+        .map(|entry| {
+            app.entry_columns
+                .0
+                .iter()
+                .map(|column| entry_cell(app, entry, column.kind, now))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let widths = entry_column_widths(&app.entry_columns.0, &cells, line_width);
+
+    let entries =
+        app.entries
+            .items
+            .iter()
+            .zip(cells)
+            .enumerate()
+            .map(|(i, (entry, cells))| {
+                // when only unread entries are listed, there is nothing to tell apart
+                let title_style = match (&app.read_mode, entry.read_at) {
+                    (ReadMode::ShowUnread, _) => Style::default(),
+                    (_, Some(_)) => READ_ENTRY_STYLE,
+                    (_, None) => UNREAD_ENTRY_STYLE,
+                };
+                let title_style = match entry.highlight {
+                    Some(highlight) => title_style.fg(highlight_color(highlight)),
+                    None => title_style,
+                };
+                let detail_style = Style::default().fg(Color::DarkGray);
+
+                let mut spans = vec![];
+
+                for ((column, cell), width) in app.entry_columns.0.iter().zip(cells).zip(&widths) {
+                    if *width == 0 {
+                        continue;
+                    }
+
+                    if !spans.is_empty() {
+                        spans.push(Span::raw(" "));
+                    }
+
+                    match column.kind {
+                        EntryColumnKind::Title => {
+                            let width = match entry.starred_at {
+                                Some(_) => {
+                                    spans.push(Span::styled(STARRED_GLYPH, STARRED_GLYPH_STYLE));
+                                    width.saturating_sub(util::display_width(STARRED_GLYPH))
+                                }
+                                None => *width,
+                            };
+                            spans.push(Span::styled(util::fit_to_width(&cell, width), title_style));
+                        }
+                        EntryColumnKind::Read => spans.push(Span::styled(
+                            util::fit_to_width(&cell, *width),
+                            UNREAD_ENTRY_STYLE,
+                        )),
+                        EntryColumnKind::Date | EntryColumnKind::Length => spans.push(
+                            Span::styled(fit_to_width_right(&cell, *width), detail_style),
+                        ),
+                        EntryColumnKind::Feed
+                        | EntryColumnKind::Author
+                        | EntryColumnKind::Language => spans.push(Span::styled(
+                            util::fit_to_width(&cell, *width),
+                            detail_style,
+                        )),
+                    }
+                }
+
+                let item = ListItem::new(Spans::from(spans));
+
+                match &visual_range {
+                    Some(visual_range) if visual_range.contains(&i) => {
+                        item.style(Style::default().bg(Color::DarkGray))
+                    }
+                    _ => item,
+                }
+            })
+            .collect::<Vec<ListItem>>();
+
+    let default_title = "Entries".to_string();
+
+    let mut title = match (&app.entries_tag, app.virtual_feed) {
+        (Some(tag), _) => format!("Tagged {}", tag),
+        (None, Some(virtual_feed)) => virtual_feed.title().to_owned(),
+        (None, None) => app
+            .current_feed
+            .as_ref()
+            .and_then(|feed| feed.title.as_ref())
+            .unwrap_or(&default_title)
+            .to_owned(),
+    };
+
+    if !app.entries_date_range.is_empty() {
+        title.push_str(", ");
+        title.push_str(&app.entries_date_range.describe());
+    }
+
+    if let Some(entries_filter) = &app.entries_filter {
+        title.push_str(" - /");
+        title.push_str(entries_filter);
+    }
+
+    let entries_titles = List::new(entries).block(
         Block::default().borders(Borders::ALL).title(Span::styled(
             title,
             Style::default()
@@ -373,7 +1407,7 @@ where
             let error_text = error_text(&app.error_flash);
 
             let block = Block::default().borders(Borders::ALL).title(Span::styled(
-                "Error - press 'q' to close",
+                "Error - press 'q' to close, 'E' for the error log",
                 Style::default()
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
@@ -397,10 +1431,11 @@ where
     B: Backend,
 {
     let scroll = app.entry_scroll_position;
-    let entry_meta = if let Selected::Entry(e) = &app.selected {
-        e
-    } else {
-        panic!("draw_entry should only be called when app.selected was Selected::Entry")
+    // the three-pane layout previews the selected entry before it is opened
+    let entry_meta = match (&app.selected, &app.current_entry_meta) {
+        (Selected::Entry(e), _) => e.as_ref(),
+        (_, Some(e)) => e,
+        _ => panic!("draw_entry should only be called with an open or selected entry"),
     };
     let default_entry_title = "No entry title".to_string();
     let default_feed_title = "No feed title".to_string();
@@ -417,6 +1452,19 @@ where
     title.push_str(" - ");
     title.push_str(feed_title);
 
+    if let Some(entry_search) = &app.entry_search {
+        title.push_str(&format!(
+            " - /{} ({}/{})",
+            entry_search,
+            if app.entry_search_matches.is_empty() {
+                0
+            } else {
+                app.entry_search_match_position + 1
+            },
+            app.entry_search_matches.len()
+        ));
+    }
+
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
         &title,
         Style::default()
@@ -424,12 +1472,21 @@ where
             .fg(Color::Cyan),
     ));
 
-    let paragraph = Paragraph::new(app.current_entry_text.as_str())
+    let text = match &app.entry_search {
+        Some(entry_search) => highlight_matches(&app.current_entry_text, entry_search),
+        None if !app.entry_line_styles.is_empty() => {
+            style_lines(&app.current_entry_text, &app.entry_line_styles)
+        }
+        None => Text::from(app.current_entry_text.as_str()),
+    };
+
+    let paragraph = Paragraph::new(text)
         .block(block)
         .wrap(Wrap { trim: false })
         .scroll((scroll, 0));
 
-    let entry_chunk_height = area.height - 2;
+    // a tiny terminal may not even have room for the borders
+    let entry_chunk_height = area.height.saturating_sub(2);
 
     let progress_gauge_chunk_percent = 3;
 
@@ -440,21 +1497,18 @@ where
 
     app.entry_lines_rendered_len = real_entry_chunk_height;
 
-    let percent = if app.entry_lines_len > 0 {
-        let furthest_visible_position = app.entry_scroll_position + real_entry_chunk_height;
-        let percent = ((furthest_visible_position as f32 / app.entry_lines_len as f32) * 100.0)
-            .floor() as usize;
+    // the last line on screen, like `less` shows
+    let last_visible_line = (app.entry_scroll_position as usize + real_entry_chunk_height as usize)
+        .min(app.entry_lines_len);
 
-        if percent <= 100 {
-            percent
-        } else {
-            100
-        }
-    } else {
-        0
-    };
+    let percent = (last_visible_line * 100)
+        .checked_div(app.entry_lines_len)
+        .unwrap_or(0);
 
-    let label = format!("{}/100", percent);
+    let label = format!(
+        "{}% [{}/{}]",
+        percent, last_visible_line, app.entry_lines_len
+    );
     let ratio = percent as f64 / 100.0;
     let gauge = LineGauge::default()
         .block(Block::default().borders(Borders::NONE))
@@ -477,7 +1531,7 @@ where
         {
             let error_text = error_text(&app.error_flash);
             let block = Block::default().borders(Borders::ALL).title(Span::styled(
-                "Error - press 'q' to close",
+                "Error - press 'q' to close, 'E' for the error log",
                 Style::default()
                     .add_modifier(Modifier::BOLD)
                     .fg(Color::Cyan),
@@ -489,6 +1543,15 @@ where
                 .scroll((0, 0));
 
             f.render_widget(paragraph, chunks[0]);
+            if app.hyperlinks {
+                record_entry_links(chunks[0], app);
+            }
+            if app.images.is_some() {
+                record_entry_images(chunks[0], app);
+            }
+            if app.scrollbar {
+                draw_scrollbar(f, chunks[0], app);
+            }
             f.render_widget(gauge, chunks[1]);
             f.render_widget(error_widget, chunks[2]);
         }
@@ -505,10 +1568,565 @@ where
             .split(area);
 
         f.render_widget(paragraph, chunks[0]);
+        if app.hyperlinks {
+            record_entry_links(chunks[0], app);
+        }
+        if app.images.is_some() {
+            record_entry_images(chunks[0], app);
+        }
+        if app.scrollbar {
+            draw_scrollbar(f, chunks[0], app);
+        }
         f.render_widget(gauge, chunks[1]);
     }
 }
 
+/// Note where the entry's lines and links ended up in `area`,
+/// for `hyperlink::write` to write the links over them once tui is done.
+fn record_entry_links(area: Rect, app: &mut AppImpl) {
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    let scroll = app.entry_scroll_position as usize;
+    let lines = app.current_entry_text.lines().collect::<Vec<_>>();
+
+    for (row, line) in lines
+        .iter()
+        .skip(scroll)
+        .take(inner.height.into())
+        .enumerate()
+    {
+        app.drawn_entry_lines.push(crate::hyperlink::DrawnLine {
+            x: inner.x,
+            y: inner.y + row as u16,
+            text: line.to_string(),
+        });
+    }
+
+    for link in &app.entry_links {
+        let row = match link.line.checked_sub(scroll) {
+            Some(row) if row < inner.height as usize => row as u16,
+            _ => continue,
+        };
+
+        let line = lines[link.line];
+        let x = inner.x as usize + util::display_width(&line[..link.range.start]);
+        let text = &line[link.range.clone()];
+
+        // the paragraph would have cut it off
+        if x + util::display_width(text) > inner.right() as usize {
+            continue;
+        }
+
+        app.drawn_links.push(crate::hyperlink::DrawnLink {
+            x: x as u16,
+            y: inner.y + row,
+            text: text.to_owned(),
+            url: link.url.clone(),
+        });
+    }
+}
+
+/// Note where the entry's images ended up in `area`, for `Images::write`.
+/// Images that are only partly on screen are left out, as not every protocol can crop them.
+fn record_entry_images(area: Rect, app: &mut AppImpl) {
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    let scroll = app.entry_scroll_position as usize;
+
+    for image in &app.entry_images {
+        let row = match image.line.checked_sub(scroll) {
+            Some(row) if row + image.rows as usize <= inner.height as usize => row as u16,
+            _ => continue,
+        };
+
+        if image.cols > inner.width {
+            continue;
+        }
+
+        app.drawn_images.push(crate::images::DrawnImage {
+            x: inner.x,
+            y: inner.y + row,
+            cols: image.cols,
+            rows: image.rows,
+            url: image.url.clone(),
+        });
+    }
+}
+
+/// A thumb over the right border of the entry's `area`, as tall as the share of
+/// the entry that is on screen, and as far down as the entry is scrolled.
+fn draw_scrollbar<B>(f: &mut Frame<B>, area: Rect, app: &AppImpl)
+where
+    B: Backend,
+{
+    let track_len = area.height.saturating_sub(2) as usize;
+
+    if track_len == 0 || area.width < 2 || app.entry_lines_len <= track_len {
+        return;
+    }
+
+    let lines_len = app.entry_lines_len;
+    let thumb_len = (track_len * track_len / lines_len).max(1);
+    let max_scroll_position = lines_len
+        .saturating_sub(app.entry_lines_rendered_len as usize)
+        .max(1);
+    let thumb_start = (app.entry_scroll_position as usize * (track_len - thumb_len)
+        / max_scroll_position)
+        .min(track_len - thumb_len);
+
+    let track = (0..track_len)
+        .map(|i| {
+            if (thumb_start..thumb_start + thumb_len).contains(&i) {
+                Spans::from(Span::styled("┃", Style::default().fg(PINK)))
+            } else {
+                Spans::from("│")
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let track_area = Rect {
+        x: area.right() - 1,
+        y: area.y + 1,
+        width: 1,
+        height: track_len as u16,
+    };
+
+    f.render_widget(Paragraph::new(track), track_area);
+}
+
+/// split the lines of `text` that have styles into spans, styled by `line_styles`
+fn style_lines<'a>(
+    text: &'a str,
+    line_styles: &HashMap<usize, crate::code::LineStyles>,
+) -> Text<'a> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| match line_styles.get(&i) {
+            Some(styles) => {
+                let mut spans = vec![];
+                let mut end = 0;
+
+                for (style, range) in styles {
+                    match line.get(range.clone()) {
+                        Some(piece) => {
+                            spans.push(Span::raw(&line[end..range.start]));
+                            spans.push(Span::styled(piece, *style));
+                            end = range.end;
+                        }
+                        None => break,
+                    }
+                }
+
+                spans.push(Span::raw(&line[end..]));
+                Spans::from(spans)
+            }
+            None => Spans::from(line),
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// split each line of `text` into spans, highlighting case-insensitive occurrences of `query`
+fn highlight_matches<'a>(text: &'a str, query: &str) -> Text<'a> {
+    let query = query.to_lowercase();
+    let highlight_style = Style::default().fg(Color::Black).bg(PINK);
+
+    let lines = text
+        .lines()
+        .map(|line| {
+            if query.is_empty() {
+                return Spans::from(line);
+            }
+
+            // lowercasing can change the byte length of some characters, so the lowercased
+            // line is built a character at a time, keeping where each of its bytes came from
+            let mut lowercase_line = String::with_capacity(line.len());
+            let mut original_chars = Vec::with_capacity(line.len());
+            for (start, c) in line.char_indices() {
+                let before = lowercase_line.len();
+                lowercase_line.extend(c.to_lowercase());
+                for _ in before..lowercase_line.len() {
+                    original_chars.push((start, start + c.len_utf8()));
+                }
+            }
+
+            let mut spans = vec![];
+            let mut last_end = 0;
+
+            for (lowercase_start, matched) in lowercase_line.match_indices(&query) {
+                // a match can start or end partway through what a character lowercased to,
+                // in which case the whole character is highlighted
+                let start = original_chars[lowercase_start].0.max(last_end);
+                let end = original_chars[lowercase_start + matched.len() - 1].1;
+                if start >= end {
+                    continue;
+                }
+                spans.push(Span::raw(&line[last_end..start]));
+                spans.push(Span::styled(&line[start..end], highlight_style));
+                last_end = end;
+            }
+
+            spans.push(Span::raw(&line[last_end..]));
+
+            Spans::from(spans)
+        })
+        .collect::<Vec<_>>();
+
+    Text::from(lines)
+}
+
+fn draw_error_log<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let mut lines = vec![];
+
+    // newest first
+    for error in app.error_log.iter().rev() {
+        let mut heading = vec![Span::styled(
+            error.at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Style::default().fg(Color::DarkGray),
+        )];
+
+        if let Some(feed) = &error.feed {
+            heading.push(Span::raw(" "));
+            heading.push(Span::styled(
+                feed.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        lines.push(Spans::from(heading));
+        lines.extend(
+            error
+                .message
+                .lines()
+                .map(|line| Spans::from(line.to_owned())),
+        );
+        lines.push(Spans::default());
+    }
+
+    if lines.is_empty() {
+        lines.push(Spans::from("No errors"));
+    }
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        format!("Error log ({}) - press 'E' to close", app.error_log.len()),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+fn draw_queue<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let queue = match &app.queue {
+        Some(queue) => queue,
+        None => return,
+    };
+
+    let mut lines = vec![];
+
+    for (i, queued_entry) in queue.iter().enumerate() {
+        // what is playing stays at the top of the queue until it is done
+        let marker = if i == 0 && app.queue_now_playing.is_some() {
+            Span::styled("▶ ", Style::default().fg(PINK))
+        } else {
+            Span::styled(format!("{}. ", i + 1), Style::default().fg(Color::DarkGray))
+        };
+
+        lines.push(Spans::from(vec![
+            marker,
+            Span::raw(
+                queued_entry
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| queued_entry.link.clone()),
+            ),
+        ]));
+    }
+
+    if lines.is_empty() {
+        lines.push(Spans::from("Nothing queued, add entries with +"));
+    }
+
+    let title = if app.queue_stopping {
+        "Queue - stopping after this one, M to keep playing, Q to close"
+    } else if app.queue_playing {
+        "Queue - M to stop after this one, Q to close"
+    } else {
+        "Queue - M to play, + to add/remove, Q to close"
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        title,
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+fn draw_stats<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    use crate::stats::{DAYS, WEEKS};
+
+    let stats = match &app.stats {
+        Some(stats) => stats,
+        None => return,
+    };
+
+    let bar_set = if app.enhanced_graphics {
+        symbols::bar::NINE_LEVELS
+    } else {
+        symbols::bar::THREE_LEVELS
+    };
+
+    let title_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+
+    let mut constraints = vec![
+        Constraint::Length(5),
+        Constraint::Length(8),
+        Constraint::Length(5),
+    ];
+    if stats.feed_activity.is_some() {
+        constraints.push(Constraint::Length(10));
+    }
+    constraints.push(Constraint::Min(0));
+
+    let chunks = Layout::default()
+        .constraints(constraints)
+        .direction(Direction::Vertical)
+        .split(area);
+
+    let refresh_interval = match stats.mean_refresh_interval {
+        Some(interval) if interval.num_days() > 0 => format!("{} days", interval.num_days()),
+        Some(interval) if interval.num_hours() > 0 => format!("{} hours", interval.num_hours()),
+        Some(interval) => format!("{} minutes", interval.num_minutes()),
+        None => "not enough refreshes yet".to_string(),
+    };
+
+    let summary = Paragraph::new(Text::from(format!(
+        "Feeds: {}\nEntries: {}, {} unread\nAverage time between refreshes: {}",
+        stats.feeds.len(),
+        stats.entries_len,
+        stats.unread_len,
+        refresh_interval
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled("Statistics - press 'D' to close", title_style)),
+    );
+    f.render_widget(summary, chunks[0]);
+
+    let week_labels = (0..WEEKS)
+        .rev()
+        .map(|weeks_ago| match weeks_ago {
+            0 => "now".to_string(),
+            _ => format!("-{}w", weeks_ago),
+        })
+        .collect::<Vec<_>>();
+    let posts_per_week = week_labels
+        .iter()
+        .map(|label| label.as_str())
+        .zip(stats.posts_per_week.iter().copied())
+        .collect::<Vec<_>>();
+
+    let posts_chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(Span::styled(
+            format!("Posts per week, last {} weeks", WEEKS),
+            title_style,
+        )))
+        .data(&posts_per_week)
+        .bar_width(4)
+        .bar_set(bar_set.clone())
+        .bar_style(Style::default().fg(PINK))
+        .value_style(Style::default().fg(Color::Black).bg(PINK));
+    f.render_widget(posts_chart, chunks[1]);
+
+    let reads_sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(Span::styled(
+            format!(
+                "Entries read, last {} days ({})",
+                DAYS,
+                stats.reads_per_day.iter().sum::<u64>()
+            ),
+            title_style,
+        )))
+        .data(&stats.reads_per_day)
+        .bar_set(bar_set)
+        .style(Style::default().fg(PINK));
+    f.render_widget(reads_sparkline, chunks[2]);
+
+    if let Some(feed_activity) = &stats.feed_activity {
+        draw_feed_activity(f, chunks[3], feed_activity, title_style);
+    }
+
+    let rows = stats.feeds.iter().map(|feed| {
+        Row::new(vec![
+            Cell::from(feed.title.as_str()),
+            Cell::from(feed.entries_len.to_string()),
+            Cell::from(feed.unread_len.to_string()),
+            Cell::from(format!("{:.1}", feed.posts_per_week)),
+            Cell::from(match (feed.failures, feed.backoff_until) {
+                (0, _) => String::new(),
+                (failures, Some(backoff_until)) => format!(
+                    "{}, skipped till {}",
+                    failures,
+                    backoff_until
+                        .with_timezone(&chrono::Local)
+                        .format("%a %H:%M")
+                ),
+                (failures, None) => failures.to_string(),
+            })
+            .style(Style::default().fg(Color::Red)),
+            Cell::from(feed.last_error.as_deref().unwrap_or_default())
+                .style(Style::default().fg(Color::Red)),
+        ])
+    });
+
+    let widths = [
+        Constraint::Min(10),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(24),
+        Constraint::Min(10),
+    ];
+
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Feed",
+                "Entries",
+                "Unread",
+                "Posts/week",
+                "Failures",
+                "Last error",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Feeds, most unread first", title_style)),
+        )
+        .widths(&widths);
+    f.render_widget(table, chunks[chunks.len() - 1]);
+}
+
+fn highlight_color(highlight: Highlight) -> Color {
+    match highlight {
+        Highlight::Red => Color::Red,
+        Highlight::Yellow => Color::Yellow,
+        Highlight::Green => Color::Green,
+        Highlight::Cyan => Color::Cyan,
+        Highlight::Blue => Color::Blue,
+        Highlight::Magenta => Color::Magenta,
+    }
+}
+
+/// a calendar of the feed's posts, a column for each week and a row for each day of it,
+/// shaded by how many it posted that day
+fn draw_feed_activity<B>(
+    f: &mut Frame<B>,
+    area: Rect,
+    activity: &crate::stats::FeedActivity,
+    title_style: Style,
+) where
+    B: Backend,
+{
+    use crate::stats::ACTIVITY_WEEKS;
+    use chrono::Datelike;
+
+    const WEEKDAYS: [&str; 7] = ["Mon ", "    ", "Wed ", "    ", "Fri ", "    ", "Sun "];
+    const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+
+    // two columns a week, beside the weekdays, so on a narrow terminal the oldest weeks go
+    let weeks_len = usize::from(area.width.saturating_sub(2 + 4) / 2).min(ACTIVITY_WEEKS);
+    let first_week = ACTIVITY_WEEKS - weeks_len;
+    let max_posts = activity.posts_per_day.iter().copied().max().unwrap_or(0);
+
+    // each month's name above the week it starts in, where there's room
+    let mut months = " ".repeat(4);
+    let mut last_month = None;
+    for week in first_week..ACTIVITY_WEEKS {
+        let monday = activity.first_day + chrono::Duration::weeks(week as i64);
+        let column = 4 + (week - first_week) * 2;
+
+        if last_month != Some(monday.month()) && months.len() <= column {
+            months.push_str(&" ".repeat(column - months.len()));
+            months.push_str(&monday.format("%b").to_string());
+        }
+        last_month = Some(monday.month());
+    }
+
+    let mut lines = vec![Spans::from(months)];
+    lines.extend(WEEKDAYS.iter().enumerate().map(|(weekday, label)| {
+        let mut spans = vec![Span::raw(*label)];
+        spans.extend((first_week..ACTIVITY_WEEKS).map(|week| {
+            match activity.posts_per_day.get(week * 7 + weekday) {
+                // not yet
+                None => Span::raw("  "),
+                Some(0) => Span::styled("· ", Style::default().fg(Color::DarkGray)),
+                Some(posts) => {
+                    let shade = ((posts * 4).div_ceil(max_posts) as usize).clamp(1, 4) - 1;
+                    Span::styled(format!("{} ", SHADES[shade]), Style::default().fg(PINK))
+                }
+            }
+        }));
+        Spans::from(spans)
+    }));
+
+    let last_posted = match activity.last_posted_at {
+        Some(last_posted_at) => format!(
+            "last {}",
+            util::relative_time(chrono::Utc::now(), last_posted_at)
+        ),
+        None => "never".to_string(),
+    };
+
+    let calendar = Paragraph::new(Text::from(lines)).block(
+        Block::default().borders(Borders::ALL).title(Span::styled(
+            format!(
+                "{}, posts per day, last {} weeks ({}, {})",
+                activity.title,
+                ACTIVITY_WEEKS,
+                activity.posts_per_day.iter().sum::<u64>(),
+                last_posted
+            ),
+            title_style,
+        )),
+    );
+    f.render_widget(calendar, area);
+}
+
 fn error_text(errors: &[anyhow::Error]) -> String {
     errors
         .iter()
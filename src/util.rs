@@ -42,6 +42,28 @@ impl<T> StatefulList<T> {
         self.state.select(Some(i));
     }
 
+    /// move the selection down `n` items, stopping at the last one
+    pub fn next_by(&mut self, n: usize) {
+        if let Some(last) = self.items.len().checked_sub(1) {
+            let i = match self.state.selected() {
+                Some(i) => i.saturating_add(n).min(last),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+
+    /// move the selection up `n` items, stopping at the first one
+    pub fn previous_by(&mut self, n: usize) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => i.saturating_sub(n),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+
     pub fn reset(&mut self) {
         self.state.select(Some(0));
     }
@@ -57,6 +79,260 @@ impl<T> From<Vec<T>> for StatefulList<T> {
     }
 }
 
+/// the longest prefix shared by all of `strings`, for tab completion
+pub(crate) fn common_prefix<'a>(strings: &[&'a str]) -> Option<&'a str> {
+    let (first, rest) = strings.split_first()?;
+
+    Some(rest.iter().fold(*first, |prefix, s| {
+        let len = prefix
+            .char_indices()
+            .zip(s.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map(|((i, c), _)| i + c.len_utf8())
+            .unwrap_or(0);
+        &prefix[..len]
+    }))
+}
+
+/// paths typed into russ don't go through a shell, so expand a leading `~` here
+pub(crate) fn expand_tilde(path: &str) -> std::path::PathBuf {
+    let home_dir = directories::BaseDirs::new().map(|base_dirs| base_dirs.home_dir().to_owned());
+
+    match (path.strip_prefix('~'), home_dir) {
+        (Some(""), Some(home_dir)) => home_dir,
+        (Some(rest), Some(home_dir)) if rest.starts_with(std::path::is_separator) => {
+            home_dir.join(&rest[1..])
+        }
+        _ => std::path::PathBuf::from(path),
+    }
+}
+
+/// an average adult's silent reading speed
+const WORDS_PER_MINUTE: usize = 238;
+
+/// the words in `text`, not counting stray punctuation like list bullets
+pub(crate) fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// how much of the shorter text's words have to be in the longer one
+/// for it to be a summary or an excerpt of it
+const SHARED_WORDS_RATIO: f64 = 0.8;
+
+/// Whether two texts say different things, rather than one being a summary
+/// or an excerpt of the other, going by how many of their words they share.
+pub(crate) fn differ_significantly(a: &str, b: &str) -> bool {
+    let words = |text: &str| {
+        text.split_whitespace()
+            .map(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase()
+            })
+            .filter(|word| !word.is_empty())
+            .collect::<std::collections::HashSet<_>>()
+    };
+
+    let (a, b) = (words(a), words(b));
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+
+    if shorter.is_empty() {
+        return false;
+    }
+
+    let shared_len = shorter.intersection(&longer).count();
+    (shared_len as f64) < shorter.len() as f64 * SHARED_WORDS_RATIO
+}
+
+/// how many minutes it takes to read `words_len` words, rounded up
+pub(crate) fn reading_minutes(words_len: usize) -> usize {
+    words_len.div_ceil(WORDS_PER_MINUTE).max(1)
+}
+
+/// `n` with commas between the thousands, like `1,540`
+pub(crate) fn format_thousands(n: usize) -> String {
+    let digits = n.to_string();
+
+    let mut groups = digits
+        .as_bytes()
+        .rchunks(3)
+        .map(|group| String::from_utf8_lossy(group))
+        .collect::<Vec<_>>();
+    groups.reverse();
+
+    groups.join(",")
+}
+
+/// `n` to two significant figures or so, like `950`, `4.2k`, or `14k`
+pub(crate) fn format_size(n: usize) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=9_949 => format!("{:.1}k", n as f64 / 1_000.0),
+        9_950..=999_499 => format!("{}k", (n + 500) / 1_000),
+        _ => format!("{:.1}M", n as f64 / 1_000_000.0),
+    }
+}
+
+/// how long ago `then` was, to the largest whole unit, like `3h ago`
+pub(crate) fn relative_time(
+    now: chrono::DateTime<chrono::Utc>,
+    then: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let age = now - then;
+
+    // a feed's clock can be a little ahead of ours
+    if age < chrono::Duration::zero() {
+        return "in the future".to_string();
+    }
+
+    let (n, unit) = if age.num_minutes() < 1 {
+        return "just now".to_string();
+    } else if age.num_hours() < 1 {
+        (age.num_minutes(), "m")
+    } else if age.num_days() < 1 {
+        (age.num_hours(), "h")
+    } else if age.num_weeks() < 1 {
+        (age.num_days(), "d")
+    } else if age.num_days() < 30 {
+        (age.num_weeks(), "w")
+    } else if age.num_days() < 365 {
+        (age.num_days() / 30, "mo")
+    } else {
+        (age.num_days() / 365, "y")
+    };
+
+    format!("{}{} ago", n, unit)
+}
+
+const INTERVAL_UNITS: &[(&str, i64)] = &[("w", 604_800), ("d", 86_400), ("h", 3_600), ("m", 60)];
+
+/// an interval written like `30m`, `2h`, `1d`, or `1w`
+pub(crate) fn parse_interval(s: &str) -> anyhow::Result<chrono::Duration> {
+    let s = s.trim();
+    let invalid = || anyhow::anyhow!("Interval must be like 30m, 2h, 1d, or 1w, not {}", s);
+
+    let unit_idx = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (n, unit) = s.split_at(unit_idx);
+    let n: i64 = n.parse().map_err(|_| invalid())?;
+    let (_, unit_secs) = INTERVAL_UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .ok_or_else(invalid)?;
+
+    if n == 0 {
+        return Err(invalid());
+    }
+
+    Ok(chrono::Duration::seconds(n * unit_secs))
+}
+
+/// the other way around from `parse_interval`, in the largest unit that fits evenly
+pub(crate) fn format_interval(interval: chrono::Duration) -> String {
+    let secs = interval.num_seconds();
+
+    INTERVAL_UNITS
+        .iter()
+        .find(|(_, unit_secs)| secs % unit_secs == 0)
+        .map(|(name, unit_secs)| format!("{}{}", secs / unit_secs, name))
+        .unwrap_or_else(|| format!("{}s", secs))
+}
+
+/// how many terminal cells `s` takes up, with CJK characters and most emoji taking two
+/// and combining characters taking none
+pub(crate) fn display_width(s: &str) -> usize {
+    unicode_width::UnicodeWidthStr::width(s)
+}
+
+/// `s` cut short with an ellipsis, or padded with spaces, to be exactly `width` cells wide
+pub(crate) fn fit_to_width(s: &str, width: usize) -> String {
+    let s_width = display_width(s);
+
+    if s_width <= width {
+        return format!("{}{}", s, " ".repeat(width - s_width));
+    }
+
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut fitted = String::new();
+    let mut fitted_width = 0;
+
+    for c in s.chars() {
+        let c_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        if fitted_width + c_width > width - 1 {
+            break;
+        }
+        fitted.push(c);
+        fitted_width += c_width;
+    }
+
+    fitted.push('…');
+    fitted_width += 1;
+
+    // a wide character that didn't fit can leave a cell over
+    fitted.push_str(&" ".repeat(width - fitted_width));
+    fitted
+}
+
+/// Wrap every line of `text` to at most `width` terminal cells, breaking between words,
+/// or anywhere around wide characters like CJK, which don't put spaces between words.
+/// Combining characters stay with the character they combine with.
+pub(crate) fn wrap_to_width(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut wrapped = String::with_capacity(text.len());
+
+    for line in text.split('\n') {
+        if !wrapped.is_empty() {
+            wrapped.push('\n');
+        }
+
+        if display_width(line) <= width {
+            wrapped.push_str(line);
+            continue;
+        }
+
+        let mut current = String::new();
+        let mut current_width = 0;
+        // where in `current` the line can be broken, if anywhere
+        let mut break_idx = None;
+
+        for c in line.chars() {
+            let c_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+
+            if c_width > 1 && !current.is_empty() {
+                break_idx = Some(current.len());
+            }
+
+            if current_width + c_width > width && !current.is_empty() {
+                let rest = match break_idx {
+                    Some(idx) if idx > 0 => current.split_off(idx),
+                    _ => String::new(),
+                };
+                wrapped.push_str(current.trim_end());
+                wrapped.push('\n');
+
+                current = rest.trim_start().to_owned();
+                current_width = display_width(&current);
+                break_idx = None;
+            }
+
+            current.push(c);
+            current_width += c_width;
+
+            if c.is_whitespace() || c_width > 1 {
+                break_idx = Some(current.len());
+            }
+        }
+
+        wrapped.push_str(&current);
+    }
+
+    wrapped
+}
+
 #[cfg(target_os = "linux")]
 pub(crate) fn set_wsl_clipboard_contents(s: &str) -> anyhow::Result<()> {
     use std::{
@@ -77,3 +353,108 @@ pub(crate) fn set_wsl_clipboard_contents(s: &str) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(target_os = "linux")]
+pub(crate) fn get_wsl_clipboard_contents() -> anyhow::Result<String> {
+    // clip.exe can only write to it
+    let output = std::process::Command::new("powershell.exe")
+        .args(["-NoProfile", "-Command", "Get-Clipboard"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Unable to read the clipboard with powershell.exe: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_estimates_reading_time() {
+        assert_eq!(word_count("* one, two -- three\n\n4 * five"), 5);
+        assert_eq!(reading_minutes(0), 1);
+        assert_eq!(reading_minutes(238), 1);
+        assert_eq!(reading_minutes(1540), 7);
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1540), "1,540");
+        assert_eq!(format_thousands(1234567), "1,234,567");
+        assert_eq!(format_size(950), "950");
+        assert_eq!(format_size(4_240), "4.2k");
+        assert_eq!(format_size(13_500), "14k");
+        assert_eq!(format_size(2_500_000), "2.5M");
+    }
+
+    #[test]
+    fn it_tells_summaries_from_different_text() {
+        let content = "Rust 1.60 is out, with faster incremental builds and better diagnostics.";
+        assert!(!differ_significantly("Rust 1.60 is out!", content));
+        assert!(!differ_significantly(content, content));
+        assert!(differ_significantly(
+            "Comments: 42 points, by someone",
+            content
+        ));
+        assert!(!differ_significantly("", content));
+    }
+
+    #[test]
+    fn it_measures_and_wraps_by_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+
+        assert_eq!(fit_to_width("日本語", 6), "日本語");
+        assert_eq!(fit_to_width("日本語", 5), "日本…");
+        assert_eq!(fit_to_width("日本語", 4), "日… ");
+        assert_eq!(fit_to_width("ab", 4), "ab  ");
+
+        assert_eq!(
+            wrap_to_width("the quick brown fox", 10),
+            "the quick\nbrown fox"
+        );
+        assert_eq!(wrap_to_width("日本語のテキスト", 6), "日本語\nのテキ\nスト");
+        assert_eq!(
+            wrap_to_width("cafe\u{301} cafe\u{301}", 5),
+            "cafe\u{301}\ncafe\u{301}"
+        );
+        assert_eq!(wrap_to_width("short\n\nlines", 10), "short\n\nlines");
+        for line in wrap_to_width("混合 mixed テキスト text 🦀🦀🦀", 7).lines() {
+            assert!(display_width(line) <= 7, "{:?} is too wide", line);
+        }
+    }
+
+    #[test]
+    fn it_describes_how_long_ago() {
+        let now: chrono::DateTime<chrono::Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let ago = |then: &str| relative_time(now, then.parse().unwrap());
+
+        assert_eq!(ago("2024-03-01T11:59:30Z"), "just now");
+        assert_eq!(ago("2024-03-01T11:15:00Z"), "45m ago");
+        assert_eq!(ago("2024-03-01T09:00:00Z"), "3h ago");
+        assert_eq!(ago("2024-02-28T12:00:00Z"), "2d ago");
+        assert_eq!(ago("2024-02-16T12:00:00Z"), "2w ago");
+        assert_eq!(ago("2023-12-01T12:00:00Z"), "3mo ago");
+        assert_eq!(ago("2021-01-01T12:00:00Z"), "3y ago");
+        assert_eq!(ago("2024-03-02T12:00:00Z"), "in the future");
+    }
+
+    #[test]
+    fn it_parses_and_formats_intervals() {
+        assert_eq!(
+            parse_interval("90m").unwrap(),
+            chrono::Duration::minutes(90)
+        );
+        assert_eq!(parse_interval(" 2w").unwrap(), chrono::Duration::weeks(2));
+        assert!(parse_interval("2").is_err());
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("1 day").is_err());
+
+        assert_eq!(format_interval(chrono::Duration::minutes(90)), "90m");
+        assert_eq!(format_interval(chrono::Duration::hours(48)), "2d");
+    }
+}
@@ -0,0 +1,165 @@
+//! Reading the open entry in a pager, or any other program,
+//! which gets the terminal to itself until it quits.
+
+use crate::config::ViewerConfig;
+use crate::pipe::{run_with_stdin, shell_command};
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// the open entry, on its way to the viewer
+#[derive(Clone, Debug)]
+pub struct View {
+    pub command: String,
+    pub contents: String,
+    /// so viewers that go by the file's extension know what it is
+    pub extension: &'static str,
+}
+
+impl View {
+    /// `text` is the entry as it is shown, and `html` is what it was rendered from,
+    /// which the config may ask for instead, through its filter if there is one.
+    pub fn new(config: &ViewerConfig, text: String, html: String) -> Result<View> {
+        let command = config
+            .command
+            .clone()
+            .or_else(|| {
+                std::env::var("PAGER")
+                    .ok()
+                    .filter(|pager| !pager.is_empty())
+            })
+            .unwrap_or_else(|| if cfg!(windows) { "more" } else { "less" }.to_string());
+
+        let (contents, extension) = match (config.html, &config.filter) {
+            (false, _) => (text, "txt"),
+            (true, None) => (html, "html"),
+            (true, Some(filter)) => (
+                run_with_stdin(shell_command(filter), html.as_bytes())?,
+                "txt",
+            ),
+        };
+
+        Ok(View {
+            command,
+            contents,
+            extension,
+        })
+    }
+
+    /// Write the entry to a temporary file, and run the viewer on it, waiting for it to quit.
+    /// The file's path is added to the end of the command.
+    pub fn run(&self) -> Result<()> {
+        let path = temp_path(self.extension);
+
+        // `create_new`, so there's no writing through a link someone else put there
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let entry_file = EntryFile { path };
+        file.write_all(self.contents.as_bytes())?;
+        drop(file);
+
+        run_viewer(&self.command, &entry_file.path)
+    }
+}
+
+/// the entry's temporary file, removed once the viewer is done with it, however that went
+struct EntryFile {
+    path: PathBuf,
+}
+
+impl Drop for EntryFile {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.path) {
+            // the viewer may have removed it itself
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("unable to remove {}: {}", self.path.display(), e)
+            }
+            _ => (),
+        }
+    }
+}
+
+fn run_viewer(command: &str, path: &Path) -> Result<()> {
+    let command = if cfg!(windows) {
+        format!("{} \"%RUSS_ENTRY_FILE%\"", command)
+    } else {
+        format!("{} \"$RUSS_ENTRY_FILE\"", command)
+    };
+
+    let status = shell_command(&command)
+        .env("RUSS_ENTRY_FILE", path)
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("Viewer failed with {}", status))
+    }
+}
+
+fn temp_path(extension: &str) -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or_default();
+
+    std::env::temp_dir().join(format!(
+        "russ-entry-{}-{}.{}",
+        std::process::id(),
+        nanos,
+        extension
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_filters_html_for_the_viewer() {
+        let config = ViewerConfig {
+            command: Some("cat".to_string()),
+            html: true,
+            filter: Some("tr a-z A-Z".to_string()),
+        };
+
+        let view = View::new(&config, "text".to_string(), "<p>html</p>".to_string()).unwrap();
+        assert_eq!(view.contents, "<P>HTML</P>");
+        assert_eq!(view.extension, "txt");
+
+        let view = View {
+            command: "grep -q '^text$'".to_string(),
+            contents: "text\n".to_string(),
+            extension: "txt",
+        };
+        assert!(view.run().is_ok());
+
+        let view = View {
+            command: "grep -q other".to_string(),
+            ..view
+        };
+        assert!(view
+            .run()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Viewer failed"));
+
+        // a viewer that cleans up after itself, or fails after it has, is fine
+        let view = View {
+            command: "rm".to_string(),
+            ..view
+        };
+        assert!(view.run().is_ok());
+        let view = View {
+            command: "rm \"$RUSS_ENTRY_FILE\"; false".to_string(),
+            ..view
+        };
+        assert!(view
+            .run()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Viewer failed"));
+    }
+}
@@ -0,0 +1,71 @@
+//! Saving entries to a Wallabag instance, to read later.
+
+use crate::config::WallabagConfig;
+use anyhow::{anyhow, Context, Result};
+
+/// Log in with the configured client and user, then add `url` to Wallabag.
+///
+/// The access token is only good for an hour, so a new one is requested every time.
+pub fn save_url(http_client: &ureq::Agent, config: &WallabagConfig, url: &str) -> Result<()> {
+    let access_token = fetch_access_token(http_client, config)?;
+
+    http_client
+        .post(&api_url(config, "api/entries.json"))
+        .set("Authorization", &format!("Bearer {}", access_token))
+        .send_form(&[("url", url)])
+        .context("Unable to save to Wallabag")?;
+
+    Ok(())
+}
+
+fn fetch_access_token(http_client: &ureq::Agent, config: &WallabagConfig) -> Result<String> {
+    let resp = http_client
+        .post(&api_url(config, "oauth/v2/token"))
+        .send_form(&[
+            ("grant_type", "password"),
+            ("client_id", &config.client_id),
+            ("client_secret", &config.client_secret),
+            ("username", &config.username),
+            ("password", &config.password),
+        ])
+        .context("Unable to log in to Wallabag")?;
+
+    let token: serde_json::Value = serde_json::from_reader(resp.into_reader())?;
+
+    token["access_token"]
+        .as_str()
+        .map(|access_token| access_token.to_owned())
+        .ok_or_else(|| anyhow!("Wallabag did not return an access token"))
+}
+
+/// `path` on the configured instance, whether or not its url ends with a `/`
+fn api_url(config: &WallabagConfig, path: &str) -> String {
+    format!("{}/{}", config.url.trim_end_matches('/'), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_api_urls() {
+        let mut config = WallabagConfig {
+            url: "https://app.wallabag.it".to_string(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            username: String::new(),
+            password: String::new(),
+        };
+
+        assert_eq!(
+            api_url(&config, "api/entries.json"),
+            "https://app.wallabag.it/api/entries.json"
+        );
+
+        config.url.push('/');
+        assert_eq!(
+            api_url(&config, "oauth/v2/token"),
+            "https://app.wallabag.it/oauth/v2/token"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Subscribing to YouTube channels and playlists by the links people actually have,
+//! as YouTube's pages don't link to their feeds.
+
+use anyhow::{anyhow, Result};
+
+const FEEDS_URL: &str = "https://www.youtube.com/feeds/videos.xml";
+
+/// The feed of the YouTube channel or playlist at `url`, or `None` if it isn't one.
+/// Channels only known by their handle, like `/@name`, are looked up on YouTube.
+pub fn feed_url(http_client: &ureq::Agent, url: &str) -> Result<Option<String>> {
+    let path = match youtube_path(url) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    if let Some(feed_url) = known_feed_url(path) {
+        return Ok(Some(feed_url));
+    }
+
+    if !(path.starts_with("/@") || path.starts_with("/c/")) {
+        return Ok(None);
+    }
+
+    log::debug!("looking up the YouTube channel of {}", url);
+    let page = http_client.get(url).call()?.into_string()?;
+
+    canonical_feed_url(&page)
+        .map(Some)
+        .ok_or_else(|| anyhow!("Unable to find the YouTube channel at {}", url))
+}
+
+/// the path and query of `url`, if it's on YouTube
+fn youtube_path(url: &str) -> Option<&str> {
+    let url = url.trim();
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(host);
+
+    if host == "youtube.com" {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// the feed for a path that says which channel or playlist it is
+fn known_feed_url(path: &str) -> Option<String> {
+    if path.starts_with("/feeds/") {
+        return None;
+    }
+
+    if let Some(playlist_id) = query_param(path, "list") {
+        return Some(format!("{}?playlist_id={}", FEEDS_URL, playlist_id));
+    }
+
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+
+    match (segments.next(), segments.next()) {
+        (Some("channel"), Some(channel_id)) => {
+            Some(format!("{}?channel_id={}", FEEDS_URL, channel_id))
+        }
+        (Some("user"), Some(user)) => Some(format!("{}?user={}", FEEDS_URL, user)),
+        _ => None,
+    }
+}
+
+fn query_param<'a>(path: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = path.split_once('?')?;
+
+    query
+        .split('#')
+        .next()?
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, value)| *key == name && !value.is_empty())
+        .map(|(_, value)| value)
+}
+
+/// a channel page's canonical link has the channel's id in it
+fn canonical_feed_url(page: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(page);
+    let selector = scraper::Selector::parse(r#"link[rel="canonical"]"#).ok()?;
+
+    let feed_url = document
+        .select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(youtube_path)
+        .find_map(known_feed_url);
+    feed_url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_feeds_for_channels_and_playlists() {
+        let feed_url = |url| youtube_path(url).and_then(known_feed_url);
+
+        assert_eq!(
+            feed_url("https://www.youtube.com/channel/UC123/videos").as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?channel_id=UC123")
+        );
+        assert_eq!(
+            feed_url("youtube.com/user/someone").as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?user=someone")
+        );
+        assert_eq!(
+            feed_url("https://m.youtube.com/watch?v=abc&list=PL456#t=1").as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?playlist_id=PL456")
+        );
+        assert_eq!(
+            feed_url("https://www.youtube.com/feeds/videos.xml?channel_id=UC123"),
+            None
+        );
+        assert_eq!(feed_url("https://www.youtube.com/@someone"), None);
+        assert_eq!(feed_url("https://notyoutube.com/channel/UC123"), None);
+    }
+
+    #[test]
+    fn it_finds_the_channel_in_its_page() {
+        let page = r#"<html><head>
+            <link rel="canonical" href="https://www.youtube.com/channel/UC789">
+            </head><body></body></html>"#;
+
+        assert_eq!(
+            canonical_feed_url(page).as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?channel_id=UC789")
+        );
+        assert_eq!(canonical_feed_url("<html></html>"), None);
+    }
+}